@@ -0,0 +1,67 @@
+#![no_main]
+//! Grammar aware mutation of lelwel grammar files.
+//!
+//! Valid inputs from the corpus are parsed and mutated on the syntax tree level
+//! by swapping nodes of the same rule, duplicating nodes (e.g. list elements), or
+//! dropping nodes and tokens (e.g. optional parts). The resulting inputs are
+//! mostly near valid, which stresses the error recovery of the parser.
+mod mutation;
+
+use lelwel::frontend::parser::{tokenize, Cst, Node, NodeRef, Parser, Rule, Span, Token};
+use lelwel::frontend::sema::SemanticPass;
+use libfuzzer_sys::{fuzz_mutator, fuzz_target, fuzzer_mutate};
+use logos::Logos;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        let mut diags = vec![];
+        let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+        let cst = Parser::parse(source, tokens, ranges, &mut diags);
+        let _ = SemanticPass::run(&cst, &mut diags);
+    }
+});
+
+fuzz_mutator!(|data: &mut [u8], size: usize, max_size: usize, seed: u32| {
+    match std::str::from_utf8(&data[..size])
+        .ok()
+        .and_then(|source| mutate(source, seed))
+    {
+        Some(mutated) if mutated.len() <= max_size => {
+            data[..mutated.len()].copy_from_slice(mutated.as_bytes());
+            mutated.len()
+        }
+        _ => fuzzer_mutate(data, size, max_size),
+    }
+});
+
+fn collect(cst: &Cst, node: NodeRef, rules: &mut Vec<(Rule, Span)>, tokens: &mut Vec<Span>) {
+    for child in cst.children(node) {
+        match cst.get(child) {
+            Node::Rule(rule, _) => {
+                if let Some(span) = cst.get_span(child) {
+                    rules.push((rule, span));
+                }
+                collect(cst, child, rules, tokens);
+            }
+            Node::Token(_) => {
+                let is_trivia = [Token::Comment, Token::DocComment, Token::Whitespace]
+                    .iter()
+                    .any(|token| cst.get_token(child, *token).is_some());
+                if !is_trivia {
+                    tokens.extend(cst.get_span(child));
+                }
+            }
+        }
+    }
+}
+
+fn mutate(source: &str, seed: u32) -> Option<String> {
+    let mut diags = vec![];
+    let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+    let cst = Parser::parse(source, tokens, ranges, &mut diags);
+
+    let mut rules = vec![];
+    let mut tokens = vec![];
+    collect(&cst, NodeRef::ROOT, &mut rules, &mut tokens);
+    mutation::mutate(source, &rules, &tokens, seed)
+}
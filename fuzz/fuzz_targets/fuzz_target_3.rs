@@ -0,0 +1,70 @@
+#![no_main]
+//! Grammar aware mutation of inputs to a generated parser.
+//!
+//! The parser is generated for the grammar of the `lelwel-features` test crate,
+//! which uses the extensions of lelwel, like LL(k) lookahead, syntactic
+//! predicates, and bounded repetitions. Inputs are mutated on the syntax tree
+//! level like in `fuzz_target_2`, which stresses the error recovery of the
+//! generated code. The syntax tree must always contain the whole input.
+//!
+//! The inputs of the tests in `tests/features/tests/data` are a good initial
+//! corpus.
+mod mutation;
+
+use lelwel_features::{tokenize, Cst, Node, NodeRef, Parser, Rule, Span, Token};
+use libfuzzer_sys::{fuzz_mutator, fuzz_target, fuzzer_mutate};
+use logos::Logos;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        let mut diags = vec![];
+        let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+        let cst = Parser::parse(source, tokens, ranges, &mut diags);
+        assert_eq!(cst.verify_round_trip(), Ok(()));
+    }
+});
+
+fuzz_mutator!(|data: &mut [u8], size: usize, max_size: usize, seed: u32| {
+    match std::str::from_utf8(&data[..size])
+        .ok()
+        .and_then(|source| mutate(source, seed))
+    {
+        Some(mutated) if mutated.len() <= max_size => {
+            data[..mutated.len()].copy_from_slice(mutated.as_bytes());
+            mutated.len()
+        }
+        _ => fuzzer_mutate(data, size, max_size),
+    }
+});
+
+fn collect(cst: &Cst, node: NodeRef, rules: &mut Vec<(Rule, Span)>, tokens: &mut Vec<Span>) {
+    for child in cst.children(node) {
+        match cst.get(child) {
+            Node::Rule(rule, _) => {
+                if let Some(span) = cst.get_span(child) {
+                    rules.push((rule, span));
+                }
+                collect(cst, child, rules, tokens);
+            }
+            Node::Token(_) => {
+                let is_trivia = [Token::Comment, Token::Whitespace]
+                    .iter()
+                    .any(|token| cst.get_token(child, *token).is_some());
+                if !is_trivia {
+                    tokens.extend(cst.get_span(child));
+                }
+            }
+        }
+    }
+}
+
+fn mutate(source: &str, seed: u32) -> Option<String> {
+    let mut diags = vec![];
+    let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+    let cst = Parser::parse(source, tokens, ranges, &mut diags);
+
+    let mut rules = vec![];
+    let mut tokens = vec![];
+    collect(&cst, NodeRef::ROOT, &mut rules, &mut tokens);
+    mutation::mutate(source, &rules, &tokens, seed)
+}
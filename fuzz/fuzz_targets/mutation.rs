@@ -0,0 +1,62 @@
+//! Grammar aware mutation of inputs, based on the spans of the syntax tree
+//! nodes of a parsed input.
+
+use std::ops::Range;
+
+struct Random(u32);
+
+impl Random {
+    fn next(&mut self, bound: usize) -> usize {
+        // xorshift32
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as usize % bound.max(1)
+    }
+}
+
+/// Mutates the `source` by swapping nodes of the same rule, duplicating nodes
+/// (e.g. list elements), or dropping nodes and tokens (e.g. optional parts).
+///
+/// The `rules` are the rule nodes of the syntax tree with their spans, and the
+/// `tokens` are the spans of the tokens that are not trivia.
+pub fn mutate<R: PartialEq>(
+    source: &str,
+    rules: &[(R, Range<usize>)],
+    tokens: &[Range<usize>],
+    seed: u32,
+) -> Option<String> {
+    let mut random = Random(seed | 1);
+    let replace = |span: &Range<usize>, text: &str| {
+        format!("{}{text}{}", &source[..span.start], &source[span.end..])
+    };
+    match random.next(4) {
+        0 => {
+            // swap with a node of the same rule (i.e. choose another alternative)
+            let (rule, span) = rules.get(random.next(rules.len()))?;
+            let others = rules
+                .iter()
+                .filter(|(r, s)| r == rule && (s.end <= span.start || span.end <= s.start))
+                .collect::<Vec<_>>();
+            let (_, other) = others.get(random.next(others.len()))?;
+            Some(replace(span, &source[other.clone()]))
+        }
+        1 => {
+            // duplicate a node (i.e. repeat a list element)
+            let (_, span) = rules.get(random.next(rules.len()))?;
+            let text = &source[span.clone()];
+            Some(replace(span, &format!("{text} {text}")))
+        }
+        2 => {
+            // drop a node (i.e. leave out an optional part)
+            let (_, span) = rules.get(random.next(rules.len()))?;
+            Some(replace(span, ""))
+        }
+        _ => {
+            // drop a single token
+            let span = tokens.get(random.next(tokens.len()))?;
+            Some(replace(span, ""))
+        }
+    }
+}
+
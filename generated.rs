@@ -0,0 +1,551 @@
+// generated by lelwel 0.6.2
+
+#[allow(unused_macros)]
+macro_rules! syntax_error_message {
+    [$span:expr, $($tk:literal),*] => {
+        {
+            let expected = [$($tk),*];
+            let mut msg = "invalid syntax, expected".to_string();
+            if expected.len() > 1 {
+                msg.push_str(" one of: ");
+            } else {
+                msg.push_str(": ");
+            }
+            let mut count = 0;
+            for e in expected {
+                count += 1;
+                let s = format!("{}", e);
+                let s = if s.starts_with('<') && s.ends_with('>') && s.len() > 2 {
+                    s
+                } else {
+                    format!("'{}'", s)
+                };
+                msg.push_str(&s);
+                if count < expected.len() {
+                    msg.push_str(", ");
+                }
+            }
+            msg
+        }
+    }
+}
+
+/// Number of syntax errors after which the rest of the input is skipped.
+const MAX_ERRORS: usize = usize::MAX;
+
+/// Error of [`Parser::parse_cancellable`] if the parse was cancelled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cancelled;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Rule {
+    Error,
+    S,
+    Orphan,
+    Helper,
+    Single,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub struct NodeRef(pub CstIndex);
+
+impl NodeRef {
+    #[allow(dead_code)]
+    pub const ROOT: NodeRef = NodeRef(0);
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Node {
+    Rule(Rule, CstIndex),
+    Token(CstIndex),
+}
+
+#[derive(Clone, Copy)]
+struct MarkOpened(CstIndex);
+#[derive(Clone, Copy)]
+struct MarkClosed(CstIndex);
+
+pub struct CstChildren<'a> {
+    iter: std::slice::Iter<'a, Node>,
+    offset: CstIndex,
+}
+#[allow(clippy::unnecessary_cast)]
+impl<'a> Iterator for CstChildren<'a> {
+    type Item = NodeRef;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset;
+        let node = self.iter.next();
+        self.offset += 1;
+        if let Some(Node::Rule(_, end_offset)) = node {
+            if *end_offset > 0 {
+                self.iter.nth((*end_offset as usize).saturating_sub(1));
+                self.offset += *end_offset;
+            }
+        }
+        if node.is_some() {
+            Some(NodeRef(offset))
+        } else {
+            None
+        }
+    }
+}
+
+pub struct Cst<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    ranges: Vec<std::ops::Range<CstIndex>>,
+    nodes: Vec<Node>,
+    token_count: CstIndex,
+}
+#[allow(clippy::unnecessary_cast, dead_code)]
+impl<'a> Cst<'a> {
+    fn new(source: &'a str, tokens: Vec<Token>, ranges: Vec<std::ops::Range<CstIndex>>) -> Self {
+        Self {
+            source,
+            tokens,
+            ranges,
+            nodes: vec![],
+            token_count: 0,
+        }
+    }
+    fn open(&mut self) -> MarkOpened {
+        let mark = MarkOpened(self.nodes.len() as CstIndex);
+        self.nodes.push(Node::Rule(Rule::Error, 0));
+        mark
+    }
+    fn close(&mut self, mark: MarkOpened, rule: Rule) -> MarkClosed {
+        self.nodes[mark.0 as usize] = Node::Rule(rule, self.nodes.len() as CstIndex - 1 - mark.0);
+        MarkClosed(mark.0)
+    }
+    fn advance(&mut self) {
+        self.nodes.push(Node::Token(self.token_count));
+        self.token_count += 1;
+    }
+    fn open_before(&mut self, mark: MarkClosed) -> MarkOpened {
+        self.nodes
+            .insert(mark.0 as usize, Node::Rule(Rule::Error, 0));
+        MarkOpened(mark.0)
+    }
+    fn mark(&self) -> MarkClosed {
+        MarkClosed(self.nodes.len() as CstIndex)
+    }
+    pub fn children(&self, node: NodeRef) -> CstChildren<'_> {
+        let iter = if let Some(Node::Rule(_, end_offset)) = self.nodes.get(node.0 as usize) {
+            self.nodes[node.0 as usize + 1..node.0 as usize + *end_offset as usize + 1].iter()
+        } else {
+            std::slice::Iter::default()
+        };
+        CstChildren {
+            iter,
+            offset: node.0 + 1,
+        }
+    }
+    pub fn get(&self, node: NodeRef) -> Node {
+        self.nodes[node.0 as usize]
+    }
+    pub fn get_span(&self, node: NodeRef) -> Option<Span> {
+        match self.nodes.get(node.0 as usize) {
+            Some(Node::Token(idx)) => {
+                let range = &self.ranges[*idx as usize];
+                Some(range.start as usize..range.end as usize)
+            }
+            Some(Node::Rule(_, _)) => self
+                .children(node)
+                .filter(|node_ref| {
+                    if let Node::Token(idx) = self.get(*node_ref) {
+                        !Parser::is_skipped(self.tokens[idx as usize])
+                    } else {
+                        true
+                    }
+                })
+                .filter_map(|n| self.get_span(n))
+                .reduce(|acc, e| acc.start.min(e.start)..acc.end.max(e.end)),
+            None => None,
+        }
+    }
+    pub fn get_token(&self, node: NodeRef, token: Token) -> Option<(&'a str, Span)> {
+        if let Some(Node::Token(idx)) = self.nodes.get(node.0 as usize) {
+            let tok = self.tokens[*idx as usize];
+            let range = &self.ranges[*idx as usize];
+            if token == tok {
+                let span = range.start as usize..range.end as usize;
+                Some((&self.source[span.clone()], span))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+    pub fn get_rule(&self, node: NodeRef, rule: Rule) -> Option<NodeRef> {
+        if let Some(Node::Rule(r, _)) = self.nodes.get(node.0 as usize) {
+            if rule == *r {
+                Some(node)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+    /// Re-emits the source text of all tokens in the syntax tree.
+    pub fn unparse(&self) -> String {
+        let mut text = String::new();
+        for node in self.nodes.iter() {
+            if let Node::Token(idx) = node {
+                if let Some(range) = self.ranges.get(*idx as usize) {
+                    text.push_str(&self.source[range.start as usize..range.end as usize]);
+                }
+            }
+        }
+        text
+    }
+    /// Checks that unparsing the syntax tree reproduces the source byte for
+    /// byte, and returns the offset of the first difference otherwise.
+    pub fn verify_round_trip(&self) -> Result<(), usize> {
+        let text = self.unparse();
+        match text
+            .bytes()
+            .zip(self.source.bytes())
+            .position(|(a, b)| a != b)
+        {
+            Some(offset) => Err(offset),
+            None if text.len() != self.source.len() => Err(text.len().min(self.source.len())),
+            None => Ok(()),
+        }
+    }
+}
+
+#[allow(clippy::unnecessary_cast)]
+impl std::fmt::Display for Cst<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const DEPTH: &str = "    ";
+        fn rec(
+            cst: &Cst,
+            f: &mut std::fmt::Formatter<'_>,
+            idx: NodeRef,
+            indent: usize,
+        ) -> std::fmt::Result {
+            match cst.get(idx) {
+                Node::Rule(rule, _) => {
+                    if let Some(range) = cst.get_span(idx) {
+                        writeln!(f, "{}{rule:?} [{range:?}]", DEPTH.repeat(indent),)?;
+                    } else {
+                        writeln!(f, "{}{rule:?}", DEPTH.repeat(indent),)?;
+                    }
+                }
+                Node::Token(tok) => {
+                    let range = &cst.ranges[tok as usize];
+                    writeln!(
+                        f,
+                        "{}{:?} {:?} [{:?}]",
+                        DEPTH.repeat(indent),
+                        cst.tokens[tok as usize],
+                        &cst.source[range.start as usize..range.end as usize],
+                        range,
+                    )?;
+                }
+            }
+            for c in cst.children(idx) {
+                rec(cst, f, c, indent + 1)?;
+            }
+            Ok(())
+        }
+        rec(self, f, NodeRef(0), 0)
+    }
+}
+
+#[allow(unused_macros)]
+macro_rules! expect {
+    ($tok:ident, $sym:literal, $self:expr, $diags:expr) => {
+        if let Token::$tok = $self.current {
+            $self.advance(false);
+        } else {
+            $self.error($diags, err![$self.span(), $sym]);
+        }
+    };
+}
+
+#[allow(unused_macros)]
+macro_rules! expect_insertable {
+    ($tok:ident, $sym:literal, $error:literal, $self:expr, $diags:expr) => {
+        if let Token::$tok = $self.current {
+            $self.advance(false);
+        } else if $self.insert_token(Token::$tok) {
+            if !$self.error_cooldown {
+                let span = $self.span();
+                $diags.push(ins![span.start..span.start, $sym]);
+            }
+        } else {
+            $self.error($diags, err![$self.span(), $error]);
+        }
+    };
+}
+
+pub struct Parser<'a> {
+    cst: Cst<'a>,
+    pos: usize,
+    current: Token,
+    error_cooldown: bool,
+    error_count: usize,
+    max_offset: usize,
+    cancel: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    cancelled: bool,
+    #[allow(dead_code)]
+    context: Context<'a>,
+}
+#[allow(clippy::while_let_loop, dead_code)]
+impl<'a> Parser<'a> {
+    fn error(&mut self, diags: &mut Vec<Diagnostic>, diag: Diagnostic) {
+        if self.error_cooldown || self.error_count == MAX_ERRORS || self.cancelled {
+            return;
+        }
+        if let Some(last) = diags.last() {
+            if last.labels.first().unwrap().range == diag.labels.first().unwrap().range {
+                return;
+            }
+        }
+        diags.push(diag);
+        self.error_count += 1;
+        if self.error_count == MAX_ERRORS && self.pos < self.cst.tokens.len() {
+            self.skip_rest(diags);
+        }
+    }
+    /// Moves the remaining tokens into an error node, so parsing ends as if
+    /// the end of file was reached.
+    fn skip_rest(&mut self, diags: &mut Vec<Diagnostic>) {
+        let m = self.cst.open();
+        while self.pos < self.cst.tokens.len() {
+            self.cst.advance();
+            self.pos += 1;
+        }
+        self.current = Token::EOF;
+        self.close(m, Rule::Error, diags);
+    }
+    /// Checks if the parse was cancelled, in which case the parser skips to
+    /// the end of file, so all rules return without consuming more tokens.
+    fn is_cancelled(&mut self) -> bool {
+        if !self.cancelled
+            && self
+                .cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.load(std::sync::atomic::Ordering::Relaxed))
+        {
+            self.cancelled = true;
+            self.pos = self.cst.tokens.len();
+            self.current = Token::EOF;
+        }
+        self.cancelled
+    }
+    /// Moves to the next token that is seen by the rules. Tokens of `skip`
+    /// declarations and lexer errors are added to the syntax tree, but are
+    /// never the current token.
+    fn advance(&mut self, error: bool) {
+        if self.is_cancelled() {
+            return;
+        }
+        if !error {
+            self.error_cooldown = false;
+        }
+        loop {
+            self.pos += 1;
+            match self.cst.tokens.get(self.pos) {
+                Some(Token::Error) => {
+                    self.cst.advance();
+                    continue;
+                }
+                Some(tok) => {
+                    self.current = *tok;
+                    self.cst.advance();
+                    break;
+                }
+                None => {
+                    self.current = Token::EOF;
+                    self.cst.advance();
+                    break;
+                }
+            }
+        }
+    }
+    fn is_skipped(token: Token) -> bool {
+        matches!(token, Token::Error)
+    }
+    fn init_skip(&mut self) {
+        loop {
+            match self.cst.tokens.get(self.pos) {
+                Some(Token::Error) => {
+                    self.pos += 1;
+                    self.cst.advance();
+                    continue;
+                }
+                Some(tok) => {
+                    self.current = *tok;
+                    break;
+                }
+                None => {
+                    self.current = Token::EOF;
+                    break;
+                }
+            }
+        }
+    }
+    fn advance_with_error(&mut self, diags: &mut Vec<Diagnostic>, diag: Diagnostic) {
+        let m = self.cst.open();
+        self.error(diags, diag);
+        self.error_cooldown = true;
+        if self.pos < self.cst.tokens.len() {
+            self.advance(true);
+        }
+        self.close(m, Rule::Error, diags);
+    }
+    #[allow(dead_code)]
+    fn peek(&self, lookahead: usize) -> Token {
+        self.cst
+            .tokens
+            .iter()
+            .skip(self.pos)
+            .filter(|token| !Self::is_skipped(**token))
+            .nth(lookahead)
+            .map_or(Token::EOF, |it| *it)
+    }
+    fn span(&self) -> std::ops::Range<CstIndex> {
+        self.cst
+            .ranges
+            .get(self.pos)
+            .map_or(self.max_offset..self.max_offset, |span| span.clone())
+    }
+    fn close(&mut self, mark: MarkOpened, rule: Rule, diags: &mut Vec<Diagnostic>) -> MarkClosed {
+        let m = self.cst.close(mark, rule);
+        self.build(rule, NodeRef(m.0), diags);
+        m
+    }
+    fn new(
+        source: &'a str,
+        tokens: Vec<Token>,
+        ranges: Vec<std::ops::Range<CstIndex>>,
+        cancel: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    ) -> Self {
+        Self {
+            current: Token::EOF,
+            cst: Cst::new(source, tokens, ranges),
+            pos: 0,
+            error_cooldown: false,
+            error_count: 0,
+            max_offset: source.len(),
+            cancel,
+            cancelled: false,
+            context: Context::default(),
+        }
+    }
+    pub fn parse(
+        source: &'a str,
+        tokens: Vec<Token>,
+        ranges: Vec<std::ops::Range<CstIndex>>,
+        diags: &mut Vec<Diagnostic>,
+    ) -> Cst<'a> {
+        let mut parser = Self::new(source, tokens, ranges, None);
+        parser.s(diags);
+        parser.cst
+    }
+    /// Same as [`Parser::parse`], but the parse stops as soon as another
+    /// thread sets the `cancel` flag, e.g. because a language server received
+    /// a newer version of the source. The flag is checked whenever a token is
+    /// consumed, and the diagnostics of a cancelled parse are incomplete.
+    pub fn parse_cancellable(
+        source: &'a str,
+        tokens: Vec<Token>,
+        ranges: Vec<std::ops::Range<CstIndex>>,
+        diags: &mut Vec<Diagnostic>,
+        cancel: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    ) -> Result<Cst<'a>, Cancelled> {
+        let mut parser = Self::new(source, tokens, ranges, Some(cancel));
+        parser.s(diags);
+        if parser.cancelled {
+            Err(Cancelled)
+        } else {
+            Ok(parser.cst)
+        }
+    }
+    /// Parses the input with the function of an entry rule, which must be
+    /// followed by the end of file. If the rule creates a single node, it is
+    /// the root of the syntax tree, otherwise the nodes of the rule are
+    /// wrapped in a node of the `kind` of the rule.
+    #[allow(dead_code, clippy::unnecessary_cast)]
+    fn entry(
+        &mut self,
+        kind: Rule,
+        rule: fn(&mut Self, &mut Vec<Diagnostic>),
+        diags: &mut Vec<Diagnostic>,
+    ) {
+        let first = self.cst.mark();
+        self.init_skip();
+        rule(self, diags);
+        let start = first.0 as usize;
+        let skipped = self.cst.nodes[start..]
+            .iter()
+            .take_while(|node| matches!(node, Node::Token(_)))
+            .count();
+        let root = match self.cst.nodes.get(start + skipped) {
+            Some(Node::Rule(kind, len))
+                if *kind != Rule::Error
+                    && start + skipped + *len as usize + 1 == self.cst.nodes.len() =>
+            {
+                Some(*kind)
+            }
+            _ => None,
+        };
+        let m = if root.is_some() {
+            // the skipped tokens before the node of the rule become its children
+            self.cst.nodes[start..=start + skipped].rotate_right(1);
+            MarkOpened(first.0)
+        } else {
+            self.cst.open_before(first)
+        };
+        if self.current != Token::EOF {
+            self.error(diags, err![self.span(), "<end of file>"]);
+            let error_tree = self.cst.open();
+            loop {
+                match self.cst.tokens.get(self.pos) {
+                    None => break,
+                    _ => self.cst.advance(),
+                }
+                self.pos += 1;
+            }
+            self.close(error_tree, Rule::Error, diags);
+        }
+        match root {
+            Some(kind) => {
+                self.cst.close(m, kind);
+            }
+            None => {
+                self.close(m, kind, diags);
+            }
+        }
+    }
+    fn r#s(&mut self, diags: &mut Vec<Diagnostic>) {
+        let m = self.cst.open();
+        self.init_skip();
+        expect!(A, "A", self, diags);
+        if self.current != Token::EOF {
+            self.error(diags, err![self.span(), "<end of file>"]);
+            let error_tree = self.cst.open();
+            loop {
+                match self.cst.tokens.get(self.pos) {
+                    None => break,
+                    _ => self.cst.advance(),
+                }
+                self.pos += 1;
+            }
+            self.close(error_tree, Rule::Error, diags);
+        }
+        self.close(m, Rule::S, diags);
+    }
+}
+
+trait PredicatesAndActions {
+    /// Called when a new syntax tree node is created
+    #[allow(clippy::ptr_arg)]
+    fn build(&mut self, _rule: Rule, _node: NodeRef, _diags: &mut Vec<Diagnostic>) {}
+}
@@ -0,0 +1,52 @@
+#![forbid(unsafe_code)]
+
+use proc_macro::{TokenStream, TokenTree};
+
+/// Generates the parser for a grammar file at compile time.
+///
+/// The path is relative to the directory of the `Cargo.toml` file of the crate.
+/// The macro expands to the content of the `generated.rs` file, so it replaces
+/// the `include!` of the generated file in `parser.rs`.
+///
+/// ```ignore
+/// lelwel_macros::grammar!("src/json.llw");
+/// ```
+#[proc_macro]
+pub fn grammar(input: TokenStream) -> TokenStream {
+    let path = match parse_path(input) {
+        Ok(path) => path,
+        Err(msg) => return compile_error(&msg),
+    };
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let path = std::path::Path::new(&dir).join(path);
+    let path = path.to_string_lossy();
+
+    match lelwel::generate(&path, &lelwel::Options::default()) {
+        Ok(generated) => {
+            // the include makes cargo recompile the crate if the grammar changes
+            let tracking = format!("const _: &str = include_str!({path:?});\n");
+            (tracking + &generated)
+                .parse()
+                .unwrap_or_else(|err| compile_error(&format!("{err}")))
+        }
+        Err(msg) => compile_error(msg.trim_end()),
+    }
+}
+
+fn parse_path(input: TokenStream) -> Result<String, String> {
+    let mut it = input.into_iter();
+    match (it.next(), it.next()) {
+        (Some(TokenTree::Literal(lit)), None) => {
+            let lit = lit.to_string();
+            match lit.strip_prefix('"').and_then(|lit| lit.strip_suffix('"')) {
+                Some(path) if !path.contains('\\') => Ok(path.to_string()),
+                _ => Err(format!("expected a plain string literal, found {lit}")),
+            }
+        }
+        _ => Err("expected the path of a grammar file".to_string()),
+    }
+}
+
+fn compile_error(msg: &str) -> TokenStream {
+    format!("compile_error!({msg:?});").parse().unwrap()
+}
@@ -0,0 +1,138 @@
+/// Number of unchanged lines shown around a change.
+const CONTEXT: usize = 3;
+
+enum Edit<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Computes the shortest edit script from `old` to `new` with the algorithm
+/// of Myers.
+fn edits<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Edit<'a>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = n + m;
+    let index = |k: isize| (k + max + 1) as usize;
+    let mut v = vec![0; 2 * max as usize + 3];
+    // furthest reaching x of the diagonals `-d..=d` before step `d`
+    let mut trace = vec![];
+    'search: for d in 0..=max {
+        trace.push(v[index(-d)..=index(d)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut edits = vec![];
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let index = |k: isize| (k + d) as usize;
+        let k = x - y;
+        let (prev_x, prev_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let prev_k = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                k + 1
+            } else {
+                k - 1
+            };
+            (v[index(prev_k)], v[index(prev_k)] - prev_k)
+        };
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit::Equal(old[x as usize]));
+        }
+        if d > 0 {
+            if x == prev_x {
+                edits.push(Edit::Insert(new[prev_y as usize]));
+            } else {
+                edits.push(Edit::Delete(old[prev_x as usize]));
+            }
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    edits.reverse();
+    edits
+}
+
+/// Returns the unified diff between the lines of `old` and `new`, or `None`
+/// if they are equal.
+pub fn unified(old_name: &str, new_name: &str, old: &str, new: &str) -> Option<String> {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+    if old == new {
+        return None;
+    }
+    let edits = edits(&old, &new);
+
+    // line numbers in the old and new text before each edit
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut old_line, mut new_line) = (0, 0);
+    for edit in edits.iter() {
+        positions.push((old_line, new_line));
+        match edit {
+            Edit::Equal(_) => {
+                old_line += 1;
+                new_line += 1;
+            }
+            Edit::Delete(_) => old_line += 1,
+            Edit::Insert(_) => new_line += 1,
+        }
+    }
+    positions.push((old_line, new_line));
+
+    let is_change = |edit: &Edit| !matches!(edit, Edit::Equal(_));
+    let mut diff = format!("--- {old_name}\n+++ {new_name}\n");
+    let mut pos = 0;
+    while let Some(first) = edits[pos..].iter().position(is_change) {
+        let start = (pos + first).saturating_sub(CONTEXT).max(pos);
+        let mut last = pos + first;
+        // merge changes with overlapping context into one hunk
+        while let Some(gap) = edits[last + 1..].iter().position(is_change) {
+            if gap > 2 * CONTEXT {
+                break;
+            }
+            last += gap + 1;
+        }
+        let end = (last + 1 + CONTEXT).min(edits.len());
+
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        let range = |start: usize, len: usize| {
+            if len == 0 {
+                format!("{start},0")
+            } else {
+                format!("{},{len}", start + 1)
+            }
+        };
+        diff += &format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_end - old_start),
+            range(new_start, new_end - new_start)
+        );
+        for edit in edits[start..end].iter() {
+            match edit {
+                Edit::Equal(line) => diff += &format!(" {line}\n"),
+                Edit::Delete(line) => diff += &format!("-{line}\n"),
+                Edit::Insert(line) => diff += &format!("+{line}\n"),
+            }
+        }
+        pos = end;
+    }
+    Some(diff)
+}
@@ -1,4 +1,4 @@
-use crate::frontend::ast::{AstNode, File, Named, Regex, RuleDecl, TokenDecl};
+use crate::frontend::ast::{Action, AstNode, File, Named, Regex, RuleDecl, TokenDecl};
 use crate::{Cst, NodeRef, SemanticData};
 use std::io::Write;
 
@@ -6,21 +6,31 @@ pub struct GraphvizOutput;
 
 impl GraphvizOutput {
     pub fn run(cst: &Cst, sema: &SemanticData) -> std::io::Result<()> {
-        let mut graph_file = std::fs::File::create("parser.gv")?;
-        graph_file.write_all(b"digraph {\n")?;
+        let mut graph = vec![];
+        Self::generate(cst, sema, &mut graph)?;
+        super::write_if_changed("parser.gv", &graph)
+    }
+
+    /// Writes the graph of the grammar rules in the DOT language.
+    pub fn generate(
+        cst: &Cst,
+        sema: &SemanticData,
+        output: &mut impl Write,
+    ) -> std::io::Result<()> {
+        output.write_all(b"digraph {\n")?;
         if let Some(file) = File::cast(cst, NodeRef::ROOT) {
             for rule in file.rule_decls(cst) {
-                Self::visit_rule(cst, sema, rule, &mut graph_file)?;
+                Self::visit_rule(cst, sema, rule, output)?;
             }
         }
-        graph_file.write_all(b"}\n")
+        output.write_all(b"}\n")
     }
 
     fn visit_rule(
         cst: &Cst,
         sema: &SemanticData,
         rule: RuleDecl,
-        output: &mut std::fs::File,
+        output: &mut impl Write,
     ) -> std::io::Result<()> {
         let name = rule.name(cst).unwrap().0;
         output
@@ -73,6 +83,16 @@ impl GraphvizOutput {
         }
     }
 
+    fn action_label(cst: &Cst, action: Action) -> String {
+        if let Some((value, _)) = action.value(cst) {
+            value.to_string()
+        } else if let Some((number, language, _, _)) = action.tagged(cst) {
+            format!("#{number}:{language}")
+        } else {
+            "=>".to_string()
+        }
+    }
+
     fn regex_to_string(cst: &Cst, sema: &SemanticData, regex: Regex) -> String {
         match regex {
             Regex::Name(name) => {
@@ -88,8 +108,9 @@ impl GraphvizOutput {
                 }
             }
             Regex::Symbol(symbol) => Self::escape(symbol.value(cst).unwrap().0),
+            Regex::Wildcard(_) => ".".to_string(),
             Regex::Predicate(pred) => pred.value(cst).unwrap().0.to_string(),
-            Regex::Action(action) => action.value(cst).unwrap().0.to_string(),
+            Regex::Action(action) => Self::action_label(cst, action),
             _ => "".to_string(),
         }
     }
@@ -98,7 +119,7 @@ impl GraphvizOutput {
         cst: &Cst,
         sema: &SemanticData,
         regex: Regex,
-        output: &mut std::fs::File,
+        output: &mut impl Write,
     ) -> std::io::Result<()> {
         match regex {
             Regex::Name(name) => {
@@ -128,6 +149,9 @@ impl GraphvizOutput {
                 )
                 .as_bytes(),
             ),
+            Regex::Wildcard(wildcard) => output.write_all(
+                format!("  \"{}\" [shape=box, label=\".\"];\n", wildcard.syntax().0).as_bytes(),
+            ),
             Regex::Concat(concat) => {
                 let mut s = String::new();
                 for (i, op) in concat.operands(cst).enumerate() {
@@ -214,6 +238,44 @@ impl GraphvizOutput {
                     .as_bytes(),
                 )
             }
+            Regex::Bounded(bounded) => {
+                output.write_all(
+                    format!(
+                        "  \"{}\" [shape=box, label=\"{}\"];\n",
+                        bounded.syntax().0,
+                        bounded.bounds_text(cst)
+                    )
+                    .as_bytes(),
+                )?;
+                let op = Self::skip_paren(cst, bounded.operand(cst).unwrap());
+                Self::visit_regex(cst, sema, op, output)?;
+                output.write_all(
+                    format!(
+                        "  \"{}\" -> \"{}\";\n",
+                        bounded.syntax().0,
+                        Self::skip_name(cst, sema, op)
+                    )
+                    .as_bytes(),
+                )
+            }
+            Regex::Separated(sep) => {
+                output.write_all(
+                    format!("  \"{}\" [shape=box, label=\"%\"];\n", sep.syntax().0).as_bytes(),
+                )?;
+                for op in [sep.operand(cst).unwrap(), sep.separator(cst).unwrap()] {
+                    let op = Self::skip_paren(cst, op);
+                    Self::visit_regex(cst, sema, op, output)?;
+                    output.write_all(
+                        format!(
+                            "  \"{}\" -> \"{}\";\n",
+                            sep.syntax().0,
+                            Self::skip_name(cst, sema, op)
+                        )
+                        .as_bytes(),
+                    )?;
+                }
+                Ok(())
+            }
             Regex::Optional(opt) => {
                 output.write_all(
                     format!("  \"{}\" [shape=box, label=\"[]\"];\n", opt.syntax().0).as_bytes(),
@@ -231,9 +293,9 @@ impl GraphvizOutput {
             }
             Regex::Action(action) => output.write_all(
                 format!(
-                    "  \"{}\" [shape=box, label=\"#{}\"];\n",
+                    "  \"{}\" [shape=box, label=\"{}\"];\n",
                     action.syntax().0,
-                    action.value(cst).unwrap().0
+                    Self::action_label(cst, action)
                 )
                 .as_bytes(),
             ),
@@ -245,6 +307,18 @@ impl GraphvizOutput {
                 )
                 .as_bytes(),
             ),
+            Regex::SyntacticPredicate(pred) => output.write_all(
+                format!(
+                    "  \"{}\" [shape=box, label=\"{}\"];\n",
+                    pred.syntax().0,
+                    if pred.is_negative(cst) {
+                        "!(...)"
+                    } else {
+                        "(...)=>"
+                    }
+                )
+                .as_bytes(),
+            ),
             Regex::Binding(bind) => output.write_all(
                 format!(
                     "  \"{}\" [shape=box, label=\"{}\"];\n",
@@ -0,0 +1,268 @@
+//! Intermediate representation of generated code.
+//!
+//! The generator builds the functions of a parser from the grammar as a tree
+//! of statements and match arms, which a backend lowers to text. Expressions
+//! and simple statements are kept as text of the target language.
+
+/// Function with its doc comment, attributes, signature, and body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    /// Lines of the doc comment without the leading `///`.
+    pub docs: Vec<String>,
+    pub attributes: Vec<String>,
+    pub public: bool,
+    pub name: String,
+    pub params: Vec<String>,
+    pub ret: Option<String>,
+    pub body: Vec<Stmt>,
+}
+
+impl Function {
+    pub fn new(name: impl Into<String>, params: &[&str], body: Vec<Stmt>) -> Self {
+        Self {
+            docs: vec![],
+            attributes: vec![],
+            public: false,
+            name: name.into(),
+            params: params.iter().map(|param| param.to_string()).collect(),
+            ret: None,
+            body,
+        }
+    }
+
+    /// Sets the return type.
+    pub fn returns(mut self, ret: impl Into<String>) -> Self {
+        self.ret = Some(ret.into());
+        self
+    }
+
+    /// Makes the function `pub`.
+    pub fn public(mut self) -> Self {
+        self.public = true;
+        self
+    }
+
+    /// Adds a line to the doc comment.
+    pub fn doc(mut self, line: impl Into<String>) -> Self {
+        self.docs.push(line.into());
+        self
+    }
+
+    /// Adds an attribute like `#[allow(unused_assignments)]`.
+    pub fn attribute(mut self, attribute: impl Into<String>) -> Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    /// Statement or expression without nested blocks, which may span multiple
+    /// lines.
+    Line(String),
+    /// Block with a head like `loop` or `if cond`, or a plain block if the
+    /// head is empty.
+    Block(String, Vec<Stmt>),
+    /// Expression, whose lists are broken into lines if it is too long for
+    /// one line.
+    Expr(Expr),
+    /// Expression statement, which is terminated by a semicolon.
+    Semi(Expr),
+    Match(Match),
+    /// Match expression that is assigned to a pattern with `let`.
+    Let(String, Match),
+    /// Nested function.
+    Function(Function),
+}
+
+impl Stmt {
+    pub fn line(line: impl Into<String>) -> Self {
+        Self::Line(line.into())
+    }
+
+    pub fn block(head: impl Into<String>, body: Vec<Stmt>) -> Self {
+        Self::Block(head.into(), body)
+    }
+}
+
+/// Expression that is either kept as text, or consists of a list of items
+/// that can be put on separate lines.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expr {
+    Text(String),
+    List(List),
+}
+
+/// Items separated by commas between an opening and a closing delimiter, like
+/// the arguments of a call or the elements of an array.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct List {
+    /// Text up to and including the opening delimiter, e.g. `f(`.
+    pub open: String,
+    pub items: Vec<Expr>,
+    pub close: String,
+    /// Whether the last item is followed by a comma if the items are put on
+    /// separate lines.
+    pub trailing_comma: bool,
+}
+
+impl Expr {
+    /// Call of the function or tuple variant `callee`.
+    pub fn call(callee: impl Into<String>, items: Vec<Expr>) -> Self {
+        Self::List(List {
+            open: format!("{}(", callee.into()),
+            items,
+            close: ")".to_string(),
+            trailing_comma: true,
+        })
+    }
+
+    /// Invocation of the macro `name` with brackets. There is no trailing
+    /// comma, as the macros of the parser module may not accept one.
+    pub fn macro_call(name: &str, items: Vec<Expr>) -> Self {
+        Self::List(List {
+            open: format!("{name}!["),
+            items,
+            close: "]".to_string(),
+            trailing_comma: false,
+        })
+    }
+
+    /// Reference to an array, i.e. a slice.
+    pub fn slice(items: Vec<Expr>) -> Self {
+        Self::List(List {
+            open: "&[".to_string(),
+            items,
+            close: "]".to_string(),
+            trailing_comma: true,
+        })
+    }
+
+    /// Struct expression with the `fields` and their values.
+    pub fn structure(name: &str, fields: Vec<(&str, Expr)>) -> Self {
+        Self::List(List {
+            open: format!("{name} {{ "),
+            items: fields
+                .into_iter()
+                .map(|(field, value)| value.prefixed(&format!("{field}: ")))
+                .collect(),
+            close: " }".to_string(),
+            trailing_comma: true,
+        })
+    }
+
+    /// Puts the `prefix` in front of the expression, e.g. the name of a
+    /// field.
+    pub fn prefixed(self, prefix: &str) -> Self {
+        match self {
+            Self::Text(text) => Self::Text(format!("{prefix}{text}")),
+            Self::List(list) => Self::List(List {
+                open: format!("{prefix}{}", list.open),
+                ..list
+            }),
+        }
+    }
+}
+
+impl From<String> for Expr {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&str> for Expr {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+/// Match expression on the `scrutinee`, whose arms are tried in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub scrutinee: String,
+    pub arms: Vec<Arm>,
+}
+
+impl Match {
+    pub fn new(scrutinee: impl Into<String>, arms: Vec<Arm>) -> Self {
+        Self {
+            scrutinee: scrutinee.into(),
+            arms,
+        }
+    }
+}
+
+/// Pattern of a match arm, which matches any of the `alternatives` if the
+/// guard holds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArmPattern {
+    pub alternatives: Vec<String>,
+    pub guard: Option<String>,
+}
+
+impl ArmPattern {
+    pub fn new(alternatives: Vec<String>) -> Self {
+        Self {
+            alternatives,
+            guard: None,
+        }
+    }
+
+    /// Adds the `condition` to the guard, if there is one.
+    pub fn guard(mut self, condition: Option<String>) -> Self {
+        if let Some(condition) = condition {
+            self.guard = Some(match self.guard {
+                Some(guard) => format!("{guard} && {condition}"),
+                None => condition,
+            });
+        }
+        self
+    }
+
+    /// Adds the alternatives of the `other` pattern without a guard.
+    pub fn or(mut self, other: ArmPattern) -> Self {
+        self.alternatives.extend(other.alternatives);
+        self
+    }
+}
+
+impl From<String> for ArmPattern {
+    fn from(pattern: String) -> Self {
+        Self::new(vec![pattern])
+    }
+}
+
+impl From<&str> for ArmPattern {
+    fn from(pattern: &str) -> Self {
+        Self::new(vec![pattern.to_string()])
+    }
+}
+
+/// Match arm with a pattern and a body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arm {
+    pub pattern: ArmPattern,
+    pub body: ArmBody,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArmBody {
+    Expr(String),
+    Block(Vec<Stmt>),
+}
+
+impl Arm {
+    pub fn expr(pattern: impl Into<ArmPattern>, expr: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            body: ArmBody::Expr(expr.into()),
+        }
+    }
+
+    pub fn block(pattern: impl Into<ArmPattern>, body: Vec<Stmt>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            body: ArmBody::Block(body),
+        }
+    }
+}
@@ -1,2 +1,16 @@
+pub mod diff;
 pub mod graphviz;
+pub mod ir;
 pub mod rust;
+
+use std::path::Path;
+
+/// Writes the `contents` to the file at `path`, unless it already has these
+/// contents, so the modification time only changes with the contents and
+/// build systems do not rebuild dependent code.
+pub fn write_if_changed(path: impl AsRef<Path>, contents: &[u8]) -> std::io::Result<()> {
+    if std::fs::read(path.as_ref()).ok().as_deref() != Some(contents) {
+        std::fs::write(path, contents)?;
+    }
+    Ok(())
+}
@@ -1,58 +1,283 @@
+use super::ir::*;
+use super::{diff, write_if_changed};
 use crate::frontend::ast::*;
-use crate::frontend::parser::{Cst, NodeRef};
+use crate::frontend::parser::{Cst, NodeRef, Rule};
 use crate::frontend::sema::*;
-use crate::VERSION;
-use std::collections::{HashMap, HashSet};
+use crate::{Options, VERSION};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::io::Write;
 use std::path::Path;
 
-trait Indent {
-    fn indent(&self, level: usize) -> String;
+/// Maximum width of a line in the generated code, which is the default of
+/// rustfmt.
+const MAX_WIDTH: usize = 100;
+
+/// First line of the generated code, which separates it from the manually
+/// edited part of the parser file in single file mode.
+const GENERATED_MARKER: &str = "// generated by lelwel";
+
+const INDENT: &str = "    ";
+
+/// Lines of the doc comments that precede `node` without the leading `///`.
+fn doc_lines<'a>(cst: &'a Cst, node: NodeRef) -> Vec<&'a str> {
+    cst.doc_comments(node)
+        .into_iter()
+        .map(|comment| comment["///".len()..].trim_end())
+        .collect()
 }
 
-impl Indent for &str {
-    fn indent(&self, level: usize) -> String {
-        let mut result = String::new();
-        for l in self.lines() {
-            if l.is_empty() {
-                continue;
+/// Lowers generated code to Rust text with consistent indentation.
+trait Lower {
+    fn lower(&self, level: usize, code: &mut String);
+
+    fn to_rust(&self, level: usize) -> String {
+        let mut code = String::new();
+        self.lower(level, &mut code);
+        code
+    }
+}
+
+/// Appends each line of `text` at the indentation `level`.
+fn push_indented(level: usize, text: &str, code: &mut String) {
+    for line in text.lines().filter(|line| !line.is_empty()) {
+        code.push_str(&INDENT.repeat(level));
+        code.push_str(line);
+        code.push('\n');
+    }
+}
+
+/// Whether the `line` fits within [`MAX_WIDTH`] at the indentation `level`.
+fn fits(level: usize, line: &str) -> bool {
+    level * INDENT.len() + line.len() <= MAX_WIDTH
+}
+
+impl Expr {
+    /// Returns the expression on a single line.
+    fn flat(&self) -> String {
+        match self {
+            Expr::Text(text) => text.clone(),
+            Expr::List(list) => {
+                let items: Vec<_> = list.items.iter().map(Expr::flat).collect();
+                format!("{}{}{}", list.open, items.join(", "), list.close)
+            }
+        }
+    }
+
+    /// Appends the expression followed by the `suffix` at the indentation
+    /// `level`. A list that does not fit on the line gets one item per line,
+    /// and the items are broken the same way.
+    fn lower_with(&self, level: usize, suffix: &str, code: &mut String) {
+        let line = format!("{}{suffix}", self.flat());
+        match self {
+            Expr::List(list) if !fits(level, &line) && !list.items.is_empty() => {
+                push_indented(level, list.open.trim_end(), code);
+                for (i, item) in list.items.iter().enumerate() {
+                    let comma = i + 1 < list.items.len() || list.trailing_comma;
+                    item.lower_with(level + 1, if comma { "," } else { "" }, code);
+                }
+                push_indented(level, &format!("{}{suffix}", list.close.trim_start()), code);
+            }
+            _ => push_indented(level, &line, code),
+        }
+    }
+}
+
+impl ArmPattern {
+    /// Appends the pattern followed by the `arrow` of the arm at the
+    /// indentation `level`. A pattern that does not fit on the line gets one
+    /// alternative per line, and the guard on its own line.
+    fn lower_with(&self, level: usize, arrow: &str, code: &mut String) {
+        let alternatives = self.alternatives.join(" | ");
+        let guard = self.guard.as_ref().map(|guard| format!("if {guard}"));
+        let line = match &guard {
+            Some(guard) => format!("{alternatives} {guard}{arrow}"),
+            None => format!("{alternatives}{arrow}"),
+        };
+        if fits(level, &line) {
+            return push_indented(level, &line, code);
+        }
+        let mut lines = if guard.is_some() && fits(level, &alternatives) {
+            vec![alternatives]
+        } else {
+            self.alternatives
+                .iter()
+                .enumerate()
+                .map(|(i, alternative)| match i {
+                    0 => alternative.clone(),
+                    _ => format!("| {alternative}"),
+                })
+                .collect()
+        };
+        match guard {
+            // the opening brace of a block follows the guard on its own line
+            Some(guard) if arrow == " => {" => {
+                lines
+                    .iter()
+                    .for_each(|line| push_indented(level, line, code));
+                push_indented(level + 1, &format!("{guard} =>"), code);
+                push_indented(level, "{", code);
+            }
+            Some(guard) => {
+                lines
+                    .iter()
+                    .for_each(|line| push_indented(level, line, code));
+                push_indented(level + 1, &format!("{guard}{arrow}"), code);
+            }
+            None => {
+                lines.last_mut().unwrap().push_str(arrow);
+                lines
+                    .iter()
+                    .for_each(|line| push_indented(level, line, code));
+            }
+        }
+    }
+}
+
+impl Lower for Function {
+    fn lower(&self, level: usize, code: &mut String) {
+        for line in self.docs.iter() {
+            push_indented(level, &format!("///{line}"), code);
+        }
+        for attribute in self.attributes.iter() {
+            push_indented(level, attribute, code);
+        }
+        let ret = self
+            .ret
+            .as_ref()
+            .map_or(String::new(), |ret| format!(" -> {ret}"));
+        let open = if self.body.is_empty() { " {}" } else { " {" };
+        let vis = if self.public { "pub " } else { "" };
+        let signature = format!(
+            "{vis}fn {}({}){ret}{open}",
+            self.name,
+            self.params.join(", ")
+        );
+        if fits(level, &signature) {
+            push_indented(level, &signature, code);
+        } else {
+            push_indented(level, &format!("{vis}fn {}(", self.name), code);
+            for param in self.params.iter() {
+                push_indented(level + 1, &format!("{param},"), code);
             }
-            result.push_str(&"    ".repeat(level));
-            result.push_str(l);
-            result.push('\n');
+            push_indented(level, &format!("){ret}{open}"), code);
+        }
+        if !self.body.is_empty() {
+            self.body.lower(level + 1, code);
+            push_indented(level, "}", code);
         }
-        if !self.ends_with('\n') {
-            result.pop();
+    }
+}
+
+impl Lower for [Stmt] {
+    fn lower(&self, level: usize, code: &mut String) {
+        for stmt in self.iter() {
+            stmt.lower(level, code);
+        }
+    }
+}
+
+impl Lower for Stmt {
+    fn lower(&self, level: usize, code: &mut String) {
+        match self {
+            Stmt::Line(line) => push_indented(level, line, code),
+            Stmt::Expr(expr) => expr.lower_with(level, "", code),
+            Stmt::Semi(expr) => expr.lower_with(level, ";", code),
+            Stmt::Block(head, body) if body.is_empty() => {
+                push_indented(level, format!("{head} {{}}").trim_start(), code);
+            }
+            Stmt::Block(head, body) => {
+                push_indented(level, format!("{head} {{").trim_start(), code);
+                body.lower(level + 1, code);
+                push_indented(level, "}", code);
+            }
+            Stmt::Match(m) => {
+                push_indented(level, &format!("match {} {{", m.scrutinee), code);
+                m.arms.lower(level + 1, code);
+                push_indented(level, "}", code);
+            }
+            Stmt::Let(pattern, m) => {
+                push_indented(
+                    level,
+                    &format!("let {pattern} = match {} {{", m.scrutinee),
+                    code,
+                );
+                m.arms.lower(level + 1, code);
+                push_indented(level, "};", code);
+            }
+            Stmt::Function(function) => function.lower(level, code),
         }
-        result
     }
 }
 
-impl Indent for String {
-    fn indent(&self, level: usize) -> String {
-        self.as_str().indent(level)
+impl Lower for [Arm] {
+    fn lower(&self, level: usize, code: &mut String) {
+        for arm in self.iter() {
+            match &arm.body {
+                ArmBody::Expr(expr) => {
+                    arm.pattern.lower_with(level, &format!(" => {expr},"), code);
+                }
+                ArmBody::Block(body) if body.is_empty() => {
+                    arm.pattern.lower_with(level, " => {}", code);
+                }
+                ArmBody::Block(body) => {
+                    arm.pattern.lower_with(level, " => {", code);
+                    body.lower(level + 1, code);
+                    push_indented(level, "}", code);
+                }
+            }
+        }
     }
 }
 
 trait Generator {
-    fn pattern(&self, level: usize) -> String;
-    fn error(&self, level: usize, token_symbols: &HashMap<&str, &str>) -> String;
+    fn pattern(&self) -> ArmPattern;
+    fn error(&self, token_symbols: &HashMap<&str, String>) -> Vec<Expr>;
 }
 
 impl<'a> Generator for std::collections::BTreeSet<TokenName<'a>> {
-    fn pattern(&self, level: usize) -> String {
-        let symbols: Vec<_> = self.iter().map(|s| format!("Token::{}", s.0)).collect();
-        symbols.join(&format!("\n{}| ", "    ".repeat(level)))
+    fn pattern(&self) -> ArmPattern {
+        ArmPattern::new(self.iter().map(|s| format!("Token::{}", s.0)).collect())
     }
-    fn error(&self, level: usize, token_symbols: &HashMap<&str, &str>) -> String {
+    fn error(&self, token_symbols: &HashMap<&str, String>) -> Vec<Expr> {
         if !self.is_empty() {
-            let symbols: Vec<_> = self
-                .iter()
-                .map(|s| format!("\"{}\"", token_symbols[s.0]))
-                .collect();
-            symbols.join(&format!(",\n{}", "    ".repeat(level)))
+            self.iter()
+                .map(|s| Expr::Text(format!("\"{}\"", token_symbols[s.0])))
+                .collect()
         } else {
-            "EOF".to_string()
+            vec![Expr::from("EOF")]
+        }
+    }
+}
+
+/// Visibility of the items that the generated parser exports from its module,
+/// like `Parser`, `Cst`, and `Token`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Visibility {
+    #[default]
+    Public,
+    Crate,
+    Private,
+}
+
+impl Visibility {
+    fn keyword(self) -> &'static str {
+        match self {
+            Self::Public => "pub ",
+            Self::Crate => "pub(crate) ",
+            Self::Private => "",
+        }
+    }
+}
+
+impl std::str::FromStr for Visibility {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pub" => Ok(Self::Public),
+            "crate" => Ok(Self::Crate),
+            "private" => Ok(Self::Private),
+            _ => Err(format!("unknown visibility `{s}`")),
         }
     }
 }
@@ -60,55 +285,251 @@ impl<'a> Generator for std::collections::BTreeSet<TokenName<'a>> {
 pub struct RustOutput {}
 
 impl RustOutput {
-    pub fn run(cst: &Cst, sema: &SemanticData, input: &Path, output: &Path) -> std::io::Result<()> {
-        let file = File::cast(cst, NodeRef::ROOT).unwrap();
-        let mut generated_file = std::fs::File::create(output.join("generated.rs"))?;
-        generated_file.write_all(format!("// generated by lelwel {}\n\n", VERSION).as_bytes())?;
-        Self::output_generated(cst, sema, file, &mut generated_file)?;
+    pub fn run(
+        cst: &Cst,
+        sema: &SemanticData,
+        input: &Path,
+        output: &Path,
+        options: &Options,
+    ) -> std::io::Result<()> {
+        let mut generated = vec![];
+        Self::generate(cst, sema, options, &mut generated)?;
+
+        let parser_path = input.parent().unwrap().join(options.parser_file());
+        if options.single_file {
+            let parser = Self::single_file(cst, sema, &parser_path, &generated, options)?;
+            if let Some(dir) = parser_path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            return write_if_changed(parser_path, &parser);
+        }
+
+        write_if_changed(output.join(options.generated_file()), &generated)?;
 
-        let parser_path = input.parent().unwrap().join("parser.rs");
         if !parser_path.exists() {
-            Self::output_parser(cst, file, sema, &parser_path)?;
+            let mut parser = vec![];
+            Self::generate_parser(cst, sema, options, &mut parser)?;
+            if let Some(dir) = parser_path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(parser_path, parser)?;
         }
 
         Ok(())
     }
 
-    fn output_parser(
+    /// Writes a unified diff of the files that [`RustOutput::run`] would write
+    /// to `writer` instead of writing them.
+    ///
+    /// Returns whether the files are up to date. The existing parser file is
+    /// only compared in single file mode, as it is edited manually otherwise.
+    pub fn diff(
         cst: &Cst,
-        file: File,
         sema: &SemanticData,
-        path: &Path,
+        input: &Path,
+        output: &Path,
+        options: &Options,
+        writer: &mut dyn Write,
+    ) -> std::io::Result<bool> {
+        let mut generated = vec![];
+        Self::generate(cst, sema, options, &mut generated)?;
+        let parser_path = input.parent().unwrap().join(options.parser_file());
+        if options.single_file {
+            let parser = Self::single_file(cst, sema, &parser_path, &generated, options)?;
+            let parser = String::from_utf8(parser).unwrap();
+            let name = parser_path.display().to_string();
+            let old = std::fs::read_to_string(&parser_path).ok();
+            let diff = diff::unified(
+                if old.is_some() { &name } else { "/dev/null" },
+                &name,
+                old.as_deref().unwrap_or_default(),
+                &parser,
+            );
+            if let Some(diff) = &diff {
+                writer.write_all(diff.as_bytes())?;
+            }
+            return Ok(diff.is_none());
+        }
+        let generated = String::from_utf8(generated).unwrap();
+        let generated_path = output.join(options.generated_file());
+        let name = generated_path.display().to_string();
+        let old = std::fs::read_to_string(&generated_path).ok();
+        let diff = diff::unified(
+            if old.is_some() { &name } else { "/dev/null" },
+            &name,
+            old.as_deref().unwrap_or_default(),
+            &generated,
+        );
+        let mut up_to_date = true;
+        if let Some(diff) = diff {
+            writer.write_all(diff.as_bytes())?;
+            up_to_date = false;
+        }
+
+        if !parser_path.exists() {
+            let mut parser = vec![];
+            Self::generate_parser(cst, sema, options, &mut parser)?;
+            let parser = String::from_utf8(parser).unwrap();
+            let name = parser_path.display().to_string();
+            if let Some(diff) = diff::unified("/dev/null", &name, "", &parser) {
+                writer.write_all(diff.as_bytes())?;
+            }
+            up_to_date = false;
+        }
+
+        Ok(up_to_date)
+    }
+
+    /// Writes the content of the `generated.rs` file.
+    pub fn generate(
+        cst: &Cst,
+        sema: &SemanticData,
+        options: &Options,
+        output: &mut impl Write,
     ) -> std::io::Result<()> {
-        let mut parser_file = std::fs::File::create(path)?;
+        let file = File::cast(cst, NodeRef::ROOT).unwrap();
+        output.write_all(format!("{GENERATED_MARKER} {VERSION}\n\n").as_bytes())?;
+        Self::output_generated(cst, sema, file, output, options)
+    }
+
+    /// Returns the content of the parser file in single file mode, which is
+    /// the manually edited part of the existing file followed by the
+    /// `generated` code. A new parser file starts with the initial content.
+    fn single_file(
+        cst: &Cst,
+        sema: &SemanticData,
+        parser_path: &Path,
+        generated: &[u8],
+        options: &Options,
+    ) -> std::io::Result<Vec<u8>> {
+        let mut parser = match std::fs::read_to_string(parser_path) {
+            Ok(parser) => match parser.find(&format!("\n{GENERATED_MARKER}")) {
+                Some(end) => parser.as_bytes()[..end + 1].to_vec(),
+                // a parser file of the default mode includes the generated file
+                None => {
+                    let mut parser = parser.replace(&Self::include(options.generated_file()), "");
+                    parser.push('\n');
+                    parser.into_bytes()
+                }
+            },
+            Err(_) => {
+                let mut parser = vec![];
+                Self::generate_parser(cst, sema, options, &mut parser)?;
+                parser.push(b'\n');
+                parser
+            }
+        };
+        parser.extend_from_slice(generated);
+        Ok(parser)
+    }
+
+    /// Returns the attribute that derives the serde `traits` if the `serde`
+    /// feature of the crate is enabled, or nothing without the serde option.
+    fn serde_derive(options: &Options, traits: &str) -> String {
+        if options.serde {
+            format!("#[cfg_attr(feature = \"serde\", derive({traits}))]\n")
+        } else {
+            String::new()
+        }
+    }
+
+    /// Returns the line of the parser file that includes the `generated_file`.
+    fn include(generated_file: &str) -> String {
+        format!("include!(concat!(env!(\"OUT_DIR\"), \"/{generated_file}\"));\n\n")
+    }
 
+    /// Writes the initial content of the `parser.rs` file, which is edited
+    /// manually afterwards and includes the generated file, unless the
+    /// generated code is appended to it in single file mode.
+    pub fn generate_parser(
+        cst: &Cst,
+        sema: &SemanticData,
+        options: &Options,
+        output: &mut impl Write,
+    ) -> std::io::Result<()> {
+        let file = File::cast(cst, NodeRef::ROOT).unwrap();
         let mut token_enumerators = "{\n    EOF,\n".to_string();
-        for token in file.token_decls(cst) {
-            if let Some((symbol, _)) = token.symbol(cst) {
-                if !(symbol.is_empty()
-                    || symbol.starts_with("'<") && symbol.ends_with(">'") && symbol.len() > 4)
-                {
-                    token_enumerators += "    #[token(\"";
-                    token_enumerators += &symbol[1..symbol.len() - 1];
-                    token_enumerators += "\")]\n";
+        let mut is_complete = true;
+        let token_lists = cst
+            .children(file.syntax())
+            .filter_map(|child| cst.get_rule(child, Rule::TokenList));
+        for list in token_lists {
+            // tokens without a doc comment are documented by the one of the list
+            let list_docs = doc_lines(cst, list);
+            for token in cst.children(list).filter_map(|c| TokenDecl::cast(cst, c)) {
+                let mut docs = doc_lines(cst, token.syntax());
+                if docs.is_empty() {
+                    docs.clone_from(&list_docs);
+                }
+                for line in docs {
+                    token_enumerators += "    ///";
+                    token_enumerators += line;
+                    token_enumerators += "\n";
+                }
+                let mut has_pattern = false;
+                if let Some((symbol, _)) = token.symbol(cst) {
+                    if !(symbol.is_empty()
+                        || symbol.starts_with("'<") && symbol.ends_with(">'") && symbol.len() > 4)
+                    {
+                        token_enumerators += "    #[token(\"";
+                        token_enumerators += &symbol[1..symbol.len() - 1];
+                        token_enumerators += if token.ignores_case(cst) {
+                            "\", ignore(ascii_case))]\n"
+                        } else {
+                            "\")]\n"
+                        };
+                        has_pattern = true;
+                    }
+                }
+                if let Some((regex, _)) = token.regex(cst) {
+                    let regex = regex[1..regex.len() - 1].replace("\\/", "/");
+                    token_enumerators += &format!("    #[regex({regex:?})]\n");
+                    has_pattern = true;
                 }
+                is_complete &= has_pattern;
+                let (name, _) = token.name(cst).unwrap();
+                token_enumerators += "    ";
+                token_enumerators += name;
+                token_enumerators += ",\n";
             }
-            let (name, _) = token.name(cst).unwrap();
-            token_enumerators += "    ";
-            token_enumerators += name;
-            token_enumerators += ",\n";
         }
 
-        let template =
-            include_str!("../skeleton/parser.rs").replace("{\n    EOF,\n", &token_enumerators);
+        // derives of the grammar are added to the ones the parser requires
+        let derive = "#[derive(Logos, Debug, PartialEq, Eq, Hash, Copy, Clone)]\n";
+        let mut derives: Vec<_> = ["Logos", "Debug", "PartialEq", "Eq", "Hash", "Copy", "Clone"]
+            .into_iter()
+            .collect();
+        for name in sema.token_derives.iter() {
+            if !derives.contains(name) {
+                derives.push(name);
+            }
+        }
+        let mut token_attributes = format!("#[derive({})]\n", derives.join(", "));
+        token_attributes += &Self::serde_derive(options, "serde::Serialize, serde::Deserialize");
+        for attribute in sema.token_attributes.iter() {
+            token_attributes += &format!("#[{attribute}]\n");
+        }
 
-        parser_file.write_all(template.as_bytes())?;
+        let include = if options.single_file {
+            String::new()
+        } else {
+            Self::include(options.generated_file())
+        };
+        let mut template = include_str!("../skeleton/parser.rs")
+            .replace("{\n    EOF,\n", &token_enumerators)
+            .replace(derive, &token_attributes)
+            .replace(&Self::include("generated.rs"), &include)
+            .replace("\npub ", &format!("\n{}", options.visibility.keyword()));
+        if is_complete {
+            template = template.replace("// TODO: implement lexer\n", "");
+        }
 
-        Self::output_predicates_and_actions(&mut parser_file, sema, false)
+        output.write_all(template.as_bytes())?;
+        Self::output_predicates_and_actions(output, sema, false)
     }
 
     fn output_predicates_and_actions(
-        output: &mut std::fs::File,
+        output: &mut impl Write,
         sema: &SemanticData,
         is_trait: bool,
     ) -> std::io::Result<()> {
@@ -120,6 +541,16 @@ impl RustOutput {
         } else {
             b"impl<'a> PredicatesAndActions for Parser<'a> {\n"
         })?;
+        if !sema.inserted.is_empty() {
+            output.write_all(if is_trait {
+                b"    /// Called before reporting a missing insertable token\
+                \n    fn insert_token(&self, token: Token) -> bool;\n"
+            } else {
+                b"    fn insert_token(&self, _token: Token) -> bool {\
+                \n        todo!()\
+                \n    }\n"
+            })?;
+        }
         let mut predicates = HashSet::new();
         for (rule, num) in sema.predicates.values() {
             if predicates.contains(&(rule, num)) {
@@ -138,7 +569,8 @@ impl RustOutput {
                 .as_bytes(),
             )?;
         }
-        let mut actions = HashSet::new();
+        // actions with a rust block are implemented in the generated parser
+        let mut actions = Self::tagged_rust_actions(sema);
         for (rule, num) in sema.actions.values() {
             if actions.contains(&(rule, num)) {
                 continue;
@@ -159,201 +591,179 @@ impl RustOutput {
         output.write_all(b"}\n")
     }
 
-    fn output_node_kind_decl(
-        output: &mut std::fs::File,
-        has_rule_binding: bool,
-        name: &str,
-        level: usize,
-        is_decl: bool,
-    ) -> std::io::Result<()> {
+    fn node_kind_decl(body: &mut Vec<Stmt>, has_rule_binding: bool, name: &str, is_decl: bool) {
         if has_rule_binding {
-            output.write_all(
-                format!(
-                    "{}node_kind = Rule::{};\n",
-                    if is_decl { "let mut " } else { "" },
-                    Self::snake_to_pascal_case(name),
-                )
-                .indent(level)
-                .as_bytes(),
-            )?;
+            body.push(Stmt::line(format!(
+                "{}node_kind = Rule::{};",
+                if is_decl { "let mut " } else { "" },
+                snake_to_pascal_case(name),
+            )));
         }
-        Ok(())
     }
 
-    fn output_cst_close(
-        output: &mut std::fs::File,
-        has_rule_binding: bool,
-        name: &str,
-        level: usize,
-        assign_lhs: bool,
-    ) -> std::io::Result<()> {
+    fn cst_close(has_rule_binding: bool, name: &str, assign_lhs: bool) -> Stmt {
         let lhs = if assign_lhs { "lhs = " } else { "" };
         if has_rule_binding {
-            output.write_all(
-                format!("{lhs}self.close(m, node_kind, diags);\n")
-                    .indent(level)
-                    .as_bytes(),
-            )
+            Stmt::line(format!("{lhs}self.close(m, node_kind, diags);"))
         } else {
-            output.write_all(
-                format!(
-                    "{lhs}self.close(m, Rule::{}, diags);\n",
-                    Self::snake_to_pascal_case(name),
-                )
-                .indent(level)
-                .as_bytes(),
-            )
+            Stmt::line(format!(
+                "{lhs}self.close(m, Rule::{}, diags);",
+                snake_to_pascal_case(name),
+            ))
+        }
+    }
+
+    /// Returns the default arm of a match, which reports a syntax error with
+    /// the `expected` tokens by calling the error `method`.
+    fn error_arm(method: &str, expected: Vec<Expr>) -> Arm {
+        let mut items = vec![Expr::from("self.span()")];
+        items.extend(expected);
+        Arm::block(
+            "_",
+            vec![Stmt::Semi(Expr::call(
+                format!("self.{method}"),
+                vec![Expr::from("diags"), Expr::macro_call("err", items)],
+            ))],
+        )
+    }
+
+    /// Returns the operands of a recursive branch after the recursive rule
+    /// reference, without the predicates.
+    fn branch_tail(cst: &Cst, branch: Regex) -> Vec<Regex> {
+        match branch {
+            Regex::Concat(concat) => concat
+                .operands(cst)
+                .filter(|op| !matches!(op, Regex::Predicate(_) | Regex::SyntacticPredicate(_)))
+                .skip(1)
+                .collect(),
+            _ => unreachable!(),
         }
     }
 
     #[allow(clippy::too_many_arguments)]
-    fn output_normal_rule(
+    fn normal_rule(
         cst: &Cst,
         sema: &SemanticData,
-        output: &mut std::fs::File,
-        token_symbols: &HashMap<&str, &str>,
+        body: &mut Vec<Stmt>,
+        token_symbols: &HashMap<&str, String>,
         has_rule_binding: bool,
         name: &str,
         regex: Regex,
         is_start: bool,
-    ) -> std::io::Result<()> {
-        output.write_all(b"        let m = self.cst.open();\n")?;
-        Self::output_node_kind_decl(output, has_rule_binding, name, 2, true)?;
+    ) {
+        body.push(Stmt::line("let m = self.cst.open();"));
+        Self::node_kind_decl(body, has_rule_binding, name, true);
         if is_start {
-            output.write_all(b"        self.init_skip();\n")?;
+            body.push(Stmt::line("self.init_skip();"));
         }
-        Self::output_regex(cst, sema, regex, output, 2, token_symbols, false, name)?;
+        Self::regex(cst, sema, regex, body, token_symbols, false, name);
         if is_start {
-            output.write_all(
-                b"        if self.current != Token::EOF {\
-                \n            self.error(diags, err![self.span(), \"<end of file>\"]);\
-                \n            let error_tree = self.cst.open();\
-                \n            loop {\
-                \n                match self.cst.tokens.get(self.pos) {\
-                \n                    None => break,\
-                \n                    _ => self.cst.advance(),\
-                \n                }\
-                \n                self.pos += 1;\
-                \n            }\
-                \n            self.close(error_tree, Rule::Error, diags);\
-                \n        }\n",
-            )?;
+            body.push(Stmt::block(
+                "if self.current != Token::EOF",
+                vec![
+                    Stmt::line("self.error(diags, err![self.span(), \"<end of file>\"]);"),
+                    Stmt::line("let error_tree = self.cst.open();"),
+                    Stmt::block(
+                        "loop",
+                        vec![
+                            Stmt::Match(Match::new(
+                                "self.cst.tokens.get(self.pos)",
+                                vec![
+                                    Arm::expr("None", "break"),
+                                    Arm::expr("_", "self.cst.advance()"),
+                                ],
+                            )),
+                            Stmt::line("self.pos += 1;"),
+                        ],
+                    ),
+                    Stmt::line("self.close(error_tree, Rule::Error, diags);"),
+                ],
+            ));
         }
-        Self::output_cst_close(output, has_rule_binding, name, 2, false)
+        body.push(Self::cst_close(has_rule_binding, name, false));
     }
 
     #[allow(clippy::too_many_arguments)]
-    fn output_left_recursive_rule(
+    fn left_recursive_rule(
         cst: &Cst,
         sema: &SemanticData,
-        output: &mut std::fs::File,
-        token_symbols: &HashMap<&str, &str>,
+        body: &mut Vec<Stmt>,
+        token_symbols: &HashMap<&str, String>,
         has_rule_binding: bool,
         name: &str,
         regex: Regex,
         branches: &[Regex],
-    ) -> std::io::Result<()> {
+    ) {
         let ops = if let Regex::Alternation(alt) = regex {
             alt.operands(cst)
         } else {
             unreachable!();
         };
-        Self::output_node_kind_decl(output, has_rule_binding, name, 2, true)?;
-        output.write_all(
-            b"        let mut lhs = self.cst.mark();\
-            \n        match self.current {\n",
-        )?;
+        Self::node_kind_decl(body, has_rule_binding, name, true);
+        body.push(Stmt::line("let mut lhs = self.cst.mark();"));
+        let mut arms = vec![];
         for op in ops {
             if branches.contains(&op) {
                 continue;
             }
-            output.write_all(
-                format!(
-                    "{}{} => {{\n",
-                    sema.predict_sets[&op.syntax()].pattern(0),
-                    Self::get_predicate(cst, name, op)
-                )
-                .indent(3)
-                .as_bytes(),
-            )?;
             let is_forwarding = if let Regex::Name(name) = op {
                 sema.decl_bindings
                     .get(&name.syntax())
-                    .map_or(false, |n| RuleDecl::cast(cst, *n).is_some())
+                    .is_some_and(|n| RuleDecl::cast(cst, *n).is_some())
             } else {
                 false
             };
+            let mut arm = vec![];
             if is_forwarding {
-                Self::output_regex(cst, sema, op, output, 5, token_symbols, false, name)?;
+                Self::regex(cst, sema, op, &mut arm, token_symbols, false, name);
             } else {
-                output.write_all(b"                    let m = self.cst.open();\n")?;
-                Self::output_regex(cst, sema, op, output, 5, token_symbols, false, name)?;
-                Self::output_cst_close(output, has_rule_binding, name, 5, false)?;
+                arm.push(Stmt::line("let m = self.cst.open();"));
+                Self::regex(cst, sema, op, &mut arm, token_symbols, false, name);
+                arm.push(Self::cst_close(has_rule_binding, name, false));
             }
-            output.write_all("}\n".indent(3).as_bytes())?;
+            arms.push(Arm::block(
+                sema.predict_sets[&op.syntax()]
+                    .pattern()
+                    .guard(Self::get_predicate(cst, sema, name, op)),
+                arm,
+            ));
         }
-        output.write_all(
-            format!(
-                "    _ => {{\
-               \n        self.error(diags, err![self.span(), {}]);\
-               \n    }}\
-               \n}}\n",
-                sema.predict_sets[&regex.syntax()].error(5, token_symbols)
-            )
-            .indent(2)
-            .as_bytes(),
-        )?;
-        output.write_all(b"        loop {\n")?;
-        Self::output_node_kind_decl(output, has_rule_binding, name, 3, false)?;
-        output.write_all(b"            match self.current {\n")?;
+        arms.push(Self::error_arm(
+            "error",
+            sema.predict_sets[&regex.syntax()].error(token_symbols),
+        ));
+        body.push(Stmt::Match(Match::new("self.current", arms)));
+
+        let mut arms = vec![];
         for branch in branches {
-            let ops = if let Regex::Concat(concat) = branch {
-                concat.operands(cst)
-            } else {
-                unreachable!()
-            };
-            for (i, op) in ops
-                .filter(|op| !matches!(op, Regex::Predicate(_)))
-                .skip(1)
-                .enumerate()
-            {
-                if i == 0 {
-                    output.write_all(
-                        format!(
-                            "{}{} => {{\n",
-                            sema.predict_sets[&op.syntax()].pattern(0),
-                            Self::get_predicate(cst, name, *branch)
-                        )
-                        .indent(4)
-                        .as_bytes(),
-                    )?;
-                    output
-                        .write_all("let m = self.cst.open_before(lhs);\n".indent(5).as_bytes())?;
-                }
-                Self::output_regex(cst, sema, op, output, 5, token_symbols, false, name)?;
+            let ops = Self::branch_tail(cst, *branch);
+            let mut arm = vec![Stmt::line("let m = self.cst.open_before(lhs);")];
+            for op in ops.iter() {
+                Self::regex(cst, sema, *op, &mut arm, token_symbols, false, name);
             }
-            Self::output_cst_close(output, has_rule_binding, name, 5, true)?;
-            output.write_all(b"                }\n")?;
+            arm.push(Self::cst_close(has_rule_binding, name, true));
+            arms.push(Arm::block(
+                sema.predict_sets[&ops[0].syntax()]
+                    .pattern()
+                    .guard(Self::get_predicate(cst, sema, name, *branch)),
+                arm,
+            ));
         }
-        output.write_all(
-            "    _ => {\
-           \n        break;\
-           \n    }\
-           \n}\n"
-                .indent(3)
-                .as_bytes(),
-        )?;
-        output.write_all("}\n".indent(2).as_bytes())
+        arms.push(Arm::block("_", vec![Stmt::line("break;")]));
+        let mut loop_body = vec![];
+        Self::node_kind_decl(&mut loop_body, has_rule_binding, name, false);
+        loop_body.push(Stmt::Match(Match::new("self.current", arms)));
+        body.push(Stmt::block("loop", loop_body));
     }
 
-    fn output_operator_precedence_rule(
+    fn operator_precedence_rule(
         cst: &Cst,
         sema: &SemanticData,
-        output: &mut std::fs::File,
+        body: &mut Vec<Stmt>,
         name: &str,
         regex: Regex,
         branches: &[Regex],
-    ) -> std::io::Result<()> {
+    ) {
         let exit = if let Regex::Alternation(alt) = regex {
             alt.operands(cst).find(|op| !branches.contains(op)).unwrap()
         } else {
@@ -364,629 +774,1047 @@ impl RustOutput {
         } else {
             unreachable!()
         };
-        output.write_all(b"        let lhs = self.cst.mark();\n")?;
-        output.write_all(
-            b"        fn rec(\
-            \n            parser: &mut Parser,\
-            \n            diags: &mut Vec<Diagnostic>,\
-            \n            min_prec: usize,\
-            \n            mut lhs: MarkClosed,\
-            \n        ) {\
-            \n            loop {\
-            \n                let prec = match parser.current {\n",
-        )?;
-        for (i, op) in branches.iter().rev().enumerate() {
-            let mut ops = if let Regex::Concat(concat) = op {
-                concat.operands(cst)
-            } else {
-                unreachable!()
-            };
-            let op = ops.nth(1).unwrap();
-            output.write_all(
-                format!("{} => {},\n", sema.predict_sets[&op.syntax()].pattern(0), i)
-                    .indent(5)
-                    .as_bytes(),
-            )?;
-        }
-        output.write_all(
-            format!(
-                "                      _ => return,\
-               \n                }};\
-               \n                if prec < min_prec {{\
-               \n                    return;\
-               \n                }}\
-               \n                parser.advance(false);\
-               \n                let m = parser.cst.open_before(lhs);\
-               \n                let rhs = parser.cst.mark();\
-               \n                parser.{exit_name}(diags);\
-               \n                loop {{\
-               \n                    let (next_prec, left_assoc) = match parser.current {{\n"
-            )
-            .as_bytes(),
-        )?;
+        let mut precedences = vec![];
+        let mut associativities = vec![];
         for (i, op) in branches.iter().rev().enumerate() {
             let mut ops = if let Regex::Concat(concat) = op {
                 concat.operands(cst)
             } else {
                 unreachable!()
             };
-            let op = ops.nth(1).unwrap();
-            for operator in sema.predict_sets[&op.syntax()].iter() {
-                output.write_all(
-                    format!(
-                        "Token::{} => ({i}, {}),\n",
-                        operator.0,
-                        !sema.right_associative.contains(operator.0),
-                    )
-                    .indent(6)
-                    .as_bytes(),
-                )?;
+            let operators = &sema.predict_sets[&ops.nth(1).unwrap().syntax()];
+            precedences.push(Arm::expr(operators.pattern(), i.to_string()));
+            for operator in operators.iter() {
+                associativities.push(Arm::expr(
+                    format!("Token::{}", operator.0),
+                    format!("({i}, {})", !sema.right_associative.contains(operator.0)),
+                ));
             }
         }
-        output.write_all(
-            format!(
-                "                        _ => break,\
-               \n                    }};\
-               \n                    if !(prec < next_prec || (!left_assoc && prec == next_prec)) {{\
-               \n                        break;\
-               \n                    }}\
-               \n                    rec(\
-               \n                        parser,\
-               \n                        diags,\
-               \n                        prec + if next_prec > prec {{ 1 }} else {{ 0 }},\
-               \n                        rhs,\
-               \n                    );\
-               \n                }}\
-               \n                lhs = parser.close(m, Rule::{}, diags);\
-               \n            }}\
-               \n        }}\
-               \n        self.{exit_name}(diags);\
-               \n        rec(self, diags, 0, lhs);\n",
-               Self::snake_to_pascal_case(name)
-        ).as_bytes())
+        precedences.push(Arm::expr("_", "return"));
+        associativities.push(Arm::expr("_", "break"));
+        let rec = Function::new(
+            "rec",
+            &[
+                "parser: &mut Parser",
+                "diags: &mut Vec<Diagnostic>",
+                "min_prec: usize",
+                "mut lhs: MarkClosed",
+            ],
+            vec![Stmt::block(
+                "loop",
+                vec![
+                    Stmt::Let(
+                        "prec".to_string(),
+                        Match::new("parser.current", precedences),
+                    ),
+                    Stmt::block("if prec < min_prec", vec![Stmt::line("return;")]),
+                    Stmt::line("parser.advance(false);"),
+                    Stmt::line("let m = parser.cst.open_before(lhs);"),
+                    Stmt::line("let rhs = parser.cst.mark();"),
+                    Stmt::line(format!("parser.{exit_name}(diags);")),
+                    Stmt::block(
+                        "loop",
+                        vec![
+                            Stmt::Let(
+                                "(next_prec, left_assoc)".to_string(),
+                                Match::new("parser.current", associativities),
+                            ),
+                            Stmt::block(
+                                "if !(prec < next_prec || (!left_assoc && prec == next_prec))",
+                                vec![Stmt::line("break;")],
+                            ),
+                            Stmt::line(
+                                "rec(\
+                                 \n    parser,\
+                                 \n    diags,\
+                                 \n    prec + if next_prec > prec { 1 } else { 0 },\
+                                 \n    rhs,\
+                                 \n);",
+                            ),
+                        ],
+                    ),
+                    Stmt::line(format!(
+                        "lhs = parser.close(m, Rule::{}, diags);",
+                        snake_to_pascal_case(name)
+                    )),
+                ],
+            )],
+        );
+        body.push(Stmt::line("let lhs = self.cst.mark();"));
+        body.push(Stmt::Function(rec));
+        body.push(Stmt::line(format!("self.{exit_name}(diags);")));
+        body.push(Stmt::line("rec(self, diags, 0, lhs);"));
     }
 
     #[allow(clippy::too_many_arguments)]
-    fn output_right_recursice_forwarding_rule(
+    fn right_recursive_forwarding_rule(
         cst: &Cst,
         sema: &SemanticData,
-        output: &mut std::fs::File,
-        token_symbols: &HashMap<&str, &str>,
+        body: &mut Vec<Stmt>,
+        token_symbols: &HashMap<&str, String>,
         has_rule_binding: bool,
         name: &str,
         regex: Regex,
         branches: &[Regex],
-    ) -> std::io::Result<()> {
-        output.write_all(b"        let lhs = self.cst.mark();\n")?;
-        Self::output_node_kind_decl(output, has_rule_binding, name, 2, true)?;
+    ) {
+        body.push(Stmt::line("let lhs = self.cst.mark();"));
+        Self::node_kind_decl(body, has_rule_binding, name, true);
         if let Regex::Alternation(alt) = regex {
-            output.write_all("match self.current {\n".indent(2).as_bytes())?;
+            let mut arms = vec![];
             for op in alt.operands(cst) {
                 let is_exit = branches.contains(&op);
-                output.write_all(
-                    format!(
-                        "{}{} => {{\n",
-                        sema.predict_sets[&op.syntax()].pattern(0),
-                        Self::get_predicate(cst, name, op)
-                    )
-                    .indent(3)
-                    .as_bytes(),
-                )?;
+                let mut arm = vec![];
                 if !is_exit {
-                    output.write_all(b"                let m = self.cst.open_before(lhs);\n")?;
+                    arm.push(Stmt::line("let m = self.cst.open_before(lhs);"));
                 }
-                Self::output_regex(cst, sema, op, output, 4, token_symbols, false, name)?;
+                Self::regex(cst, sema, op, &mut arm, token_symbols, false, name);
                 if !is_exit {
-                    Self::output_cst_close(output, has_rule_binding, name, 4, false)?;
+                    arm.push(Self::cst_close(has_rule_binding, name, false));
                 }
-                output.write_all("}\n".indent(3).as_bytes())?;
+                arms.push(Arm::block(
+                    sema.predict_sets[&op.syntax()]
+                        .pattern()
+                        .guard(Self::get_predicate(cst, sema, name, op)),
+                    arm,
+                ));
             }
-            output.write_all(
-                format!(
-                    "    _ => {{\
-                   \n        self.error(diags, err![self.span(), {}]);\
-                   \n    }}\
-                   \n}}\n",
-                    sema.predict_sets[&regex.syntax()].error(5, token_symbols),
-                )
-                .indent(2)
-                .as_bytes(),
-            )?;
+            arms.push(Self::error_arm(
+                "error",
+                sema.predict_sets[&regex.syntax()].error(token_symbols),
+            ));
+            body.push(Stmt::Match(Match::new("self.current", arms)));
         }
-        Ok(())
     }
 
-    fn output_conditional_forwarding_rule(
+    fn conditional_forwarding_rule(
         cst: &Cst,
         sema: &SemanticData,
-        output: &mut std::fs::File,
-        token_symbols: &HashMap<&str, &str>,
+        body: &mut Vec<Stmt>,
+        token_symbols: &HashMap<&str, String>,
         has_rule_binding: bool,
         name: &str,
         regex: Regex,
-    ) -> std::io::Result<()> {
+    ) {
         let (head, tail) = if let Regex::Concat(concat) = regex {
             let mut ops = concat.operands(cst);
             (ops.next().unwrap(), ops)
         } else {
             unreachable!();
         };
-        output.write_all(
-            b"        let lhs = self.cst.mark();\
-            \n        let mut m = None;\n",
-        )?;
-        Self::output_node_kind_decl(output, has_rule_binding, name, 2, true)?;
-        Self::output_regex(cst, sema, head, output, 2, token_symbols, false, name)?;
+        body.push(Stmt::line("let lhs = self.cst.mark();"));
+        body.push(Stmt::line("let mut m = None;"));
+        Self::node_kind_decl(body, has_rule_binding, name, true);
+        Self::regex(cst, sema, head, body, token_symbols, false, name);
         for op in tail {
-            Self::output_regex(cst, sema, op, output, 2, token_symbols, true, name)?;
+            Self::regex(cst, sema, op, body, token_symbols, true, name);
         }
-        output.write_all(b"        if let Some(m) = m {\n")?;
-        Self::output_cst_close(output, has_rule_binding, name, 3, false)?;
-        output.write_all(b"        }\n")
+        body.push(Stmt::block(
+            "if let Some(m) = m",
+            vec![Self::cst_close(has_rule_binding, name, false)],
+        ));
     }
 
-    fn output_maybe_empty_rule(
+    fn maybe_empty_rule(
         cst: &Cst,
         sema: &SemanticData,
-        output: &mut std::fs::File,
-        token_symbols: &HashMap<&str, &str>,
+        body: &mut Vec<Stmt>,
+        token_symbols: &HashMap<&str, String>,
         has_rule_binding: bool,
         name: &str,
         regex: Regex,
-    ) -> std::io::Result<()> {
-        output.write_all(
-            b"        let lhs = self.cst.mark();\
-            \n        let mut m = None;\n",
-        )?;
-        Self::output_node_kind_decl(output, has_rule_binding, name, 2, true)?;
-        Self::output_regex(cst, sema, regex, output, 2, token_symbols, true, name)?;
-        output.write_all(b"        if let Some(m) = m {\n")?;
-        Self::output_cst_close(output, has_rule_binding, name, 3, false)?;
-        output.write_all(b"        }\n")
+    ) {
+        body.push(Stmt::line("let lhs = self.cst.mark();"));
+        body.push(Stmt::line("let mut m = None;"));
+        Self::node_kind_decl(body, has_rule_binding, name, true);
+        Self::regex(cst, sema, regex, body, token_symbols, true, name);
+        body.push(Stmt::block(
+            "if let Some(m) = m",
+            vec![Self::cst_close(has_rule_binding, name, false)],
+        ));
     }
 
-    fn output_rule(
+    /// Returns the parsing function of a rule, unless the rule is unused.
+    fn rule(
         cst: &Cst,
         sema: &SemanticData,
         rule: RuleDecl,
-        output: &mut std::fs::File,
-        token_symbols: &HashMap<&str, &str>,
-    ) -> std::io::Result<()> {
+        token_symbols: &HashMap<&str, String>,
+        trace: bool,
+    ) -> Option<Function> {
         if !sema.used.contains(&rule.syntax()) {
             // don't generate code for unused rules
-            return Ok(());
+            return None;
+        }
+        if sema.inlined.contains(&rule) && sema.start != Some(rule) && !sema.entries.contains(&rule)
+        {
+            // inlined rules are expanded at their references
+            return None;
         }
         let name = rule.name(cst).unwrap().0;
         let pattern = sema.patterns.get(&rule);
         let is_start = sema.start.unwrap() == rule;
         let has_rule_binding = sema.has_rule_binding.contains(&rule);
 
-        output.write_all(
-            format!(
-                "    {}fn r#{name}(&mut self, diags: &mut Vec<Diagnostic>) {{\n",
-                if has_rule_binding {
-                    "#[allow(unused_assignments)]\n    "
-                } else {
-                    ""
-                }
-            )
-            .as_bytes(),
-        )?;
+        let mut body = vec![];
+        if is_start && trace {
+            body.push(Stmt::line("DECISION_TRACE.with_borrow_mut(Vec::clear);"));
+        }
         if let Some(regex) = rule.regex(cst) {
+            let body = &mut body;
             match pattern {
-                None => Self::output_normal_rule(
+                None => Self::normal_rule(
                     cst,
                     sema,
-                    output,
+                    body,
                     token_symbols,
                     has_rule_binding,
                     name,
                     regex,
                     is_start,
-                )?,
-                Some(Pattern::LeftRecursive(branches)) => Self::output_left_recursive_rule(
+                ),
+                Some(Pattern::LeftRecursive(branches)) => Self::left_recursive_rule(
                     cst,
                     sema,
-                    output,
+                    body,
                     token_symbols,
                     has_rule_binding,
                     name,
                     regex,
                     branches,
-                )?,
+                ),
                 Some(Pattern::OperatorPrecedence(branches)) => {
-                    Self::output_operator_precedence_rule(cst, sema, output, name, regex, branches)?
+                    Self::operator_precedence_rule(cst, sema, body, name, regex, branches)
                 }
                 Some(Pattern::UnconditionalForwarding) => {
-                    Self::output_regex(cst, sema, regex, output, 2, token_symbols, false, name)?;
-                }
-                Some(Pattern::ConditionalForwarding) => {
-                    Self::output_conditional_forwarding_rule(
-                        cst,
-                        sema,
-                        output,
-                        token_symbols,
-                        has_rule_binding,
-                        name,
-                        regex,
-                    )?;
-                }
-                Some(Pattern::MaybeEmpty) => {
-                    Self::output_maybe_empty_rule(
-                        cst,
-                        sema,
-                        output,
-                        token_symbols,
-                        has_rule_binding,
-                        name,
-                        regex,
-                    )?;
+                    Self::regex(cst, sema, regex, body, token_symbols, false, name);
                 }
+                Some(Pattern::ConditionalForwarding) => Self::conditional_forwarding_rule(
+                    cst,
+                    sema,
+                    body,
+                    token_symbols,
+                    has_rule_binding,
+                    name,
+                    regex,
+                ),
+                Some(Pattern::MaybeEmpty) => Self::maybe_empty_rule(
+                    cst,
+                    sema,
+                    body,
+                    token_symbols,
+                    has_rule_binding,
+                    name,
+                    regex,
+                ),
                 Some(Pattern::RightRecursiveForwarding(branches)) => {
-                    Self::output_right_recursice_forwarding_rule(
+                    Self::right_recursive_forwarding_rule(
                         cst,
                         sema,
-                        output,
+                        body,
                         token_symbols,
                         has_rule_binding,
                         name,
                         regex,
                         branches,
-                    )?;
+                    )
                 }
             }
         }
-        output.write_all(b"    }\n")?;
-        Ok(())
+        let function = doc_lines(cst, rule.syntax()).into_iter().fold(
+            Function::new(
+                format!("r#{name}"),
+                &["&mut self", "diags: &mut Vec<Diagnostic>"],
+                body,
+            ),
+            Function::doc,
+        );
+        Some(if has_rule_binding {
+            function.attribute("#[allow(unused_assignments)]")
+        } else {
+            function
+        })
+    }
+
+    fn tagged_rust_actions<'a>(sema: &'a SemanticData) -> HashSet<(&'a &'a str, &'a &'a str)> {
+        sema.tagged_actions
+            .values()
+            .filter(|(_, _, language)| *language == "rust")
+            .map(|(rule, num, _)| (rule, num))
+            .collect()
+    }
+
+    /// Returns the text of a token in syntax error messages, which is its
+    /// description in angle brackets if there is one.
+    fn token_error(cst: &Cst, token: TokenDecl) -> String {
+        let name = token.name(cst).unwrap().0;
+        match (token.description(cst), token.symbol(cst)) {
+            (Some((desc, _)), _) => format!("<{}>", &desc[1..desc.len() - 1]),
+            (None, Some((sym, _))) => sym[1..sym.len() - 1].to_string(),
+            (None, None) => name.to_string(),
+        }
+    }
+
+    fn expect(cst: &Cst, sema: &SemanticData, token: TokenDecl) -> String {
+        let name = token.name(cst).unwrap().0;
+        let error = Self::token_error(cst, token);
+        if sema.inserted.contains(&token) {
+            let sym = token
+                .symbol(cst)
+                .map_or(name, |(sym, _)| &sym[1..sym.len() - 1]);
+            format!("expect_insertable!({name}, \"{sym}\", \"{error}\", self, diags);")
+        } else {
+            format!("expect!({name}, \"{error}\", self, diags);")
+        }
+    }
+
+    /// Checks if the regex is a wildcard or an alternation of tokens, which
+    /// are matched by a single match arm.
+    fn is_token_set(cst: &Cst, sema: &SemanticData, regex: Regex) -> bool {
+        match regex {
+            Regex::Wildcard(_) => true,
+            Regex::Alternation(alt) => alt.operands(cst).all(|op| match op {
+                Regex::Name(_) | Regex::Symbol(_) => sema
+                    .decl_bindings
+                    .get(&op.syntax())
+                    .and_then(|decl| TokenDecl::cast(cst, *decl))
+                    .is_some_and(|token| !sema.inserted.contains(&token)),
+                _ => false,
+            }),
+            _ => false,
+        }
+    }
+
+    /// Returns the expected tokens of the error for a token set.
+    fn token_set_error(
+        sema: &SemanticData,
+        regex: Regex,
+        token_symbols: &HashMap<&str, String>,
+    ) -> Vec<Expr> {
+        if let Regex::Wildcard(_) = regex {
+            vec![Expr::from("\"<any token>\"")]
+        } else {
+            sema.predict_sets[&regex.syntax()].error(token_symbols)
+        }
+    }
+
+    fn get_predicate(
+        cst: &Cst,
+        sema: &SemanticData,
+        rule_name: &str,
+        regex: Regex,
+    ) -> Option<String> {
+        Self::predicate_method(cst, sema, rule_name, regex).map(|method| format!("self.{method}()"))
     }
 
-    fn get_predicate(cst: &Cst, rule_name: &str, regex: Regex) -> String {
+    /// Returns the guard that checks the lookahead of an alternative, which is
+    /// not predicted by a single token. A speculative guard checks the tokens
+    /// at the offset of a recognizer.
+    fn get_lookahead(sema: &SemanticData, regex: Regex, speculative: bool) -> Option<String> {
+        let sequences = sema.lookahead_sets.get(&regex.syntax())?;
+        let tuple = (0..sema.lookahead)
+            .map(|i| match (i, speculative) {
+                (0, false) => "self.current".to_string(),
+                (0, true) => "self.peek(*i)".to_string(),
+                (i, false) => format!("self.peek({i})"),
+                (i, true) => format!("self.peek(*i + {i})"),
+            })
+            .collect::<Vec<_>>();
+        let patterns = sequences
+            .iter()
+            .map(|seq| {
+                let mut pattern = seq
+                    .iter()
+                    .map(|token| format!("Token::{}", token.0))
+                    .collect::<Vec<_>>();
+                if seq.len() < sema.lookahead {
+                    pattern.push("..".to_string());
+                }
+                format!("({})", pattern.join(", "))
+            })
+            .collect::<Vec<_>>();
+        Some(format!(
+            "matches!(({}), {})",
+            tuple.join(", "),
+            patterns.join(" | ")
+        ))
+    }
+
+    /// Returns the method of the predicate that guards the regex.
+    fn predicate_method(
+        cst: &Cst,
+        sema: &SemanticData,
+        rule_name: &str,
+        regex: Regex,
+    ) -> Option<String> {
         match regex {
             Regex::Concat(concat) => match concat.operands(cst).next().unwrap() {
-                Regex::Predicate(pred) => {
-                    format!(
-                        " if self.predicate_{rule_name}_{}()",
-                        &pred.value(cst).unwrap().0[1..]
-                    )
+                Regex::Predicate(pred) => Some(format!(
+                    "predicate_{rule_name}_{}",
+                    &pred.value(cst).unwrap().0[1..]
+                )),
+                Regex::SyntacticPredicate(pred) => {
+                    let index = sema.syntactic_predicates[&pred.syntax()].1;
+                    Some(format!("syntactic_predicate_{rule_name}_{index}"))
                 }
-                _ => "".to_string(),
+                _ => None,
             },
-            Regex::Paren(paren) => Self::get_predicate(cst, rule_name, paren.inner(cst).unwrap()),
-            _ => "".to_string(),
+            Regex::Paren(paren) => {
+                Self::predicate_method(cst, sema, rule_name, paren.inner(cst).unwrap())
+            }
+            _ => None,
         }
     }
 
+    /// Returns the statement that opens the node of a conditionally forwarding
+    /// rule before the left hand side, unless it is already open.
+    fn open_before() -> Stmt {
+        Stmt::block(
+            "if m.is_none()",
+            vec![Stmt::line("m = Some(self.cst.open_before(lhs));")],
+        )
+    }
+
+    /// Returns the loop of a repetition with the `head` (e.g. `loop`), which
+    /// runs the `body` while the `pattern` matches, and stops at the follow and
+    /// recovery sets of the `regex`.
+    fn repetition(
+        sema: &SemanticData,
+        regex: Regex,
+        head: &str,
+        pattern: ArmPattern,
+        body: Vec<Stmt>,
+        expected: Vec<Expr>,
+    ) -> Stmt {
+        let recovery = &sema.recovery_sets[&regex.syntax()];
+        Stmt::block(
+            head,
+            vec![Stmt::Match(Match::new(
+                "self.current",
+                vec![
+                    Arm::block(pattern, body),
+                    Arm::expr(
+                        sema.follow_sets[&regex.syntax()]
+                            .pattern()
+                            .or(recovery.pattern()),
+                        "break",
+                    ),
+                    Self::error_arm("advance_with_error", expected),
+                ],
+            ))],
+        )
+    }
+
     #[allow(clippy::too_many_arguments)]
-    fn output_regex(
+    fn regex(
         cst: &Cst,
         sema: &SemanticData,
         regex: Regex,
-        output: &mut std::fs::File,
-        level: usize,
-        token_symbols: &HashMap<&str, &str>,
+        body: &mut Vec<Stmt>,
+        token_symbols: &HashMap<&str, String>,
         open_before: bool,
         rule_name: &str,
-    ) -> std::io::Result<()> {
+    ) {
         match regex {
             Regex::Name(name) => {
                 let decl = sema.decl_bindings[&name.syntax()];
                 if let Some(rule) = RuleDecl::cast(cst, decl) {
                     let name = rule.name(cst).unwrap().0;
-                    output
-                        .write_all(format!("self.r#{name}(diags);\n").indent(level).as_bytes())?;
+                    if sema.inlined.contains(&rule) {
+                        // the block scopes the node mark of the inlined rule
+                        let mut inlined = vec![];
+                        Self::normal_rule(
+                            cst,
+                            sema,
+                            &mut inlined,
+                            token_symbols,
+                            sema.has_rule_binding.contains(&rule),
+                            name,
+                            rule.regex(cst).unwrap(),
+                            false,
+                        );
+                        body.push(Stmt::block("", inlined));
+                    } else {
+                        body.push(Stmt::line(format!("self.r#{name}(diags);")));
+                    }
                 } else if let Some(token) = TokenDecl::cast(cst, decl) {
-                    let name = token.name(cst).unwrap().0;
-                    let sym = token
-                        .symbol(cst)
-                        .map_or(name, |(sym, _)| &sym[1..sym.len() - 1]);
-                    output.write_all(
-                        format!("expect!({name}, \"{sym}\", self, diags);\n",)
-                            .indent(level)
-                            .as_bytes(),
-                    )?;
+                    body.push(Stmt::line(Self::expect(cst, sema, token)));
                 }
             }
             Regex::Symbol(sym) => {
                 let decl = sema.decl_bindings[&sym.syntax()];
                 if let Some(token) = TokenDecl::cast(cst, decl) {
-                    let name = token.name(cst).unwrap().0;
-                    let sym = token.symbol(cst).unwrap().0;
-                    let sym = &sym[1..sym.len() - 1];
-                    output.write_all(
-                        format!("expect!({name}, \"{sym}\", self, diags);\n",)
-                            .indent(level)
-                            .as_bytes(),
-                    )?;
+                    body.push(Stmt::line(Self::expect(cst, sema, token)));
                 }
             }
             Regex::Concat(concat) => {
                 for op in concat.operands(cst) {
-                    Self::output_regex(
-                        cst,
-                        sema,
-                        op,
-                        output,
-                        level,
-                        token_symbols,
-                        false,
-                        rule_name,
-                    )?;
+                    Self::regex(cst, sema, op, body, token_symbols, false, rule_name);
                 }
             }
+            Regex::Alternation(_) | Regex::Wildcard(_) if Self::is_token_set(cst, sema, regex) => {
+                body.push(Stmt::Match(Match::new(
+                    "self.current",
+                    vec![
+                        Arm::expr(
+                            sema.first_sets[&regex.syntax()].pattern(),
+                            "self.advance(false)",
+                        ),
+                        Self::error_arm("error", Self::token_set_error(sema, regex, token_symbols)),
+                    ],
+                )));
+            }
             Regex::Alternation(alt) => {
-                output.write_all("match self.current {\n".indent(level).as_bytes())?;
+                let mut arms = vec![];
                 for op in alt.operands(cst) {
-                    output.write_all(
-                        format!(
-                            "{}{} => {{\n",
-                            sema.predict_sets[&op.syntax()].pattern(0),
-                            Self::get_predicate(cst, rule_name, op)
-                        )
-                        .indent(level + 1)
-                        .as_bytes(),
-                    )?;
-                    Self::output_regex(
-                        cst,
-                        sema,
-                        op,
-                        output,
-                        level + 2,
-                        token_symbols,
-                        false,
-                        rule_name,
-                    )?;
-                    output.write_all("}\n".indent(level + 1).as_bytes())?;
+                    let mut arm = vec![];
+                    Self::regex(cst, sema, op, &mut arm, token_symbols, false, rule_name);
+                    arms.push(Arm::block(
+                        sema.predict_sets[&op.syntax()]
+                            .pattern()
+                            .guard(Self::get_predicate(cst, sema, rule_name, op))
+                            .guard(Self::get_lookahead(sema, op, false)),
+                        arm,
+                    ));
                 }
-                output.write_all(
-                    format!(
-                        "    _ => {{\
-                       \n        self.error(diags, err![self.span(), {}]);\
-                       \n    }}\
-                       \n}}\n",
-                        sema.predict_sets[&regex.syntax()].error(5, token_symbols),
-                    )
-                    .indent(level)
-                    .as_bytes(),
-                )?;
+                arms.push(Self::error_arm(
+                    "error",
+                    sema.predict_sets[&regex.syntax()].error(token_symbols),
+                ));
+                body.push(Stmt::Match(Match::new("self.current", arms)));
             }
-            Regex::Star(star) => {
-                let op = star.operand(cst).unwrap();
-                output.write_all(
-                    format!(
-                        "loop {{\
-                       \n    match self.current {{\
-                       \n        {}{} => {{\n",
-                        sema.first_sets[&op.syntax()].pattern(2),
-                        Self::get_predicate(cst, rule_name, op)
-                    )
-                    .indent(level)
-                    .as_bytes(),
-                )?;
+            Regex::Star(_) | Regex::Plus(_) => {
+                let op = match regex {
+                    Regex::Star(star) => star.operand(cst).unwrap(),
+                    Regex::Plus(plus) => plus.operand(cst).unwrap(),
+                    _ => unreachable!(),
+                };
+                if let Regex::Plus(_) = regex {
+                    Self::regex(cst, sema, op, body, token_symbols, false, rule_name);
+                }
+                let mut arm = vec![];
                 if open_before {
-                    output.write_all(
-                        "if m.is_none() {\
-                       \n    m = Some(self.cst.open_before(lhs));\
-                       \n}\n"
-                            .indent(5)
-                            .as_bytes(),
-                    )?;
+                    arm.push(Self::open_before());
                 }
-                Self::output_regex(
-                    cst,
+                Self::regex(cst, sema, op, &mut arm, token_symbols, false, rule_name);
+                body.push(Self::repetition(
                     sema,
-                    op,
-                    output,
-                    level + 3,
-                    token_symbols,
-                    false,
-                    rule_name,
-                )?;
-                let recovery = &sema.recovery_sets[&regex.syntax()];
-                output.write_all(
-                    format!(
-                        "        }}\
-                       \n        {}{}{} => break,\
-                       \n        _ => {{\
-                       \n            self.advance_with_error(diags, err![self.span(), {}]);\
-                       \n        }}\
-                       \n    }}\
-                       \n}}\n",
-                        sema.follow_sets[&regex.syntax()].pattern(2),
-                        if recovery.is_empty() {
-                            ""
-                        } else {
-                            "\n        | "
-                        },
-                        recovery.pattern(2),
-                        sema.predict_sets[&regex.syntax()].error(5, token_symbols),
-                    )
-                    .indent(level)
-                    .as_bytes(),
-                )?;
+                    regex,
+                    "loop",
+                    sema.first_sets[&op.syntax()]
+                        .pattern()
+                        .guard(Self::get_predicate(cst, sema, rule_name, op)),
+                    arm,
+                    sema.predict_sets[&regex.syntax()].error(token_symbols),
+                ));
             }
-            Regex::Plus(plus) => {
-                let op = plus.operand(cst).unwrap();
-                Self::output_regex(
+            Regex::Bounded(bounded) => {
+                let op = bounded.operand(cst).unwrap();
+                let (min, max) = bounded.bounds(cst).unwrap();
+                let mut required = vec![];
+                Self::regex(
                     cst,
                     sema,
                     op,
-                    output,
-                    level,
+                    &mut required,
                     token_symbols,
                     false,
                     rule_name,
-                )?;
-                output.write_all(
-                    format!(
-                        "loop {{\
-                       \n    match self.current {{\
-                       \n        {}{} => {{\n",
-                        sema.first_sets[&op.syntax()].pattern(2),
-                        Self::get_predicate(cst, rule_name, op)
-                    )
-                    .indent(level)
-                    .as_bytes(),
-                )?;
+                );
+                match min {
+                    0 => {}
+                    1 => body.append(&mut required),
+                    _ => body.push(Stmt::block(format!("for _ in 0..{min}"), required)),
+                }
+                if max == Some(min) {
+                    return;
+                }
+                let mut arm = vec![];
+                let head = if let Some(max) = max {
+                    // the count is incremented first, so nested repetitions can shadow it
+                    body.push(Stmt::line("let mut count = 0;"));
+                    arm.push(Stmt::line("count += 1;"));
+                    format!("while count < {}", max - min)
+                } else {
+                    "loop".to_string()
+                };
                 if open_before {
-                    output.write_all(
-                        "if m.is_none() {\
-                       \n    m = Some(self.cst.open_before(lhs));\
-                       \n}\n"
-                            .indent(5)
-                            .as_bytes(),
-                    )?;
+                    arm.push(Self::open_before());
                 }
-                Self::output_regex(
-                    cst,
+                Self::regex(cst, sema, op, &mut arm, token_symbols, false, rule_name);
+                body.push(Self::repetition(
                     sema,
-                    op,
-                    output,
-                    level + 3,
-                    token_symbols,
-                    false,
-                    rule_name,
-                )?;
-                let recovery = &sema.recovery_sets[&regex.syntax()];
-                output.write_all(
-                    format!(
-                        "        }}\
-                       \n        {}{}{} => break,\
-                       \n        _ => {{\
-                       \n            self.advance_with_error(diags, err![self.span(), {}]);\
-                       \n        }}\
-                       \n    }}\
-                       \n}}\n",
-                        sema.follow_sets[&regex.syntax()].pattern(2),
-                        if recovery.is_empty() {
-                            ""
-                        } else {
-                            "\n        | "
-                        },
-                        recovery.pattern(2),
-                        sema.predict_sets[&regex.syntax()].error(5, token_symbols),
-                    )
-                    .indent(level)
-                    .as_bytes(),
-                )?;
+                    regex,
+                    &head,
+                    sema.first_sets[&op.syntax()]
+                        .pattern()
+                        .guard(Self::get_predicate(cst, sema, rule_name, op)),
+                    arm,
+                    sema.predict_sets[&regex.syntax()].error(token_symbols),
+                ));
+            }
+            Regex::Separated(sep) => {
+                let op = sep.operand(cst).unwrap();
+                let separator = sep.separator(cst).unwrap();
+                Self::regex(cst, sema, op, body, token_symbols, false, rule_name);
+                let mut arm = vec![];
+                if open_before {
+                    arm.push(Self::open_before());
+                }
+                for op in [separator, op] {
+                    Self::regex(cst, sema, op, &mut arm, token_symbols, false, rule_name);
+                }
+                body.push(Self::repetition(
+                    sema,
+                    regex,
+                    "loop",
+                    sema.first_sets[&separator.syntax()]
+                        .pattern()
+                        .guard(Self::get_predicate(cst, sema, rule_name, separator)),
+                    arm,
+                    sema.predict_sets[&separator.syntax()].error(token_symbols),
+                ));
             }
             Regex::Optional(opt) => {
                 let op = opt.operand(cst).unwrap();
-                output.write_all(
-                    format!(
-                        "match self.current {{\
-                       \n    {}{} => {{\n",
-                        sema.first_sets[&op.syntax()].pattern(1),
-                        Self::get_predicate(cst, rule_name, op)
-                    )
-                    .indent(level)
-                    .as_bytes(),
-                )?;
+                let mut arm = vec![];
                 if open_before {
-                    output.write_all(
-                        "if m.is_none() {\
-                       \n    m = Some(self.cst.open_before(lhs));\
-                       \n}\n"
-                            .indent(4)
-                            .as_bytes(),
-                    )?;
+                    arm.push(Self::open_before());
                 }
-                Self::output_regex(
-                    cst,
-                    sema,
-                    op,
-                    output,
-                    level + 2,
-                    token_symbols,
-                    false,
-                    rule_name,
-                )?;
-                output.write_all(
-                    format!(
-                        "    }}\
-                       \n    {} => {{}}\
-                       \n    _ => {{\
-                       \n        self.error(diags, err![self.span(), {}]);\
-                       \n    }}\
-                       \n}}\n",
-                        sema.follow_sets[&regex.syntax()].pattern(1),
-                        sema.predict_sets[&regex.syntax()].error(5, token_symbols),
-                    )
-                    .indent(level)
-                    .as_bytes(),
-                )?;
+                Self::regex(cst, sema, op, &mut arm, token_symbols, false, rule_name);
+                body.push(Stmt::Match(Match::new(
+                    "self.current",
+                    vec![
+                        Arm::block(
+                            sema.first_sets[&op.syntax()]
+                                .pattern()
+                                .guard(Self::get_predicate(cst, sema, rule_name, op)),
+                            arm,
+                        ),
+                        Arm::block(sema.follow_sets[&regex.syntax()].pattern(), vec![]),
+                        Self::error_arm(
+                            "error",
+                            sema.predict_sets[&regex.syntax()].error(token_symbols),
+                        ),
+                    ],
+                )));
             }
             Regex::Paren(paren) => {
-                Self::output_regex(
-                    cst,
-                    sema,
-                    paren.inner(cst).unwrap(),
-                    output,
-                    level,
-                    token_symbols,
-                    false,
-                    rule_name,
-                )?;
+                let inner = paren.inner(cst).unwrap();
+                Self::regex(cst, sema, inner, body, token_symbols, false, rule_name);
             }
             Regex::Action(action) => {
-                output.write_all(
+                let call = if let Some((_, index)) = sema.inline_actions.get(&action.syntax()) {
+                    format!("self.inline_action_{rule_name}_{index}(diags);")
+                } else if let Some((_, num, language)) = sema.tagged_actions.get(&action.syntax()) {
+                    if *language != "rust" {
+                        // blocks for other backends
+                        return;
+                    }
+                    format!("self.action_{rule_name}_{num}(diags);")
+                } else {
                     format!(
-                        "self.action_{rule_name}_{}(diags);\n",
+                        "self.action_{rule_name}_{}(diags);",
                         &action.value(cst).unwrap().0[1..]
                     )
-                    .indent(level)
-                    .as_bytes(),
-                )?;
+                };
+                body.push(Stmt::line(call));
             }
             Regex::Binding(bind) => {
                 let name = &bind.value(cst).unwrap().0[1..];
                 if !name.is_empty() {
-                    output.write_all(
-                        format!("node_kind = Rule::{};\n", Self::snake_to_pascal_case(name))
-                            .indent(level)
-                            .as_bytes(),
-                    )?;
+                    body.push(Stmt::line(format!(
+                        "node_kind = Rule::{};",
+                        snake_to_pascal_case(name)
+                    )));
                 }
             }
             Regex::OpenNode(open) => {
                 let number = open.number(cst).unwrap();
-                output.write_all(
-                    format!("let m{number} = self.cst.mark();\n")
-                        .indent(level)
-                        .as_bytes(),
-                )?;
+                body.push(Stmt::line(format!("let m{number} = self.cst.mark();")));
             }
             Regex::CloseNode(close) => {
                 let number = close.number(cst).unwrap();
                 let node_name = close.node_name(cst).unwrap();
-                output.write_all(
-                    format!(
-                        "let open_node = self.cst.open_before(m{number});\
-                        \nself.close(open_node, Rule::{}, diags);\n",
-                        Self::snake_to_pascal_case(node_name)
-                    )
-                    .indent(level)
-                    .as_bytes(),
-                )?;
+                body.push(Stmt::line(format!(
+                    "let open_node = self.cst.open_before(m{number});"
+                )));
+                body.push(Stmt::line(format!(
+                    "self.close(open_node, Rule::{}, diags);",
+                    snake_to_pascal_case(node_name)
+                )));
             }
             _ => {}
         }
-        Ok(())
     }
 
-    fn snake_to_pascal_case(name: &str) -> String {
-        let mut res = String::new();
-        let mut upper = true;
-        for c in name.chars() {
-            if upper {
-                res.push(c.to_ascii_uppercase());
-                upper = false;
-            } else if c == '_' {
-                upper = true;
-            } else {
-                res.push(c);
+    /// Returns the syntactic predicates, and the recognizers of the rules
+    /// that are referenced by them.
+    ///
+    /// A recognizer matches the input at a lookahead offset without consuming
+    /// tokens or building the syntax tree, so a failed speculation needs no
+    /// rollback. Semantic predicates and actions are not evaluated during the
+    /// speculation.
+    fn syntactic_predicates(
+        cst: &Cst,
+        sema: &SemanticData,
+        file: File,
+        trace: bool,
+    ) -> Vec<Function> {
+        let used_rules = file
+            .rule_decls(cst)
+            .filter(|rule| sema.used.contains(&rule.syntax()))
+            .filter_map(|rule| rule.name(cst).map(|(name, _)| name))
+            .collect::<HashSet<_>>();
+        let mut functions = vec![];
+        let mut pending = vec![];
+        for (node, (rule_name, index)) in sema.syntactic_predicates.iter() {
+            if !used_rules.contains(rule_name) {
+                continue;
+            }
+            let pred = SyntacticPredicate::cast(cst, *node).unwrap();
+            let inner = pred.inner(cst).unwrap();
+            let recognize = format!(
+                "{}self.recognize_syntactic_predicate_{rule_name}_{index}(0)",
+                if pred.is_negative(cst) { "!" } else { "" }
+            );
+            functions.push(
+                Function::new(
+                    format!("syntactic_predicate_{rule_name}_{index}"),
+                    &["&self"],
+                    vec![Stmt::line(if trace {
+                        format!("trace_decision({recognize})")
+                    } else {
+                        recognize
+                    })],
+                )
+                .returns("bool"),
+            );
+            let mut body = vec![Stmt::line("let i = &mut i;")];
+            Self::recognizer(cst, sema, inner, &mut body, &mut pending);
+            body.push(Stmt::line("true"));
+            functions.push(
+                Function::new(
+                    format!("recognize_syntactic_predicate_{rule_name}_{index}"),
+                    &["&self", "mut i: usize"],
+                    body,
+                )
+                .returns("bool")
+                .attribute("#[allow(unreachable_patterns)]"),
+            );
+        }
+        let mut recognizers = HashMap::new();
+        while let Some(rule) = pending.pop() {
+            if let std::collections::hash_map::Entry::Vacant(entry) = recognizers.entry(rule) {
+                entry.insert(Self::rule_recognizer(cst, sema, rule, &mut pending));
+            }
+        }
+        for rule in file.rule_decls(cst) {
+            if let Some(mut body) = recognizers.remove(&rule) {
+                let offset = if body.is_empty() {
+                    "_i: &mut usize"
+                } else {
+                    "i: &mut usize"
+                };
+                body.push(Stmt::line("true"));
+                functions.push(
+                    Function::new(
+                        format!("recognize_{}", rule.name(cst).unwrap().0),
+                        &["&self", offset],
+                        body,
+                    )
+                    .returns("bool")
+                    .attribute("#[allow(unreachable_patterns)]"),
+                );
+            }
+        }
+        functions
+    }
+
+    /// Returns the guard of a regex in a recognizer, which checks the syntactic
+    /// predicate at the start of the regex.
+    fn get_speculative_predicate(cst: &Cst, sema: &SemanticData, regex: Regex) -> Option<String> {
+        match regex {
+            Regex::Concat(concat) => match concat.operands(cst).next() {
+                Some(Regex::SyntacticPredicate(pred)) => {
+                    let (rule_name, index) = sema.syntactic_predicates[&pred.syntax()];
+                    Some(format!(
+                        "{}self.recognize_syntactic_predicate_{rule_name}_{index}(*i)",
+                        if pred.is_negative(cst) { "!" } else { "" }
+                    ))
+                }
+                _ => None,
+            },
+            Regex::Paren(paren) => {
+                Self::get_speculative_predicate(cst, sema, paren.inner(cst).unwrap())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the loop of a repetition in a recognizer, which runs the
+    /// `body` while the `pattern` matches.
+    fn recognizer_loop(head: &str, pattern: ArmPattern, body: Vec<Stmt>) -> Stmt {
+        Stmt::block(
+            head,
+            vec![Stmt::Match(Match::new(
+                "self.peek(*i)",
+                vec![Arm::block(pattern, body), Arm::expr("_", "break")],
+            ))],
+        )
+    }
+
+    /// Returns the recognizer of a rule. Left recursion is recognized by a
+    /// loop over the recursive branches.
+    fn rule_recognizer(
+        cst: &Cst,
+        sema: &SemanticData,
+        rule: RuleDecl,
+        pending: &mut Vec<RuleDecl>,
+    ) -> Vec<Stmt> {
+        let mut body = vec![];
+        let Some(regex) = rule.regex(cst) else {
+            return body;
+        };
+        let ops = || match regex {
+            Regex::Alternation(alt) => alt.operands(cst),
+            _ => unreachable!(),
+        };
+        match sema.patterns.get(&rule) {
+            Some(Pattern::LeftRecursive(branches)) => {
+                let mut arms = vec![];
+                for op in ops().filter(|op| !branches.contains(op)) {
+                    let mut arm = vec![];
+                    Self::recognizer(cst, sema, op, &mut arm, pending);
+                    arms.push(Arm::block(
+                        sema.predict_sets[&op.syntax()]
+                            .pattern()
+                            .guard(Self::get_speculative_predicate(cst, sema, op))
+                            .guard(Self::get_lookahead(sema, op, true)),
+                        arm,
+                    ));
+                }
+                arms.push(Arm::expr("_", "return false"));
+                body.push(Stmt::Match(Match::new("self.peek(*i)", arms)));
+                let mut arms = vec![];
+                for branch in branches {
+                    let ops = Self::branch_tail(cst, *branch);
+                    let mut arm = vec![];
+                    for op in ops.iter() {
+                        Self::recognizer(cst, sema, *op, &mut arm, pending);
+                    }
+                    arms.push(Arm::block(
+                        sema.predict_sets[&ops[0].syntax()]
+                            .pattern()
+                            .guard(Self::get_speculative_predicate(cst, sema, *branch)),
+                        arm,
+                    ));
+                }
+                arms.push(Arm::expr("_", "break"));
+                body.push(Stmt::block(
+                    "loop",
+                    vec![Stmt::Match(Match::new("self.peek(*i)", arms))],
+                ));
+            }
+            Some(Pattern::OperatorPrecedence(branches)) => {
+                // the precedence of the operators does not change the
+                // recognized language
+                let exit = ops().find(|op| !branches.contains(op)).unwrap();
+                let mut operators = std::collections::BTreeSet::new();
+                for branch in branches {
+                    let ops = Self::branch_tail(cst, *branch);
+                    operators.extend(sema.predict_sets[&ops[0].syntax()].iter());
+                }
+                Self::recognizer(cst, sema, exit, &mut body, pending);
+                let mut arm = vec![Stmt::line("*i += 1;")];
+                Self::recognizer(cst, sema, exit, &mut arm, pending);
+                body.push(Self::recognizer_loop("loop", operators.pattern(), arm));
+            }
+            _ => Self::recognizer(cst, sema, regex, &mut body, pending),
+        }
+        body
+    }
+
+    /// Appends the recognizer of a regex to the `body`, which advances the
+    /// offset `i` past the matched tokens, or returns `false`.
+    fn recognizer(
+        cst: &Cst,
+        sema: &SemanticData,
+        regex: Regex,
+        body: &mut Vec<Stmt>,
+        pending: &mut Vec<RuleDecl>,
+    ) {
+        let first = |op: Regex| {
+            let mut first = sema.first_sets[&op.syntax()].clone();
+            first.remove(&TokenName("ɛ"));
+            first
+        };
+        let token = |name: &str| {
+            [
+                Stmt::block(
+                    format!("if self.peek(*i) != Token::{name}"),
+                    vec![Stmt::line("return false;")],
+                ),
+                Stmt::line("*i += 1;"),
+            ]
+        };
+        match regex {
+            Regex::Name(name) => {
+                let decl = sema.decl_bindings[&name.syntax()];
+                if let Some(rule) = RuleDecl::cast(cst, decl) {
+                    pending.push(rule);
+                    body.push(Stmt::block(
+                        format!("if !self.recognize_{}(i)", rule.name(cst).unwrap().0),
+                        vec![Stmt::line("return false;")],
+                    ));
+                } else if let Some(decl) = TokenDecl::cast(cst, decl) {
+                    body.extend(token(decl.name(cst).unwrap().0));
+                }
+            }
+            Regex::Symbol(sym) => {
+                let decl = sema.decl_bindings[&sym.syntax()];
+                if let Some(decl) = TokenDecl::cast(cst, decl) {
+                    body.extend(token(decl.name(cst).unwrap().0));
+                }
+            }
+            Regex::Wildcard(_) => {
+                body.push(Stmt::Match(Match::new(
+                    "self.peek(*i)",
+                    vec![
+                        Arm::expr(sema.first_sets[&regex.syntax()].pattern(), "*i += 1"),
+                        Arm::expr("_", "return false"),
+                    ],
+                )));
+            }
+            Regex::Concat(concat) => {
+                for op in concat.operands(cst) {
+                    Self::recognizer(cst, sema, op, body, pending);
+                }
+            }
+            Regex::Alternation(alt) => {
+                let mut arms = vec![];
+                for op in alt.operands(cst) {
+                    let tokens = &sema.predict_sets[&op.syntax()];
+                    if tokens.is_empty() {
+                        continue;
+                    }
+                    let mut arm = vec![];
+                    Self::recognizer(cst, sema, op, &mut arm, pending);
+                    arms.push(Arm::block(
+                        tokens
+                            .pattern()
+                            .guard(Self::get_speculative_predicate(cst, sema, op))
+                            .guard(Self::get_lookahead(sema, op, true)),
+                        arm,
+                    ));
+                }
+                if sema.first_sets[&regex.syntax()].contains(&TokenName("ɛ")) {
+                    arms.push(Arm::block("_", vec![]));
+                } else {
+                    arms.push(Arm::expr("_", "return false"));
+                }
+                body.push(Stmt::Match(Match::new("self.peek(*i)", arms)));
+            }
+            Regex::Star(_) | Regex::Plus(_) => {
+                let op = match regex {
+                    Regex::Star(star) => star.operand(cst).unwrap(),
+                    Regex::Plus(plus) => plus.operand(cst).unwrap(),
+                    _ => unreachable!(),
+                };
+                if let Regex::Plus(_) = regex {
+                    Self::recognizer(cst, sema, op, body, pending);
+                }
+                let tokens = first(op);
+                if tokens.is_empty() {
+                    return;
+                }
+                let mut arm = vec![];
+                Self::recognizer(cst, sema, op, &mut arm, pending);
+                body.push(Self::recognizer_loop(
+                    "loop",
+                    tokens
+                        .pattern()
+                        .guard(Self::get_speculative_predicate(cst, sema, op)),
+                    arm,
+                ));
+            }
+            Regex::Bounded(bounded) => {
+                let op = bounded.operand(cst).unwrap();
+                let (min, max) = bounded.bounds(cst).unwrap();
+                let mut required = vec![];
+                Self::recognizer(cst, sema, op, &mut required, pending);
+                match min {
+                    0 => {}
+                    1 => body.append(&mut required),
+                    _ => body.push(Stmt::block(format!("for _ in 0..{min}"), required)),
+                }
+                let tokens = first(op);
+                if max == Some(min) || tokens.is_empty() {
+                    return;
+                }
+                let mut arm = vec![];
+                let head = if let Some(max) = max {
+                    body.push(Stmt::line("let mut count = 0;"));
+                    arm.push(Stmt::line("count += 1;"));
+                    format!("while count < {}", max - min)
+                } else {
+                    "loop".to_string()
+                };
+                Self::recognizer(cst, sema, op, &mut arm, pending);
+                body.push(Self::recognizer_loop(
+                    &head,
+                    tokens
+                        .pattern()
+                        .guard(Self::get_speculative_predicate(cst, sema, op)),
+                    arm,
+                ));
             }
+            Regex::Separated(sep) => {
+                let op = sep.operand(cst).unwrap();
+                let separator = sep.separator(cst).unwrap();
+                Self::recognizer(cst, sema, op, body, pending);
+                let tokens = first(separator);
+                if tokens.is_empty() {
+                    return;
+                }
+                let mut arm = vec![];
+                Self::recognizer(cst, sema, separator, &mut arm, pending);
+                Self::recognizer(cst, sema, op, &mut arm, pending);
+                body.push(Self::recognizer_loop(
+                    "loop",
+                    tokens
+                        .pattern()
+                        .guard(Self::get_speculative_predicate(cst, sema, separator)),
+                    arm,
+                ));
+            }
+            Regex::Optional(opt) => {
+                let op = opt.operand(cst).unwrap();
+                let tokens = first(op);
+                if tokens.is_empty() {
+                    return;
+                }
+                let mut arm = vec![];
+                Self::recognizer(cst, sema, op, &mut arm, pending);
+                body.push(Stmt::Match(Match::new(
+                    "self.peek(*i)",
+                    vec![
+                        Arm::block(
+                            tokens
+                                .pattern()
+                                .guard(Self::get_speculative_predicate(cst, sema, op)),
+                            arm,
+                        ),
+                        Arm::block("_", vec![]),
+                    ],
+                )));
+            }
+            Regex::Paren(paren) => {
+                let inner = paren.inner(cst).unwrap();
+                Self::recognizer(cst, sema, inner, body, pending);
+            }
+            _ => {}
         }
-        res
     }
 
     /// Outputs the Parser struct and impl.
@@ -994,23 +1822,26 @@ impl RustOutput {
         cst: &Cst,
         sema: &SemanticData,
         file: File,
-        output: &mut std::fs::File,
+        output: &mut impl Write,
+        options: &Options,
     ) -> std::io::Result<()> {
-        let mut token_symbols = HashMap::from([("EOF", "<end of file>")]);
+        let trace = options.trace;
+        let mut token_symbols = HashMap::from([("EOF", "<end of file>".to_string())]);
         for token in file.token_decls(cst) {
             let name = token.name(cst).unwrap().0;
-            let sym = token
-                .symbol(cst)
-                .map_or(name, |(sym, _)| &sym[1..sym.len() - 1]);
-            token_symbols.insert(name, sym);
+            token_symbols.insert(name, Self::token_error(cst, token));
         }
         let mut rules = "".to_string();
         let mut rule_names = HashSet::new();
         for rule in file.rule_decls(cst) {
             let rule_name = rule.name(cst).unwrap().0;
             rule_names.insert(rule_name);
+            for line in doc_lines(cst, rule.syntax()) {
+                rules += "\n    ///";
+                rules += line;
+            }
             rules += "\n    ";
-            rules += &Self::snake_to_pascal_case(rule_name);
+            rules += &snake_to_pascal_case(rule_name);
             rules += ",";
         }
         for rule_name in sema.rule_bindings.iter() {
@@ -1018,7 +1849,7 @@ impl RustOutput {
                 continue;
             }
             rules += "\n    ";
-            rules += &Self::snake_to_pascal_case(rule_name);
+            rules += &snake_to_pascal_case(rule_name);
             rules += ",";
         }
         let mut skip = "".to_string();
@@ -1032,15 +1863,977 @@ impl RustOutput {
                 include_str!("../skeleton/generated.rs"),
                 rules,
                 skip,
-                sema.start.unwrap().name(cst).unwrap().0
+                sema.start.unwrap().name(cst).unwrap().0,
+                sema.max_errors
+                    .map_or("usize::MAX".to_string(), |n| n.to_string()),
+                options.visibility.keyword(),
+                Self::serde_derive(options, "serde::Serialize, serde::Deserialize"),
+                // the syntax tree borrows the source, so it is only serialized
+                Self::serde_derive(options, "serde::Serialize")
             )
             .as_bytes(),
         )?;
-        for rule in file.rule_decls(cst) {
-            Self::output_rule(cst, sema, rule, output, &token_symbols)?;
+        let mut tables = Tables::default();
+        if options.table {
+            tables.output_dispatch(cst, sema, file, output, &token_symbols, trace)?;
+        } else {
+            for rule in file.rule_decls(cst) {
+                if let Some(function) = Self::rule(cst, sema, rule, &token_symbols, trace) {
+                    output.write_all(function.to_rust(1).as_bytes())?;
+                }
+            }
+        }
+        for (node, (rule_name, index)) in sema.inline_actions.iter() {
+            let code = Action::cast(cst, *node).and_then(|action| action.code(cst));
+            output.write_all(
+                format!(
+                    "    #[allow(unused_variables)]\
+                   \n    fn inline_action_{rule_name}_{index}(&mut self, diags: &mut Vec<Diagnostic>) {}\n",
+                    code.unwrap().0
+                )
+                .as_bytes(),
+            )?;
+        }
+        for (node, (rule_name, num, language)) in sema.tagged_actions.iter() {
+            if *language != "rust" {
+                continue;
+            }
+            let code = Action::cast(cst, *node).and_then(|action| action.tagged(cst));
+            output.write_all(
+                format!(
+                    "    #[allow(unused_variables)]\
+                   \n    fn action_{rule_name}_{num}(&mut self, diags: &mut Vec<Diagnostic>) {}\n",
+                    code.unwrap().2
+                )
+                .as_bytes(),
+            )?;
+        }
+        let mut functions = Self::entries(cst, sema, trace);
+        functions.extend(Self::syntactic_predicates(cst, sema, file, trace));
+        if trace {
+            functions.extend(Self::trace_decisions(sema));
+        }
+        for function in functions {
+            output.write_all(function.to_rust(1).as_bytes())?;
         }
         output.write_all(b"}\n\n")?;
 
-        Self::output_predicates_and_actions(output, sema, true)
+        Self::output_predicates_and_actions(output, sema, true)?;
+        Self::output_keywords(cst, file, output)?;
+        if options.table {
+            tables.output_tables(output)?;
+        }
+        if trace {
+            Self::output_trace(output)?;
+        }
+        Ok(())
+    }
+
+    /// Outputs a function that maps the text of an identifier to the keyword
+    /// token with this symbol, if the grammar has tokens with symbols that are
+    /// identifiers. Symbols of tokens with the `ignore_case` attribute match in
+    /// any ASCII case.
+    fn output_keywords(cst: &Cst, file: File, output: &mut impl Write) -> std::io::Result<()> {
+        let mut arms = vec![];
+        let mut ignore_case_arms = vec![];
+        for token in file.token_decls(cst) {
+            let Some((symbol, _)) = token.symbol(cst) else {
+                continue;
+            };
+            let text = &symbol[1..symbol.len() - 1];
+            let mut chars = text.chars();
+            if chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                let name = token.name(cst).unwrap().0;
+                let expr = format!("Some(Token::{name})");
+                if token.ignores_case(cst) {
+                    let pattern = format!("_ if text.eq_ignore_ascii_case(\"{text}\")");
+                    ignore_case_arms.push(Arm::expr(pattern, expr));
+                } else {
+                    arms.push(Arm::expr(format!("\"{text}\""), expr));
+                }
+            }
+        }
+        if arms.is_empty() && ignore_case_arms.is_empty() {
+            return Ok(());
+        }
+        // exact matches take precedence over the case-insensitive ones
+        arms.extend(ignore_case_arms);
+        arms.push(Arm::expr("_", "None"));
+        let function = Function::new(
+            "keyword",
+            &["text: &str"],
+            vec![Stmt::Match(Match::new("text", arms))],
+        )
+        .returns("Option<Token>")
+        .public()
+        .doc(" Keyword token with the symbol `text`, for lexers that lex keywords as")
+        .doc(" identifiers first.")
+        .attribute("#[allow(dead_code)]");
+        output.write_all(b"\nimpl Token {\n")?;
+        output.write_all(function.to_rust(1).as_bytes())?;
+        output.write_all(b"}\n")
+    }
+
+    /// Returns the parse functions of the entry rules, which parse the input
+    /// with the rule instead of the start rule.
+    fn entries(cst: &Cst, sema: &SemanticData, trace: bool) -> Vec<Function> {
+        let mut functions = vec![];
+        for rule in sema.entries.iter() {
+            let name = rule.name(cst).unwrap().0;
+            let mut body = vec![Stmt::line(
+                "let mut parser = Self::new(source, tokens, ranges, None);",
+            )];
+            if sema.start == Some(*rule) {
+                body.push(Stmt::line(format!("parser.r#{name}(diags);")));
+            } else {
+                if trace {
+                    body.push(Stmt::line("DECISION_TRACE.with_borrow_mut(Vec::clear);"));
+                }
+                body.push(Stmt::line(format!(
+                    "parser.entry(Rule::{}, Self::r#{name}, diags);",
+                    snake_to_pascal_case(name)
+                )));
+            }
+            body.push(Stmt::line("parser.cst"));
+            functions.push(
+                Function::new(
+                    format!("parse_{name}"),
+                    &[
+                        "source: &'a str",
+                        "tokens: Vec<Token>",
+                        "ranges: Vec<std::ops::Range<CstIndex>>",
+                        "diags: &mut Vec<Diagnostic>",
+                    ],
+                    body,
+                )
+                .returns("Cst<'a>")
+                .public()
+                .doc(format!(
+                    " Parses the input with the `{name}` rule, which must be followed by the"
+                ))
+                .doc(" end of file."),
+            );
+        }
+        functions
+    }
+
+    /// Returns inherent methods that shadow the predicate and insertion methods
+    /// of the `PredicatesAndActions` trait, so their results are recorded.
+    fn trace_decisions(sema: &SemanticData) -> Vec<Function> {
+        let mut functions = vec![];
+        let mut predicates = HashSet::new();
+        for (rule, num) in sema.predicates.values() {
+            if predicates.insert((rule, num)) {
+                let name = format!("predicate_{rule}_{num}");
+                let body = vec![Stmt::line(format!(
+                    "trace_decision(PredicatesAndActions::{name}(self))"
+                ))];
+                functions.push(Function::new(name, &["&self"], body).returns("bool"));
+            }
+        }
+        if !sema.inserted.is_empty() {
+            let body = vec![Stmt::line(
+                "trace_decision(PredicatesAndActions::insert_token(self, token))",
+            )];
+            functions.push(
+                Function::new("insert_token", &["&self", "token: Token"], body).returns("bool"),
+            );
+        }
+        functions
+    }
+
+    /// Outputs the recording of the parser trace, which can be replayed with
+    /// `llw --replay`.
+    fn output_trace(output: &mut impl Write) -> std::io::Result<()> {
+        output.write_all(
+            b"\nthread_local! {\
+            \n    static DECISION_TRACE: std::cell::RefCell<Vec<bool>> = const { std::cell::RefCell::new(vec![]) };\
+            \n}\
+            \n\
+            \n#[allow(dead_code)]\
+            \nfn trace_decision(decision: bool) -> bool {\
+            \n    DECISION_TRACE.with_borrow_mut(|trace| trace.push(decision));\
+            \n    decision\
+            \n}\
+            \n\
+            \nimpl Cst<'_> {\
+            \n    /// Writes the token kinds of the syntax tree and the runtime decisions of\
+            \n    /// the last parse on this thread as a trace for `llw --replay`.\
+            \n    #[allow(dead_code)]\
+            \n    pub fn write_trace<W: std::io::Write>(&self, output: &mut W) -> std::io::Result<()> {\
+            \n        write!(output, \"tokens:\")?;\
+            \n        for token in self.tokens.iter() {\
+            \n            write!(output, \" {token:?}\")?;\
+            \n        }\
+            \n        write!(output, \"\\ndecisions:\")?;\
+            \n        DECISION_TRACE.with_borrow(|trace| {\
+            \n            trace\
+            \n                .iter()\
+            \n                .try_for_each(|decision| write!(output, \" {}\", *decision as u8))\
+            \n        })?;\
+            \n        writeln!(output)\
+            \n    }\
+            \n}\n",
+        )
+    }
+}
+
+/// Flag of a cell in the prediction table for a token that ends a repetition
+/// or an optional, or for a right associative operator.
+const EXIT: u16 = 1;
+/// Flag of a cell in the prediction table for a token that predicts several
+/// branches, which are tried in order.
+const CONFLICT: u16 = 0x8000;
+
+/// Branch of a decision in the generated tables.
+struct TableBranch<'a, 'b> {
+    tokens: &'b BTreeSet<TokenName<'a>>,
+    production: usize,
+    /// Regex whose predicate and lookahead guard the branch.
+    guard: Option<Regex>,
+    /// Whether the branch is an exit branch of a right recursive rule or a
+    /// forwarding branch of a left recursive rule.
+    flag: bool,
+}
+
+/// State for the generation of a table-driven parser.
+///
+/// The parser is output as flat tables that are run by the driver in
+/// `skeleton/table.rs`. The prediction table has a row for each decision and a
+/// column for each token, and a cell holds the branch of the decision that is
+/// selected by the token. The branches refer to their productions by the
+/// index of the first instruction in the array of all productions.
+/// Predicates, actions, and diagnostics are referenced by index, as the tables
+/// cannot contain closures.
+#[derive(Default)]
+struct Tables<'a> {
+    /// Columns of the tokens in the prediction table, where the last column
+    /// is for tokens that are not declared in the grammar.
+    columns: HashMap<&'a str, usize>,
+    rule_indices: HashMap<RuleDecl, usize>,
+    rules: Vec<String>,
+    /// Instructions of the productions, where each production ends with a
+    /// `Return`, and the name of the rule in front of its first production.
+    productions: Vec<(Option<&'a str>, Vec<String>)>,
+    /// Index of the next production in the array of instructions.
+    next_production: usize,
+    rows: Vec<Vec<u16>>,
+    decisions: Vec<String>,
+    branches: Vec<String>,
+    candidates: Vec<u16>,
+    candidate_offsets: HashMap<Vec<u16>, usize>,
+    lookahead: Vec<String>,
+    errors: Vec<Expr>,
+    error_indices: HashMap<Expr, usize>,
+    predicates: Vec<String>,
+    actions: Vec<String>,
+}
+
+impl<'a> Tables<'a> {
+    fn index_of(items: &mut Vec<String>, item: String) -> usize {
+        items.iter().position(|i| *i == item).unwrap_or_else(|| {
+            items.push(item);
+            items.len() - 1
+        })
+    }
+
+    /// Returns the index of the diagnostic.
+    fn error(&mut self, diagnostic: Expr) -> usize {
+        let index = *self
+            .error_indices
+            .entry(diagnostic.clone())
+            .or_insert(self.errors.len());
+        if index == self.errors.len() {
+            self.errors.push(diagnostic);
+        }
+        index
+    }
+
+    fn syntax_error(
+        &mut self,
+        set: &BTreeSet<TokenName>,
+        token_symbols: &HashMap<&str, String>,
+    ) -> usize {
+        self.error(Self::diagnostic("err", set.error(token_symbols)))
+    }
+
+    /// Returns the invocation of the diagnostic macro `name` for the `span`
+    /// and the expected `tokens`.
+    fn diagnostic(name: &str, tokens: Vec<Expr>) -> Expr {
+        let mut items = vec![Expr::from("span")];
+        items.extend(tokens);
+        Expr::macro_call(name, items)
+    }
+
+    /// Adds a production with the instructions and returns its index, where
+    /// the empty production is the `Return` at index zero.
+    fn production(&mut self, rule_name: Option<&'a str>, mut ops: Vec<String>) -> usize {
+        if ops.is_empty() && rule_name.is_none() {
+            return 0;
+        }
+        let index = self.next_production;
+        ops.push("Return".to_string());
+        self.next_production += ops.len();
+        self.productions.push((rule_name, ops));
+        index
+    }
+
+    /// Adds a row to the prediction table with the decision and returns its
+    /// index.
+    fn row(&mut self, row: Vec<u16>, first_branch: usize, error: usize, open: bool) -> usize {
+        self.rows.push(row);
+        self.decisions
+            .push(format!("({first_branch}, {error}, {open})"));
+        self.rows.len() - 1
+    }
+
+    /// Adds a row with a cell for each token of the `set`.
+    fn set_row(&mut self, set: &BTreeSet<TokenName>, error: usize) -> usize {
+        let mut row = vec![0; self.columns.len() + 1];
+        for token in set.iter() {
+            row[self.columns[token.0]] = 1 << 1;
+        }
+        self.row(row, 0, error, false)
+    }
+
+    /// Adds a decision between the branches, which ends at the `exit` tokens
+    /// for repetitions and optionals, and returns its row.
+    #[allow(clippy::too_many_arguments)]
+    fn decision(
+        &mut self,
+        cst: &Cst,
+        sema: &SemanticData,
+        rule_name: &str,
+        branches: Vec<TableBranch>,
+        exit: Option<&BTreeSet<TokenName>>,
+        error: usize,
+        open: bool,
+    ) -> usize {
+        let first_branch = self.branches.len();
+        let mut candidates = vec![vec![]; self.columns.len() + 1];
+        for (i, branch) in branches.iter().enumerate() {
+            for token in branch.tokens.iter() {
+                candidates[self.columns[token.0]].push(i as u16 + 1);
+            }
+            let predicate = branch
+                .guard
+                .and_then(|guard| RustOutput::predicate_method(cst, sema, rule_name, guard))
+                .map_or(0, |method| Self::index_of(&mut self.predicates, method) + 1);
+            let start = self.lookahead.len();
+            let sequences = branch
+                .guard
+                .and_then(|guard| sema.lookahead_sets.get(&guard.syntax()));
+            for seq in sequences.into_iter().flatten() {
+                let tokens = seq
+                    .iter()
+                    .map(|token| format!("Token::{}", token.0))
+                    .collect::<Vec<_>>();
+                self.lookahead.push(format!("&[{}]", tokens.join(", ")));
+            }
+            self.branches.push(format!(
+                "({}, {predicate}, {start}, {}, {})",
+                branch.production,
+                self.lookahead.len(),
+                branch.flag
+            ));
+        }
+        let mut row = vec![0; self.columns.len() + 1];
+        for (cell, candidates) in row.iter_mut().zip(candidates) {
+            *cell = match candidates.len() {
+                0 => 0,
+                1 => candidates[0] << 1,
+                _ => {
+                    let offset = *self
+                        .candidate_offsets
+                        .entry(candidates.clone())
+                        .or_insert(self.candidates.len());
+                    if offset == self.candidates.len() {
+                        self.candidates.extend(candidates);
+                        self.candidates.push(0);
+                    }
+                    CONFLICT | (offset as u16) << 1
+                }
+            };
+        }
+        for token in exit.into_iter().flatten() {
+            row[self.columns[token.0]] |= EXIT;
+        }
+        self.row(row, first_branch, error, open)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn ops(
+        &mut self,
+        cst: &Cst,
+        sema: &SemanticData,
+        regex: Regex,
+        token_symbols: &HashMap<&str, String>,
+        open_before: bool,
+        rule_name: &str,
+        ops: &mut Vec<String>,
+    ) {
+        let expect = |tables: &mut Self, token: TokenDecl, ops: &mut Vec<String>| {
+            let name = token.name(cst).unwrap().0;
+            let sym = token
+                .symbol(cst)
+                .map_or(name, |(sym, _)| &sym[1..sym.len() - 1]);
+            let error = RustOutput::token_error(cst, token);
+            let error = tables.error(Self::diagnostic("err", vec![format!("\"{error}\"").into()]));
+            if sema.inserted.contains(&token) {
+                let insertion =
+                    tables.error(Self::diagnostic("ins", vec![format!("\"{sym}\"").into()]));
+                ops.push(format!("Insertable(Token::{name}, {error}, {insertion})"));
+            } else {
+                ops.push(format!("Expect(Token::{name}, {error})"));
+            }
+        };
+        match regex {
+            Regex::Name(name) => {
+                let decl = sema.decl_bindings[&name.syntax()];
+                if let Some(rule) = RuleDecl::cast(cst, decl) {
+                    ops.push(format!("Call({})", self.rule_indices[&rule]));
+                } else if let Some(token) = TokenDecl::cast(cst, decl) {
+                    expect(self, token, ops);
+                }
+            }
+            Regex::Symbol(sym) => {
+                let decl = sema.decl_bindings[&sym.syntax()];
+                if let Some(token) = TokenDecl::cast(cst, decl) {
+                    expect(self, token, ops);
+                }
+            }
+            Regex::Concat(concat) => {
+                for op in concat.operands(cst) {
+                    self.ops(cst, sema, op, token_symbols, false, rule_name, ops);
+                }
+            }
+            Regex::Alternation(_) | Regex::Wildcard(_)
+                if RustOutput::is_token_set(cst, sema, regex) =>
+            {
+                let error = self.error(Self::diagnostic(
+                    "err",
+                    RustOutput::token_set_error(sema, regex, token_symbols),
+                ));
+                let row = self.set_row(&sema.first_sets[&regex.syntax()], error);
+                ops.push(format!("Set({row})"));
+            }
+            Regex::Alternation(alt) => {
+                let mut branches = vec![];
+                for op in alt.operands(cst) {
+                    let mut body = vec![];
+                    self.ops(cst, sema, op, token_symbols, false, rule_name, &mut body);
+                    branches.push(TableBranch {
+                        tokens: &sema.predict_sets[&op.syntax()],
+                        production: self.production(None, body),
+                        guard: Some(op),
+                        flag: false,
+                    });
+                }
+                let error = self.syntax_error(&sema.predict_sets[&regex.syntax()], token_symbols);
+                let row = self.decision(cst, sema, rule_name, branches, None, error, false);
+                ops.push(format!("Alt({row})"));
+            }
+            Regex::Star(_) | Regex::Plus(_) | Regex::Optional(_) | Regex::Bounded(_) => {
+                let (kind, op) = match regex {
+                    Regex::Star(star) => ("Star", star.operand(cst).unwrap()),
+                    Regex::Plus(plus) => ("Plus", plus.operand(cst).unwrap()),
+                    Regex::Optional(opt) => ("Optional", opt.operand(cst).unwrap()),
+                    Regex::Bounded(bounded) => ("Bounded", bounded.operand(cst).unwrap()),
+                    _ => unreachable!(),
+                };
+                let mut body = vec![];
+                self.ops(cst, sema, op, token_symbols, false, rule_name, &mut body);
+                let branch = TableBranch {
+                    tokens: &sema.first_sets[&op.syntax()],
+                    production: self.production(None, body),
+                    guard: Some(op),
+                    flag: false,
+                };
+                let mut exit = sema.follow_sets[&regex.syntax()].clone();
+                if kind != "Optional" {
+                    exit.extend(sema.recovery_sets[&regex.syntax()].iter().copied());
+                }
+                let error = self.syntax_error(&sema.predict_sets[&regex.syntax()], token_symbols);
+                let row = self.decision(
+                    cst,
+                    sema,
+                    rule_name,
+                    vec![branch],
+                    Some(&exit),
+                    error,
+                    open_before,
+                );
+                if let Regex::Bounded(bounded) = regex {
+                    let (min, max) = bounded.bounds(cst).unwrap();
+                    let max = max.map_or("u16::MAX".to_string(), |max| max.to_string());
+                    ops.push(format!("Bounded({row}, {min}, {max})"));
+                } else {
+                    ops.push(format!("{kind}({row})"));
+                }
+            }
+            Regex::Separated(sep) => {
+                // `a % b` is executed as `a (b a)*`
+                let op = sep.operand(cst).unwrap();
+                let separator = sep.separator(cst).unwrap();
+                self.ops(cst, sema, op, token_symbols, false, rule_name, ops);
+                let mut body = vec![];
+                for op in [separator, op] {
+                    self.ops(cst, sema, op, token_symbols, false, rule_name, &mut body);
+                }
+                let branch = TableBranch {
+                    tokens: &sema.first_sets[&separator.syntax()],
+                    production: self.production(None, body),
+                    guard: Some(separator),
+                    flag: false,
+                };
+                let mut exit = sema.follow_sets[&regex.syntax()].clone();
+                exit.extend(sema.recovery_sets[&regex.syntax()].iter().copied());
+                let error =
+                    self.syntax_error(&sema.predict_sets[&separator.syntax()], token_symbols);
+                let row = self.decision(
+                    cst,
+                    sema,
+                    rule_name,
+                    vec![branch],
+                    Some(&exit),
+                    error,
+                    open_before,
+                );
+                ops.push(format!("Star({row})"));
+            }
+            Regex::Paren(paren) => {
+                let inner = paren.inner(cst).unwrap();
+                self.ops(cst, sema, inner, token_symbols, false, rule_name, ops);
+            }
+            Regex::Action(action) => {
+                let method = if let Some((_, index)) = sema.inline_actions.get(&action.syntax()) {
+                    format!("inline_action_{rule_name}_{index}")
+                } else if let Some((_, num, language)) = sema.tagged_actions.get(&action.syntax()) {
+                    if *language != "rust" {
+                        // blocks for other backends
+                        return;
+                    }
+                    format!("action_{rule_name}_{num}")
+                } else {
+                    format!("action_{rule_name}_{}", &action.value(cst).unwrap().0[1..])
+                };
+                let index = Self::index_of(&mut self.actions, method);
+                ops.push(format!("Action({index})"));
+            }
+            Regex::Binding(bind) => {
+                let name = &bind.value(cst).unwrap().0[1..];
+                if !name.is_empty() {
+                    ops.push(format!("Binding(Rule::{})", snake_to_pascal_case(name)));
+                }
+            }
+            Regex::OpenNode(open) => {
+                ops.push(format!("OpenNode({})", open.number(cst).unwrap()));
+            }
+            Regex::CloseNode(close) => {
+                ops.push(format!(
+                    "CloseNode({}, Rule::{})",
+                    close.number(cst).unwrap(),
+                    snake_to_pascal_case(close.node_name(cst).unwrap())
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the shape of the rule with its productions and decisions.
+    fn rule_shape(
+        &mut self,
+        cst: &'a Cst,
+        sema: &SemanticData,
+        rule: RuleDecl,
+        token_symbols: &HashMap<&str, String>,
+    ) -> String {
+        let (Some(regex), true) = (rule.regex(cst), sema.used.contains(&rule.syntax())) else {
+            // unused rules are never called
+            return "Forwarding(0)".to_string();
+        };
+        let name = rule.name(cst).unwrap().0;
+        let mut ops = vec![];
+        match sema.patterns.get(&rule) {
+            None => {
+                self.ops(cst, sema, regex, token_symbols, false, name, &mut ops);
+                let production = self.production(Some(name), ops);
+                format!("Normal({production}, {})", sema.start == Some(rule))
+            }
+            Some(Pattern::UnconditionalForwarding) => {
+                self.ops(cst, sema, regex, token_symbols, false, name, &mut ops);
+                let production = self.production(Some(name), ops);
+                format!("Forwarding({production})")
+            }
+            Some(Pattern::ConditionalForwarding) => {
+                let Regex::Concat(concat) = regex else {
+                    unreachable!()
+                };
+                for (i, op) in concat.operands(cst).enumerate() {
+                    self.ops(cst, sema, op, token_symbols, i > 0, name, &mut ops);
+                }
+                let production = self.production(Some(name), ops);
+                format!("Forwarding({production})")
+            }
+            Some(Pattern::MaybeEmpty) => {
+                self.ops(cst, sema, regex, token_symbols, true, name, &mut ops);
+                let production = self.production(Some(name), ops);
+                format!("Forwarding({production})")
+            }
+            Some(Pattern::RightRecursiveForwarding(exits)) => {
+                let Regex::Alternation(alt) = regex else {
+                    unreachable!()
+                };
+                let mut branches = vec![];
+                for op in alt.operands(cst) {
+                    let mut body = vec![];
+                    self.ops(cst, sema, op, token_symbols, false, name, &mut body);
+                    branches.push(TableBranch {
+                        tokens: &sema.predict_sets[&op.syntax()],
+                        production: self.production(None, body),
+                        guard: Some(op),
+                        flag: exits.contains(&op),
+                    });
+                }
+                let error = self.syntax_error(&sema.predict_sets[&regex.syntax()], token_symbols);
+                let row = self.decision(cst, sema, name, branches, None, error, false);
+                format!("RightRecursiveForwarding({row})")
+            }
+            Some(Pattern::LeftRecursive(recursive)) => {
+                let Regex::Alternation(alt) = regex else {
+                    unreachable!()
+                };
+                let mut branches = vec![];
+                for op in alt.operands(cst).filter(|op| !recursive.contains(op)) {
+                    let is_forwarding = if let Regex::Name(name) = op {
+                        sema.decl_bindings
+                            .get(&name.syntax())
+                            .is_some_and(|n| RuleDecl::cast(cst, *n).is_some())
+                    } else {
+                        false
+                    };
+                    let mut body = vec![];
+                    self.ops(cst, sema, op, token_symbols, false, name, &mut body);
+                    branches.push(TableBranch {
+                        tokens: &sema.predict_sets[&op.syntax()],
+                        production: self.production(None, body),
+                        guard: Some(op),
+                        flag: is_forwarding,
+                    });
+                }
+                let mut loops = vec![];
+                for branch in recursive {
+                    let Regex::Concat(concat) = branch else {
+                        unreachable!()
+                    };
+                    let ops = concat
+                        .operands(cst)
+                        .filter(|op| {
+                            !matches!(op, Regex::Predicate(_) | Regex::SyntacticPredicate(_))
+                        })
+                        .skip(1)
+                        .collect::<Vec<_>>();
+                    let mut body = vec![];
+                    for op in ops.iter() {
+                        self.ops(cst, sema, *op, token_symbols, false, name, &mut body);
+                    }
+                    loops.push(TableBranch {
+                        tokens: &sema.predict_sets[&ops[0].syntax()],
+                        production: self.production(None, body),
+                        guard: Some(*branch),
+                        flag: false,
+                    });
+                }
+                let error = self.syntax_error(&sema.predict_sets[&regex.syntax()], token_symbols);
+                let row = self.decision(cst, sema, name, branches, None, error, false);
+                let loop_row = self.decision(cst, sema, name, loops, None, error, false);
+                format!("LeftRecursive({row}, {loop_row})")
+            }
+            Some(Pattern::OperatorPrecedence(branches)) => {
+                let Regex::Alternation(alt) = regex else {
+                    unreachable!()
+                };
+                let exit = alt.operands(cst).find(|op| !branches.contains(op)).unwrap();
+                let exit = match exit {
+                    Regex::Name(name) => sema.decl_bindings[&name.syntax()],
+                    _ => unreachable!(),
+                };
+                let exit = self.rule_indices[&RuleDecl::cast(cst, exit).unwrap()];
+                let mut row = vec![0; self.columns.len() + 1];
+                for (i, branch) in branches.iter().rev().enumerate() {
+                    let Regex::Concat(concat) = branch else {
+                        unreachable!()
+                    };
+                    let op = concat.operands(cst).nth(1).unwrap();
+                    for token in sema.predict_sets[&op.syntax()].iter() {
+                        let cell = &mut row[self.columns[token.0]];
+                        if *cell == 0 {
+                            *cell = (i as u16 + 1) << 1;
+                            if sema.right_associative.contains(token.0) {
+                                *cell |= EXIT;
+                            }
+                        }
+                    }
+                }
+                let row = self.row(row, 0, 0, false);
+                format!("OperatorPrecedence({exit}, {row})")
+            }
+        }
+    }
+
+    /// Outputs the start rule and the dispatch methods of the driver into the
+    /// `Parser` impl, and collects the tables.
+    fn output_dispatch(
+        &mut self,
+        cst: &'a Cst,
+        sema: &SemanticData,
+        file: File,
+        output: &mut impl Write,
+        token_symbols: &HashMap<&str, String>,
+        trace: bool,
+    ) -> std::io::Result<()> {
+        self.columns.insert("EOF", 0);
+        for token in file.token_decls(cst) {
+            self.columns
+                .insert(token.name(cst).unwrap().0, self.columns.len());
+        }
+        for (i, rule) in file.rule_decls(cst).enumerate() {
+            self.rule_indices.insert(rule, i);
+        }
+        // the empty production
+        self.productions.push((None, vec!["Return".to_string()]));
+        self.next_production = 1;
+        for rule in file.rule_decls(cst) {
+            let shape = self.rule_shape(cst, sema, rule, token_symbols);
+            let kind = snake_to_pascal_case(rule.name(cst).unwrap().0);
+            self.rules.push(format!("(Rule::{kind}, {shape})"));
+        }
+        let start = sema.start.unwrap();
+        let mut body = vec![];
+        if trace {
+            body.push(Stmt::line("DECISION_TRACE.with_borrow_mut(Vec::clear);"));
+        }
+        body.push(Stmt::line(format!(
+            "self.run_rule({}, diags);",
+            self.rule_indices[&start]
+        )));
+        let mut functions = vec![Function::new(
+            format!("r#{}", start.name(cst).unwrap().0),
+            &["&mut self", "diags: &mut Vec<Diagnostic>"],
+            body,
+        )];
+        for rule in sema.entries.iter().filter(|rule| **rule != start) {
+            functions.push(Function::new(
+                format!("r#{}", rule.name(cst).unwrap().0),
+                &["&mut self", "diags: &mut Vec<Diagnostic>"],
+                vec![Stmt::line(format!(
+                    "self.run_rule({}, diags);",
+                    self.rule_indices[rule]
+                ))],
+            ));
+        }
+
+        // dispatch of the indices in the tables to the methods
+        let dispatch = |name: &str, params: &[&str], methods: &[String], args: &str| {
+            if methods.is_empty() {
+                // the parameters besides the receiver are unused
+                let params: Vec<_> = params
+                    .iter()
+                    .map(|param| match param.starts_with('&') {
+                        true => param.to_string(),
+                        false => format!("_{param}"),
+                    })
+                    .collect();
+                let params: Vec<_> = params.iter().map(String::as_str).collect();
+                return Function::new(name, &params, vec![Stmt::line("unreachable!()")]);
+            }
+            let mut arms: Vec<_> = methods
+                .iter()
+                .enumerate()
+                .map(|(i, method)| Arm::expr(i.to_string(), format!("self.{method}({args})")))
+                .collect();
+            arms.push(Arm::expr("_", "unreachable!()"));
+            Function::new(name, params, vec![Stmt::Match(Match::new("index", arms))])
+        };
+        functions.push(
+            dispatch(
+                "predicate",
+                &["&self", "index: usize"],
+                &self.predicates,
+                "",
+            )
+            .returns("bool"),
+        );
+        functions.push(dispatch(
+            "action",
+            &["&mut self", "index: usize", "diags: &mut Vec<Diagnostic>"],
+            &self.actions,
+            "diags",
+        ));
+        functions.push(if sema.inserted.is_empty() {
+            Function::new(
+                "insert",
+                &["&self", "_token: Token"],
+                vec![Stmt::line("false")],
+            )
+            .returns("bool")
+        } else {
+            Function::new(
+                "insert",
+                &["&self", "token: Token"],
+                vec![Stmt::line("self.insert_token(token)")],
+            )
+            .returns("bool")
+        });
+        for function in functions {
+            output.write_all(function.to_rust(1).as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Appends the items separated by commas, with as many items on a line as
+    /// fit.
+    fn push_filled(
+        level: usize,
+        items: impl IntoIterator<Item = String>,
+        trailing_comma: bool,
+        code: &mut String,
+    ) {
+        let mut line = String::new();
+        let mut items = items.into_iter().peekable();
+        while let Some(item) = items.next() {
+            let comma = if trailing_comma || items.peek().is_some() {
+                ","
+            } else {
+                ""
+            };
+            if !line.is_empty() && !fits(level, &format!("{line} {item}{comma}")) {
+                push_indented(level, &line, code);
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&item);
+            line.push_str(comma);
+        }
+        push_indented(level, &line, code);
+    }
+
+    /// Appends an array with the `items` as a static item, where the variants
+    /// of `variants` can be used without their enum name.
+    fn push_static<T: ToString>(
+        name: &str,
+        ty: &str,
+        variants: Option<&str>,
+        items: &[T],
+        code: &mut String,
+    ) {
+        let items = items.iter().map(ToString::to_string);
+        let len = items.len();
+        match variants {
+            Some(variants) => {
+                code.push_str(&format!(
+                    "\nstatic {name}: [{ty}; {len}] = {{\n    use {variants}::*;\n    [\n"
+                ));
+                Self::push_filled(2, items, true, code);
+                code.push_str("    ]\n};\n");
+            }
+            None if len == 0 => code.push_str(&format!("\nstatic {name}: [{ty}; 0] = [];\n")),
+            None => {
+                code.push_str(&format!("\nstatic {name}: [{ty}; {len}] = [\n"));
+                Self::push_filled(1, items, true, code);
+                code.push_str("];\n");
+            }
+        }
+    }
+
+    /// Outputs the driver, the tables, and the diagnostics.
+    fn output_tables(&self, output: &mut impl Write) -> std::io::Result<()> {
+        output.write_all(include_str!("../skeleton/table.rs").as_bytes())?;
+        let mut code = String::new();
+        Self::push_static(
+            "RULES",
+            "(Rule, Shape)",
+            Some("Shape"),
+            &self.rules,
+            &mut code,
+        );
+        code.push_str(&format!(
+            "\nstatic PRODUCTIONS: [Op; {}] = {{\n    use Op::*;\n    [\n",
+            self.next_production
+        ));
+        for (rule_name, ops) in self.productions.iter() {
+            if let Some(rule_name) = rule_name {
+                push_indented(2, &format!("// {rule_name}"), &mut code);
+            }
+            Self::push_filled(2, ops.iter().cloned(), true, &mut code);
+        }
+        code.push_str("    ]\n};\n");
+        let columns = self.columns.len() + 1;
+        code.push_str(&format!(
+            "\nstatic PREDICT: [[u16; {columns}]; {}] = [\n",
+            self.rows.len()
+        ));
+        for row in self.rows.iter() {
+            let cells = row.iter().map(ToString::to_string).collect::<Vec<_>>();
+            let line = format!("[{}],", cells.join(", "));
+            if fits(1, &line) {
+                push_indented(1, &line, &mut code);
+            } else {
+                push_indented(1, "[", &mut code);
+                Self::push_filled(2, cells, true, &mut code);
+                push_indented(1, "],", &mut code);
+            }
+        }
+        code.push_str("];\n");
+        Self::push_static("DECISIONS", "Decision", None, &self.decisions, &mut code);
+        Self::push_static("BRANCHES", "Branch", None, &self.branches, &mut code);
+        Self::push_static("CANDIDATES", "u16", None, &self.candidates, &mut code);
+        Self::push_static("LOOKAHEAD", "&[Token]", None, &self.lookahead, &mut code);
+        output.write_all(code.as_bytes())?;
+
+        let mut columns = self
+            .columns
+            .iter()
+            .map(|(token, column)| {
+                (
+                    *column,
+                    Arm::expr(format!("Token::{token}"), column.to_string()),
+                )
+            })
+            .collect::<Vec<_>>();
+        columns.sort_by_key(|(column, _)| *column);
+        let mut arms = columns.into_iter().map(|(_, arm)| arm).collect::<Vec<_>>();
+        arms.push(Arm::expr("_", self.columns.len().to_string()));
+        let function = Function::new(
+            "column",
+            &["token: Token"],
+            vec![Stmt::Match(Match::new("token", arms))],
+        )
+        .returns("usize")
+        .doc(" Column of the token in the prediction table.")
+        .attribute("#[allow(unreachable_patterns)]");
+        output.write_all(format!("\n{}", function.to_rust(0)).as_bytes())?;
+
+        // the arguments of long diagnostics are filled like the cells of a row,
+        // which is also how rustfmt breaks arrays of short items
+        let mut code = "\nfn diagnostic(index: u16, span: std::ops::Range<CstIndex>) -> Diagnostic {\n    match index {\n".to_string();
+        for (i, diagnostic) in self.errors.iter().enumerate() {
+            match diagnostic {
+                Expr::List(list) if !fits(2, &format!("{i} => {},", diagnostic.flat())) => {
+                    push_indented(2, &format!("{i} => {}", list.open), &mut code);
+                    Self::push_filled(3, list.items.iter().map(Expr::flat), false, &mut code);
+                    push_indented(2, &format!("{},", list.close), &mut code);
+                }
+                _ => push_indented(2, &format!("{i} => {},", diagnostic.flat()), &mut code),
+            }
+        }
+        code.push_str("        _ => unreachable!(),\n    }\n}\n");
+        output.write_all(code.as_bytes())
     }
 }
@@ -1,8 +1,23 @@
 #![cfg(feature = "cli")]
 
-use clap::{arg, crate_name, crate_version, error::ErrorKind, ArgAction, Command};
+use std::path::Path;
+
+use clap::parser::ValueSource;
+use clap::{arg, crate_name, crate_version, error::ErrorKind, Arg, ArgAction, Command};
+use lelwel::config::{CommandLine, Config};
+use lelwel::frontend::lint::{LintLevel, LintLevels, Warning};
+use lelwel::frontend::sema::ComplexityLimits;
+use lelwel::Options;
 
 fn main() {
+    // flags that can be set in the configuration file also take an explicit
+    // value, so `--table=false` overrides `table = true`
+    let config_flag = |arg: Arg| {
+        arg.value_parser(clap::value_parser!(bool))
+            .num_args(0..=1)
+            .require_equals(true)
+            .default_missing_value("true")
+    };
     let mut cmd = Command::new(crate_name!())
         .max_term_width(80)
         .version(crate_version!())
@@ -10,28 +25,326 @@ fn main() {
         .arg(arg!(-c --check "Only check the file for errors"))
         .arg(arg!(-g --graph "Output a graphviz file for the grammar"))
         .arg(arg!(-s --short "Use short diagnostics"))
+        .arg(
+            arg!(--"message-format" <FORMAT> "Sets the format of diagnostics")
+                .value_parser(["human", "short", "json", "sarif"])
+                .alias("format")
+                .default_value("human")
+                .required(false),
+        )
+        .arg(
+            arg!(--"max-diagnostics" <N> "Sets the maximum number of printed diagnostics")
+                .value_parser(clap::value_parser!(usize))
+                .required(false),
+        )
+        .arg(
+            arg!(--color <WHEN> "Sets when to color diagnostics")
+                .value_parser(["auto", "always", "never"])
+                .default_value("auto")
+                .required(false),
+        )
+        .arg(
+            arg!(-r --report <KIND> "Prints an analysis report of the grammar")
+                .value_parser(["predicates", "tokens", "sets", "reachability"])
+                .required(false),
+        )
+        .arg(
+            arg!(--"max-depth" <N> "Sets the maximum nesting depth of a rule")
+                .value_parser(clap::value_parser!(usize))
+                .required(false),
+        )
+        .arg(
+            arg!(--"max-alternatives" <N> "Sets the maximum number of alternatives in a rule")
+                .value_parser(clap::value_parser!(usize))
+                .required(false),
+        )
+        .args(
+            [
+                arg!(-A --allow <WARNING> "Allows a warning or lint by its name or code"),
+                arg!(-W --warn <WARNING> "Warns about a warning or lint by its name or code"),
+                arg!(-D --deny <WARNING> "Reports a warning or lint as an error"),
+            ]
+            .map(|arg| {
+                arg.value_parser(|s: &str| s.parse::<Warning>())
+                    .action(ArgAction::Append)
+                    .required(false)
+            }),
+        )
+        .arg(config_flag(arg!(
+            --"deny-warnings" [BOOL] "Reports all warnings that are not allowed as errors"
+        )))
+        .arg(config_flag(arg!(
+            --trace [BOOL] "Generates a parser that records a trace for replay"
+        )))
+        .arg(config_flag(
+            arg!(--table [BOOL] "Generates a table-driven parser"),
+        ))
+        .arg(
+            arg!(--replay <TRACE> "Replays a parser trace with the grammar and prints the tree")
+                .required(false),
+        )
+        .arg(
+            arg!(--compat <OLD> "Classifies the changes from an old version of the grammar")
+                .required(false),
+        )
+        .arg(
+            arg!(--"generated-file" <FILE> "Sets the file name of the generated parser")
+                .required(false),
+        )
+        .arg(
+            arg!(--"parser-file" <FILE> "Sets the path of the parser file relative to the input")
+                .required(false),
+        )
+        .arg(config_flag(arg!(
+            --"single-file" [BOOL] "Appends the generated code to the parser file"
+        )))
+        .arg(
+            arg!(--visibility <VIS> "Sets the visibility of the items of the generated parser")
+                .value_parser(["pub", "crate", "private"])
+                .default_value("pub")
+                .required(false),
+        )
+        .arg(config_flag(arg!(
+            --serde [BOOL] "Derives serde traits for the syntax tree behind a serde feature"
+        )))
+        .arg(arg!(--"dry-run" "Prints a diff of the generated files instead of writing them"))
+        .arg(
+            arg!(--"emit-depfile" <FILE> "Writes a dependency file listing the grammar files")
+                .required(false),
+        )
         .arg(arg!(-v --verbose "Sets the level of verbosity").action(ArgAction::Count))
         .arg(
             arg!(-o --output <FILE> "Sets the output directory")
                 .default_value(".")
                 .required(false),
         )
-        .arg(arg!(<INPUT> "Sets the input file to use"))
+        .arg(
+            arg!(--config <FILE> "Sets the project configuration file [default: lelwel.toml]")
+                .required(false),
+        )
+        .arg(
+            arg!([INPUT] "Sets the input files to use instead of the configuration file")
+                .long_help(
+                    "Sets the input files to use instead of the configuration file\n\
+                     With several input files, the generated and parser files of each grammar\n\
+                     are prefixed with its file stem, e.g. `calc_generated.rs`.",
+                )
+                .num_args(1..),
+        )
+        .subcommand(
+            Command::new("run")
+                .about("Interprets the grammar on an input file and prints the syntax tree")
+                .arg(arg!(<GRAMMAR> "Sets the grammar file"))
+                .arg(arg!(<INPUT> "Sets the input file to parse")),
+        )
+        .subcommand(
+            Command::new("import")
+                .about(
+                    "Translates a yacc, bison, or pest grammar to a lelwel grammar and prints it",
+                )
+                .arg(arg!(<GRAMMAR> "Sets the grammar file to import (.y, .yy, or .pest)")),
+        )
+        .subcommand(
+            Command::new("explain")
+                .about("Prints a detailed explanation of a diagnostic code")
+                .arg(arg!(<CODE> "Sets the diagnostic code, e.g. E011")),
+        )
         .after_help("Report bugs to <https://github.com/0x2a-42/lelwel>.");
+    #[cfg(feature = "lsp")]
+    {
+        cmd = cmd.subcommand(
+            Command::new("lsp").about("Runs the language server over stdin and stdout"),
+        );
+    }
 
     let matches = cmd.get_matches_mut();
 
-    let input = matches.get_one::<String>("INPUT").unwrap();
+    #[cfg(feature = "lsp")]
+    if let Some(("lsp", _)) = matches.subcommand() {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(lelwel::ide::serve());
+        return;
+    }
+
+    if let Some(("explain", explain)) = matches.subcommand() {
+        let code = explain.get_one::<String>("CODE").unwrap();
+        match lelwel::frontend::explain::explain(code) {
+            Some(explanation) => println!("{explanation}"),
+            None => cmd
+                .error(
+                    ErrorKind::InvalidValue,
+                    format!("no explanation for `{code}`"),
+                )
+                .exit(),
+        }
+        return;
+    }
+
+    let inputs: Vec<String> = matches
+        .get_many::<String>("INPUT")
+        .map_or(vec![], |inputs| inputs.cloned().collect());
     let output = matches.get_one::<String>("output").unwrap();
-    match lelwel::compile(
-        input,
-        output,
-        matches.get_flag("check"),
-        matches.get_count("verbose"),
-        matches.get_flag("graph"),
-        matches.get_flag("short"),
-    ) {
-        Ok(success) => std::process::exit(if success { 0 } else { 1 }),
-        Err(e) => cmd.error(ErrorKind::InvalidValue, format!("{}", e)).exit(),
+    let mut limits = ComplexityLimits::default();
+    if let Some(max_depth) = matches.get_one::<usize>("max-depth") {
+        limits.max_depth = *max_depth;
+    }
+    if let Some(max_alternatives) = matches.get_one::<usize>("max-alternatives") {
+        limits.max_alternatives = *max_alternatives;
+    }
+    // later lint arguments take precedence over earlier ones
+    let mut lint_args = vec![];
+    for (arg, level) in [
+        ("allow", LintLevel::Allow),
+        ("warn", LintLevel::Warn),
+        ("deny", LintLevel::Deny),
+    ] {
+        if let (Some(indices), Some(values)) =
+            (matches.indices_of(arg), matches.get_many::<Warning>(arg))
+        {
+            lint_args.extend(
+                indices
+                    .zip(values)
+                    .map(|(index, warning)| (index, *warning, level)),
+            );
+        }
+    }
+    lint_args.sort_by_key(|(index, _, _)| *index);
+    let lint_args: Vec<_> = lint_args
+        .into_iter()
+        .map(|(_, warning, level)| (warning, level))
+        .collect();
+    let mut lints = LintLevels::default();
+    for (warning, level) in lint_args.iter() {
+        lints.set_warning(*warning, *level);
+    }
+    let config_flag = |id| matches.get_one::<bool>(id).copied();
+    lints.deny_warnings = config_flag("deny-warnings").unwrap_or_default();
+    let options = Options {
+        check: matches.get_flag("check"),
+        verbose: matches.get_count("verbose"),
+        graph: matches.get_flag("graph"),
+        short: matches.get_flag("short")
+            || matches.get_one::<String>("message-format").unwrap() == "short",
+        json: matches.get_one::<String>("message-format").unwrap() == "json",
+        sarif: matches.get_one::<String>("message-format").unwrap() == "sarif",
+        max_diagnostics: matches.get_one::<usize>("max-diagnostics").copied(),
+        color: matches.get_one::<String>("color").unwrap().parse().unwrap(),
+        report: matches
+            .get_one::<String>("report")
+            .map(|kind| kind.parse().unwrap()),
+        limits,
+        lints,
+        trace: config_flag("trace").unwrap_or_default(),
+        table: config_flag("table").unwrap_or_default(),
+        replay: matches.get_one::<String>("replay").cloned(),
+        dry_run: matches.get_flag("dry-run"),
+        run: None,
+        depfile: matches.get_one::<String>("emit-depfile").cloned(),
+        compat: matches.get_one::<String>("compat").cloned(),
+        generated_file: matches.get_one::<String>("generated-file").cloned(),
+        parser_file: matches.get_one::<String>("parser-file").cloned(),
+        single_file: config_flag("single-file").unwrap_or_default(),
+        visibility: matches
+            .get_one::<String>("visibility")
+            .unwrap()
+            .parse()
+            .unwrap(),
+        serde: config_flag("serde").unwrap_or_default(),
+    };
+    if let Some(("run", run)) = matches.subcommand() {
+        let grammar = run.get_one::<String>("GRAMMAR").unwrap();
+        let options = Options {
+            check: true,
+            run: run.get_one::<String>("INPUT").cloned(),
+            ..options
+        };
+        match lelwel::compile_with_options(grammar, output, &options) {
+            Ok(success) => std::process::exit(if success { 0 } else { 1 }),
+            Err(e) => cmd.error(ErrorKind::InvalidValue, format!("{}", e)).exit(),
+        }
+    }
+    if let Some(("import", import)) = matches.subcommand() {
+        let grammar = import.get_one::<String>("GRAMMAR").unwrap();
+        match lelwel::import_grammar(grammar, &options) {
+            Ok(success) => std::process::exit(if success { 0 } else { 1 }),
+            Err(e) => cmd.error(ErrorKind::InvalidValue, format!("{}", e)).exit(),
+        }
+    }
+    let grammars: Vec<_> = if inputs.is_empty() {
+        let path = matches
+            .get_one::<String>("config")
+            .map_or("lelwel.toml", |path| path.as_str());
+        let config =
+            Config::load(path).unwrap_or_else(|err| cmd.error(ErrorKind::InvalidValue, err).exit());
+        let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        let is_set = |arg| matches.value_source(arg) == Some(ValueSource::CommandLine);
+        let command_line = CommandLine {
+            output: is_set("output").then(|| output.clone()),
+            max_depth: matches.get_one::<usize>("max-depth").copied(),
+            max_alternatives: matches.get_one::<usize>("max-alternatives").copied(),
+            visibility: is_set("visibility").then_some(options.visibility),
+            table: config_flag("table"),
+            trace: config_flag("trace"),
+            single_file: config_flag("single-file"),
+            serde: config_flag("serde"),
+            deny_warnings: config_flag("deny-warnings"),
+            lints: lint_args,
+        };
+        config
+            .grammars(dir, &options, &command_line)
+            .into_iter()
+            .map(|grammar| (grammar.path, grammar.output, grammar.options))
+            .collect()
+    } else if matches.contains_id("config") {
+        cmd.error(
+            ErrorKind::ArgumentConflict,
+            "--config cannot be used with input files",
+        )
+        .exit()
+    } else if let [input] = &inputs[..] {
+        vec![(input.clone(), output.clone(), options)]
+    } else {
+        inputs
+            .iter()
+            .map(|input| {
+                // prefix the files, so grammars in one directory do not collide
+                let stem = Path::new(input)
+                    .file_stem()
+                    .map_or("".into(), |stem| stem.to_string_lossy());
+                let options = Options {
+                    generated_file: Some(format!("{stem}_generated.rs")),
+                    parser_file: Some(format!("{stem}_parser.rs")),
+                    ..options.clone()
+                };
+                (input.clone(), output.clone(), options)
+            })
+            .collect()
+    };
+    if let [(input, output, options)] = &grammars[..] {
+        match lelwel::compile_with_options(input, output, options) {
+            Ok(success) => std::process::exit(if success { 0 } else { 1 }),
+            Err(e) => cmd.error(ErrorKind::InvalidValue, format!("{}", e)).exit(),
+        }
+    }
+    for arg in [
+        "graph",
+        "report",
+        "replay",
+        "compat",
+        "verbose",
+        "generated-file",
+        "parser-file",
+        "emit-depfile",
+    ] {
+        if matches.value_source(arg) == Some(ValueSource::CommandLine) {
+            cmd.error(
+                ErrorKind::ArgumentConflict,
+                format!("--{arg} cannot be used with multiple grammars"),
+            )
+            .exit();
+        }
     }
+    let success = lelwel::compile_all(&grammars);
+    std::process::exit(if success { 0 } else { 1 })
 }
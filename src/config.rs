@@ -0,0 +1,208 @@
+#![cfg(feature = "config")]
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::backend::rust::Visibility;
+use crate::frontend::lint::{LintLevel, Warning};
+use crate::Options;
+
+/// Options of a grammar in a `lelwel.toml` file, which are set for all
+/// grammars in the `[defaults]` table and for each grammar in a `[[grammar]]`
+/// table.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct GrammarConfig {
+    path: Option<String>,
+    output: Option<String>,
+    table: Option<bool>,
+    trace: Option<bool>,
+    max_depth: Option<usize>,
+    max_alternatives: Option<usize>,
+    generated_file: Option<String>,
+    parser_file: Option<String>,
+    single_file: Option<bool>,
+    visibility: Option<String>,
+    serde: Option<bool>,
+    #[serde(default)]
+    lints: BTreeMap<String, String>,
+    deny_warnings: Option<bool>,
+}
+
+impl GrammarConfig {
+    /// Applies the options that are not set on the `command_line`.
+    fn apply(&self, output: &mut String, options: &mut Options, command_line: &CommandLine) {
+        if let Some(value) = &self.output {
+            output.clone_from(value);
+        }
+        if let Some(value) = command_line.table.or(self.table) {
+            options.table = value;
+        }
+        if let Some(value) = command_line.trace.or(self.trace) {
+            options.trace = value;
+        }
+        if let Some(value) = command_line.max_depth.or(self.max_depth) {
+            options.limits.max_depth = value;
+        }
+        if let Some(value) = command_line.max_alternatives.or(self.max_alternatives) {
+            options.limits.max_alternatives = value;
+        }
+        if let (Some(value), None) = (&self.generated_file, &options.generated_file) {
+            options.generated_file = Some(value.clone());
+        }
+        if let (Some(value), None) = (&self.parser_file, &options.parser_file) {
+            options.parser_file = Some(value.clone());
+        }
+        if let Some(value) = command_line.single_file.or(self.single_file) {
+            options.single_file = value;
+        }
+        if let Some(value) = command_line.visibility {
+            options.visibility = value;
+        } else if let Some(value) = &self.visibility {
+            options.visibility = value.parse().unwrap();
+        }
+        if let Some(value) = command_line.serde.or(self.serde) {
+            options.serde = value;
+        }
+        if let Some(value) = command_line.deny_warnings.or(self.deny_warnings) {
+            options.lints.deny_warnings = value;
+        }
+        for (warning, level) in self.lints.iter() {
+            options
+                .lints
+                .set_warning(warning.parse().unwrap(), level.parse().unwrap());
+        }
+        // lints on the command line take precedence over the configuration
+        for (warning, level) in command_line.lints.iter() {
+            options.lints.set_warning(*warning, *level);
+        }
+    }
+
+    /// Options of a grammar that override the `defaults`.
+    fn or(&self, defaults: &GrammarConfig) -> GrammarConfig {
+        let mut lints = defaults.lints.clone();
+        lints.extend(self.lints.clone());
+        GrammarConfig {
+            path: self.path.clone(),
+            output: self.output.clone().or(defaults.output.clone()),
+            table: self.table.or(defaults.table),
+            trace: self.trace.or(defaults.trace),
+            max_depth: self.max_depth.or(defaults.max_depth),
+            max_alternatives: self.max_alternatives.or(defaults.max_alternatives),
+            generated_file: self
+                .generated_file
+                .clone()
+                .or(defaults.generated_file.clone()),
+            parser_file: self.parser_file.clone().or(defaults.parser_file.clone()),
+            single_file: self.single_file.or(defaults.single_file),
+            visibility: self.visibility.clone().or(defaults.visibility.clone()),
+            serde: self.serde.or(defaults.serde),
+            lints,
+            deny_warnings: self.deny_warnings.or(defaults.deny_warnings),
+        }
+    }
+}
+
+/// Options that are given on the command line and take precedence over the
+/// configuration, besides the file names of the [`Options`].
+///
+/// Flags are `None` if they are not given, and `Some(false)` if they are
+/// given as `false`, which also overrides the configuration.
+#[derive(Default)]
+pub struct CommandLine {
+    pub output: Option<String>,
+    pub max_depth: Option<usize>,
+    pub max_alternatives: Option<usize>,
+    pub visibility: Option<Visibility>,
+    pub table: Option<bool>,
+    pub trace: Option<bool>,
+    pub single_file: Option<bool>,
+    pub serde: Option<bool>,
+    pub deny_warnings: Option<bool>,
+    /// Lint levels in the order of the arguments.
+    pub lints: Vec<(Warning, LintLevel)>,
+}
+
+/// Project configuration, which records the grammars of a project and the
+/// options to compile them with.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    defaults: GrammarConfig,
+    #[serde(default)]
+    grammar: Vec<GrammarConfig>,
+}
+
+/// Grammar of a project with the output directory and the options it is
+/// compiled with.
+pub struct Grammar {
+    pub path: String,
+    pub output: String,
+    pub options: Options,
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+        let config: Config = toml::from_str(&source).map_err(|err| format!("{path}: {err}"))?;
+        if config.defaults.path.is_some() {
+            return Err(format!("{path}: `path` cannot be set in `[defaults]`"));
+        }
+        if config.grammar.iter().any(|grammar| grammar.path.is_none()) {
+            return Err(format!("{path}: missing `path` in `[[grammar]]`"));
+        }
+        for grammar in std::iter::once(&config.defaults).chain(config.grammar.iter()) {
+            for (warning, level) in grammar.lints.iter() {
+                warning
+                    .parse::<Warning>()
+                    .and(level.parse::<LintLevel>())
+                    .map_err(|err| format!("{path}: {err}"))?;
+            }
+            if let Some(visibility) = &grammar.visibility {
+                visibility
+                    .parse::<Visibility>()
+                    .map_err(|err| format!("{path}: {err}"))?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Returns the grammars with the `options` of the command line, where
+    /// options that are not set on the `command_line` are taken from the
+    /// configuration.
+    ///
+    /// Grammar paths and output directories are relative to the directory
+    /// `dir` of the configuration file, while an output directory on the
+    /// command line is used as is.
+    pub fn grammars(
+        &self,
+        dir: &Path,
+        options: &Options,
+        command_line: &CommandLine,
+    ) -> Vec<Grammar> {
+        self.grammar
+            .iter()
+            .map(|grammar| {
+                let mut output = ".".to_string();
+                let mut options = options.clone();
+                grammar
+                    .or(&self.defaults)
+                    .apply(&mut output, &mut options, command_line);
+                Grammar {
+                    path: dir
+                        .join(grammar.path.as_ref().unwrap())
+                        .to_string_lossy()
+                        .into_owned(),
+                    output: match &command_line.output {
+                        Some(output) => output.clone(),
+                        None => dir.join(output).to_string_lossy().into_owned(),
+                    },
+                    options,
+                }
+            })
+            .collect()
+    }
+}
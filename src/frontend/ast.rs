@@ -30,7 +30,7 @@ macro_rules! ast_node {
             }
         }
     };
-    ($node_name:ident, $rule_name:ident, $token_name:ident) => {
+    ($node_name:ident, $rule_name:ident, $($token_name:ident)|+) => {
         #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Ord, PartialOrd)]
         pub struct $node_name {
             syntax: NodeRef,
@@ -41,7 +41,9 @@ macro_rules! ast_node {
                     Node::Rule(Rule::$rule_name, _)
                         if cst
                             .children(syntax)
-                            .find_map(|n| cst.get_token(n, Token::$token_name))
+                            .find_map(|n| {
+                                None$(.or_else(|| cst.get_token(n, Token::$token_name)))+
+                            })
                             .is_some() =>
                     {
                         Some(Self { syntax })
@@ -83,17 +85,28 @@ ast_node!(RuleDecl);
 ast_node!(StartDecl);
 ast_node!(RightDecl);
 ast_node!(SkipDecl);
+ast_node!(InsertDecl);
+ast_node!(SyncDecl);
+ast_node!(LookaheadDecl);
+ast_node!(MaxErrorsDecl);
+ast_node!(TokenDeriveDecl);
+ast_node!(ImportDecl);
+ast_node!(Attribute);
 ast_node!(
     Regex,
     (
         Alternation,
         Concat,
         Paren,
+        SyntacticPredicate,
         Optional,
         Star,
         Plus,
+        Separated,
+        Bounded,
         Name,
         Symbol,
+        Wildcard,
         Predicate,
         Action,
         Binding,
@@ -104,13 +117,17 @@ ast_node!(
 ast_node!(Alternation);
 ast_node!(Concat);
 ast_node!(Paren);
+ast_node!(SyntacticPredicate);
 ast_node!(Optional);
 ast_node!(Star, Postfix, Star);
 ast_node!(Plus, Postfix, Plus);
+ast_node!(Separated, Postfix, Percent);
+ast_node!(Bounded, Postfix, LBrace);
 ast_node!(Name, Atomic, Id);
 ast_node!(Symbol, Atomic, Str);
+ast_node!(Wildcard, Atomic, Dot);
 ast_node!(Predicate, Atomic, Predicate);
-ast_node!(Action, Atomic, Action);
+ast_node!(Action, Atomic, Action | InlineAction | TaggedAction);
 ast_node!(Binding, Atomic, Binding);
 ast_node!(OpenNode, Atomic, OpenNode);
 ast_node!(CloseNode, Atomic, CloseNode);
@@ -122,7 +139,7 @@ impl Cst<'_> {
     fn child_node_iter<T: AstNode>(
         &self,
         syntax: NodeRef,
-    ) -> std::iter::FilterMap<CstChildren, impl FnMut(NodeRef) -> Option<T> + '_> {
+    ) -> std::iter::FilterMap<CstChildren<'_>, impl FnMut(NodeRef) -> Option<T> + '_> {
         self.children(syntax).filter_map(|c| T::cast(self, c))
     }
     fn child_token(&self, syntax: NodeRef, token: Token) -> Option<(&str, Span)> {
@@ -133,7 +150,39 @@ impl Cst<'_> {
 pub trait Named: AstNode {
     fn name<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)>;
 }
+/// Declaration that may be preceded by attributes like `#[allow(unused)]`.
+pub trait Attributed: AstNode {
+    fn attributes<'a>(
+        &self,
+        cst: &'a Cst,
+    ) -> std::iter::FilterMap<CstChildren<'a>, impl FnMut(NodeRef) -> Option<Attribute> + 'a> {
+        cst.child_node_iter(self.syntax())
+    }
+    /// Checks if the warning with the `name` is allowed by an attribute.
+    fn allows(&self, cst: &Cst, name: &str) -> bool {
+        self.attributes(cst).any(|attr| {
+            attr.name(cst)
+                .is_some_and(|(attr_name, _)| attr_name == "allow")
+                && attr.arguments(cst).any(|(arg, _)| arg == name)
+        })
+    }
+}
+impl Attributed for TokenDecl {}
+impl Attributed for RuleDecl {}
+impl Attributed for Concat {}
+impl Attribute {
+    pub fn name<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
+        cst.child_token(self.syntax, Token::Id)
+    }
+    /// Names in the parentheses of the attribute.
+    pub fn arguments<'a>(&self, cst: &'a Cst) -> impl Iterator<Item = (&'a str, Span)> + 'a {
+        cst.children(self.syntax)
+            .filter_map(|c| cst.get_token(c, Token::Id))
+            .skip(1)
+    }
+}
 impl File {
+    #[allow(clippy::type_complexity)]
     pub fn token_decls<'a>(
         &self,
         cst: &'a Cst,
@@ -172,6 +221,45 @@ impl File {
     ) -> std::iter::FilterMap<CstChildren<'a>, impl FnMut(NodeRef) -> Option<SkipDecl> + 'a> {
         cst.child_node_iter(self.syntax)
     }
+    pub fn insert_decls<'a>(
+        &self,
+        cst: &'a Cst,
+    ) -> std::iter::FilterMap<CstChildren<'a>, impl FnMut(NodeRef) -> Option<InsertDecl> + 'a> {
+        cst.child_node_iter(self.syntax)
+    }
+    pub fn sync_decls<'a>(
+        &self,
+        cst: &'a Cst,
+    ) -> std::iter::FilterMap<CstChildren<'a>, impl FnMut(NodeRef) -> Option<SyncDecl> + 'a> {
+        cst.child_node_iter(self.syntax)
+    }
+    pub fn lookahead_decls<'a>(
+        &self,
+        cst: &'a Cst,
+    ) -> std::iter::FilterMap<CstChildren<'a>, impl FnMut(NodeRef) -> Option<LookaheadDecl> + 'a>
+    {
+        cst.child_node_iter(self.syntax)
+    }
+    pub fn max_errors_decls<'a>(
+        &self,
+        cst: &'a Cst,
+    ) -> std::iter::FilterMap<CstChildren<'a>, impl FnMut(NodeRef) -> Option<MaxErrorsDecl> + 'a>
+    {
+        cst.child_node_iter(self.syntax)
+    }
+    pub fn token_derive_decls<'a>(
+        &self,
+        cst: &'a Cst,
+    ) -> std::iter::FilterMap<CstChildren<'a>, impl FnMut(NodeRef) -> Option<TokenDeriveDecl> + 'a>
+    {
+        cst.child_node_iter(self.syntax)
+    }
+    pub fn import_decls<'a>(
+        &self,
+        cst: &'a Cst,
+    ) -> std::iter::FilterMap<CstChildren<'a>, impl FnMut(NodeRef) -> Option<ImportDecl> + 'a> {
+        cst.child_node_iter(self.syntax)
+    }
 }
 impl Named for TokenDecl {
     fn name<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
@@ -182,6 +270,22 @@ impl TokenDecl {
     pub fn symbol<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
         cst.child_token(self.syntax, Token::Str)
     }
+    pub fn description<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
+        cst.children(self.syntax)
+            .filter_map(|c| cst.get_token(c, Token::Str))
+            .nth(1)
+    }
+    pub fn regex<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
+        cst.child_token(self.syntax, Token::Regex)
+    }
+    /// Checks if the symbol is matched case-insensitively, which is set with
+    /// the `ignore_case` attribute.
+    pub fn ignores_case(&self, cst: &Cst) -> bool {
+        self.attributes(cst).any(|attr| {
+            attr.name(cst)
+                .is_some_and(|(name, _)| name == "ignore_case")
+        })
+    }
 }
 impl Named for RuleDecl {
     fn name<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
@@ -192,6 +296,18 @@ impl RuleDecl {
     pub fn regex(&self, cst: &Cst) -> Option<Regex> {
         cst.child_node(self.syntax)
     }
+    /// Checks if the rule is an entry point of the generated parser, which is
+    /// set with the `entry` attribute.
+    pub fn is_entry(&self, cst: &Cst) -> bool {
+        self.attributes(cst)
+            .any(|attr| attr.name(cst).is_some_and(|(name, _)| name == "entry"))
+    }
+    /// Checks if the rule is expanded at its references, which is set with
+    /// the `inline` attribute.
+    pub fn is_inline(&self, cst: &Cst) -> bool {
+        self.attributes(cst)
+            .any(|attr| attr.name(cst).is_some_and(|(name, _)| name == "inline"))
+    }
 }
 impl StartDecl {
     pub fn rule_name<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
@@ -218,6 +334,55 @@ impl SkipDecl {
             .for_each(f);
     }
 }
+impl InsertDecl {
+    pub fn token_names<'a, F: FnMut((&'a str, Span))>(&self, cst: &'a Cst, f: F) {
+        cst.children(self.syntax)
+            .filter_map(|c| {
+                cst.get_token(c, Token::Id)
+                    .or_else(|| cst.get_token(c, Token::Str))
+            })
+            .for_each(f);
+    }
+}
+impl SyncDecl {
+    pub fn token_names<'a, F: FnMut((&'a str, Span))>(&self, cst: &'a Cst, f: F) {
+        cst.children(self.syntax)
+            .filter_map(|c| {
+                cst.get_token(c, Token::Id)
+                    .or_else(|| cst.get_token(c, Token::Str))
+            })
+            .for_each(f);
+    }
+}
+impl LookaheadDecl {
+    pub fn value<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
+        cst.child_token(self.syntax, Token::Number)
+    }
+}
+impl MaxErrorsDecl {
+    pub fn value<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
+        cst.child_token(self.syntax, Token::Number)
+    }
+}
+impl TokenDeriveDecl {
+    /// Names of the derive macros.
+    pub fn derives<'a, F: FnMut((&'a str, Span))>(&self, cst: &'a Cst, f: F) {
+        cst.children(self.syntax)
+            .filter_map(|c| cst.get_token(c, Token::Id))
+            .for_each(f);
+    }
+    /// Strings with the content of additional attributes.
+    pub fn attributes<'a, F: FnMut((&'a str, Span))>(&self, cst: &'a Cst, f: F) {
+        cst.children(self.syntax)
+            .filter_map(|c| cst.get_token(c, Token::Str))
+            .for_each(f);
+    }
+}
+impl ImportDecl {
+    pub fn path<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
+        cst.child_token(self.syntax, Token::Str)
+    }
+}
 impl Alternation {
     pub fn operands<'a>(
         &self,
@@ -239,6 +404,16 @@ impl Paren {
         cst.child_node(self.syntax)
     }
 }
+impl SyntacticPredicate {
+    pub fn inner(&self, cst: &Cst) -> Option<Regex> {
+        cst.child_node(self.syntax)
+    }
+    /// Checks if the predicate holds when the input does not start with the
+    /// regex (`!(A B)`).
+    pub fn is_negative(&self, cst: &Cst) -> bool {
+        cst.child_token(self.syntax, Token::Not).is_some()
+    }
+}
 impl Optional {
     pub fn operand(&self, cst: &Cst) -> Option<Regex> {
         cst.child_node(self.syntax)
@@ -254,6 +429,49 @@ impl Plus {
         cst.child_node(self.syntax)
     }
 }
+impl Separated {
+    pub fn operand(&self, cst: &Cst) -> Option<Regex> {
+        cst.child_node(self.syntax)
+    }
+    pub fn separator(&self, cst: &Cst) -> Option<Regex> {
+        cst.child_node_iter(self.syntax).nth(1)
+    }
+}
+impl Bounded {
+    pub fn operand(&self, cst: &Cst) -> Option<Regex> {
+        cst.child_node(self.syntax)
+    }
+    /// Minimum and maximum number of repetitions, where the maximum is `None`
+    /// for an unbounded repetition like `{2,}`.
+    pub fn bounds(&self, cst: &Cst) -> Option<(usize, Option<usize>)> {
+        let mut numbers = cst
+            .children(self.syntax)
+            .filter_map(|c| cst.get_token(c, Token::Number))
+            .map(|(value, _)| value.parse().unwrap_or(usize::MAX));
+        let min = numbers.next()?;
+        let max = if cst.child_token(self.syntax, Token::Comma).is_some() {
+            numbers.next()
+        } else {
+            Some(min)
+        };
+        Some((min, max))
+    }
+    /// Formats the bounds without whitespace, like `{2,4}`.
+    pub fn bounds_text(&self, cst: &Cst) -> String {
+        match self.bounds(cst) {
+            Some((min, Some(max))) if min == max => format!("{{{min}}}"),
+            Some((min, Some(max))) => format!("{{{min},{max}}}"),
+            Some((min, None)) => format!("{{{min},}}"),
+            None => String::new(),
+        }
+    }
+    /// Span of the bounds from `{` to `}`.
+    pub fn bounds_span(&self, cst: &Cst) -> Option<Span> {
+        let (_, start) = cst.child_token(self.syntax, Token::LBrace)?;
+        let (_, end) = cst.child_token(self.syntax, Token::RBrace)?;
+        Some(start.start..end.end)
+    }
+}
 impl Name {
     pub fn value<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
         cst.child_token(self.syntax, Token::Id)
@@ -273,6 +491,21 @@ impl Action {
     pub fn value<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
         cst.child_token(self.syntax, Token::Action)
     }
+    /// Returns the code block of an inline action (`=> { code }`).
+    pub fn code<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
+        cst.child_token(self.syntax, Token::InlineAction)
+            .map(|(code, span)| (code[2..].trim_start(), span))
+    }
+    /// Returns the number, language, and code block of a tagged action
+    /// (`#1:rust { code }`).
+    pub fn tagged<'a>(&self, cst: &'a Cst) -> Option<(&'a str, &'a str, &'a str, Span)> {
+        cst.child_token(self.syntax, Token::TaggedAction)
+            .and_then(|(text, span)| {
+                let (tag, code) = text.split_at(text.find('{')?);
+                let (number, language) = tag[1..].trim_end().split_once(':')?;
+                Some((number, language, code, span))
+            })
+    }
 }
 impl Binding {
     pub fn value<'a>(&self, cst: &'a Cst) -> Option<(&'a str, Span)> {
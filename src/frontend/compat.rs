@@ -0,0 +1,429 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::ast::*;
+use super::parser::*;
+use super::sema::*;
+
+/// Classification of the changes between two versions of a grammar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Compatibility {
+    /// The grammars are identical.
+    Unchanged,
+    /// Only actions, bindings, or node markers changed, so the accepted
+    /// language is the same.
+    ActionOnly,
+    /// The new grammar accepts a superset of the old language.
+    Compatible,
+    /// The new grammar may reject inputs accepted by the old grammar.
+    Breaking,
+}
+
+impl std::fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Compatibility::Unchanged => "unchanged",
+                Compatibility::ActionOnly => "action-only",
+                Compatibility::Compatible => "compatible",
+                Compatibility::Breaking => "breaking",
+            }
+        )
+    }
+}
+
+/// Language relevant structure of a rule regex.
+#[derive(Debug, PartialEq, Eq)]
+enum Term<'a> {
+    Token(&'a str),
+    Wildcard,
+    Rule(&'a str),
+    Predicate(&'a str),
+    Seq(Vec<Term<'a>>),
+    Alt(Vec<Term<'a>>),
+    Star(Box<Term<'a>>),
+    Plus(Box<Term<'a>>),
+    Opt(Box<Term<'a>>),
+    /// Repetition with the minimum and the optional maximum count.
+    Bounded(Box<Term<'a>>, usize, Option<usize>),
+}
+
+impl<'a> Term<'a> {
+    fn new(cst: &'a Cst, sema: &SemanticData, regex: Regex) -> Self {
+        let boxed = |op: Option<Regex>| Box::new(Self::new(cst, sema, op.unwrap()));
+        let decl_name = |node: NodeRef| {
+            let decl = sema.decl_bindings.get(&node).copied();
+            if let Some(rule) = decl.and_then(|decl| RuleDecl::cast(cst, decl)) {
+                Term::Rule(rule.name(cst).unwrap_or_default().0)
+            } else if let Some(token) = decl.and_then(|decl| TokenDecl::cast(cst, decl)) {
+                Term::Token(token.name(cst).unwrap_or_default().0)
+            } else {
+                Term::Seq(vec![])
+            }
+        };
+        match regex {
+            Regex::Name(name) => decl_name(name.syntax()),
+            Regex::Symbol(sym) => decl_name(sym.syntax()),
+            Regex::Wildcard(_) => Term::Wildcard,
+            Regex::Predicate(pred) => Term::Predicate(pred.value(cst).unwrap_or_default().0),
+            Regex::Concat(concat) => {
+                let mut ops = concat
+                    .operands(cst)
+                    .map(|op| Self::new(cst, sema, op))
+                    .filter(|term| *term != Term::Seq(vec![]))
+                    .collect::<Vec<_>>();
+                if ops.len() == 1 {
+                    ops.pop().unwrap()
+                } else {
+                    Term::Seq(ops)
+                }
+            }
+            Regex::Alternation(alt) => Term::Alt(
+                alt.operands(cst)
+                    .map(|op| Self::new(cst, sema, op))
+                    .collect(),
+            ),
+            Regex::Star(star) => Term::Star(boxed(star.operand(cst))),
+            Regex::Plus(plus) => Term::Plus(boxed(plus.operand(cst))),
+            Regex::Optional(opt) => Term::Opt(boxed(opt.operand(cst))),
+            Regex::Bounded(bounded) => {
+                let (min, max) = bounded.bounds(cst).unwrap_or((0, None));
+                Term::Bounded(boxed(bounded.operand(cst)), min, max)
+            }
+            Regex::Separated(sep) => {
+                // `a % b` is compared as `a (b a)*`
+                let op = || Self::new(cst, sema, sep.operand(cst).unwrap());
+                let separator = Self::new(cst, sema, sep.separator(cst).unwrap());
+                Term::Seq(vec![
+                    op(),
+                    Term::Star(Box::new(Term::Seq(vec![separator, op()]))),
+                ])
+            }
+            Regex::Paren(paren) => Self::new(cst, sema, paren.inner(cst).unwrap()),
+            _ => Term::Seq(vec![]),
+        }
+    }
+
+    fn is_nullable(&self) -> bool {
+        match self {
+            Term::Star(_) | Term::Opt(_) | Term::Predicate(_) => true,
+            Term::Seq(ops) => ops.iter().all(Term::is_nullable),
+            Term::Alt(ops) => ops.iter().any(Term::is_nullable),
+            Term::Plus(op) => op.is_nullable(),
+            Term::Bounded(op, min, _) => *min == 0 || op.is_nullable(),
+            Term::Token(_) | Term::Wildcard | Term::Rule(_) => false,
+        }
+    }
+
+    fn predicates(&self, predicates: &mut BTreeSet<&'a str>) {
+        match self {
+            Term::Predicate(pred) => {
+                predicates.insert(pred);
+            }
+            Term::Seq(ops) | Term::Alt(ops) => ops.iter().for_each(|op| op.predicates(predicates)),
+            Term::Star(op) | Term::Plus(op) | Term::Opt(op) | Term::Bounded(op, ..) => {
+                op.predicates(predicates)
+            }
+            Term::Token(_) | Term::Wildcard | Term::Rule(_) => {}
+        }
+    }
+
+    /// Checks if the language of `self` is a superset of the language of `old`,
+    /// assuming that the referenced rules are supersets of their old versions.
+    ///
+    /// The check is conservative, so it may fail for equivalent regexes with a
+    /// different structure.
+    fn includes(&self, old: &Term) -> bool {
+        if self == old {
+            return true;
+        }
+        match (self, old) {
+            (_, Term::Alt(olds)) if olds.iter().all(|old| self.includes(old)) => true,
+            (Term::Alt(news), _) => news.iter().any(|new| new.includes(old)),
+            (Term::Wildcard, Term::Token(_)) => true,
+            (Term::Opt(new), Term::Opt(old)) => new.includes(old),
+            (Term::Star(new), Term::Star(old) | Term::Plus(old) | Term::Opt(old)) => {
+                new.includes(old)
+            }
+            (Term::Plus(new), Term::Plus(old)) => new.includes(old),
+            (Term::Star(new), Term::Bounded(old, ..)) => new.includes(old),
+            (Term::Plus(new), Term::Bounded(old, min, _)) if *min > 0 => new.includes(old),
+            (Term::Bounded(new, new_min, new_max), Term::Bounded(old, old_min, old_max)) => {
+                new_min <= old_min
+                    && match (new_max, old_max) {
+                        (None, _) => true,
+                        (Some(new_max), Some(old_max)) => new_max >= old_max,
+                        (Some(_), None) => false,
+                    }
+                    && new.includes(old)
+            }
+            (Term::Bounded(new, 0, None), Term::Star(old) | Term::Plus(old) | Term::Opt(old))
+            | (Term::Bounded(new, 1, None), Term::Plus(old))
+            | (Term::Bounded(new, 0, Some(1..)), Term::Opt(old)) => new.includes(old),
+            (Term::Seq(news), Term::Seq(olds)) => Self::includes_seq(news, olds),
+            (Term::Seq(news), _) => Self::includes_seq(news, std::slice::from_ref(old)),
+            (Term::Opt(new) | Term::Star(new) | Term::Plus(new), _) => new.includes(old),
+            (Term::Bounded(new, 0 | 1, max), _) if *max != Some(0) => new.includes(old),
+            _ => false,
+        }
+    }
+
+    fn includes_seq(news: &[Term], olds: &[Term]) -> bool {
+        match (news.split_first(), olds.split_first()) {
+            (None, None) => true,
+            (None, Some(_)) => false,
+            (Some((new, news_rest)), _) => {
+                if let Some((old, olds_rest)) = olds.split_first() {
+                    if new.includes(old) && Self::includes_seq(news_rest, olds_rest) {
+                        return true;
+                    }
+                }
+                new.is_nullable()
+                    && !matches!(new, Term::Predicate(_))
+                    && Self::includes_seq(news_rest, olds)
+            }
+        }
+    }
+}
+
+/// Declarations of a grammar that are relevant for the comparison.
+struct Grammar<'a> {
+    tokens: BTreeMap<&'a str, Option<&'a str>>,
+    regexes: BTreeMap<&'a str, &'a str>,
+    skipped: BTreeSet<&'a str>,
+    rules: BTreeMap<&'a str, (RuleDecl, Term<'a>)>,
+    start: Option<&'a str>,
+}
+
+impl<'a> Grammar<'a> {
+    fn new(cst: &'a Cst, sema: &SemanticData) -> Self {
+        let mut grammar = Self {
+            tokens: BTreeMap::new(),
+            regexes: BTreeMap::new(),
+            skipped: BTreeSet::new(),
+            rules: BTreeMap::new(),
+            start: sema
+                .start
+                .and_then(|start| start.name(cst))
+                .map(|(name, _)| name),
+        };
+        let Some(file) = File::cast(cst, NodeRef::ROOT) else {
+            return grammar;
+        };
+        for token in file.token_decls(cst) {
+            if let Some((name, _)) = token.name(cst) {
+                grammar
+                    .tokens
+                    .insert(name, token.symbol(cst).map(|(sym, _)| sym));
+                if let Some((regex, _)) = token.regex(cst) {
+                    grammar.regexes.insert(name, regex);
+                }
+                if sema.skipped.contains(&token) {
+                    grammar.skipped.insert(name);
+                }
+            }
+        }
+        for rule in file.rule_decls(cst) {
+            if let (Some((name, _)), Some(regex)) = (rule.name(cst), rule.regex(cst)) {
+                grammar
+                    .rules
+                    .insert(name, (rule, Term::new(cst, sema, regex)));
+            }
+        }
+        grammar
+    }
+}
+
+pub struct Compat;
+
+impl Compat {
+    /// Classifies the changes from the old to the new grammar and prints the
+    /// reasons for the classification.
+    pub fn run<W: std::io::Write + ?Sized>(
+        old_source: &str,
+        old_cst: &Cst,
+        old_sema: &SemanticData,
+        new_source: &str,
+        new_cst: &Cst,
+        new_sema: &SemanticData,
+        output: &mut W,
+    ) -> std::io::Result<Compatibility> {
+        let old = Grammar::new(old_cst, old_sema);
+        let new = Grammar::new(new_cst, new_sema);
+        let mut kind = Compatibility::Unchanged;
+        let mut reasons = vec![];
+        let mut change = |change_kind: Compatibility, reason: String| {
+            kind = kind.max(change_kind);
+            reasons.push(format!("{change_kind}: {reason}"));
+        };
+
+        if old.start != new.start {
+            change(
+                Compatibility::Breaking,
+                format!(
+                    "start rule changed from `{}` to `{}`",
+                    old.start.unwrap_or_default(),
+                    new.start.unwrap_or_default()
+                ),
+            );
+        }
+        for (name, symbol) in old.tokens.iter() {
+            match new.tokens.get(name) {
+                None => change(
+                    Compatibility::Breaking,
+                    format!("token `{name}` was removed"),
+                ),
+                Some(new_symbol) if new_symbol != symbol => change(
+                    Compatibility::Breaking,
+                    format!(
+                        "symbol of token `{name}` changed from {} to {}",
+                        symbol.unwrap_or("none"),
+                        new_symbol.unwrap_or("none")
+                    ),
+                ),
+                _ => {}
+            }
+        }
+        for name in new
+            .tokens
+            .keys()
+            .filter(|name| !old.tokens.contains_key(*name))
+        {
+            change(
+                Compatibility::Compatible,
+                format!("token `{name}` was added"),
+            );
+        }
+        for name in old
+            .tokens
+            .keys()
+            .filter(|name| new.tokens.contains_key(*name))
+        {
+            let (old_regex, new_regex) = (old.regexes.get(name), new.regexes.get(name));
+            if old_regex != new_regex {
+                change(
+                    Compatibility::Breaking,
+                    format!(
+                        "regular expression of token `{name}` changed from {} to {}",
+                        old_regex.unwrap_or(&"none"),
+                        new_regex.unwrap_or(&"none")
+                    ),
+                );
+            }
+        }
+        for name in old.skipped.difference(&new.skipped) {
+            change(
+                Compatibility::Breaking,
+                format!("token `{name}` is no longer skipped"),
+            );
+        }
+        for name in new.skipped.difference(&old.skipped) {
+            change(
+                Compatibility::Breaking,
+                format!("token `{name}` is now skipped"),
+            );
+        }
+
+        for (name, (old_rule, old_term)) in old.rules.iter() {
+            let Some((new_rule, new_term)) = new.rules.get(name) else {
+                if old_sema.used.contains(&old_rule.syntax()) {
+                    change(
+                        Compatibility::Breaking,
+                        format!("rule `{name}` was removed"),
+                    );
+                }
+                continue;
+            };
+            if new_term == old_term {
+                let old_text = Self::text(old_source, old_cst, old_rule.syntax());
+                let new_text = Self::text(new_source, new_cst, new_rule.syntax());
+                if old_text != new_text {
+                    change(
+                        Compatibility::ActionOnly,
+                        format!("rule `{name}` changed without changing its language"),
+                    );
+                }
+            } else if new_term.includes(old_term) {
+                let (mut old_predicates, mut new_predicates) = (BTreeSet::new(), BTreeSet::new());
+                old_term.predicates(&mut old_predicates);
+                new_term.predicates(&mut new_predicates);
+                if new_predicates.is_subset(&old_predicates) {
+                    change(
+                        Compatibility::Compatible,
+                        format!("rule `{name}` accepts additional inputs"),
+                    );
+                } else {
+                    change(
+                        Compatibility::Breaking,
+                        format!(
+                            "rule `{name}` has new predicates, which may reject previous inputs"
+                        ),
+                    );
+                }
+            } else {
+                let old_first = Self::first_set(old_sema, old_rule.regex(old_cst));
+                let new_first = Self::first_set(new_sema, new_rule.regex(new_cst));
+                let missing = old_first
+                    .difference(&new_first)
+                    .copied()
+                    .collect::<Vec<_>>();
+                change(
+                    Compatibility::Breaking,
+                    if missing.is_empty() {
+                        format!("rule `{name}` may reject inputs of the old grammar")
+                    } else {
+                        format!(
+                            "rule `{name}` can no longer start with {}",
+                            missing.join(", ")
+                        )
+                    },
+                );
+            }
+        }
+        for name in new
+            .rules
+            .keys()
+            .filter(|name| !old.rules.contains_key(*name))
+        {
+            change(
+                Compatibility::Compatible,
+                format!("rule `{name}` was added"),
+            );
+        }
+
+        writeln!(output, "{kind}")?;
+        for reason in reasons {
+            writeln!(output, "  {reason}")?;
+        }
+        Ok(kind)
+    }
+
+    fn first_set<'a>(sema: &SemanticData<'a>, regex: Option<Regex>) -> BTreeSet<&'a str> {
+        regex
+            .and_then(|regex| sema.first_sets.get(&regex.syntax()))
+            .into_iter()
+            .flatten()
+            .map(|token| token.0)
+            .collect()
+    }
+
+    /// Source text of a node without the trivia.
+    fn text<'a>(source: &'a str, cst: &Cst, node: NodeRef) -> Vec<&'a str> {
+        let mut text = vec![];
+        for child in cst.children(node) {
+            match cst.get(child) {
+                Node::Rule(_, _) => text.extend(Self::text(source, cst, child)),
+                Node::Token(_) => {
+                    let is_trivia = [Token::Comment, Token::DocComment, Token::Whitespace]
+                        .iter()
+                        .any(|token| cst.get_token(child, *token).is_some());
+                    if let (false, Some(span)) = (is_trivia, cst.get_span(child)) {
+                        text.push(&source[span]);
+                    }
+                }
+            }
+        }
+        text
+    }
+}
@@ -1,4 +1,6 @@
-use codespan_reporting::diagnostic::Label;
+use std::collections::HashSet;
+
+use codespan_reporting::diagnostic::{Label, LabelStyle};
 
 use super::parser::{Diagnostic, Span};
 
@@ -21,9 +23,164 @@ pub const REDEFINE_AS_SKIPPED: &str = "E016";
 pub const USED_SKIPPED: &str = "E017";
 pub const EXPECTED_TOKEN: &str = "E018";
 pub const REDEFINE_AS_RIGHT: &str = "E019";
+pub const REDEFINE_AS_INSERTED: &str = "E020";
+pub const INSERT_SKIPPED: &str = "E021";
+pub const RESERVED_RULE_NAME: &str = "E022";
+pub const NODE_KIND_COLLISION: &str = "E023";
+pub const DUPLICATE_ACTION_BLOCK: &str = "E024";
+pub const INVALID_LOOKAHEAD: &str = "E025";
+pub const INVALID_SYNTACTIC_PREDICATE: &str = "E026";
+pub const UNRESOLVED_IMPORT: &str = "E027";
+pub const REDEFINE_AS_SYNC: &str = "E028";
+pub const SYNC_SKIPPED: &str = "E029";
+pub const INVALID_MAX_ERRORS: &str = "E030";
+pub const LEFT_RECURSION_CYCLE: &str = "E031";
+pub const INVALID_SYNTAX: &str = "E032";
+pub const INVALID_TOKEN: &str = "E033";
+pub const UNTERMINATED_LITERAL: &str = "E034";
+pub const INVALID_ESCAPE: &str = "E035";
+pub const UNKNOWN_ATTRIBUTE: &str = "E036";
+pub const INVALID_TOKEN_REGEX: &str = "E037";
+pub const IMPLICIT_TOKEN_COLLISION: &str = "E038";
+pub const INVALID_REPETITION_BOUNDS: &str = "E039";
+pub const INVALID_INLINE_RULE: &str = "E040";
+pub const INVALID_ATTRIBUTE_POS: &str = "E041";
 
 pub const UNUSED_RULE: &str = "W001";
 pub const UNUSED_TOKEN: &str = "W002";
+pub const COMPLEX_RULE: &str = "W003";
+pub const MISSING_ACTION_BLOCK: &str = "W004";
+pub const NAMING_CONVENTION: &str = "W005";
+pub const LONG_ALTERNATIVE: &str = "W006";
+pub const SINGLE_USE_RULE: &str = "W007";
+pub const MISSING_SYNC: &str = "W008";
+pub const UNREACHABLE_ALTERNATIVE: &str = "W009";
+
+/// Confidence that applying a fix results in the intended grammar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applicability {
+    /// The fix is what is intended and can be applied automatically.
+    MachineApplicable,
+    /// The fix may change the meaning of the grammar and should be reviewed.
+    MaybeIncorrect,
+}
+
+impl Applicability {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MachineApplicable => "machine-applicable",
+            Self::MaybeIncorrect => "maybe-incorrect",
+        }
+    }
+}
+
+/// Suggestion for a diagnostic, which replaces spans of the grammar with new
+/// text.
+#[derive(Debug, Clone)]
+pub struct Fix {
+    /// Code of the diagnostic the fix belongs to.
+    pub code: &'static str,
+    /// Primary span of the diagnostic the fix belongs to.
+    pub span: Span,
+    pub message: String,
+    pub edits: Vec<(Span, String)>,
+    pub applicability: Applicability,
+}
+
+impl Fix {
+    /// Checks if the fix belongs to the diagnostic.
+    pub fn applies_to(&self, diag: &Diagnostic) -> bool {
+        diag.code.as_deref() == Some(self.code)
+            && diag
+                .labels
+                .iter()
+                .any(|label| label.style == LabelStyle::Primary && label.range == self.span)
+    }
+
+    /// Removes an unused rule and the rules that are only used by it.
+    pub fn remove_rules(span: &Span, removals: Vec<Span>) -> Self {
+        Self {
+            code: UNUSED_RULE,
+            span: span.clone(),
+            message: if removals.len() > 1 {
+                "remove the unused rules".to_string()
+            } else {
+                "remove the unused rule".to_string()
+            },
+            edits: removals
+                .into_iter()
+                .map(|removal| (removal, String::new()))
+                .collect(),
+            applicability: Applicability::MachineApplicable,
+        }
+    }
+
+    pub fn remove_token(span: &Span, removal: Span) -> Self {
+        Self {
+            code: UNUSED_TOKEN,
+            span: span.clone(),
+            message: "remove the unused token".to_string(),
+            edits: vec![(removal, String::new())],
+            applicability: Applicability::MachineApplicable,
+        }
+    }
+
+    /// Makes the token used by skipping it, where `insertion` is the edit
+    /// that adds the token to a skip definition.
+    pub fn skip_token(span: &Span, insertion: (Span, String)) -> Self {
+        Self {
+            code: UNUSED_TOKEN,
+            span: span.clone(),
+            message: "skip the unused token".to_string(),
+            edits: vec![insertion],
+            applicability: Applicability::MaybeIncorrect,
+        }
+    }
+
+    /// Inserts a `;` at the `offset` for a syntax error at the `span`, which
+    /// expects one.
+    pub fn insert_semicolon(span: &Span, offset: usize) -> Self {
+        Self {
+            code: INVALID_SYNTAX,
+            span: span.clone(),
+            message: "insert the missing `;`".to_string(),
+            edits: vec![(offset..offset, ";".to_string())],
+            applicability: Applicability::MachineApplicable,
+        }
+    }
+}
+
+/// Removes diagnostics that have the same severity, code, message, and
+/// primary range as an earlier diagnostic, as multiple passes may report the
+/// same problem.
+pub fn deduplicate(diags: &mut Vec<Diagnostic>) {
+    let mut seen = HashSet::new();
+    diags.retain(|diag| {
+        let range = diag
+            .labels
+            .iter()
+            .find(|label| label.style == LabelStyle::Primary)
+            .map(|label| label.range.clone());
+        seen.insert((
+            diag.severity,
+            diag.code.clone(),
+            diag.message.clone(),
+            range,
+        ))
+    });
+}
+
+/// Sorts diagnostics by the start of their primary label, which orders them
+/// by file and position in a merged grammar text. Diagnostics without a
+/// location come first, and the order of emission is kept for equal starts.
+pub fn sort_by_position(diags: &mut [Diagnostic]) {
+    diags.sort_by_key(|diag| {
+        diag.labels
+            .iter()
+            .find(|label| label.style == LabelStyle::Primary)
+            .map(|label| label.range.start)
+    });
+}
 
 pub trait LanguageErrors<'a> {
     fn invalid_binding_pos(span: &Span) -> Self;
@@ -36,17 +193,42 @@ pub trait LanguageErrors<'a> {
     fn missing_start_rule() -> Self;
     fn reference_start_rule(span: &Span) -> Self;
     fn predefined_token_name(span: &Span) -> Self;
-    fn unused_rule(span: &Span) -> Self;
+    fn unused_rule(span: &Span, roots: Vec<Span>, members: Vec<Span>) -> Self;
     fn unused_token(span: &Span) -> Self;
+    fn complex_rule(span: &Span, reason: String, lines: usize, splits: Vec<Span>) -> Self;
     fn ll1_conflict_alt(span: &Span, conflicting: Vec<(Span, String)>) -> Self;
     fn ll1_conflict_left_rec(span: &Span, conflicting: Vec<(Span, String)>) -> Self;
+    fn left_recursion_cycle(references: Vec<(Span, String)>, cycle: String) -> Self;
     fn ll1_conflict_rep(span: &Span, conflicting: String) -> Self;
+    fn unreachable_alternative(span: &Span, shadowing: Vec<Span>) -> Self;
     fn ll1_conflict_opt(span: &Span, conflicting: String) -> Self;
     fn consume_tokens(span: &Span) -> Self;
     fn redefine_as_skipped(span: &Span) -> Self;
     fn used_skipped(span: &Span) -> Self;
     fn expected_token(span: &Span) -> Self;
     fn redefine_as_right(span: &Span) -> Self;
+    fn redefine_as_inserted(span: &Span) -> Self;
+    fn insert_skipped(span: &Span) -> Self;
+    fn redefine_as_sync(span: &Span) -> Self;
+    fn sync_skipped(span: &Span) -> Self;
+    fn reserved_rule_name(span: &Span, name: &str) -> Self;
+    fn node_kind_collision(span: &Span, kind: &str, old_span: &Span) -> Self;
+    fn duplicate_action_block(span: &Span, number: &str, language: &str, old_span: &Span) -> Self;
+    fn missing_action_block(span: &Span, number: &str, language: &str) -> Self;
+    fn naming_convention(span: &Span, kind: &str, case: &str, name: String) -> Self;
+    fn long_alternative(span: &Span, len: usize, max: usize) -> Self;
+    fn single_use_rule(span: &Span, use_span: &Span) -> Self;
+    fn missing_sync(span: &Span) -> Self;
+    fn invalid_lookahead(span: &Span) -> Self;
+    fn invalid_syntactic_predicate(span: &Span) -> Self;
+    fn invalid_max_errors(span: &Span) -> Self;
+    fn unresolved_import(span: &Span, path: &str, reason: &str) -> Self;
+    fn unknown_attribute(span: &Span, kind: &str, name: &str) -> Self;
+    fn invalid_token_regex(span: &Span, reason: &str) -> Self;
+    fn implicit_token_collision(span: &Span, name: &str, old_span: &Span) -> Self;
+    fn invalid_repetition_bounds(span: &Span) -> Self;
+    fn invalid_inline_rule(span: &Span, name: &str, reason: &str) -> Self;
+    fn invalid_attribute_pos(span: &Span) -> Self;
 }
 
 impl<'a> LanguageErrors<'a> for Diagnostic {
@@ -183,6 +365,43 @@ impl<'a> LanguageErrors<'a> for Diagnostic {
             .with_labels(labels)
     }
 
+    fn left_recursion_cycle(references: Vec<(Span, String)>, cycle: String) -> Self {
+        let labels = references
+            .into_iter()
+            .enumerate()
+            .map(|(i, (span, msg))| {
+                if i == 0 {
+                    Label::primary((), span).with_message(msg)
+                } else {
+                    Label::secondary((), span).with_message(msg)
+                }
+            })
+            .collect();
+        Diagnostic::error()
+            .with_code(LEFT_RECURSION_CYCLE)
+            .with_message("indirect left recursion")
+            .with_labels(labels)
+            .with_notes(vec![
+                format!("note: the cycle is `{cycle}`"),
+                "note: only left recursion of a rule to itself is supported".to_string(),
+            ])
+    }
+
+    fn unreachable_alternative(span: &Span, shadowing: Vec<Span>) -> Self {
+        let mut labels = vec![Label::primary((), span.clone())];
+        labels.extend(shadowing.into_iter().map(|span| {
+            Label::secondary((), span).with_message("already selected by this alternative")
+        }));
+        Diagnostic::warning()
+            .with_code(UNREACHABLE_ALTERNATIVE)
+            .with_message("unreachable alternative")
+            .with_labels(labels)
+            .with_notes(vec![
+                "note: an earlier alternative with the same predicate is selected by the same lookahead"
+                    .to_string(),
+            ])
+    }
+
     fn ll1_conflict_rep(span: &Span, conflicting: String) -> Self {
         Diagnostic::error()
             .with_code(LL1_CONFLICT_REP)
@@ -247,11 +466,172 @@ impl<'a> LanguageErrors<'a> for Diagnostic {
             .with_labels(vec![Label::primary((), span.clone())])
     }
 
-    fn unused_rule(span: &Span) -> Self {
+    fn redefine_as_inserted(span: &Span) -> Self {
+        Diagnostic::error()
+            .with_code(REDEFINE_AS_INSERTED)
+            .with_message("token is already insertable")
+            .with_labels(vec![Label::primary((), span.clone())])
+    }
+
+    fn insert_skipped(span: &Span) -> Self {
+        Diagnostic::error()
+            .with_code(INSERT_SKIPPED)
+            .with_message("skipped token cannot be inserted")
+            .with_labels(vec![Label::primary((), span.clone())])
+    }
+
+    fn redefine_as_sync(span: &Span) -> Self {
+        Diagnostic::error()
+            .with_code(REDEFINE_AS_SYNC)
+            .with_message("token is already a synchronization token")
+            .with_labels(vec![Label::primary((), span.clone())])
+    }
+
+    fn sync_skipped(span: &Span) -> Self {
+        Diagnostic::error()
+            .with_code(SYNC_SKIPPED)
+            .with_message("skipped token cannot be a synchronization token")
+            .with_labels(vec![Label::primary((), span.clone())])
+    }
+
+    fn reserved_rule_name(span: &Span, name: &str) -> Self {
+        Diagnostic::error()
+            .with_code(RESERVED_RULE_NAME)
+            .with_message(format!(
+                "name `{name}` collides with an identifier of the generated parser"
+            ))
+            .with_labels(vec![Label::primary((), span.clone())])
+    }
+
+    fn node_kind_collision(span: &Span, kind: &str, old_span: &Span) -> Self {
+        Diagnostic::error()
+            .with_code(NODE_KIND_COLLISION)
+            .with_message(format!(
+                "node kind `{kind}` is generated for different names"
+            ))
+            .with_labels(vec![
+                Label::primary((), span.clone()),
+                Label::secondary((), old_span.clone()).with_message("previous name"),
+            ])
+    }
+
+    fn duplicate_action_block(span: &Span, number: &str, language: &str, old_span: &Span) -> Self {
+        Diagnostic::error()
+            .with_code(DUPLICATE_ACTION_BLOCK)
+            .with_message(format!(
+                "action #{number} already has a `{language}` block in this rule"
+            ))
+            .with_labels(vec![
+                Label::primary((), span.clone()),
+                Label::secondary((), old_span.clone()).with_message("previous block"),
+            ])
+            .with_notes(vec![format!(
+                "note: use `#{number}` to run the same block at another position"
+            )])
+    }
+
+    fn invalid_lookahead(span: &Span) -> Self {
+        Diagnostic::error()
+            .with_code(INVALID_LOOKAHEAD)
+            .with_message("invalid number of lookahead tokens")
+            .with_labels(vec![Label::primary((), span.clone())])
+            .with_notes(vec![
+                "note: the lookahead must be at least 1 and at most 8 tokens".to_string(),
+            ])
+    }
+    fn invalid_syntactic_predicate(span: &Span) -> Self {
+        Diagnostic::error()
+            .with_code(INVALID_SYNTACTIC_PREDICATE)
+            .with_message("invalid element in syntactic predicate")
+            .with_labels(vec![Label::primary((), span.clone())])
+            .with_notes(vec![
+                "note: syntactic predicates may only contain tokens and rules".to_string(),
+            ])
+    }
+    fn invalid_max_errors(span: &Span) -> Self {
+        Diagnostic::error()
+            .with_code(INVALID_MAX_ERRORS)
+            .with_message("invalid maximum number of errors")
+            .with_labels(vec![Label::primary((), span.clone())])
+            .with_notes(vec![
+                "note: the maximum number of errors must be at least 1".to_string(),
+            ])
+    }
+    fn unresolved_import(span: &Span, path: &str, reason: &str) -> Self {
+        Diagnostic::error()
+            .with_code(UNRESOLVED_IMPORT)
+            .with_message(format!("cannot import `{path}`"))
+            .with_labels(vec![Label::primary((), span.clone()).with_message(reason)])
+    }
+    fn unknown_attribute(span: &Span, kind: &str, name: &str) -> Self {
+        Diagnostic::error()
+            .with_code(UNKNOWN_ATTRIBUTE)
+            .with_message(format!("unknown {kind} `{name}`"))
+            .with_labels(vec![Label::primary((), span.clone())])
+            .with_notes(vec![if kind == "attribute" {
+                "note: only the `allow` attribute, the `ignore_case` attribute of tokens, and the `entry` and `inline` attributes of rules are supported".to_string()
+            } else {
+                "note: the `allow` attribute accepts `unused`, `complex_rule`, `missing_action_block`, and the lint names".to_string()
+            }])
+    }
+    fn invalid_token_regex(span: &Span, reason: &str) -> Self {
+        Diagnostic::error()
+            .with_code(INVALID_TOKEN_REGEX)
+            .with_message("invalid regular expression")
+            .with_labels(vec![Label::primary((), span.clone()).with_message(reason)])
+    }
+    fn implicit_token_collision(span: &Span, name: &str, old_span: &Span) -> Self {
+        Diagnostic::error()
+            .with_code(IMPLICIT_TOKEN_COLLISION)
+            .with_message(format!("implicit token name `{name}` is already used"))
+            .with_labels(vec![
+                Label::primary((), span.clone()),
+                Label::secondary((), old_span.clone()).with_message("previous definition"),
+            ])
+            .with_notes(vec![
+                "note: declare a token with this symbol and a different name".to_string(),
+            ])
+    }
+    fn invalid_repetition_bounds(span: &Span) -> Self {
+        Diagnostic::error()
+            .with_code(INVALID_REPETITION_BOUNDS)
+            .with_message("invalid repetition bounds")
+            .with_labels(vec![Label::primary((), span.clone())])
+            .with_notes(vec![
+                "note: the maximum must be at least 1 and not less than the minimum, and the bounds must not exceed 65535".to_string(),
+            ])
+    }
+    fn invalid_inline_rule(span: &Span, name: &str, reason: &str) -> Self {
+        Diagnostic::error()
+            .with_code(INVALID_INLINE_RULE)
+            .with_message(format!("rule `{name}` cannot be inlined"))
+            .with_labels(vec![Label::primary((), span.clone()).with_message(reason)])
+    }
+    fn invalid_attribute_pos(span: &Span) -> Self {
+        Diagnostic::error()
+            .with_code(INVALID_ATTRIBUTE_POS)
+            .with_message("invalid attribute position")
+            .with_labels(vec![Label::primary((), span.clone())])
+            .with_notes(vec![
+                "note: attributes in a regex must precede the first element of an alternative"
+                    .to_string(),
+            ])
+    }
+
+    fn unused_rule(span: &Span, roots: Vec<Span>, members: Vec<Span>) -> Self {
+        let mut labels = vec![Label::primary((), span.clone())];
+        labels.extend(
+            roots
+                .into_iter()
+                .map(|span| Label::secondary((), span).with_message("unused rule")),
+        );
+        labels.extend(members.into_iter().map(|span| {
+            Label::secondary((), span).with_message("only reachable from unused rules")
+        }));
         Diagnostic::warning()
             .with_code(UNUSED_RULE)
             .with_message("unused rule")
-            .with_labels(vec![Label::primary((), span.clone())])
+            .with_labels(labels)
     }
 
     fn unused_token(span: &Span) -> Self {
@@ -260,4 +640,67 @@ impl<'a> LanguageErrors<'a> for Diagnostic {
             .with_message("unused token")
             .with_labels(vec![Label::primary((), span.clone())])
     }
+
+    fn complex_rule(span: &Span, reason: String, lines: usize, splits: Vec<Span>) -> Self {
+        let mut labels = vec![Label::primary((), span.clone()).with_message(reason)];
+        labels.extend(splits.into_iter().map(|span| {
+            Label::secondary((), span).with_message("consider moving this into a separate rule")
+        }));
+        Diagnostic::warning()
+            .with_code(COMPLEX_RULE)
+            .with_message("rule is too complex")
+            .with_labels(labels)
+            .with_notes(vec![format!(
+                "note: the generated function has an estimated size of {lines} lines"
+            )])
+    }
+
+    fn missing_action_block(span: &Span, number: &str, language: &str) -> Self {
+        Diagnostic::warning()
+            .with_code(MISSING_ACTION_BLOCK)
+            .with_message(format!("action #{number} has no `{language}` block"))
+            .with_labels(vec![Label::primary((), span.clone())])
+            .with_notes(vec![format!(
+                "note: the action is not executed by the `{language}` backend"
+            )])
+    }
+
+    fn naming_convention(span: &Span, kind: &str, case: &str, name: String) -> Self {
+        Diagnostic::warning()
+            .with_code(NAMING_CONVENTION)
+            .with_message(format!("{kind} name is not in {case} case"))
+            .with_labels(vec![
+                Label::primary((), span.clone()).with_message(format!("rename to `{name}`"))
+            ])
+    }
+
+    fn long_alternative(span: &Span, len: usize, max: usize) -> Self {
+        Diagnostic::warning()
+            .with_code(LONG_ALTERNATIVE)
+            .with_message("alternative is too long")
+            .with_labels(vec![Label::primary((), span.clone()).with_message(
+                format!("alternative has {len} elements, but at most {max} are recommended"),
+            )])
+    }
+
+    fn single_use_rule(span: &Span, use_span: &Span) -> Self {
+        Diagnostic::warning()
+            .with_code(SINGLE_USE_RULE)
+            .with_message("rule is only used once")
+            .with_labels(vec![
+                Label::primary((), span.clone()),
+                Label::secondary((), use_span.clone()).with_message("consider inlining it here"),
+            ])
+    }
+
+    fn missing_sync(span: &Span) -> Self {
+        Diagnostic::warning()
+            .with_code(MISSING_SYNC)
+            .with_message("start rule repeats rules without sync tokens")
+            .with_labels(vec![Label::primary((), span.clone())])
+            .with_notes(vec![
+                "note: add a `sync` declaration to recover from errors in the repeated rules"
+                    .to_string(),
+            ])
+    }
 }
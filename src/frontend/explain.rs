@@ -0,0 +1,686 @@
+use super::diag::*;
+
+/// Longer descriptions of the diagnostic codes with examples, which are
+/// printed by `llw explain`.
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        INVALID_BINDING_POS,
+        "A binding is not at the end of a concatenation.
+
+A binding `@name` renames the syntax tree node of the enclosing rule or branch,
+so it must be the last element of a concatenation.
+
+Erroneous example:
+
+    s: @node A B;
+
+Move the binding to the end:
+
+    s: A B @node;",
+    ),
+    (
+        INVALID_PREDICATE_POS,
+        "A semantic or syntactic predicate is at a position where it is never
+evaluated.
+
+Predicates decide between alternatives and repetitions, so they may only appear
+at the start of an alternation branch, or at the start of a `*`, `+`, or `[]`
+regex.
+
+Erroneous example:
+
+    s: A ?1 B | C;
+
+Move the predicate to the start of the branch:
+
+    s: ?1 A B | C;",
+    ),
+    (
+        UNDEFINED_RULE,
+        "A regex references a rule that is not defined.
+
+Erroneous example:
+
+    token A='a';
+    start s;
+    s: A t;
+
+Define the rule or fix the name of the reference:
+
+    s: A t;
+    t: A;",
+    ),
+    (
+        UNDEFINED_TOKEN,
+        "A regex references a token by its name or symbol, but no token with this
+name or symbol is declared.
+
+Erroneous example:
+
+    token A='a';
+    start s;
+    s: A ';';
+
+Declare the token in a token list:
+
+    token A='a' Semi=';';",
+    ),
+    (
+        REDEFINITION,
+        "A rule or token is defined more than once, or a rule and a token have the
+same name.
+
+Erroneous example:
+
+    token A='a' A='b';
+
+Rename one of the definitions:
+
+    token A='a' B='b';",
+    ),
+    (
+        UPPERCASE_RULE,
+        "A rule name starts with an upper case letter, which is reserved for token
+names.
+
+Erroneous example:
+
+    Expr: A;
+
+Rename the rule:
+
+    expr: A;",
+    ),
+    (
+        LOWERCASE_TOKEN,
+        "A token name starts with a lower case letter, which is reserved for rule
+names.
+
+Erroneous example:
+
+    token semi=';';
+
+Rename the token:
+
+    token Semi=';';",
+    ),
+    (
+        MISSING_START_RULE,
+        "The grammar does not specify a start rule, so the generated parser has no
+entry point.
+
+Erroneous example:
+
+    token A='a';
+    file: A*;
+
+Specify the start rule with a `start` declaration:
+
+    start file;",
+    ),
+    (
+        REFERENCE_START_RULE,
+        "A regex references the start rule, which is only parsed at the top level.
+
+Erroneous example:
+
+    start file;
+    file: A | '(' file ')';
+
+Move the recursive part into a separate rule:
+
+    file: expr;
+    expr: A | '(' expr ')';",
+    ),
+    (
+        PREDEFINED_TOKEN_NAME,
+        "A token is named `EOF`, which is the predefined token for the end of the
+input.
+
+Erroneous example:
+
+    token EOF;
+
+Remove the token, as the end of the input is matched implicitly.",
+    ),
+    (
+        LL1_CONFLICT_ALT,
+        "Multiple branches of an alternation start with the same token, so the
+parser cannot decide which branch to take with the lookahead.
+
+Erroneous example:
+
+    s: A B | A C;
+
+Factor out the common prefix:
+
+    s: A (B | C);
+
+Alternatively, add a predicate to the first branch to decide the conflict.",
+    ),
+    (
+        LL1_CONFLICT_LEFT_REC,
+        "Multiple recursive or multiple non-recursive branches of a left recursive
+rule start with the same token, so the parser cannot decide which branch to
+take with the lookahead.
+
+Erroneous example:
+
+    expr: expr A | expr A B | A;
+
+Factor out the common prefix:
+
+    expr: expr A [B] | A;",
+    ),
+    (
+        LL1_CONFLICT_REP,
+        "A repetition starts with a token that can also follow it, so the parser
+cannot decide whether to continue the repetition.
+
+Erroneous example:
+
+    s: A* A;
+
+Remove the ambiguity or add a predicate to the start of the repetition:
+
+    s: A+;",
+    ),
+    (
+        LL1_CONFLICT_OPT,
+        "An option starts with a token that can also follow it, so the parser
+cannot decide whether to parse the option.
+
+Erroneous example:
+
+    s: [A] A;
+
+Remove the ambiguity or add a predicate to the start of the option:
+
+    s: A [A];",
+    ),
+    (
+        CONSUME_TOKENS,
+        "A regex references a rule that cannot derive any sequence of tokens,
+because all of its branches are recursive. The generated parser would overflow
+the stack.
+
+Erroneous example:
+
+    s: A t;
+    t: t A;
+
+Add a branch that ends the recursion:
+
+    t: t A | A;",
+    ),
+    (
+        REDEFINE_AS_SKIPPED,
+        "A token is listed in more than one `skip` declaration.
+
+Erroneous example:
+
+    skip Whitespace;
+    skip Whitespace;
+
+Remove the duplicate.",
+    ),
+    (
+        USED_SKIPPED,
+        "A regex references a skipped token, which is never seen by the parser.
+
+Erroneous example:
+
+    skip Comment;
+    s: Comment A;
+
+Remove the reference or do not skip the token.",
+    ),
+    (
+        EXPECTED_TOKEN,
+        "A `right`, `skip`, `insert`, or `sync` declaration names a rule instead of
+a token.
+
+Erroneous example:
+
+    skip comment;
+
+Only list tokens in these declarations:
+
+    skip Comment;",
+    ),
+    (
+        REDEFINE_AS_RIGHT,
+        "A token is listed in more than one `right` declaration.
+
+Erroneous example:
+
+    right '^';
+    right '^';
+
+Remove the duplicate.",
+    ),
+    (
+        REDEFINE_AS_INSERTED,
+        "A token is listed in more than one `insert` declaration.
+
+Erroneous example:
+
+    insert ';';
+    insert ';';
+
+Remove the duplicate.",
+    ),
+    (
+        INSERT_SKIPPED,
+        "A skipped token is listed in an `insert` declaration, but skipped tokens are
+never expected by the parser.
+
+Erroneous example:
+
+    skip Whitespace;
+    insert Whitespace;
+
+Remove the token from one of the declarations.",
+    ),
+    (
+        RESERVED_RULE_NAME,
+        "A rule name collides with an identifier of the generated parser, which
+would not compile.
+
+In table mode (`--table`), the start and entry rules also cannot be named like
+the methods of the table driver (e.g. `exec` or `run_rule`).
+
+Erroneous example:
+
+    error: A;
+
+Rename the rule:
+
+    error_stmt: A;",
+    ),
+    (
+        NODE_KIND_COLLISION,
+        "Different names in the grammar generate the same node kind in the syntax
+tree, e.g. a rule and a binding that only differ in their case.
+
+Erroneous example:
+
+    s: a_b | A @aB;
+
+Rename one of them, so the generated names are distinct.",
+    ),
+    (
+        DUPLICATE_ACTION_BLOCK,
+        "A tagged action has more than one block for the same language in a rule.
+
+Erroneous example:
+
+    s: #1:rust { a() } A #1:rust { b() };
+
+Use a plain `#1` to run the same block again:
+
+    s: #1:rust { a() } A #1;",
+    ),
+    (
+        INVALID_LOOKAHEAD,
+        "The number of lookahead tokens in a `lookahead` declaration is not between 1
+and 8.
+
+Erroneous example:
+
+    lookahead 0;
+
+Use a supported number of tokens:
+
+    lookahead 2;",
+    ),
+    (
+        INVALID_SYNTACTIC_PREDICATE,
+        "A syntactic predicate contains something else than tokens and rules, which
+cannot be evaluated during the speculative match.
+
+Erroneous example:
+
+    s: (A #1 B)=> A B | A C;
+
+Remove the actions, predicates, bindings, and node marks from the predicate:
+
+    s: (A B)=> A B | A C;",
+    ),
+    (
+        UNRESOLVED_IMPORT,
+        "A grammar file in an `import` declaration cannot be read.
+
+Erroneous example:
+
+    import 'missing.llw';
+
+Import paths are relative to the importing file.",
+    ),
+    (
+        REDEFINE_AS_SYNC,
+        "A token is listed in more than one `sync` declaration.
+
+Erroneous example:
+
+    sync ';';
+    sync ';';
+
+Remove the duplicate.",
+    ),
+    (
+        SYNC_SKIPPED,
+        "A skipped token is listed in a `sync` declaration, but the parser cannot
+synchronize on tokens it never sees.
+
+Erroneous example:
+
+    skip Whitespace;
+    sync Whitespace;
+
+Remove the token from one of the declarations.",
+    ),
+    (
+        INVALID_MAX_ERRORS,
+        "The maximum number of errors in a `max_errors` declaration is 0, so the
+parser would stop before reporting any error.
+
+Erroneous example:
+
+    max_errors 0;
+
+Use at least one error:
+
+    max_errors 10;",
+    ),
+    (
+        LEFT_RECURSION_CYCLE,
+        "Rules reference each other at the start of their branches, which is an
+indirect left recursion. Only direct left recursion of a rule to itself is
+supported.
+
+Erroneous example:
+
+    a: b A | A;
+    b: a B;
+
+Inline the rules of the cycle, so the recursion is direct:
+
+    a: a B A | A;",
+    ),
+    (
+        INVALID_SYNTAX,
+        "The grammar file does not match the syntax of lelwel grammars, e.g. a
+declaration is not terminated.
+
+Erroneous example:
+
+    token A='a'
+    start s;
+
+Terminate the declaration with a `;`:
+
+    token A='a';",
+    ),
+    (
+        INVALID_TOKEN,
+        "The grammar file contains a character that does not start any token of the
+grammar syntax.
+
+Erroneous example:
+
+    s: A $ B;
+
+Remove the character.",
+    ),
+    (
+        UNTERMINATED_LITERAL,
+        "A string, comment, code block, or regular expression is not closed before
+the end of the file.
+
+Erroneous example:
+
+    token A='a;
+
+Close the literal:
+
+    token A='a';",
+    ),
+    (
+        INVALID_ESCAPE,
+        "A string contains an escape sequence other than `\\'` and `\\\\`.
+
+Erroneous example:
+
+    token Newline='\\n';
+
+Use a description for tokens that cannot be written as a symbol:
+
+    token Newline='<newline>';",
+    ),
+    (
+        UNKNOWN_ATTRIBUTE,
+        "An attribute of a rule, token, or alternative is not `allow`, the
+`ignore_case` attribute of a token, or the `entry` or `inline` attribute of a
+rule, or it allows a warning that does not exist.
+
+The `allow` attribute suppresses the `unused`, `complex_rule`, and
+`missing_action_block` warnings, and the lints by their names. The
+`ignore_case` attribute makes a token symbol match case-insensitively, the
+`entry` attribute generates a parse function for a rule, and the `inline`
+attribute expands a rule at its references.
+
+Erroneous example:
+
+    #[allow(unused_rule)]
+    reserved: A;
+
+Use the name of the warning:
+
+    #[allow(unused)]
+    reserved: A;",
+    ),
+    (
+        INVALID_TOKEN_REGEX,
+        "The regular expression of a token cannot be parsed, for example because it
+contains an unknown Unicode property or a range whose start is greater than its
+end.
+
+Erroneous example:
+
+    token Id /[z-a]+/;
+
+Fix the regular expression:
+
+    token Id /[a-z]+/;",
+    ),
+    (
+        IMPLICIT_TOKEN_COLLISION,
+        "A string literal in a rule is not the symbol of a declared token, and the
+name that is derived for its implicit token is already the name of another
+token.
+
+Erroneous example:
+
+    token LParen='<left parenthesis>';
+    s: '(' s ')' | LParen;
+
+Declare the token with a different name:
+
+    token LParen='<left parenthesis>' LPar='(' RPar=')';
+    s: '(' s ')' | LParen;",
+    ),
+    (
+        INVALID_REPETITION_BOUNDS,
+        "The bounds of a bounded repetition `e{n,m}` are invalid. The maximum must be
+at least 1 and not less than the minimum, and neither bound may exceed 65535.
+
+Erroneous example:
+
+    token A='a';
+    s: A{3,2};
+
+Swap the bounds:
+
+    token A='a';
+    s: A{2,3};",
+    ),
+    (
+        INVALID_INLINE_RULE,
+        "A rule with the `inline` attribute cannot be expanded at its references.
+
+Only rules that always create their syntax tree node can be inlined, so left
+recursive rules, operator precedence rules, forwarding rules, and rules that
+may be empty are not inlined. A rule that references itself through inlined
+rules would be expanded without end.
+
+Erroneous example:
+
+    #[inline]
+    list: A [list];
+
+Remove the attribute from one of the rules in the cycle, or use a repetition:
+
+    #[inline]
+    list: A+;",
+    ),
+    (
+        INVALID_ATTRIBUTE_POS,
+        "An attribute in the regex of a rule is not at the start of an alternative,
+or the alternative has no other elements.
+
+Erroneous example:
+
+    s: A #[allow(long_alternative)] B | C;
+
+Move the attribute before the first element of the alternative:
+
+    s: #[allow(long_alternative)] A B | C;",
+    ),
+    (
+        UNUSED_RULE,
+        "A rule is not reachable from the start rule, so it is never parsed.
+
+Example:
+
+    start s;
+    s: A;
+    t: B;
+
+Remove the rule or reference it. If the rule is intentionally unused, allow
+the warning:
+
+    #[allow(unused)]
+    t: B;",
+    ),
+    (
+        UNUSED_TOKEN,
+        "A token is declared, but neither used in a rule nor skipped.
+
+Example:
+
+    token A='a' B='b';
+    start s;
+    s: A;
+
+Remove the token, or skip it if the lexer produces it:
+
+    skip B;",
+    ),
+    (
+        COMPLEX_RULE,
+        "A rule is nested too deeply or has too many alternatives, which makes the
+generated function hard to read and debug. The thresholds are set with
+`--max-depth` and `--max-alternatives`.
+
+Move nested regexes into separate rules to reduce the complexity, or allow the
+warning with `#[allow(complex_rule)]` on the rule.",
+    ),
+    (
+        MISSING_ACTION_BLOCK,
+        "A tagged action has blocks for other languages, but no block for the
+language of the backend, so the action is not executed.
+
+Example:
+
+    s: #1:ts { a() } A;
+
+Add a block for the backend:
+
+    s: #1:ts { a() } #1:rust { a() } A;",
+    ),
+    (
+        NAMING_CONVENTION,
+        "A rule name is not in snake case or a token name is not in pascal case.
+
+This is reported by the `naming_convention` lint.
+
+Example:
+
+    token Semi_Colon=';';
+    exprList: expr*;
+
+Rename the declarations:
+
+    token SemiColon=';';
+    expr_list: expr*;",
+    ),
+    (
+        LONG_ALTERNATIVE,
+        "An alternative has more elements than recommended, which makes it hard to
+read.
+
+This is reported by the `long_alternative` lint, which is allowed by default.
+
+Move parts of the alternative into separate rules.",
+    ),
+    (
+        SINGLE_USE_RULE,
+        "A rule is only referenced once, so it could be inlined.
+
+This is reported by the `single_use_rule` lint, which is allowed by default.
+
+Example:
+
+    s: t B;
+    t: A;
+
+Inline the rule, if it does not need its own syntax tree node:
+
+    s: A B;",
+    ),
+    (
+        MISSING_SYNC,
+        "The start rule repeats other rules, but the grammar has no synchronization
+tokens, so the parser cannot recover from errors in the repeated rules.
+
+This is reported by the `missing_sync` lint, which is allowed by default.
+
+Example:
+
+    file: stmt*;
+
+Declare tokens that end the repeated rules as synchronization tokens:
+
+    sync ';';",
+    ),
+    (
+        UNREACHABLE_ALTERNATIVE,
+        "An alternative is always shadowed by an earlier alternative with the same
+predicate and lookahead, so it is never selected.
+
+Example:
+
+    s: ?1 A | ?1 A B;
+
+Use different predicates or remove the unreachable alternative.",
+    ),
+];
+
+/// Longer description of the diagnostic `code` with examples.
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(c, _)| c.eq_ignore_ascii_case(code))
+        .map(|(_, explanation)| *explanation)
+}
@@ -30,6 +30,13 @@ macro_rules! syntax_error_message {
     }
 }
 
+/// Number of syntax errors after which the rest of the input is skipped.
+const MAX_ERRORS: usize = usize::MAX;
+
+/// Error of [`Parser::parse_cancellable`] if the parse was cancelled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cancelled;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum Rule {
@@ -39,9 +46,16 @@ pub enum Rule {
     StartDecl,
     RightDecl,
     SkipDecl,
+    InsertDecl,
+    SyncDecl,
+    LookaheadDecl,
+    MaxErrorsDecl,
+    TokenDeriveDecl,
+    ImportDecl,
     TokenList,
     TokenDecl,
     RuleDecl,
+    Attribute,
     Regex,
     Alternation,
     Concat,
@@ -49,6 +63,7 @@ pub enum Rule {
     Paren,
     Optional,
     Atomic,
+    SyntacticPredicate,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
@@ -135,7 +150,7 @@ impl<'a> Cst<'a> {
     fn mark(&self) -> MarkClosed {
         MarkClosed(self.nodes.len() as CstIndex)
     }
-    pub fn children(&self, node: NodeRef) -> CstChildren {
+    pub fn children(&self, node: NodeRef) -> CstChildren<'_> {
         let iter = if let Some(Node::Rule(_, end_offset)) = self.nodes.get(node.0 as usize) {
             self.nodes[node.0 as usize + 1..node.0 as usize + *end_offset as usize + 1].iter()
         } else {
@@ -159,10 +174,7 @@ impl<'a> Cst<'a> {
                 .children(node)
                 .filter(|node_ref| {
                     if let Node::Token(idx) = self.get(*node_ref) {
-                        !matches!(
-                            self.tokens[idx as usize],
-                            Token::Error | Token::Comment | Token::DocComment | Token::Whitespace
-                        )
+                        !Parser::is_skipped(self.tokens[idx as usize])
                     } else {
                         true
                     }
@@ -197,6 +209,32 @@ impl<'a> Cst<'a> {
             None
         }
     }
+    /// Re-emits the source text of all tokens in the syntax tree.
+    pub fn unparse(&self) -> String {
+        let mut text = String::new();
+        for node in self.nodes.iter() {
+            if let Node::Token(idx) = node {
+                if let Some(range) = self.ranges.get(*idx as usize) {
+                    text.push_str(&self.source[range.start as usize..range.end as usize]);
+                }
+            }
+        }
+        text
+    }
+    /// Checks that unparsing the syntax tree reproduces the source byte for
+    /// byte, and returns the offset of the first difference otherwise.
+    pub fn verify_round_trip(&self) -> Result<(), usize> {
+        let text = self.unparse();
+        match text
+            .bytes()
+            .zip(self.source.bytes())
+            .position(|(a, b)| a != b)
+        {
+            Some(offset) => Err(offset),
+            None if text.len() != self.source.len() => Err(text.len().min(self.source.len())),
+            None => Ok(()),
+        }
+    }
 }
 
 #[allow(clippy::unnecessary_cast)]
@@ -238,6 +276,7 @@ impl std::fmt::Display for Cst<'_> {
     }
 }
 
+#[allow(unused_macros)]
 macro_rules! expect {
     ($tok:ident, $sym:literal, $self:expr, $diags:expr) => {
         if let Token::$tok = $self.current {
@@ -248,19 +287,38 @@ macro_rules! expect {
     };
 }
 
+#[allow(unused_macros)]
+macro_rules! expect_insertable {
+    ($tok:ident, $sym:literal, $error:literal, $self:expr, $diags:expr) => {
+        if let Token::$tok = $self.current {
+            $self.advance(false);
+        } else if $self.insert_token(Token::$tok) {
+            if !$self.error_cooldown {
+                let span = $self.span();
+                $diags.push(ins![span.start..span.start, $sym]);
+            }
+        } else {
+            $self.error($diags, err![$self.span(), $error]);
+        }
+    };
+}
+
 pub struct Parser<'a> {
     cst: Cst<'a>,
     pos: usize,
     current: Token,
     error_cooldown: bool,
+    error_count: usize,
     max_offset: usize,
+    cancel: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    cancelled: bool,
     #[allow(dead_code)]
     context: Context<'a>,
 }
 #[allow(clippy::while_let_loop, dead_code)]
 impl<'a> Parser<'a> {
-    fn error(&self, diags: &mut Vec<Diagnostic>, diag: Diagnostic) {
-        if self.error_cooldown {
+    fn error(&mut self, diags: &mut Vec<Diagnostic>, diag: Diagnostic) {
+        if self.error_cooldown || self.error_count == MAX_ERRORS || self.cancelled {
             return;
         }
         if let Some(last) = diags.last() {
@@ -269,8 +327,44 @@ impl<'a> Parser<'a> {
             }
         }
         diags.push(diag);
+        self.error_count += 1;
+        if self.error_count == MAX_ERRORS && self.pos < self.cst.tokens.len() {
+            self.skip_rest(diags);
+        }
+    }
+    /// Moves the remaining tokens into an error node, so parsing ends as if
+    /// the end of file was reached.
+    fn skip_rest(&mut self, diags: &mut Vec<Diagnostic>) {
+        let m = self.cst.open();
+        while self.pos < self.cst.tokens.len() {
+            self.cst.advance();
+            self.pos += 1;
+        }
+        self.current = Token::EOF;
+        self.close(m, Rule::Error, diags);
     }
+    /// Checks if the parse was cancelled, in which case the parser skips to
+    /// the end of file, so all rules return without consuming more tokens.
+    fn is_cancelled(&mut self) -> bool {
+        if !self.cancelled
+            && self
+                .cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.load(std::sync::atomic::Ordering::Relaxed))
+        {
+            self.cancelled = true;
+            self.pos = self.cst.tokens.len();
+            self.current = Token::EOF;
+        }
+        self.cancelled
+    }
+    /// Moves to the next token that is seen by the rules. Tokens of `skip`
+    /// declarations and lexer errors are added to the syntax tree, but are
+    /// never the current token.
     fn advance(&mut self, error: bool) {
+        if self.is_cancelled() {
+            return;
+        }
         if !error {
             self.error_cooldown = false;
         }
@@ -323,7 +417,9 @@ impl<'a> Parser<'a> {
         let m = self.cst.open();
         self.error(diags, diag);
         self.error_cooldown = true;
-        self.advance(true);
+        if self.pos < self.cst.tokens.len() {
+            self.advance(true);
+        }
         self.close(m, Rule::Error, diags);
     }
     #[allow(dead_code)]
@@ -347,30 +443,126 @@ impl<'a> Parser<'a> {
         self.build(rule, NodeRef(m.0), diags);
         m
     }
-    pub fn parse(
+    fn new(
         source: &'a str,
         tokens: Vec<Token>,
         ranges: Vec<std::ops::Range<CstIndex>>,
-        diags: &mut Vec<Diagnostic>,
-    ) -> Cst<'a> {
-        let max_offset = source.len();
-        let mut parser = Self {
+        cancel: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    ) -> Self {
+        Self {
             current: Token::EOF,
             cst: Cst::new(source, tokens, ranges),
             pos: 0,
             error_cooldown: false,
-            max_offset,
+            error_count: 0,
+            max_offset: source.len(),
+            cancel,
+            cancelled: false,
             context: Context::default(),
-        };
+        }
+    }
+    pub fn parse(
+        source: &'a str,
+        tokens: Vec<Token>,
+        ranges: Vec<std::ops::Range<CstIndex>>,
+        diags: &mut Vec<Diagnostic>,
+    ) -> Cst<'a> {
+        let mut parser = Self::new(source, tokens, ranges, None);
         parser.file(diags);
         parser.cst
     }
+    /// Same as [`Parser::parse`], but the parse stops as soon as another
+    /// thread sets the `cancel` flag, e.g. because a language server received
+    /// a newer version of the source. The flag is checked whenever a token is
+    /// consumed, and the diagnostics of a cancelled parse are incomplete.
+    pub fn parse_cancellable(
+        source: &'a str,
+        tokens: Vec<Token>,
+        ranges: Vec<std::ops::Range<CstIndex>>,
+        diags: &mut Vec<Diagnostic>,
+        cancel: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    ) -> Result<Cst<'a>, Cancelled> {
+        let mut parser = Self::new(source, tokens, ranges, Some(cancel));
+        parser.file(diags);
+        if parser.cancelled {
+            Err(Cancelled)
+        } else {
+            Ok(parser.cst)
+        }
+    }
+    /// Parses the input with the function of an entry rule, which must be
+    /// followed by the end of file. If the rule creates a single node, it is
+    /// the root of the syntax tree, otherwise the nodes of the rule are
+    /// wrapped in a node of the `kind` of the rule.
+    #[allow(dead_code, clippy::unnecessary_cast)]
+    fn entry(
+        &mut self,
+        kind: Rule,
+        rule: fn(&mut Self, &mut Vec<Diagnostic>),
+        diags: &mut Vec<Diagnostic>,
+    ) {
+        let first = self.cst.mark();
+        self.init_skip();
+        rule(self, diags);
+        let start = first.0 as usize;
+        let skipped = self.cst.nodes[start..]
+            .iter()
+            .take_while(|node| matches!(node, Node::Token(_)))
+            .count();
+        let root = match self.cst.nodes.get(start + skipped) {
+            Some(Node::Rule(kind, len))
+                if *kind != Rule::Error
+                    && start + skipped + *len as usize + 1 == self.cst.nodes.len() =>
+            {
+                Some(*kind)
+            }
+            _ => None,
+        };
+        let m = if root.is_some() {
+            // the skipped tokens before the node of the rule become its children
+            self.cst.nodes[start..=start + skipped].rotate_right(1);
+            MarkOpened(first.0)
+        } else {
+            self.cst.open_before(first)
+        };
+        if self.current != Token::EOF {
+            self.error(diags, err![self.span(), "<end of file>"]);
+            let error_tree = self.cst.open();
+            loop {
+                match self.cst.tokens.get(self.pos) {
+                    None => break,
+                    _ => self.cst.advance(),
+                }
+                self.pos += 1;
+            }
+            self.close(error_tree, Rule::Error, diags);
+        }
+        match root {
+            Some(kind) => {
+                self.cst.close(m, kind);
+            }
+            None => {
+                self.close(m, kind, diags);
+            }
+        }
+    }
     fn r#file(&mut self, diags: &mut Vec<Diagnostic>) {
         let m = self.cst.open();
         self.init_skip();
         loop {
             match self.current {
-                Token::Id | Token::Right | Token::Skip | Token::Start | Token::Token => {
+                Token::AttrStart
+                | Token::Id
+                | Token::Import
+                | Token::Insert
+                | Token::Lookahead
+                | Token::MaxErrors
+                | Token::Right
+                | Token::Skip
+                | Token::Start
+                | Token::Sync
+                | Token::Token
+                | Token::TokenDerive => {
                     self.r#decl(diags);
                 }
                 Token::EOF => break,
@@ -379,12 +571,19 @@ impl<'a> Parser<'a> {
                         diags,
                         err![
                             self.span(),
+                            "#[",
                             "<end of file>",
                             "<identifier>",
+                            "import",
+                            "insert",
+                            "lookahead",
+                            "max_errors",
                             "right",
                             "skip",
                             "start",
-                            "token"
+                            "sync",
+                            "token",
+                            "token_derive"
                         ],
                     );
                 }
@@ -409,7 +608,7 @@ impl<'a> Parser<'a> {
             Token::Token => {
                 self.r#token_list(diags);
             }
-            Token::Id => {
+            Token::AttrStart | Token::Id => {
                 self.r#rule_decl(diags);
             }
             Token::Start => {
@@ -421,16 +620,41 @@ impl<'a> Parser<'a> {
             Token::Skip => {
                 self.r#skip_decl(diags);
             }
+            Token::Insert => {
+                self.r#insert_decl(diags);
+            }
+            Token::Lookahead => {
+                self.r#lookahead_decl(diags);
+            }
+            Token::Import => {
+                self.r#import_decl(diags);
+            }
+            Token::Sync => {
+                self.r#sync_decl(diags);
+            }
+            Token::MaxErrors => {
+                self.r#max_errors_decl(diags);
+            }
+            Token::TokenDerive => {
+                self.r#token_derive_decl(diags);
+            }
             _ => {
                 self.error(
                     diags,
                     err![
                         self.span(),
+                        "#[",
                         "<identifier>",
+                        "import",
+                        "insert",
+                        "lookahead",
+                        "max_errors",
                         "right",
                         "skip",
                         "start",
-                        "token"
+                        "sync",
+                        "token",
+                        "token_derive"
                     ],
                 );
             }
@@ -447,12 +671,7 @@ impl<'a> Parser<'a> {
         let m = self.cst.open();
         expect!(Right, "right", self, diags);
         match self.current {
-            Token::Id => {
-                expect!(Id, "<identifier>", self, diags);
-            }
-            Token::Str => {
-                expect!(Str, "<string literal>", self, diags);
-            }
+            Token::Id | Token::Str => self.advance(false),
             _ => {
                 self.error(diags, err![self.span(), "<identifier>", "<string literal>"]);
             }
@@ -460,22 +679,24 @@ impl<'a> Parser<'a> {
         loop {
             match self.current {
                 Token::Id | Token::Str => match self.current {
-                    Token::Id => {
-                        expect!(Id, "<identifier>", self, diags);
-                    }
-                    Token::Str => {
-                        expect!(Str, "<string literal>", self, diags);
-                    }
+                    Token::Id | Token::Str => self.advance(false),
                     _ => {
                         self.error(diags, err![self.span(), "<identifier>", "<string literal>"]);
                     }
                 },
                 Token::Semi
+                | Token::AttrStart
                 | Token::EOF
+                | Token::Import
+                | Token::Insert
+                | Token::Lookahead
+                | Token::MaxErrors
                 | Token::Right
                 | Token::Skip
                 | Token::Start
-                | Token::Token => break,
+                | Token::Sync
+                | Token::Token
+                | Token::TokenDerive => break,
                 _ => {
                     self.advance_with_error(
                         diags,
@@ -491,12 +712,48 @@ impl<'a> Parser<'a> {
         let m = self.cst.open();
         expect!(Skip, "skip", self, diags);
         match self.current {
-            Token::Id => {
-                expect!(Id, "<identifier>", self, diags);
+            Token::Id | Token::Str => self.advance(false),
+            _ => {
+                self.error(diags, err![self.span(), "<identifier>", "<string literal>"]);
             }
-            Token::Str => {
-                expect!(Str, "<string literal>", self, diags);
+        }
+        loop {
+            match self.current {
+                Token::Id | Token::Str => match self.current {
+                    Token::Id | Token::Str => self.advance(false),
+                    _ => {
+                        self.error(diags, err![self.span(), "<identifier>", "<string literal>"]);
+                    }
+                },
+                Token::Semi
+                | Token::AttrStart
+                | Token::EOF
+                | Token::Import
+                | Token::Insert
+                | Token::Lookahead
+                | Token::MaxErrors
+                | Token::Right
+                | Token::Skip
+                | Token::Start
+                | Token::Sync
+                | Token::Token
+                | Token::TokenDerive => break,
+                _ => {
+                    self.advance_with_error(
+                        diags,
+                        err![self.span(), "<identifier>", "<string literal>"],
+                    );
+                }
             }
+        }
+        expect!(Semi, ";", self, diags);
+        self.close(m, Rule::SkipDecl, diags);
+    }
+    fn r#insert_decl(&mut self, diags: &mut Vec<Diagnostic>) {
+        let m = self.cst.open();
+        expect!(Insert, "insert", self, diags);
+        match self.current {
+            Token::Id | Token::Str => self.advance(false),
             _ => {
                 self.error(diags, err![self.span(), "<identifier>", "<string literal>"]);
             }
@@ -504,22 +761,120 @@ impl<'a> Parser<'a> {
         loop {
             match self.current {
                 Token::Id | Token::Str => match self.current {
-                    Token::Id => {
-                        expect!(Id, "<identifier>", self, diags);
+                    Token::Id | Token::Str => self.advance(false),
+                    _ => {
+                        self.error(diags, err![self.span(), "<identifier>", "<string literal>"]);
                     }
-                    Token::Str => {
-                        expect!(Str, "<string literal>", self, diags);
+                },
+                Token::Semi
+                | Token::AttrStart
+                | Token::EOF
+                | Token::Import
+                | Token::Insert
+                | Token::Lookahead
+                | Token::MaxErrors
+                | Token::Right
+                | Token::Skip
+                | Token::Start
+                | Token::Sync
+                | Token::Token
+                | Token::TokenDerive => break,
+                _ => {
+                    self.advance_with_error(
+                        diags,
+                        err![self.span(), "<identifier>", "<string literal>"],
+                    );
+                }
+            }
+        }
+        expect!(Semi, ";", self, diags);
+        self.close(m, Rule::InsertDecl, diags);
+    }
+    fn r#sync_decl(&mut self, diags: &mut Vec<Diagnostic>) {
+        let m = self.cst.open();
+        expect!(Sync, "sync", self, diags);
+        match self.current {
+            Token::Id | Token::Str => self.advance(false),
+            _ => {
+                self.error(diags, err![self.span(), "<identifier>", "<string literal>"]);
+            }
+        }
+        loop {
+            match self.current {
+                Token::Id | Token::Str => match self.current {
+                    Token::Id | Token::Str => self.advance(false),
+                    _ => {
+                        self.error(diags, err![self.span(), "<identifier>", "<string literal>"]);
                     }
+                },
+                Token::Semi
+                | Token::AttrStart
+                | Token::EOF
+                | Token::Import
+                | Token::Insert
+                | Token::Lookahead
+                | Token::MaxErrors
+                | Token::Right
+                | Token::Skip
+                | Token::Start
+                | Token::Sync
+                | Token::Token
+                | Token::TokenDerive => break,
+                _ => {
+                    self.advance_with_error(
+                        diags,
+                        err![self.span(), "<identifier>", "<string literal>"],
+                    );
+                }
+            }
+        }
+        expect!(Semi, ";", self, diags);
+        self.close(m, Rule::SyncDecl, diags);
+    }
+    fn r#lookahead_decl(&mut self, diags: &mut Vec<Diagnostic>) {
+        let m = self.cst.open();
+        expect!(Lookahead, "lookahead", self, diags);
+        expect!(Number, "<number>", self, diags);
+        expect!(Semi, ";", self, diags);
+        self.close(m, Rule::LookaheadDecl, diags);
+    }
+    fn r#max_errors_decl(&mut self, diags: &mut Vec<Diagnostic>) {
+        let m = self.cst.open();
+        expect!(MaxErrors, "max_errors", self, diags);
+        expect!(Number, "<number>", self, diags);
+        expect!(Semi, ";", self, diags);
+        self.close(m, Rule::MaxErrorsDecl, diags);
+    }
+    fn r#token_derive_decl(&mut self, diags: &mut Vec<Diagnostic>) {
+        let m = self.cst.open();
+        expect!(TokenDerive, "token_derive", self, diags);
+        match self.current {
+            Token::Id | Token::Str => self.advance(false),
+            _ => {
+                self.error(diags, err![self.span(), "<identifier>", "<string literal>"]);
+            }
+        }
+        loop {
+            match self.current {
+                Token::Id | Token::Str => match self.current {
+                    Token::Id | Token::Str => self.advance(false),
                     _ => {
                         self.error(diags, err![self.span(), "<identifier>", "<string literal>"]);
                     }
                 },
                 Token::Semi
+                | Token::AttrStart
                 | Token::EOF
+                | Token::Import
+                | Token::Insert
+                | Token::Lookahead
+                | Token::MaxErrors
                 | Token::Right
                 | Token::Skip
                 | Token::Start
-                | Token::Token => break,
+                | Token::Sync
+                | Token::Token
+                | Token::TokenDerive => break,
                 _ => {
                     self.advance_with_error(
                         diags,
@@ -529,7 +884,14 @@ impl<'a> Parser<'a> {
             }
         }
         expect!(Semi, ";", self, diags);
-        self.close(m, Rule::SkipDecl, diags);
+        self.close(m, Rule::TokenDeriveDecl, diags);
+    }
+    fn r#import_decl(&mut self, diags: &mut Vec<Diagnostic>) {
+        let m = self.cst.open();
+        expect!(Import, "import", self, diags);
+        expect!(Str, "<string literal>", self, diags);
+        expect!(Semi, ";", self, diags);
+        self.close(m, Rule::ImportDecl, diags);
     }
     fn r#token_list(&mut self, diags: &mut Vec<Diagnostic>) {
         let m = self.cst.open();
@@ -537,17 +899,23 @@ impl<'a> Parser<'a> {
         self.r#token_decl(diags);
         loop {
             match self.current {
-                Token::Id => {
+                Token::AttrStart | Token::Id => {
                     self.r#token_decl(diags);
                 }
                 Token::Semi
                 | Token::EOF
+                | Token::Import
+                | Token::Insert
+                | Token::Lookahead
+                | Token::MaxErrors
                 | Token::Right
                 | Token::Skip
                 | Token::Start
-                | Token::Token => break,
+                | Token::Sync
+                | Token::Token
+                | Token::TokenDerive => break,
                 _ => {
-                    self.advance_with_error(diags, err![self.span(), "<identifier>"]);
+                    self.advance_with_error(diags, err![self.span(), "#[", "<identifier>"]);
                 }
             }
         }
@@ -556,33 +924,130 @@ impl<'a> Parser<'a> {
     }
     fn r#token_decl(&mut self, diags: &mut Vec<Diagnostic>) {
         let m = self.cst.open();
+        loop {
+            match self.current {
+                Token::AttrStart => {
+                    self.r#attribute(diags);
+                }
+                Token::Id
+                | Token::EOF
+                | Token::Import
+                | Token::Insert
+                | Token::Lookahead
+                | Token::MaxErrors
+                | Token::Right
+                | Token::Semi
+                | Token::Skip
+                | Token::Start
+                | Token::Sync
+                | Token::Token
+                | Token::TokenDerive => break,
+                _ => {
+                    self.advance_with_error(diags, err![self.span(), "#[", "<identifier>"]);
+                }
+            }
+        }
         expect!(Id, "<identifier>", self, diags);
         match self.current {
             Token::Equal => {
                 expect!(Equal, "=", self, diags);
                 expect!(Str, "<string literal>", self, diags);
+                match self.current {
+                    Token::Str => {
+                        expect!(Str, "<string literal>", self, diags);
+                    }
+                    Token::AttrStart | Token::Id | Token::Regex | Token::Semi => {}
+                    _ => {
+                        self.error(
+                            diags,
+                            err![
+                                self.span(),
+                                "#[",
+                                "<identifier>",
+                                "<regular expression>",
+                                ";",
+                                "<string literal>"
+                            ],
+                        );
+                    }
+                }
+            }
+            Token::AttrStart | Token::Id | Token::Regex | Token::Semi => {}
+            _ => {
+                self.error(
+                    diags,
+                    err![
+                        self.span(),
+                        "#[",
+                        "=",
+                        "<identifier>",
+                        "<regular expression>",
+                        ";"
+                    ],
+                );
             }
-            Token::Id | Token::Semi => {}
+        }
+        match self.current {
+            Token::Regex => {
+                expect!(Regex, "<regular expression>", self, diags);
+            }
+            Token::AttrStart | Token::Id | Token::Semi => {}
             _ => {
-                self.error(diags, err![self.span(), "=", "<identifier>", ";"]);
+                self.error(
+                    diags,
+                    err![
+                        self.span(),
+                        "#[",
+                        "<identifier>",
+                        "<regular expression>",
+                        ";"
+                    ],
+                );
             }
         }
         self.close(m, Rule::TokenDecl, diags);
     }
     fn r#rule_decl(&mut self, diags: &mut Vec<Diagnostic>) {
         let m = self.cst.open();
+        loop {
+            match self.current {
+                Token::AttrStart => {
+                    self.r#attribute(diags);
+                }
+                Token::Id
+                | Token::EOF
+                | Token::Import
+                | Token::Insert
+                | Token::Lookahead
+                | Token::MaxErrors
+                | Token::Right
+                | Token::Skip
+                | Token::Start
+                | Token::Sync
+                | Token::Token
+                | Token::TokenDerive => break,
+                _ => {
+                    self.advance_with_error(diags, err![self.span(), "#[", "<identifier>"]);
+                }
+            }
+        }
         expect!(Id, "<identifier>", self, diags);
         expect!(Colon, ":", self, diags);
         match self.current {
             Token::Action
+            | Token::AttrStart
             | Token::Binding
             | Token::CloseNode
+            | Token::Dot
             | Token::Id
+            | Token::InlineAction
             | Token::LBrak
             | Token::LPar
+            | Token::Not
             | Token::OpenNode
             | Token::Predicate
-            | Token::Str => {
+            | Token::Str
+            | Token::TaggedAction => {
                 self.r#regex(diags);
             }
             Token::Semi => {}
@@ -592,15 +1057,20 @@ impl<'a> Parser<'a> {
                     err![
                         self.span(),
                         "<semantic action>",
+                        "#[",
                         "<binding>",
                         "<close node mark>",
+                        ".",
                         "<identifier>",
+                        "<inline action>",
                         "[",
                         "(",
+                        "!",
                         "<open node mark>",
                         "<semantic predicate>",
                         ";",
-                        "<string literal>"
+                        "<string literal>",
+                        "<tagged action>"
                     ],
                 );
             }
@@ -608,6 +1078,76 @@ impl<'a> Parser<'a> {
         expect!(Semi, ";", self, diags);
         self.close(m, Rule::RuleDecl, diags);
     }
+    fn r#attribute(&mut self, diags: &mut Vec<Diagnostic>) {
+        let m = self.cst.open();
+        expect!(AttrStart, "#[", self, diags);
+        expect!(Id, "<identifier>", self, diags);
+        match self.current {
+            Token::LPar => {
+                expect!(LPar, "(", self, diags);
+                match self.current {
+                    Token::Id => {
+                        expect!(Id, "<identifier>", self, diags);
+                        loop {
+                            match self.current {
+                                Token::Comma => {
+                                    expect!(Comma, ",", self, diags);
+                                    expect!(Id, "<identifier>", self, diags);
+                                }
+                                Token::RPar
+                                | Token::Action
+                                | Token::AttrStart
+                                | Token::Binding
+                                | Token::CloseNode
+                                | Token::Dot
+                                | Token::EOF
+                                | Token::Id
+                                | Token::Import
+                                | Token::InlineAction
+                                | Token::Insert
+                                | Token::LBrace
+                                | Token::LBrak
+                                | Token::LPar
+                                | Token::Lookahead
+                                | Token::MaxErrors
+                                | Token::Not
+                                | Token::OpenNode
+                                | Token::Or
+                                | Token::Percent
+                                | Token::Plus
+                                | Token::Predicate
+                                | Token::RBrak
+                                | Token::Right
+                                | Token::Semi
+                                | Token::Skip
+                                | Token::Star
+                                | Token::Start
+                                | Token::Str
+                                | Token::Sync
+                                | Token::TaggedAction
+                                | Token::Token
+                                | Token::TokenDerive => break,
+                                _ => {
+                                    self.advance_with_error(diags, err![self.span(), ",", ")"]);
+                                }
+                            }
+                        }
+                    }
+                    Token::RPar => {}
+                    _ => {
+                        self.error(diags, err![self.span(), "<identifier>", ")"]);
+                    }
+                }
+                expect!(RPar, ")", self, diags);
+            }
+            Token::RBrak => {}
+            _ => {
+                self.error(diags, err![self.span(), "(", "]"]);
+            }
+        }
+        expect!(RBrak, "]", self, diags);
+        self.close(m, Rule::Attribute, diags);
+    }
     fn r#regex(&mut self, diags: &mut Vec<Diagnostic>) {
         self.r#alternation(diags);
     }
@@ -627,12 +1167,19 @@ impl<'a> Parser<'a> {
                 Token::RBrak
                 | Token::RPar
                 | Token::Semi
+                | Token::AttrStart
                 | Token::EOF
                 | Token::Id
+                | Token::Import
+                | Token::Insert
+                | Token::Lookahead
+                | Token::MaxErrors
                 | Token::Right
                 | Token::Skip
                 | Token::Start
-                | Token::Token => break,
+                | Token::Sync
+                | Token::Token
+                | Token::TokenDerive => break,
                 _ => {
                     self.advance_with_error(diags, err![self.span(), "|", "]", ")", ";"]);
                 }
@@ -649,14 +1196,19 @@ impl<'a> Parser<'a> {
         loop {
             match self.current {
                 Token::Action
+                | Token::AttrStart
                 | Token::Binding
                 | Token::CloseNode
+                | Token::Dot
                 | Token::Id
+                | Token::InlineAction
                 | Token::LBrak
                 | Token::LPar
+                | Token::Not
                 | Token::OpenNode
                 | Token::Predicate
-                | Token::Str => {
+                | Token::Str
+                | Token::TaggedAction => {
                     if m.is_none() {
                         m = Some(self.cst.open_before(lhs));
                     }
@@ -667,28 +1219,39 @@ impl<'a> Parser<'a> {
                 | Token::RPar
                 | Token::Semi
                 | Token::EOF
+                | Token::Import
+                | Token::Insert
+                | Token::Lookahead
+                | Token::MaxErrors
                 | Token::Right
                 | Token::Skip
                 | Token::Start
-                | Token::Token => break,
+                | Token::Sync
+                | Token::Token
+                | Token::TokenDerive => break,
                 _ => {
                     self.advance_with_error(
                         diags,
                         err![
                             self.span(),
                             "<semantic action>",
+                            "#[",
                             "<binding>",
                             "<close node mark>",
+                            ".",
                             "<identifier>",
+                            "<inline action>",
                             "[",
                             "(",
+                            "!",
                             "<open node mark>",
                             "|",
                             "<semantic predicate>",
                             "]",
                             ")",
                             ";",
-                            "<string literal>"
+                            "<string literal>",
+                            "<tagged action>"
                         ],
                     );
                 }
@@ -698,16 +1261,21 @@ impl<'a> Parser<'a> {
             self.close(m, Rule::Concat, diags);
         }
     }
+    #[allow(unused_assignments)]
     fn r#postfix(&mut self, diags: &mut Vec<Diagnostic>) {
+        let mut node_kind = Rule::Postfix;
         let mut lhs = self.cst.mark();
         match self.current {
             Token::Action
             | Token::Binding
             | Token::CloseNode
+            | Token::Dot
             | Token::Id
+            | Token::InlineAction
             | Token::OpenNode
             | Token::Predicate
-            | Token::Str => {
+            | Token::Str
+            | Token::TaggedAction => {
                 self.r#atomic(diags);
             }
             Token::LPar => {
@@ -716,27 +1284,45 @@ impl<'a> Parser<'a> {
             Token::LBrak => {
                 self.r#optional(diags);
             }
+            Token::AttrStart => {
+                self.r#attribute(diags);
+            }
+            Token::Not => {
+                let m = self.cst.open();
+                expect!(Not, "!", self, diags);
+                expect!(LPar, "(", self, diags);
+                self.r#regex(diags);
+                expect!(RPar, ")", self, diags);
+                node_kind = Rule::SyntacticPredicate;
+                self.close(m, node_kind, diags);
+            }
             _ => {
                 self.error(
                     diags,
                     err![
                         self.span(),
                         "<semantic action>",
+                        "#[",
                         "<binding>",
                         "<close node mark>",
+                        ".",
                         "<identifier>",
+                        "<inline action>",
                         "[",
                         "(",
+                        "!",
                         "<open node mark>",
                         "<semantic predicate>",
-                        "<string literal>"
+                        "<string literal>",
+                        "<tagged action>"
                     ],
                 );
             }
         }
         loop {
+            node_kind = Rule::Postfix;
             match self.current {
-                Token::Plus | Token::Star => {
+                Token::LBrace | Token::Percent | Token::Plus | Token::Star => {
                     let m = self.cst.open_before(lhs);
                     match self.current {
                         Token::Star => {
@@ -745,11 +1331,77 @@ impl<'a> Parser<'a> {
                         Token::Plus => {
                             expect!(Plus, "+", self, diags);
                         }
+                        Token::Percent => {
+                            expect!(Percent, "%", self, diags);
+                            match self.current {
+                                Token::Action
+                                | Token::Binding
+                                | Token::CloseNode
+                                | Token::Dot
+                                | Token::Id
+                                | Token::InlineAction
+                                | Token::OpenNode
+                                | Token::Predicate
+                                | Token::Str
+                                | Token::TaggedAction => {
+                                    self.r#atomic(diags);
+                                }
+                                Token::LPar => {
+                                    self.r#paren(diags);
+                                }
+                                Token::LBrak => {
+                                    self.r#optional(diags);
+                                }
+                                _ => {
+                                    self.error(
+                                        diags,
+                                        err![
+                                            self.span(),
+                                            "<semantic action>",
+                                            "<binding>",
+                                            "<close node mark>",
+                                            ".",
+                                            "<identifier>",
+                                            "<inline action>",
+                                            "[",
+                                            "(",
+                                            "<open node mark>",
+                                            "<semantic predicate>",
+                                            "<string literal>",
+                                            "<tagged action>"
+                                        ],
+                                    );
+                                }
+                            }
+                        }
+                        Token::LBrace => {
+                            expect!(LBrace, "{", self, diags);
+                            expect!(Number, "<number>", self, diags);
+                            match self.current {
+                                Token::Comma => {
+                                    expect!(Comma, ",", self, diags);
+                                    match self.current {
+                                        Token::Number => {
+                                            expect!(Number, "<number>", self, diags);
+                                        }
+                                        Token::RBrace => {}
+                                        _ => {
+                                            self.error(diags, err![self.span(), "<number>", "}"]);
+                                        }
+                                    }
+                                }
+                                Token::RBrace => {}
+                                _ => {
+                                    self.error(diags, err![self.span(), ",", "}"]);
+                                }
+                            }
+                            expect!(RBrace, "}", self, diags);
+                        }
                         _ => {
-                            self.error(diags, err![self.span(), "+", "*"]);
+                            self.error(diags, err![self.span(), "{", "%", "+", "*"]);
                         }
                     }
-                    lhs = self.close(m, Rule::Postfix, diags);
+                    lhs = self.close(m, node_kind, diags);
                 }
                 _ => {
                     break;
@@ -757,12 +1409,73 @@ impl<'a> Parser<'a> {
             }
         }
     }
+    #[allow(unused_assignments)]
     fn r#paren(&mut self, diags: &mut Vec<Diagnostic>) {
         let m = self.cst.open();
+        let mut node_kind = Rule::Paren;
         expect!(LPar, "(", self, diags);
         self.r#regex(diags);
         expect!(RPar, ")", self, diags);
-        self.close(m, Rule::Paren, diags);
+        match self.current {
+            Token::Arrow => {
+                expect!(Arrow, "=>", self, diags);
+                node_kind = Rule::SyntacticPredicate;
+            }
+            Token::Action
+            | Token::AttrStart
+            | Token::Binding
+            | Token::CloseNode
+            | Token::Dot
+            | Token::Id
+            | Token::InlineAction
+            | Token::LBrace
+            | Token::LBrak
+            | Token::LPar
+            | Token::Not
+            | Token::OpenNode
+            | Token::Or
+            | Token::Percent
+            | Token::Plus
+            | Token::Predicate
+            | Token::RBrak
+            | Token::RPar
+            | Token::Semi
+            | Token::Star
+            | Token::Str
+            | Token::TaggedAction => {}
+            _ => {
+                self.error(
+                    diags,
+                    err![
+                        self.span(),
+                        "<semantic action>",
+                        "=>",
+                        "#[",
+                        "<binding>",
+                        "<close node mark>",
+                        ".",
+                        "<identifier>",
+                        "<inline action>",
+                        "{",
+                        "[",
+                        "(",
+                        "!",
+                        "<open node mark>",
+                        "|",
+                        "%",
+                        "+",
+                        "<semantic predicate>",
+                        "]",
+                        ")",
+                        ";",
+                        "*",
+                        "<string literal>",
+                        "<tagged action>"
+                    ],
+                );
+            }
+        }
+        self.close(m, node_kind, diags);
     }
     fn r#optional(&mut self, diags: &mut Vec<Diagnostic>) {
         let m = self.cst.open();
@@ -774,27 +1487,16 @@ impl<'a> Parser<'a> {
     fn r#atomic(&mut self, diags: &mut Vec<Diagnostic>) {
         let m = self.cst.open();
         match self.current {
-            Token::Id => {
-                expect!(Id, "<identifier>", self, diags);
-            }
-            Token::Str => {
-                expect!(Str, "<string literal>", self, diags);
-            }
-            Token::Predicate => {
-                expect!(Predicate, "<semantic predicate>", self, diags);
-            }
-            Token::Action => {
-                expect!(Action, "<semantic action>", self, diags);
-            }
-            Token::Binding => {
-                expect!(Binding, "<binding>", self, diags);
-            }
-            Token::OpenNode => {
-                expect!(OpenNode, "<open node mark>", self, diags);
-            }
-            Token::CloseNode => {
-                expect!(CloseNode, "<close node mark>", self, diags);
-            }
+            Token::Action
+            | Token::Binding
+            | Token::CloseNode
+            | Token::Dot
+            | Token::Id
+            | Token::InlineAction
+            | Token::OpenNode
+            | Token::Predicate
+            | Token::Str
+            | Token::TaggedAction => self.advance(false),
             _ => {
                 self.error(
                     diags,
@@ -803,10 +1505,13 @@ impl<'a> Parser<'a> {
                         "<semantic action>",
                         "<binding>",
                         "<close node mark>",
+                        ".",
                         "<identifier>",
+                        "<inline action>",
                         "<open node mark>",
                         "<semantic predicate>",
-                        "<string literal>"
+                        "<string literal>",
+                        "<tagged action>"
                     ],
                 );
             }
@@ -820,3 +1525,24 @@ trait PredicatesAndActions {
     #[allow(clippy::ptr_arg)]
     fn build(&mut self, _rule: Rule, _node: NodeRef, _diags: &mut Vec<Diagnostic>) {}
 }
+
+impl Token {
+    /// Keyword token with the symbol `text`, for lexers that lex keywords as
+    /// identifiers first.
+    #[allow(dead_code)]
+    pub fn keyword(text: &str) -> Option<Token> {
+        match text {
+            "token" => Some(Token::Token),
+            "start" => Some(Token::Start),
+            "right" => Some(Token::Right),
+            "skip" => Some(Token::Skip),
+            "insert" => Some(Token::Insert),
+            "lookahead" => Some(Token::Lookahead),
+            "import" => Some(Token::Import),
+            "sync" => Some(Token::Sync),
+            "max_errors" => Some(Token::MaxErrors),
+            "token_derive" => Some(Token::TokenDerive),
+            _ => None,
+        }
+    }
+}
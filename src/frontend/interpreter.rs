@@ -0,0 +1,642 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::ast::*;
+use super::parser::*;
+use super::sema::*;
+
+/// Syntax tree built by the interpreter.
+pub enum Tree<'t> {
+    Rule(String, Vec<Tree<'t>>),
+    Token(&'t str),
+}
+
+impl std::fmt::Display for Tree<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const DEPTH: &str = "    ";
+        fn rec(tree: &Tree, f: &mut std::fmt::Formatter<'_>, indent: usize) -> std::fmt::Result {
+            match tree {
+                Tree::Rule(rule, children) => {
+                    writeln!(f, "{}{rule}", DEPTH.repeat(indent))?;
+                    for child in children {
+                        rec(child, f, indent + 1)?;
+                    }
+                    Ok(())
+                }
+                Tree::Token(token) => writeln!(f, "{}{token}", DEPTH.repeat(indent)),
+            }
+        }
+        rec(self, f, 0)
+    }
+}
+
+/// Recorded run of a generated parser.
+///
+/// The trace consists of the token kinds and the results of the runtime
+/// decisions (semantic predicates and token insertions) in evaluation order.
+/// All other decisions of the parser are determined by the grammar.
+/// ```text
+/// tokens: Id Colon Id Semi
+/// decisions: 1 0
+/// ```
+pub struct Trace<'t> {
+    pub tokens: Vec<&'t str>,
+    pub decisions: Vec<bool>,
+}
+
+impl<'t> Trace<'t> {
+    pub fn parse(source: &'t str) -> Result<Self, String> {
+        let mut tokens = None;
+        let mut decisions = None;
+        for line in source.lines().filter(|line| !line.trim().is_empty()) {
+            if let Some(rest) = line.strip_prefix("tokens:") {
+                tokens = Some(rest.split_whitespace().collect());
+            } else if let Some(rest) = line.strip_prefix("decisions:") {
+                decisions = Some(
+                    rest.split_whitespace()
+                        .map(|decision| match decision {
+                            "0" => Ok(false),
+                            "1" => Ok(true),
+                            _ => Err(format!("invalid decision `{decision}` in trace")),
+                        })
+                        .collect::<Result<_, _>>()?,
+                );
+            } else {
+                return Err(format!("invalid line `{line}` in trace"));
+            }
+        }
+        Ok(Self {
+            tokens: tokens.ok_or("missing tokens in trace")?,
+            decisions: decisions.unwrap_or_default(),
+        })
+    }
+}
+
+/// Error that stops the interpretation of a trace.
+pub struct ReplayError<'t> {
+    /// Index of the token in the trace.
+    pub position: usize,
+    pub message: String,
+    /// Syntax tree built up to the error.
+    pub partial: Vec<Tree<'t>>,
+}
+
+impl std::fmt::Display for ReplayError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "token {}: {}", self.position, self.message)
+    }
+}
+
+/// Rule local state, which corresponds to the local variables of a rule
+/// function in the generated parser.
+struct Frame<'a> {
+    node_kind: String,
+    marks: HashMap<&'a str, usize>,
+    opened: bool,
+}
+
+impl Frame<'_> {
+    fn new(rule_name: &str) -> Self {
+        Self {
+            node_kind: snake_to_pascal_case(rule_name),
+            marks: HashMap::new(),
+            opened: false,
+        }
+    }
+}
+
+/// Interprets the grammar on a recorded trace.
+///
+/// The decisions are taken in the same order as in the generated parser, so
+/// the interpreter consumes the recorded predicate and insertion results in
+/// sync with the original run. Unlike the generated parser, the interpreter
+/// stops at the first syntax error.
+pub struct Interpreter<'a, 't> {
+    cst: &'a Cst<'a>,
+    sema: &'a SemanticData<'a>,
+    skipped: HashSet<&'a str>,
+    inserted: HashSet<&'a str>,
+    tokens: &'t [&'t str],
+    /// Recorded decisions, or `None` if predicates are assumed to hold.
+    decisions: Option<std::slice::Iter<'t, bool>>,
+    pos: usize,
+    current: &'t str,
+    nodes: Vec<Tree<'t>>,
+}
+
+type ReplayResult<'t, T> = std::result::Result<T, ReplayError<'t>>;
+
+impl<'a, 't> Interpreter<'a, 't> {
+    pub fn run(
+        cst: &'a Cst,
+        sema: &'a SemanticData,
+        trace: &'t Trace,
+    ) -> ReplayResult<'t, Tree<'t>> {
+        Self::interpret(cst, sema, &trace.tokens, Some(trace.decisions.iter()))
+    }
+
+    /// Interprets the grammar on the token kinds of an input without a trace.
+    ///
+    /// As there is no parser to evaluate them, semantic and syntactic
+    /// predicates are assumed to hold and no tokens are inserted.
+    pub fn run_tokens(
+        cst: &'a Cst,
+        sema: &'a SemanticData,
+        tokens: &'t [&'t str],
+    ) -> ReplayResult<'t, Tree<'t>> {
+        Self::interpret(cst, sema, tokens, None)
+    }
+
+    fn interpret(
+        cst: &'a Cst,
+        sema: &'a SemanticData,
+        tokens: &'t [&'t str],
+        decisions: Option<std::slice::Iter<'t, bool>>,
+    ) -> ReplayResult<'t, Tree<'t>> {
+        let mut interpreter = Self {
+            cst,
+            sema,
+            skipped: HashSet::from(["Error"]),
+            inserted: HashSet::new(),
+            tokens,
+            decisions,
+            pos: 0,
+            current: "EOF",
+            nodes: vec![],
+        };
+        let mut declared = HashSet::from(["Error"]);
+        if let Some(file) = File::cast(cst, NodeRef::ROOT) {
+            for token in file.token_decls(cst) {
+                if let Some((name, _)) = token.name(cst) {
+                    declared.insert(name);
+                    if sema.skipped.contains(&token) {
+                        interpreter.skipped.insert(name);
+                    }
+                    if sema.inserted.contains(&token) && interpreter.decisions.is_some() {
+                        interpreter.inserted.insert(name);
+                    }
+                }
+            }
+        }
+        if let Some((position, token)) = tokens
+            .iter()
+            .enumerate()
+            .find(|(_, token)| !declared.contains(*token))
+        {
+            interpreter.pos = position;
+            return Err(interpreter.error(format!("undeclared token `{token}` in trace")));
+        }
+        let Some(start) = sema.start else {
+            return Err(interpreter.error("missing start rule".to_string()));
+        };
+
+        interpreter.skip();
+        interpreter.rule(start)?;
+        if interpreter.current != "EOF" {
+            return Err(interpreter.error("expected end of file".to_string()));
+        }
+        let unused = interpreter.decisions.as_ref().map_or(0, |it| it.len());
+        if unused > 0 {
+            return Err(interpreter.error(format!("{unused} unused decisions in trace")));
+        }
+        if interpreter.nodes.len() == 1 {
+            Ok(interpreter.nodes.pop().unwrap())
+        } else {
+            let name = start.name(cst).unwrap_or_default().0;
+            Ok(Tree::Rule(
+                snake_to_pascal_case(name),
+                std::mem::take(&mut interpreter.nodes),
+            ))
+        }
+    }
+
+    fn error(&mut self, message: String) -> ReplayError<'t> {
+        ReplayError {
+            position: self.pos,
+            message,
+            partial: std::mem::take(&mut self.nodes),
+        }
+    }
+    fn syntax_error(&mut self, expected: Option<&BTreeSet<TokenName>>) -> ReplayError<'t> {
+        let expected = expected
+            .into_iter()
+            .flatten()
+            .map(|token| token.0)
+            .collect::<Vec<_>>();
+        let message = if expected.contains(&self.current) {
+            format!(
+                "{} is rejected by the recorded predicate results",
+                self.current
+            )
+        } else {
+            format!("found {}, expected: {}", self.current, expected.join(", "))
+        };
+        self.error(message)
+    }
+    fn decision(&mut self, what: &str) -> ReplayResult<'t, bool> {
+        match self.decisions.as_mut().map(|it| it.next()) {
+            Some(Some(decision)) => Ok(*decision),
+            Some(None) => Err(self.error(format!("missing decision for {what} in trace"))),
+            None => Ok(true),
+        }
+    }
+    fn skip(&mut self) {
+        while let Some(token) = self.tokens.get(self.pos) {
+            if !self.skipped.contains(token) {
+                break;
+            }
+            self.nodes.push(Tree::Token(token));
+            self.pos += 1;
+        }
+        self.current = self.tokens.get(self.pos).copied().unwrap_or("EOF");
+    }
+    fn peek(&self, lookahead: usize) -> &str {
+        self.tokens
+            .iter()
+            .skip(self.pos)
+            .filter(|token| !self.skipped.contains(*token))
+            .nth(lookahead)
+            .copied()
+            .unwrap_or("EOF")
+    }
+    fn advance(&mut self) {
+        if let Some(token) = self.tokens.get(self.pos) {
+            self.nodes.push(Tree::Token(token));
+            self.pos += 1;
+        }
+        self.skip();
+    }
+    fn close(&mut self, start: usize, node_kind: String) {
+        let children = self.nodes.split_off(start);
+        self.nodes.push(Tree::Rule(node_kind, children));
+    }
+    fn matches(&self, set: Option<&BTreeSet<TokenName>>) -> bool {
+        set.is_some_and(|set| set.iter().any(|token| token.0 == self.current))
+    }
+    fn has_predicate(&self, regex: Regex) -> bool {
+        match regex {
+            Regex::Concat(concat) => {
+                matches!(
+                    concat.operands(self.cst).next(),
+                    Some(Regex::Predicate(_) | Regex::SyntacticPredicate(_))
+                )
+            }
+            Regex::Paren(paren) => paren
+                .inner(self.cst)
+                .is_some_and(|inner| self.has_predicate(inner)),
+            _ => false,
+        }
+    }
+    /// Checks if the regex is selected by the current token and its
+    /// predicate, like a match arm with guard in the generated parser.
+    fn selects(
+        &mut self,
+        regex: Regex,
+        set: Option<&BTreeSet<TokenName>>,
+    ) -> ReplayResult<'t, bool> {
+        if !self.matches(set) {
+            return Ok(false);
+        }
+        if let Some(sequences) = self.sema.lookahead_sets.get(&regex.syntax()) {
+            let matches = |seq: &Vec<TokenName>| {
+                seq.iter()
+                    .enumerate()
+                    .all(|(i, token)| token.0 == self.peek(i))
+            };
+            if !sequences.iter().any(matches) {
+                return Ok(false);
+            }
+        }
+        if self.has_predicate(regex) {
+            return self.decision("predicate");
+        }
+        Ok(true)
+    }
+    fn choose(&mut self, ops: impl Iterator<Item = Regex>) -> ReplayResult<'t, Option<Regex>> {
+        for op in ops {
+            if self.selects(op, self.sema.predict_sets.get(&op.syntax()))? {
+                return Ok(Some(op));
+            }
+        }
+        Ok(None)
+    }
+    fn expect(&mut self, name: &str) -> ReplayResult<'t, ()> {
+        if self.current == name {
+            self.advance();
+        } else if !self.inserted.contains(name) || !self.decision("token insertion")? {
+            let message = format!("found {}, expected: {name}", self.current);
+            return Err(self.error(message));
+        }
+        Ok(())
+    }
+
+    fn rule(&mut self, rule: RuleDecl) -> ReplayResult<'t, ()> {
+        let Some(regex) = rule.regex(self.cst) else {
+            return Ok(());
+        };
+        let name = rule.name(self.cst).unwrap_or_default().0;
+        let mut frame = Frame::new(name);
+        let lhs = self.nodes.len();
+        match self.sema.patterns.get(&rule) {
+            None => {
+                self.regex(regex, &mut frame, false)?;
+                self.close(lhs, frame.node_kind);
+            }
+            Some(Pattern::UnconditionalForwarding) => {
+                self.regex(regex, &mut frame, false)?;
+            }
+            Some(Pattern::ConditionalForwarding) => {
+                let Regex::Concat(concat) = regex else {
+                    unreachable!()
+                };
+                let mut ops = concat.operands(self.cst);
+                if let Some(head) = ops.next() {
+                    self.regex(head, &mut frame, false)?;
+                }
+                for op in ops {
+                    self.regex(op, &mut frame, true)?;
+                }
+                if frame.opened {
+                    self.close(lhs, frame.node_kind);
+                }
+            }
+            Some(Pattern::MaybeEmpty) => {
+                self.regex(regex, &mut frame, true)?;
+                if frame.opened {
+                    self.close(lhs, frame.node_kind);
+                }
+            }
+            Some(Pattern::RightRecursiveForwarding(branches)) => {
+                let Regex::Alternation(alt) = regex else {
+                    unreachable!()
+                };
+                let Some(op) = self.choose(alt.operands(self.cst))? else {
+                    return Err(self.syntax_error(self.sema.predict_sets.get(&regex.syntax())));
+                };
+                self.regex(op, &mut frame, false)?;
+                if !branches.contains(&op) {
+                    self.close(lhs, frame.node_kind);
+                }
+            }
+            Some(Pattern::LeftRecursive(branches)) => {
+                let Regex::Alternation(alt) = regex else {
+                    unreachable!()
+                };
+                let ops = alt.operands(self.cst).filter(|op| !branches.contains(op));
+                let Some(op) = self.choose(ops)? else {
+                    return Err(self.syntax_error(self.sema.predict_sets.get(&regex.syntax())));
+                };
+                let is_forwarding = if let Regex::Name(name) = op {
+                    self.sema
+                        .decl_bindings
+                        .get(&name.syntax())
+                        .is_some_and(|n| RuleDecl::cast(self.cst, *n).is_some())
+                } else {
+                    false
+                };
+                self.regex(op, &mut frame, false)?;
+                if !is_forwarding {
+                    self.close(lhs, frame.node_kind.clone());
+                }
+                'outer: loop {
+                    frame.node_kind = snake_to_pascal_case(name);
+                    for branch in branches {
+                        let Regex::Concat(concat) = branch else {
+                            unreachable!()
+                        };
+                        let mut ops = concat
+                            .operands(self.cst)
+                            .filter(|op| {
+                                !matches!(op, Regex::Predicate(_) | Regex::SyntacticPredicate(_))
+                            })
+                            .skip(1)
+                            .peekable();
+                        let Some(first) = ops.peek() else {
+                            continue;
+                        };
+                        let predict = self.sema.predict_sets.get(&first.syntax());
+                        if self.matches(predict)
+                            && (!self.has_predicate(*branch) || self.decision("predicate")?)
+                        {
+                            for op in ops {
+                                self.regex(op, &mut frame, false)?;
+                            }
+                            self.close(lhs, frame.node_kind.clone());
+                            continue 'outer;
+                        }
+                    }
+                    break;
+                }
+            }
+            Some(Pattern::OperatorPrecedence(branches)) => {
+                let Regex::Alternation(alt) = regex else {
+                    unreachable!()
+                };
+                let exit = alt
+                    .operands(self.cst)
+                    .find(|op| !branches.contains(op))
+                    .and_then(|op| match op {
+                        Regex::Name(name) => self.sema.decl_bindings.get(&name.syntax()),
+                        _ => None,
+                    })
+                    .and_then(|n| RuleDecl::cast(self.cst, *n))
+                    .unwrap();
+                let operators = branches
+                    .iter()
+                    .rev()
+                    .map(|branch| {
+                        let Regex::Concat(concat) = branch else {
+                            unreachable!()
+                        };
+                        self.sema
+                            .predict_sets
+                            .get(&concat.operands(self.cst).nth(1).unwrap().syntax())
+                    })
+                    .collect::<Vec<_>>();
+                self.rule(exit)?;
+                self.precedence(name, exit, &operators, 0, lhs)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Precedence climbing as in the generated parser, where the precedence of
+    /// an operator is given by its position in `operators`.
+    fn precedence(
+        &mut self,
+        name: &str,
+        exit: RuleDecl,
+        operators: &[Option<&BTreeSet<TokenName>>],
+        min_prec: usize,
+        lhs: usize,
+    ) -> ReplayResult<'t, ()> {
+        let operator_prec = |this: &Self| operators.iter().position(|set| this.matches(*set));
+        loop {
+            let Some(prec) = operator_prec(self) else {
+                return Ok(());
+            };
+            if prec < min_prec {
+                return Ok(());
+            }
+            self.advance();
+            let rhs = self.nodes.len();
+            self.rule(exit)?;
+            while let Some(next_prec) = operator_prec(self) {
+                let left_assoc = !self.sema.right_associative.contains(self.current);
+                if !(prec < next_prec || (!left_assoc && prec == next_prec)) {
+                    break;
+                }
+                let min_prec = prec + if next_prec > prec { 1 } else { 0 };
+                self.precedence(name, exit, operators, min_prec, rhs)?;
+            }
+            self.close(lhs, snake_to_pascal_case(name));
+        }
+    }
+
+    fn regex(
+        &mut self,
+        regex: Regex,
+        frame: &mut Frame<'a>,
+        open_before: bool,
+    ) -> ReplayResult<'t, ()> {
+        let sema = self.sema;
+        match regex {
+            Regex::Name(name) => {
+                let decl = sema.decl_bindings[&name.syntax()];
+                if let Some(rule) = RuleDecl::cast(self.cst, decl) {
+                    self.rule(rule)?;
+                } else if let Some(token) = TokenDecl::cast(self.cst, decl) {
+                    self.expect(token.name(self.cst).unwrap_or_default().0)?;
+                }
+            }
+            Regex::Symbol(sym) => {
+                let decl = sema.decl_bindings[&sym.syntax()];
+                if let Some(token) = TokenDecl::cast(self.cst, decl) {
+                    self.expect(token.name(self.cst).unwrap_or_default().0)?;
+                }
+            }
+            Regex::Wildcard(_) => {
+                if !self.matches(sema.first_sets.get(&regex.syntax())) {
+                    return Err(self.syntax_error(sema.first_sets.get(&regex.syntax())));
+                }
+                self.advance();
+            }
+            Regex::Concat(concat) => {
+                for op in concat.operands(self.cst) {
+                    self.regex(op, frame, false)?;
+                }
+            }
+            Regex::Alternation(alt) => {
+                let Some(op) = self.choose(alt.operands(self.cst))? else {
+                    return Err(self.syntax_error(sema.predict_sets.get(&regex.syntax())));
+                };
+                self.regex(op, frame, false)?;
+            }
+            Regex::Star(_) | Regex::Plus(_) => {
+                let op = match regex {
+                    Regex::Star(star) => star.operand(self.cst),
+                    Regex::Plus(plus) => plus.operand(self.cst),
+                    _ => unreachable!(),
+                };
+                let Some(op) = op else {
+                    return Ok(());
+                };
+                if let Regex::Plus(_) = regex {
+                    self.regex(op, frame, false)?;
+                }
+                loop {
+                    if self.selects(op, sema.first_sets.get(&op.syntax()))? {
+                        frame.opened |= open_before;
+                        self.regex(op, frame, false)?;
+                    } else if self.matches(sema.follow_sets.get(&regex.syntax()))
+                        || self.matches(sema.recovery_sets.get(&regex.syntax()))
+                    {
+                        break;
+                    } else {
+                        return Err(self.syntax_error(sema.predict_sets.get(&regex.syntax())));
+                    }
+                }
+            }
+            Regex::Bounded(bounded) => {
+                let (Some(op), Some((min, max))) =
+                    (bounded.operand(self.cst), bounded.bounds(self.cst))
+                else {
+                    return Ok(());
+                };
+                for _ in 0..min {
+                    self.regex(op, frame, false)?;
+                }
+                let mut count = min;
+                while max.map_or(true, |max| count < max) {
+                    if self.selects(op, sema.first_sets.get(&op.syntax()))? {
+                        frame.opened |= open_before;
+                        self.regex(op, frame, false)?;
+                        count += 1;
+                    } else if self.matches(sema.follow_sets.get(&regex.syntax()))
+                        || self.matches(sema.recovery_sets.get(&regex.syntax()))
+                    {
+                        break;
+                    } else {
+                        return Err(self.syntax_error(sema.predict_sets.get(&regex.syntax())));
+                    }
+                }
+            }
+            Regex::Separated(sep) => {
+                let (Some(op), Some(separator)) = (sep.operand(self.cst), sep.separator(self.cst))
+                else {
+                    return Ok(());
+                };
+                self.regex(op, frame, false)?;
+                loop {
+                    if self.selects(separator, sema.first_sets.get(&separator.syntax()))? {
+                        frame.opened |= open_before;
+                        self.regex(separator, frame, false)?;
+                        self.regex(op, frame, false)?;
+                    } else if self.matches(sema.follow_sets.get(&regex.syntax()))
+                        || self.matches(sema.recovery_sets.get(&regex.syntax()))
+                    {
+                        break;
+                    } else {
+                        return Err(self.syntax_error(sema.predict_sets.get(&separator.syntax())));
+                    }
+                }
+            }
+            Regex::Optional(opt) => {
+                let Some(op) = opt.operand(self.cst) else {
+                    return Ok(());
+                };
+                if self.selects(op, sema.first_sets.get(&op.syntax()))? {
+                    frame.opened |= open_before;
+                    self.regex(op, frame, false)?;
+                } else if !self.matches(sema.follow_sets.get(&regex.syntax())) {
+                    return Err(self.syntax_error(sema.predict_sets.get(&regex.syntax())));
+                }
+            }
+            Regex::Paren(paren) => {
+                if let Some(inner) = paren.inner(self.cst) {
+                    self.regex(inner, frame, false)?;
+                }
+            }
+            Regex::Binding(bind) => {
+                let name = &bind.value(self.cst).unwrap_or_default().0[1..];
+                if !name.is_empty() {
+                    frame.node_kind = snake_to_pascal_case(name);
+                }
+            }
+            Regex::OpenNode(open) => {
+                if let Some(number) = open.number(self.cst) {
+                    frame.marks.insert(number, self.nodes.len());
+                }
+            }
+            Regex::CloseNode(close) => {
+                if let (Some(number), Some(node_name)) =
+                    (close.number(self.cst), close.node_name(self.cst))
+                {
+                    let start = frame.marks.get(number).copied().unwrap_or(self.nodes.len());
+                    self.close(start, snake_to_pascal_case(node_name));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeSet;
+
+use regex_syntax::hir::{Class, Hir, HirKind, Look};
+
+use super::ast::*;
+use super::parser::*;
+
+/// Pattern of a token, which is either its symbol or its regex.
+enum Pattern {
+    /// Symbol and whether it ignores the ASCII case.
+    Literal(String, bool),
+    Regex(Hir),
+}
+
+/// Lexer that is derived from the token declarations of a grammar.
+///
+/// Tokens are matched by their symbols and regular expressions, where the
+/// longest match is taken and symbols take precedence over regular expressions
+/// of the same length. Symbols of tokens with the `ignore_case` attribute
+/// match in any ASCII case. Tokens without a pattern are never produced, and
+/// whitespace that no token matches is skipped.
+pub struct GrammarLexer<'a> {
+    patterns: Vec<(&'a str, Pattern)>,
+}
+
+/// Error for input that is not matched by any token.
+pub struct LexerError {
+    pub offset: usize,
+}
+
+impl<'a> GrammarLexer<'a> {
+    /// Creates the lexer for the token declarations of the grammar.
+    ///
+    /// Returns an error if a token regex cannot be parsed.
+    pub fn new(cst: &'a Cst) -> Result<Self, String> {
+        let mut patterns = vec![];
+        let Some(file) = File::cast(cst, NodeRef::ROOT) else {
+            return Ok(Self { patterns });
+        };
+        for token in file.token_decls(cst) {
+            let Some((name, _)) = token.name(cst) else {
+                continue;
+            };
+            if let Some((symbol, _)) = token.symbol(cst) {
+                let symbol = &symbol[1..symbol.len() - 1];
+                let is_description =
+                    symbol.starts_with('<') && symbol.ends_with('>') && symbol.len() > 2;
+                if !symbol.is_empty() && !is_description {
+                    let literal = symbol.replace("\\'", "'").replace("\\\\", "\\");
+                    patterns.push((name, Pattern::Literal(literal, token.ignores_case(cst))));
+                }
+            }
+            if let Some((regex, _)) = token.regex(cst) {
+                let regex = regex[1..regex.len() - 1].replace("\\/", "/");
+                let hir = regex_syntax::parse(&regex)
+                    .map_err(|err| format!("invalid regex of token `{name}`: {err}"))?;
+                patterns.push((name, Pattern::Regex(hir)));
+            }
+        }
+        Ok(Self { patterns })
+    }
+
+    /// Splits the `input` into the token kinds and their spans.
+    pub fn tokenize(&self, input: &str) -> Result<(Vec<&'a str>, Vec<Span>), LexerError> {
+        let mut tokens = vec![];
+        let mut spans = vec![];
+        let mut pos = 0;
+        while pos < input.len() {
+            let mut longest: Option<(usize, bool, &str)> = None;
+            for (name, pattern) in self.patterns.iter() {
+                let (end, is_literal) = match pattern {
+                    Pattern::Literal(literal, ignore_case) => {
+                        let Some(text) = input.get(pos..pos + literal.len()) else {
+                            continue;
+                        };
+                        if !(text == literal || *ignore_case && text.eq_ignore_ascii_case(literal))
+                        {
+                            continue;
+                        }
+                        (pos + literal.len(), true)
+                    }
+                    Pattern::Regex(hir) => match ends(hir, input, BTreeSet::from([pos])).last() {
+                        Some(end) if *end > pos => (*end, false),
+                        _ => continue,
+                    },
+                };
+                if longest.map_or(true, |(longest_end, longest_is_literal, _)| {
+                    end > longest_end || end == longest_end && is_literal && !longest_is_literal
+                }) {
+                    longest = Some((end, is_literal, name));
+                }
+            }
+            match longest {
+                Some((end, _, name)) => {
+                    tokens.push(name);
+                    spans.push(pos..end);
+                    pos = end;
+                }
+                None => {
+                    let c = input[pos..].chars().next().unwrap();
+                    if !c.is_whitespace() {
+                        return Err(LexerError { offset: pos });
+                    }
+                    pos += c.len_utf8();
+                }
+            }
+        }
+        Ok((tokens, spans))
+    }
+}
+
+/// End offsets of all matches of the `hir` in the `input` that start at one of
+/// the `starts` offsets.
+fn ends(hir: &Hir, input: &str, starts: BTreeSet<usize>) -> BTreeSet<usize> {
+    match hir.kind() {
+        HirKind::Empty => starts,
+        HirKind::Literal(literal) => starts
+            .into_iter()
+            .filter(|start| input.as_bytes()[*start..].starts_with(&literal.0))
+            .map(|start| start + literal.0.len())
+            .collect(),
+        HirKind::Class(Class::Unicode(class)) => starts
+            .into_iter()
+            .filter_map(|start| {
+                let c = input.get(start..)?.chars().next()?;
+                class
+                    .ranges()
+                    .iter()
+                    .any(|range| range.start() <= c && c <= range.end())
+                    .then_some(start + c.len_utf8())
+            })
+            .collect(),
+        HirKind::Class(Class::Bytes(class)) => starts
+            .into_iter()
+            .filter_map(|start| {
+                let b = *input.as_bytes().get(start)?;
+                class
+                    .ranges()
+                    .iter()
+                    .any(|range| range.start() <= b && b <= range.end())
+                    .then_some(start + 1)
+            })
+            .collect(),
+        HirKind::Look(look) => starts
+            .into_iter()
+            .filter(|start| match look {
+                Look::Start => *start == 0,
+                Look::End => *start == input.len(),
+                Look::StartLF => *start == 0 || input.as_bytes()[start - 1] == b'\n',
+                Look::EndLF => *start == input.len() || input.as_bytes()[*start] == b'\n',
+                _ => true,
+            })
+            .collect(),
+        HirKind::Repetition(repetition) => {
+            let mut result = BTreeSet::new();
+            let mut current = starts;
+            let mut count = 0;
+            loop {
+                if count >= repetition.min {
+                    let len = result.len();
+                    result.extend(current.iter().copied());
+                    // later iterations cannot reach new offsets anymore
+                    if count > repetition.min && result.len() == len {
+                        break;
+                    }
+                }
+                if current.is_empty() || repetition.max == Some(count) {
+                    break;
+                }
+                current = ends(&repetition.sub, input, current);
+                count += 1;
+            }
+            result
+        }
+        HirKind::Capture(capture) => ends(&capture.sub, input, starts),
+        HirKind::Concat(hirs) => hirs
+            .iter()
+            .fold(starts, |starts, hir| ends(hir, input, starts)),
+        HirKind::Alternation(hirs) => hirs
+            .iter()
+            .flat_map(|hir| ends(hir, input, starts.clone()))
+            .collect(),
+    }
+}
@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+
+use codespan_reporting::diagnostic::Severity;
+
+use super::ast::*;
+use super::diag::*;
+use super::parser::*;
+use super::sema::SemanticData;
+
+/// Warnings of the semantic analysis, which can be allowed by the names in
+/// `#[allow(...)]` attributes and set to a level by their codes.
+pub const WARNINGS: [(&str, &[&str]); 3] = [
+    ("unused", &[UNUSED_RULE, UNUSED_TOKEN]),
+    ("complex_rule", &[COMPLEX_RULE]),
+    ("missing_action_block", &[MISSING_ACTION_BLOCK]),
+];
+
+/// Codes of the warnings that are not lints.
+const WARNING_CODES: [&str; 5] = [
+    UNUSED_RULE,
+    UNUSED_TOKEN,
+    COMPLEX_RULE,
+    MISSING_ACTION_BLOCK,
+    UNREACHABLE_ALTERNATIVE,
+];
+
+/// Maximum number of elements in an alternative for the `long_alternative`
+/// lint.
+pub const MAX_ALTERNATIVE_LENGTH: usize = 12;
+
+/// Style checks of a grammar, which do not affect the generated parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// Rule names that are not in snake case and token names that are not in
+    /// pascal case.
+    NamingConvention,
+    /// Alternatives with more than [`MAX_ALTERNATIVE_LENGTH`] elements.
+    LongAlternative,
+    /// Rules that are only used once and could be inlined.
+    SingleUseRule,
+    /// Start rules that repeat other rules without any sync tokens to recover
+    /// from errors.
+    MissingSync,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 4] = [
+        Self::NamingConvention,
+        Self::LongAlternative,
+        Self::SingleUseRule,
+        Self::MissingSync,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NamingConvention => "naming_convention",
+            Self::LongAlternative => "long_alternative",
+            Self::SingleUseRule => "single_use_rule",
+            Self::MissingSync => "missing_sync",
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NamingConvention => NAMING_CONVENTION,
+            Self::LongAlternative => LONG_ALTERNATIVE,
+            Self::SingleUseRule => SINGLE_USE_RULE,
+            Self::MissingSync => MISSING_SYNC,
+        }
+    }
+
+    fn default_level(&self) -> LintLevel {
+        match self {
+            Self::NamingConvention => LintLevel::Warn,
+            Self::LongAlternative | Self::SingleUseRule | Self::MissingSync => LintLevel::Allow,
+        }
+    }
+}
+
+impl std::str::FromStr for Lint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|lint| lint.name() == s)
+            .ok_or_else(|| format!("unknown lint `{s}`"))
+    }
+}
+
+/// Warning that is set to a level on the command line or in the
+/// configuration file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Warning {
+    /// Lint by its name or code.
+    Lint(Lint),
+    /// Warnings of the semantic analysis by their name or code.
+    Codes(&'static [&'static str]),
+}
+
+impl std::str::FromStr for Warning {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(lint) = Lint::ALL
+            .into_iter()
+            .find(|lint| lint.name() == s || lint.code().eq_ignore_ascii_case(s))
+        {
+            return Ok(Self::Lint(lint));
+        }
+        if let Some((_, codes)) = WARNINGS.iter().find(|(name, _)| *name == s) {
+            return Ok(Self::Codes(codes));
+        }
+        WARNING_CODES
+            .iter()
+            .position(|code| code.eq_ignore_ascii_case(s))
+            .map(|i| Self::Codes(std::slice::from_ref(&WARNING_CODES[i])))
+            .ok_or_else(|| format!("unknown warning `{s}`"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    /// The lint is not checked.
+    Allow,
+    /// The lint is reported as a warning.
+    Warn,
+    /// The lint is reported as an error.
+    Deny,
+}
+
+impl std::str::FromStr for LintLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Self::Allow),
+            "warn" => Ok(Self::Warn),
+            "deny" => Ok(Self::Deny),
+            _ => Err(format!("unknown lint level `{s}`")),
+        }
+    }
+}
+
+/// Levels of the lints and warnings, where lints that are not set have their
+/// default level and warnings are reported as warnings.
+///
+/// The levels apply to the whole grammar, but `#[allow(...)]` attributes take
+/// precedence for their declaration.
+#[derive(Debug, Clone, Default)]
+pub struct LintLevels {
+    levels: HashMap<Lint, LintLevel>,
+    warnings: HashMap<&'static str, LintLevel>,
+    /// Report all warnings that are not allowed as errors.
+    pub deny_warnings: bool,
+}
+
+impl LintLevels {
+    pub fn set(&mut self, lint: Lint, level: LintLevel) {
+        self.levels.insert(lint, level);
+    }
+
+    pub fn set_warning(&mut self, warning: Warning, level: LintLevel) {
+        match warning {
+            Warning::Lint(lint) => self.set(lint, level),
+            Warning::Codes(codes) => {
+                for code in codes {
+                    self.warnings.insert(code, level);
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, lint: Lint) -> LintLevel {
+        self.levels
+            .get(&lint)
+            .copied()
+            .unwrap_or_else(|| lint.default_level())
+    }
+}
+
+pub struct LintPass<'a, 'b> {
+    cst: &'b Cst<'a>,
+    sema: &'b SemanticData<'a>,
+    levels: &'b LintLevels,
+    diags: &'b mut Vec<Diagnostic>,
+}
+
+impl<'a, 'b> LintPass<'a, 'b> {
+    /// Checks the lints that are not allowed by the `levels`.
+    pub fn run(
+        cst: &'b Cst<'a>,
+        sema: &'b SemanticData<'a>,
+        levels: &'b LintLevels,
+        diags: &'b mut Vec<Diagnostic>,
+    ) {
+        let mut pass = Self {
+            cst,
+            sema,
+            levels,
+            diags,
+        };
+        if let Some(file) = File::cast(cst, NodeRef::ROOT) {
+            pass.check_naming(file);
+            pass.check_alternatives(file);
+            pass.check_single_use(file);
+            pass.check_sync();
+        }
+        pass.apply_warning_levels();
+    }
+
+    /// Allows or denies the warnings of the semantic analysis by their code,
+    /// and denies all remaining warnings with
+    /// [`deny_warnings`](LintLevels::deny_warnings).
+    fn apply_warning_levels(&mut self) {
+        let levels = self.levels;
+        self.diags.retain_mut(|diag| {
+            if diag.severity != Severity::Warning {
+                return true;
+            }
+            let code = diag.code.as_deref().unwrap_or_default();
+            match levels.warnings.get(code) {
+                Some(LintLevel::Allow) => return false,
+                Some(LintLevel::Deny) => {
+                    diag.severity = Severity::Error;
+                    diag.notes
+                        .push(format!("note: the `{code}` warning is set to `deny`"));
+                }
+                Some(LintLevel::Warn) | None if levels.deny_warnings => {
+                    diag.severity = Severity::Error;
+                    diag.notes
+                        .push("note: warnings are denied by `--deny-warnings`".to_string());
+                }
+                Some(LintLevel::Warn) | None => {}
+            }
+            true
+        });
+    }
+
+    fn report(&mut self, lint: Lint, mut diag: Diagnostic) {
+        let level = match self.levels.get(lint) {
+            LintLevel::Allow => return,
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => {
+                diag.severity = Severity::Error;
+                "deny"
+            }
+        };
+        diag.notes.push(format!(
+            "note: the `{}` lint is set to `{level}`",
+            lint.name()
+        ));
+        self.diags.push(diag);
+    }
+
+    fn check_naming(&mut self, file: File) {
+        if self.levels.get(Lint::NamingConvention) == LintLevel::Allow {
+            return;
+        }
+        for rule in file.rule_decls(self.cst) {
+            let Some((name, span)) = rule
+                .name(self.cst)
+                .filter(|_| !rule.allows(self.cst, Lint::NamingConvention.name()))
+            else {
+                continue;
+            };
+            // names that start with the wrong case are already reported as errors
+            if name.starts_with(|c: char| c.is_lowercase())
+                && name.contains(|c: char| c.is_uppercase())
+            {
+                let mut snake = String::new();
+                for (i, c) in name.char_indices() {
+                    if c.is_uppercase() {
+                        if i > 0 && !name[..i].ends_with('_') {
+                            snake.push('_');
+                        }
+                        snake.extend(c.to_lowercase());
+                    } else {
+                        snake.push(c);
+                    }
+                }
+                let diag = Diagnostic::naming_convention(&span, "rule", "snake", snake);
+                self.report(Lint::NamingConvention, diag);
+            }
+        }
+        for token in file.token_decls(self.cst) {
+            let Some((name, span)) = token
+                .name(self.cst)
+                .filter(|_| !token.allows(self.cst, Lint::NamingConvention.name()))
+            else {
+                continue;
+            };
+            if name.starts_with(|c: char| c.is_uppercase()) && name.contains('_') {
+                let pascal = name
+                    .split('_')
+                    .filter(|word| !word.is_empty())
+                    .map(|word| {
+                        let rest = &word[word.chars().next().unwrap().len_utf8()..];
+                        let first = word.chars().next().unwrap().to_uppercase();
+                        if rest.contains(|c: char| c.is_lowercase()) {
+                            format!("{first}{rest}")
+                        } else {
+                            format!("{first}{}", rest.to_lowercase())
+                        }
+                    })
+                    .collect();
+                let diag = Diagnostic::naming_convention(&span, "token", "pascal", pascal);
+                self.report(Lint::NamingConvention, diag);
+            }
+        }
+    }
+
+    fn check_alternatives(&mut self, file: File) {
+        if self.levels.get(Lint::LongAlternative) == LintLevel::Allow {
+            return;
+        }
+        for rule in file.rule_decls(self.cst) {
+            if rule.allows(self.cst, Lint::LongAlternative.name()) {
+                continue;
+            }
+            let mut alternations = vec![];
+            Self::collect(self.cst, rule.syntax(), &mut |regex| {
+                if let Regex::Alternation(alternation) = regex {
+                    alternations.push(alternation);
+                }
+            });
+            for alternation in alternations {
+                for operand in alternation.operands(self.cst) {
+                    let len = match operand {
+                        Regex::Concat(concat)
+                            if concat.allows(self.cst, Lint::LongAlternative.name()) =>
+                        {
+                            continue;
+                        }
+                        Regex::Concat(concat) => concat
+                            .operands(self.cst)
+                            .filter(|regex| {
+                                !matches!(
+                                    regex,
+                                    Regex::Predicate(_)
+                                        | Regex::Action(_)
+                                        | Regex::Binding(_)
+                                        | Regex::OpenNode(_)
+                                        | Regex::CloseNode(_)
+                                )
+                            })
+                            .count(),
+                        _ => 1,
+                    };
+                    if len > MAX_ALTERNATIVE_LENGTH {
+                        let span = operand.span(self.cst);
+                        let diag = Diagnostic::long_alternative(&span, len, MAX_ALTERNATIVE_LENGTH);
+                        self.report(Lint::LongAlternative, diag);
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_single_use(&mut self, file: File) {
+        if self.levels.get(Lint::SingleUseRule) == LintLevel::Allow {
+            return;
+        }
+        let mut uses: HashMap<NodeRef, Vec<Span>> = HashMap::new();
+        for (name, decl) in self.sema.decl_bindings.iter() {
+            uses.entry(*decl)
+                .or_default()
+                .push(self.cst.get_span(*name).unwrap());
+        }
+        for rule in file.rule_decls(self.cst) {
+            if self.sema.start == Some(rule) || rule.allows(self.cst, Lint::SingleUseRule.name()) {
+                continue;
+            }
+            let Some([use_span]) = uses.get(&rule.syntax()).map(Vec::as_slice) else {
+                continue;
+            };
+            let rule_span = rule.span(self.cst);
+            // recursive rules cannot be inlined
+            if rule_span.start <= use_span.start && use_span.end <= rule_span.end {
+                continue;
+            }
+            let Some((_, span)) = rule.name(self.cst) else {
+                continue;
+            };
+            let diag = Diagnostic::single_use_rule(&span, use_span);
+            self.report(Lint::SingleUseRule, diag);
+        }
+    }
+
+    fn check_sync(&mut self) {
+        if self.levels.get(Lint::MissingSync) == LintLevel::Allow || !self.sema.sync.is_empty() {
+            return;
+        }
+        let Some(start) = self
+            .sema
+            .start
+            .filter(|start| !start.allows(self.cst, Lint::MissingSync.name()))
+        else {
+            return;
+        };
+        let mut repeats_rule = false;
+        Self::collect(self.cst, start.syntax(), &mut |regex| {
+            let operand = match regex {
+                Regex::Star(regex) => regex.operand(self.cst),
+                Regex::Plus(regex) => regex.operand(self.cst),
+                Regex::Separated(regex) => regex.operand(self.cst),
+                Regex::Bounded(regex) => regex.operand(self.cst),
+                _ => None,
+            };
+            if let Some(operand) = operand {
+                Self::collect(self.cst, operand.syntax(), &mut |regex| {
+                    repeats_rule |= matches!(regex, Regex::Name(name)
+                        if self.sema.decl_bindings.get(&name.syntax())
+                            .is_some_and(|decl| RuleDecl::cast(self.cst, *decl).is_some()));
+                });
+            }
+        });
+        if let Some((_, span)) = start.name(self.cst).filter(|_| repeats_rule) {
+            self.report(Lint::MissingSync, Diagnostic::missing_sync(&span));
+        }
+    }
+
+    /// Calls `f` for all regexes in the subtree of `syntax`.
+    fn collect<F: FnMut(Regex)>(cst: &Cst, syntax: NodeRef, f: &mut F) {
+        if let Some(regex) = Regex::cast(cst, syntax) {
+            f(regex);
+        }
+        for child in cst.children(syntax) {
+            Self::collect(cst, child, f);
+        }
+    }
+}
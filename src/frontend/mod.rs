@@ -1,5 +1,12 @@
 pub mod ast;
+pub mod compat;
 pub mod diag;
+pub mod explain;
+pub mod interpreter;
+pub mod lexer;
+pub mod lint;
 pub mod parser;
 pub mod printer;
+pub mod report;
 pub mod sema;
+pub mod source;
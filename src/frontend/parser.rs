@@ -1,12 +1,15 @@
 use codespan_reporting::diagnostic::Label;
 use logos::{Lexer, Logos};
 
+use super::diag::*;
+
 pub type Span = core::ops::Range<usize>;
 pub type Diagnostic = codespan_reporting::diagnostic::Diagnostic<()>;
 
 macro_rules! err {
     [$span:expr, $($tk:literal),*] => {
         Diagnostic::error()
+            .with_code(INVALID_SYNTAX)
             .with_message(syntax_error_message!($span, $($tk),*))
             .with_labels(vec![Label::primary((), $span.start as usize..$span.end as usize)])
     }
@@ -18,20 +21,33 @@ pub enum LexerError {
     Invalid,
     UnterminatedString,
     UnterminatedComment,
+    UnterminatedInlineAction,
+    UnterminatedRegex,
 }
 
 impl LexerError {
     pub fn into_diagnostic(self, span: Span) -> Diagnostic {
         match self {
             LexerError::Invalid => Diagnostic::error()
+                .with_code(INVALID_TOKEN)
                 .with_message("invalid token")
                 .with_labels(vec![Label::primary((), span)]),
             LexerError::UnterminatedString => Diagnostic::error()
+                .with_code(UNTERMINATED_LITERAL)
                 .with_message("unterminated string literal")
                 .with_labels(vec![Label::primary((), span)]),
             LexerError::UnterminatedComment => Diagnostic::error()
+                .with_code(UNTERMINATED_LITERAL)
                 .with_message("unterminated comment")
                 .with_labels(vec![Label::primary((), span)]),
+            LexerError::UnterminatedInlineAction => Diagnostic::error()
+                .with_code(UNTERMINATED_LITERAL)
+                .with_message("unterminated inline action")
+                .with_labels(vec![Label::primary((), span)]),
+            LexerError::UnterminatedRegex => Diagnostic::error()
+                .with_code(UNTERMINATED_LITERAL)
+                .with_message("unterminated regular expression")
+                .with_labels(vec![Label::primary((), span)]),
         }
     }
 }
@@ -72,8 +88,97 @@ fn parse_block_comment(lexer: &mut Lexer<'_, Token>) -> Result<(), LexerError> {
     }
 }
 
+fn parse_regex(lexer: &mut Lexer<'_, Token>) -> Result<(), LexerError> {
+    let mut it = lexer.remainder().chars();
+    while let Some(c) = it.next() {
+        match c {
+            '/' => {
+                lexer.bump(1);
+                return Ok(());
+            }
+            '\n' => return Err(LexerError::UnterminatedRegex),
+            '\\' => {
+                lexer.bump(1);
+                if let Some(c) = it.next() {
+                    lexer.bump(c.len_utf8());
+                }
+            }
+            c => {
+                lexer.bump(c.len_utf8());
+            }
+        }
+    }
+    Err(LexerError::UnterminatedRegex)
+}
+
+fn parse_arrow(lexer: &mut Lexer<'_, Token>) -> Result<(), LexerError> {
+    if lexer.remainder().trim_start().starts_with('{') {
+        parse_inline_action(lexer)
+    } else {
+        // arrow of a syntactic predicate, which is checked by `tokenize`
+        Ok(())
+    }
+}
+
+fn parse_inline_action(lexer: &mut Lexer<'_, Token>) -> Result<(), LexerError> {
+    let remainder = lexer.remainder();
+    let start = remainder.len() - remainder.trim_start().len();
+    if !remainder[start..].starts_with('{') {
+        return Err(LexerError::UnterminatedInlineAction);
+    }
+    let mut depth = 0;
+    let mut it = remainder.char_indices().skip(start).peekable();
+    while let Some((i, c)) = it.next() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    lexer.bump(i + 1);
+                    return Ok(());
+                }
+            }
+            '"' => {
+                // skip string literal, which may contain braces
+                while let Some((_, c)) = it.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => {
+                            it.next();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            '\'' => {
+                // skip character literal, which may contain braces or quotes,
+                // but not a lifetime (e.g. `'a`)
+                let mut lookahead = it.clone();
+                let is_char = match lookahead.next() {
+                    Some((_, '\\')) => {
+                        lookahead.next();
+                        while lookahead
+                            .next_if(|(_, c)| c.is_ascii_hexdigit() || matches!(c, '{' | '}'))
+                            .is_some()
+                        {}
+                        lookahead.next_if(|(_, c)| *c == '\'').is_some()
+                    }
+                    Some((_, '\'')) | None => false,
+                    Some(_) => lookahead.next_if(|(_, c)| *c == '\'').is_some(),
+                };
+                if is_char {
+                    it = lookahead;
+                }
+            }
+            _ => {}
+        }
+    }
+    lexer.bump(remainder.len());
+    Err(LexerError::UnterminatedInlineAction)
+}
+
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Logos, Debug, PartialEq, Copy, Clone)]
+#[derive(Logos, Debug, PartialEq, Eq, Hash, Copy, Clone)]
 #[logos(error = LexerError)]
 pub enum Token {
     EOF,
@@ -92,6 +197,13 @@ pub enum Token {
     Right,
     #[token("skip")]
     Skip,
+    // contextual keywords, which are lexed as identifiers
+    Insert,
+    Lookahead,
+    Import,
+    Sync,
+    MaxErrors,
+    TokenDerive,
     #[token(":")]
     Colon,
     #[token(";")]
@@ -112,14 +224,37 @@ pub enum Token {
     Star,
     #[token("+")]
     Plus,
+    #[token("%")]
+    Percent,
+    #[token(".")]
+    Dot,
+    #[token("!")]
+    Not,
+    Arrow,
+    #[token(",")]
+    Comma,
+    #[token("#[")]
+    AttrStart,
+    #[token("{")]
+    LBrace,
+    #[token("}")]
+    RBrace,
     #[regex("[a-zA-Z][a-zA-Z_0-9]*")]
     Id,
     #[regex("'", parse_string)]
     Str,
+    #[regex("[0-9]+")]
+    Number,
     #[regex(r"\?[0-9]+")]
     Predicate,
     #[regex(r"#[0-9]+")]
     Action,
+    #[token("=>", parse_arrow)]
+    InlineAction,
+    #[regex(r"#[0-9]+:[a-zA-Z][a-zA-Z_0-9]*", parse_inline_action)]
+    TaggedAction,
+    #[token("/", parse_regex)]
+    Regex,
     #[regex(r"@([a-zA-Z][a-zA-Z_0-9]*)?")]
     Binding,
     #[regex("<[0-9]+")]
@@ -145,6 +280,7 @@ fn check_string(value: &str, span: &Span, diags: &mut Vec<Diagnostic>) {
                 Some((i, _)) => {
                     diags.push(
                         Diagnostic::error()
+                            .with_code(INVALID_ESCAPE)
                             .with_message("invalid escape sequence")
                             .with_labels(vec![Label::primary(
                                 (),
@@ -172,7 +308,25 @@ pub fn tokenize(
                 if token == Token::Str {
                     check_string(&source[span.start..span.end], &span, diags);
                 }
-                tokens.push(token);
+                if token == Token::InlineAction && span.len() == 2 {
+                    // an arrow without code block must follow a syntactic predicate
+                    let previous = tokens.iter().rev().find(|token| {
+                        !matches!(
+                            token,
+                            Token::Comment | Token::DocComment | Token::Whitespace
+                        )
+                    });
+                    if previous == Some(&Token::RPar) {
+                        tokens.push(Token::Arrow);
+                    } else {
+                        diags.push(
+                            LexerError::UnterminatedInlineAction.into_diagnostic(span.clone()),
+                        );
+                        tokens.push(Token::Error);
+                    }
+                } else {
+                    tokens.push(token);
+                }
             }
             Err(err) => {
                 diags.push(err.into_diagnostic(span.clone()));
@@ -181,9 +335,146 @@ pub fn tokenize(
         }
         ranges.push(span.start as CstIndex..span.end as CstIndex);
     }
+    mark_contextual_keywords(source, &mut tokens, &ranges);
     (tokens, ranges)
 }
 
+/// Identifiers that are keywords at the start of a declaration, so they can
+/// still be used as rule names.
+const CONTEXTUAL_KEYWORDS: &[(&str, Token)] = &[
+    ("insert", Token::Insert),
+    ("lookahead", Token::Lookahead),
+    ("import", Token::Import),
+    ("sync", Token::Sync),
+    ("max_errors", Token::MaxErrors),
+    ("token_derive", Token::TokenDerive),
+];
+
+/// Turns identifiers into contextual keywords if they start a declaration,
+/// i.e. they follow a `;` and are not the name of a rule.
+fn mark_contextual_keywords(
+    source: &str,
+    tokens: &mut [Token],
+    ranges: &[std::ops::Range<CstIndex>],
+) {
+    let significant: Vec<usize> = (0..tokens.len())
+        .filter(|i| {
+            !matches!(
+                tokens[*i],
+                Token::Comment | Token::DocComment | Token::Whitespace
+            )
+        })
+        .collect();
+    for (k, &i) in significant.iter().enumerate() {
+        if tokens[i] != Token::Id
+            || k > 0 && tokens[significant[k - 1]] != Token::Semi
+            || significant
+                .get(k + 1)
+                .is_some_and(|j| tokens[*j] == Token::Colon)
+        {
+            continue;
+        }
+        let text = &source[ranges[i].clone()];
+        if let Some((_, keyword)) = CONTEXTUAL_KEYWORDS.iter().find(|(name, _)| *name == text) {
+            tokens[i] = *keyword;
+        }
+    }
+}
+
 include!("./generated.rs");
 
 impl PredicatesAndActions for Parser<'_> {}
+
+impl Cst<'_> {
+    /// Text of the tokens in the subtree of `node` without trivia, where the
+    /// tokens are separated by single spaces.
+    pub fn text(&self, node: NodeRef) -> String {
+        let mut text = String::new();
+        for node in self.nodes[node.0..self.end(node).0].iter() {
+            let Node::Token(idx) = node else {
+                continue;
+            };
+            if matches!(
+                self.tokens[*idx],
+                Token::Comment | Token::DocComment | Token::Whitespace
+            ) {
+                continue;
+            }
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(&self.source[self.ranges[*idx].clone()]);
+        }
+        text
+    }
+
+    /// Kind of the token `node`, or `None` if it is no token.
+    pub fn token_kind(&self, node: NodeRef) -> Option<Token> {
+        match self.nodes.get(node.0) {
+            Some(Node::Token(idx)) => Some(self.tokens[*idx]),
+            _ => None,
+        }
+    }
+
+    /// Span of a declaration including the whitespace and comment that follow
+    /// it on the same line, so removing the declaration leaves no empty line.
+    pub fn removal_span(&self, node: NodeRef) -> Span {
+        let mut span = self.get_span(node).unwrap();
+        for child in self.children(node) {
+            let trivia = self
+                .get_token(child, Token::Whitespace)
+                .or_else(|| self.get_token(child, Token::Comment));
+            match trivia {
+                Some((_, child_span)) if child_span.start < span.end => {}
+                Some((text, child_span)) if child_span.start == span.end => {
+                    span.end = child_span.end;
+                    if text.contains('\n') {
+                        break;
+                    }
+                }
+                _ if self.get_span(child).is_some_and(|c| c.start < span.end) => {}
+                _ => break,
+            }
+        }
+        span
+    }
+
+    /// Doc comments that precede `node`, which may be separated by
+    /// whitespace.
+    pub fn doc_comments(&self, node: NodeRef) -> Vec<&str> {
+        let mut comments = vec![];
+        for index in (0..node.0).rev() {
+            if let Some((comment, _)) = self.get_token(NodeRef(index), Token::DocComment) {
+                comments.push(comment);
+            } else if self.get_token(NodeRef(index), Token::Whitespace).is_none() {
+                break;
+            }
+        }
+        comments.reverse();
+        comments
+    }
+
+    /// End of the last token before `offset`, which is no trivia.
+    pub fn previous_token_end(&self, offset: usize) -> Option<usize> {
+        self.tokens
+            .iter()
+            .zip(self.ranges.iter())
+            .take_while(|(_, range)| range.end <= offset)
+            .filter(|(token, _)| {
+                !matches!(
+                    token,
+                    Token::Error | Token::Comment | Token::DocComment | Token::Whitespace
+                )
+            })
+            .map(|(_, range)| range.end)
+            .last()
+    }
+
+    /// Node after the last node in the subtree of `node`.
+    pub fn end(&self, node: NodeRef) -> NodeRef {
+        match self.nodes.get(node.0) {
+            Some(Node::Rule(_, end_offset)) => NodeRef(node.0 + end_offset + 1),
+            _ => NodeRef(node.0 + 1),
+        }
+    }
+}
@@ -91,6 +91,43 @@ impl DebugPrinter {
                     s.branch(it.peek().is_none(), |s| s.print_skip_decl(cst, decl));
                 }
             });
+            self.branch(false, |s| {
+                println!("{}", member!("insert_decls"));
+                let mut it = file.insert_decls(cst).peekable();
+                while let Some(decl) = it.next() {
+                    s.branch(it.peek().is_none(), |s| s.print_insert_decl(cst, decl));
+                }
+            });
+            self.branch(false, |s| {
+                println!("{}", member!("sync_decls"));
+                let mut it = file.sync_decls(cst).peekable();
+                while let Some(decl) = it.next() {
+                    s.branch(it.peek().is_none(), |s| s.print_sync_decl(cst, decl));
+                }
+            });
+            self.branch(false, |s| {
+                println!("{}", member!("lookahead_decls"));
+                let mut it = file.lookahead_decls(cst).peekable();
+                while let Some(decl) = it.next() {
+                    s.branch(it.peek().is_none(), |s| s.print_lookahead_decl(cst, decl));
+                }
+            });
+            self.branch(false, |s| {
+                println!("{}", member!("max_errors_decls"));
+                let mut it = file.max_errors_decls(cst).peekable();
+                while let Some(decl) = it.next() {
+                    s.branch(it.peek().is_none(), |s| s.print_max_errors_decl(cst, decl));
+                }
+            });
+            self.branch(false, |s| {
+                println!("{}", member!("token_derive_decls"));
+                let mut it = file.token_derive_decls(cst).peekable();
+                while let Some(decl) = it.next() {
+                    s.branch(it.peek().is_none(), |s| {
+                        s.print_token_derive_decl(cst, decl)
+                    });
+                }
+            });
             self.branch(false, |s| {
                 println!("{}", member!("token_decls"));
                 let mut it = file.token_decls(cst).peekable();
@@ -110,10 +147,14 @@ impl DebugPrinter {
     fn print_token_decl(&mut self, cst: &Cst, decl: TokenDecl) {
         let name = decl.name(cst).map_or("", |(val, _)| val);
         let symbol = decl.symbol(cst).map_or("", |(val, _)| val);
+        let description = decl.description(cst).map_or("", |(val, _)| val);
+        let regex = decl.regex(cst).map_or("", |(val, _)| val);
         println!(
-            "Token {} {} {} {}",
+            "Token {} {} {} {} {} {}",
             member!(name),
             member!(symbol),
+            member!(description),
+            member!(regex),
             pos!(decl.span(cst)),
             syntax!(decl.syntax().0),
         );
@@ -160,6 +201,57 @@ impl DebugPrinter {
             syntax!(decl.syntax().0),
         );
     }
+    fn print_insert_decl(&mut self, cst: &Cst, decl: InsertDecl) {
+        let mut token_names = vec![];
+        decl.token_names(cst, |(val, _)| token_names.push(val));
+        println!(
+            "Insert {} {} {}",
+            member!(token_names),
+            pos!(decl.span(cst)),
+            syntax!(decl.syntax().0),
+        );
+    }
+    fn print_sync_decl(&mut self, cst: &Cst, decl: SyncDecl) {
+        let mut token_names = vec![];
+        decl.token_names(cst, |(val, _)| token_names.push(val));
+        println!(
+            "Sync {} {} {}",
+            member!(token_names),
+            pos!(decl.span(cst)),
+            syntax!(decl.syntax().0),
+        );
+    }
+    fn print_lookahead_decl(&mut self, cst: &Cst, decl: LookaheadDecl) {
+        let value = decl.value(cst).map_or("", |(val, _)| val);
+        println!(
+            "Lookahead {} {} {}",
+            member!(value),
+            pos!(decl.span(cst)),
+            syntax!(decl.syntax().0),
+        );
+    }
+    fn print_max_errors_decl(&mut self, cst: &Cst, decl: MaxErrorsDecl) {
+        let value = decl.value(cst).map_or("", |(val, _)| val);
+        println!(
+            "MaxErrors {} {} {}",
+            member!(value),
+            pos!(decl.span(cst)),
+            syntax!(decl.syntax().0),
+        );
+    }
+    fn print_token_derive_decl(&mut self, cst: &Cst, decl: TokenDeriveDecl) {
+        let mut derives = vec![];
+        decl.derives(cst, |(val, _)| derives.push(val));
+        let mut attributes = vec![];
+        decl.attributes(cst, |(val, _)| attributes.push(val));
+        println!(
+            "TokenDerive {} {} {} {}",
+            member!(derives),
+            member!(attributes),
+            pos!(decl.span(cst)),
+            syntax!(decl.syntax().0),
+        );
+    }
     fn print_regex(&mut self, cst: &Cst, sema: &SemanticData, regex: Regex) {
         let first = &sema
             .first_sets
@@ -212,6 +304,17 @@ impl DebugPrinter {
                     .inner(cst)
                     .inspect(|r| self.branch(true, |s| s.print_regex(cst, sema, *r)));
             }
+            Regex::SyntacticPredicate(pred) => {
+                println!(
+                    "SyntacticPredicate {} {} {} {}",
+                    set!(first),
+                    set!(follow),
+                    pos!(pred.span(cst)),
+                    syntax!(pred.syntax().0),
+                );
+                pred.inner(cst)
+                    .inspect(|r| self.branch(true, |s| s.print_regex(cst, sema, *r)));
+            }
             Regex::Optional(opt) => {
                 println!(
                     "Optional {} {} {} {}",
@@ -247,6 +350,34 @@ impl DebugPrinter {
                 plus.operand(cst)
                     .inspect(|r| self.branch(true, |s| s.print_regex(cst, sema, *r)));
             }
+            Regex::Bounded(bounded) => {
+                println!(
+                    "Bounded {} {} {} {} {} {}",
+                    bounded.bounds_text(cst),
+                    set!(first),
+                    set!(follow),
+                    set!(recovery),
+                    pos!(bounded.span(cst)),
+                    syntax!(bounded.syntax().0),
+                );
+                bounded
+                    .operand(cst)
+                    .inspect(|r| self.branch(true, |s| s.print_regex(cst, sema, *r)));
+            }
+            Regex::Separated(sep) => {
+                println!(
+                    "Separated {} {} {} {} {}",
+                    set!(first),
+                    set!(follow),
+                    set!(recovery),
+                    pos!(sep.span(cst)),
+                    syntax!(sep.syntax().0),
+                );
+                sep.operand(cst)
+                    .inspect(|r| self.branch(false, |s| s.print_regex(cst, sema, *r)));
+                sep.separator(cst)
+                    .inspect(|r| self.branch(true, |s| s.print_regex(cst, sema, *r)));
+            }
             Regex::Name(name) => {
                 let value = name.value(cst).map_or("", |(val, _)| val);
                 let binding = sema.decl_bindings.get(&name.syntax());
@@ -273,6 +404,15 @@ impl DebugPrinter {
                     syntax!(symbol.syntax().0),
                 );
             }
+            Regex::Wildcard(wildcard) => {
+                println!(
+                    "Wildcard {} {} {} {}",
+                    set!(first),
+                    set!(follow),
+                    pos!(wildcard.span(cst)),
+                    syntax!(wildcard.syntax().0),
+                );
+            }
             Regex::Predicate(pred) => {
                 let value = pred.value(cst).map_or("", |(val, _)| val);
                 println!(
@@ -285,7 +425,11 @@ impl DebugPrinter {
                 );
             }
             Regex::Action(alt) => {
-                let value = alt.value(cst).map_or("", |(val, _)| val);
+                let value = alt
+                    .value(cst)
+                    .or_else(|| alt.code(cst))
+                    .or_else(|| alt.tagged(cst).map(|(_, _, code, span)| (code, span)))
+                    .map_or("", |(val, _)| val);
                 println!(
                     "Action {} {} {} {} {}",
                     member!(value),
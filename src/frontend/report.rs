@@ -0,0 +1,557 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::ast::*;
+use super::parser::*;
+use super::sema::*;
+use super::source::GrammarSource;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportKind {
+    Predicates,
+    Tokens,
+    Sets,
+    Reachability,
+}
+
+impl std::str::FromStr for ReportKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "predicates" => Ok(Self::Predicates),
+            "tokens" => Ok(Self::Tokens),
+            "sets" => Ok(Self::Sets),
+            "reachability" => Ok(Self::Reachability),
+            _ => Err(format!("unknown report kind `{s}`")),
+        }
+    }
+}
+
+/// A decision of the generated parser that is guarded by a semantic predicate.
+struct GuardedDecision<'a> {
+    rule: RuleDecl,
+    predicate: Predicate,
+    /// The regex that is parsed if the predicate holds.
+    guarded: Regex,
+    /// Tokens that select the guarded regex.
+    prediction: BTreeSet<TokenName<'a>>,
+    /// Tokens that select some other path at the same decision.
+    competing: BTreeSet<TokenName<'a>>,
+}
+
+pub struct Report;
+
+impl Report {
+    pub fn run<W: std::io::Write + ?Sized>(
+        cst: &Cst,
+        sema: &SemanticData,
+        source: &GrammarSource,
+        kind: ReportKind,
+        output: &mut W,
+    ) -> std::io::Result<()> {
+        match kind {
+            ReportKind::Predicates => Self::predicates(cst, sema, source, output),
+            ReportKind::Tokens => Self::tokens(cst, sema, output),
+            ReportKind::Sets => Self::sets(cst, sema, source, output),
+            ReportKind::Reachability => Self::reachability(cst, sema, output),
+        }
+    }
+
+    /// Token symbols by name, where tokens with a description are shown by
+    /// their name.
+    fn symbols<'a>(cst: &'a Cst, root: File) -> HashMap<&'a str, &'a str> {
+        let mut symbols = HashMap::new();
+        for token in root.token_decls(cst) {
+            if let Some((name, _)) = token.name(cst) {
+                let symbol = token
+                    .symbol(cst)
+                    .map(|(sym, _)| sym)
+                    .filter(|sym| !sym.starts_with("'<"));
+                symbols.insert(name, symbol.unwrap_or(name));
+            }
+        }
+        symbols
+    }
+
+    /// Reports for each predicate guarded decision, which lookahead tokens
+    /// can only be resolved by evaluating the predicate at runtime.
+    fn predicates<W: std::io::Write + ?Sized>(
+        cst: &Cst,
+        sema: &SemanticData,
+        source: &GrammarSource,
+        output: &mut W,
+    ) -> std::io::Result<()> {
+        let Some(root) = File::cast(cst, NodeRef::ROOT) else {
+            return Ok(());
+        };
+        let symbols = Self::symbols(cst, root);
+        let display = |tokens: &mut dyn Iterator<Item = &TokenName>| {
+            tokens
+                .map(|token| *symbols.get(token.0).unwrap_or(&token.0))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let mut decisions = vec![];
+        for rule in root.rule_decls(cst) {
+            let mut left_recursive: &[Regex] = &[];
+            if let Some(
+                Pattern::LeftRecursive(ref branches) | Pattern::OperatorPrecedence(ref branches),
+            ) = sema.patterns.get(&rule)
+            {
+                left_recursive = branches;
+            }
+            if let Some(regex) = rule.regex(cst) {
+                Self::collect_decisions(cst, sema, regex, rule, left_recursive, &mut decisions);
+            }
+        }
+
+        decisions.sort_by_key(|decision| decision.predicate.span(cst).start);
+
+        let samples = Samples::new(cst, sema);
+        for decision in decisions {
+            let rule_name = decision.rule.name(cst).unwrap_or_default().0;
+            let (predicate, span) = decision.predicate.value(cst).unwrap_or_default();
+            let location = source.location(span.start);
+            writeln!(
+                output,
+                "{rule_name}:{}:{}: predicate {predicate}",
+                location.line_number, location.column_number
+            )?;
+            let ambiguous = decision
+                .prediction
+                .intersection(&decision.competing)
+                .copied()
+                .collect::<BTreeSet<_>>();
+            if ambiguous.is_empty() {
+                writeln!(output, "  not required for the decision")?;
+                continue;
+            }
+            if ambiguous == decision.prediction {
+                writeln!(output, "  decision depends entirely on the predicate")?;
+            } else {
+                writeln!(
+                    output,
+                    "  decision depends on the predicate for: {}",
+                    display(&mut ambiguous.iter())
+                )?;
+            }
+            for token in ambiguous.iter() {
+                if let Some(example) = samples.example(decision.guarded, Some(*token)) {
+                    writeln!(output, "  example: {}", display(&mut example.iter()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reports the first and follow set of each rule, and the prediction sets
+    /// of the decisions in the rule.
+    fn sets<W: std::io::Write + ?Sized>(
+        cst: &Cst,
+        sema: &SemanticData,
+        source: &GrammarSource,
+        output: &mut W,
+    ) -> std::io::Result<()> {
+        let Some(root) = File::cast(cst, NodeRef::ROOT) else {
+            return Ok(());
+        };
+        let symbols = Self::symbols(cst, root);
+        let display = |set: Option<&BTreeSet<TokenName>>| {
+            set.into_iter()
+                .flatten()
+                .map(|token| *symbols.get(token.0).unwrap_or(&token.0))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        for rule in root.rule_decls(cst) {
+            let (Some((name, span)), Some(regex)) = (rule.name(cst), rule.regex(cst)) else {
+                continue;
+            };
+            let location = source.location(span.start);
+            writeln!(
+                output,
+                "{name}:{}:{}:",
+                location.line_number, location.column_number
+            )?;
+            writeln!(
+                output,
+                "  first: {}",
+                display(sema.first_sets.get(&regex.syntax()))
+            )?;
+            writeln!(
+                output,
+                "  follow: {}",
+                display(sema.follow_sets.get(&regex.syntax()))
+            )?;
+            let mut decisions = vec![];
+            Self::collect_sets_decisions(cst, regex, &mut decisions);
+            for (regex, branches) in decisions {
+                let location = source.location(regex.span(cst).start);
+                let kind = match regex {
+                    Regex::Alternation(_) => "alternation",
+                    Regex::Star(_) => "repetition",
+                    Regex::Plus(_) | Regex::Bounded(_) => "repetition",
+                    Regex::Optional(_) => "option",
+                    _ => "separated list",
+                };
+                writeln!(
+                    output,
+                    "  {kind} at {}:{}:",
+                    location.line_number, location.column_number
+                )?;
+                for branch in branches {
+                    let span = branch.span(cst);
+                    let text = source.text()[span].split_whitespace();
+                    writeln!(
+                        output,
+                        "    {}: {}",
+                        text.collect::<Vec<_>>().join(" "),
+                        display(sema.predict_sets.get(&branch.syntax()))
+                    )?;
+                }
+                if !matches!(regex, Regex::Alternation(_)) {
+                    writeln!(
+                        output,
+                        "    <exit>: {}",
+                        display(sema.follow_sets.get(&regex.syntax()))
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the decisions of a regex with the regexes of their branches.
+    fn collect_sets_decisions(cst: &Cst, regex: Regex, decisions: &mut Vec<(Regex, Vec<Regex>)>) {
+        match regex {
+            Regex::Alternation(alt) => {
+                decisions.push((regex, alt.operands(cst).collect()));
+                for op in alt.operands(cst) {
+                    Self::collect_sets_decisions(cst, op, decisions);
+                }
+            }
+            Regex::Star(_) | Regex::Plus(_) | Regex::Optional(_) | Regex::Bounded(_) => {
+                let op = match regex {
+                    Regex::Star(star) => star.operand(cst),
+                    Regex::Plus(plus) => plus.operand(cst),
+                    Regex::Optional(opt) => opt.operand(cst),
+                    Regex::Bounded(bounded) => bounded.operand(cst),
+                    _ => unreachable!(),
+                };
+                if let Some(op) = op {
+                    decisions.push((regex, vec![op]));
+                    Self::collect_sets_decisions(cst, op, decisions);
+                }
+            }
+            Regex::Separated(sep) => {
+                if let (Some(op), Some(separator)) = (sep.operand(cst), sep.separator(cst)) {
+                    Self::collect_sets_decisions(cst, op, decisions);
+                    decisions.push((regex, vec![separator]));
+                    Self::collect_sets_decisions(cst, separator, decisions);
+                }
+            }
+            Regex::Concat(concat) => {
+                for op in concat.operands(cst) {
+                    Self::collect_sets_decisions(cst, op, decisions);
+                }
+            }
+            Regex::Paren(paren) => {
+                if let Some(inner) = paren.inner(cst) {
+                    Self::collect_sets_decisions(cst, inner, decisions);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Outputs the token declarations as JSON, so they can be used by external
+    /// lexer generators.
+    fn tokens<W: std::io::Write + ?Sized>(
+        cst: &Cst,
+        sema: &SemanticData,
+        output: &mut W,
+    ) -> std::io::Result<()> {
+        let Some(root) = File::cast(cst, NodeRef::ROOT) else {
+            return Ok(());
+        };
+        writeln!(output, "{{\n  \"tokens\": [")?;
+        let mut it = root.token_decls(cst).peekable();
+        while let Some(token) = it.next() {
+            let name = token.name(cst).unwrap_or_default().0;
+            let symbol = token.symbol(cst).map(|(sym, _)| Self::unescape(sym));
+            let (kind, symbol) = match symbol {
+                Some(sym) if sym.starts_with('<') && sym.ends_with('>') && sym.len() > 2 => {
+                    ("class", Self::json_string(&sym))
+                }
+                Some(sym) => ("literal", Self::json_string(&sym)),
+                None => ("none", "null".to_string()),
+            };
+            let regex = token.regex(cst).map_or("null".to_string(), |(regex, _)| {
+                Self::json_string(&regex[1..regex.len() - 1].replace("\\/", "/"))
+            });
+            writeln!(
+                output,
+                "    {{ \"name\": {}, \"symbol\": {symbol}, \"kind\": \"{kind}\", \
+                 \"regex\": {regex}, \"skip\": {}, \"insert\": {}, \"right\": {} }}{}",
+                Self::json_string(name),
+                sema.skipped.contains(&token),
+                sema.inserted.contains(&token),
+                sema.right_associative.contains(name),
+                if it.peek().is_some() { "," } else { "" }
+            )?;
+        }
+        writeln!(output, "  ]\n}}")
+    }
+
+    /// Writes the graph of the rule and token references in the DOT language.
+    ///
+    /// The start rule is bold, and the rules and tokens that are not reachable
+    /// from it are gray and grouped into one cluster per orphaned subgraph.
+    fn reachability<W: std::io::Write + ?Sized>(
+        cst: &Cst,
+        sema: &SemanticData,
+        output: &mut W,
+    ) -> std::io::Result<()> {
+        let Some(root) = File::cast(cst, NodeRef::ROOT) else {
+            return Ok(());
+        };
+        let name = |node: NodeRef| {
+            RuleDecl::cast(cst, node)
+                .and_then(|rule| rule.name(cst))
+                .or_else(|| TokenDecl::cast(cst, node).and_then(|token| token.name(cst)))
+                .map_or("", |(name, _)| name)
+        };
+        let style = |node: NodeRef| {
+            if sema.start.is_some_and(|start| start.syntax() == node) {
+                ", style=bold"
+            } else if !sema.used.contains(&node) {
+                ", color=gray, fontcolor=gray"
+            } else {
+                ""
+            }
+        };
+        writeln!(output, "digraph reachability {{")?;
+        for rule in root.rule_decls(cst) {
+            let node = rule.syntax();
+            writeln!(output, "  \"{}\" [shape=box{}];", name(node), style(node))?;
+        }
+        for token in root.token_decls(cst) {
+            let node = token.syntax();
+            writeln!(
+                output,
+                "  \"{}\" [shape=ellipse{}];",
+                name(node),
+                style(node)
+            )?;
+        }
+        for (i, orphans) in sema.orphans.iter().enumerate() {
+            writeln!(output, "  subgraph \"cluster_{i}\" {{")?;
+            writeln!(output, "    color=gray;")?;
+            let nodes = orphans
+                .roots
+                .iter()
+                .map(|root| root.syntax())
+                .chain(orphans.members.iter().copied());
+            for node in nodes {
+                writeln!(output, "    \"{}\";", name(node))?;
+            }
+            writeln!(output, "  }}")?;
+        }
+        for rule in root.rule_decls(cst) {
+            for node in sema.references.get(&rule).into_iter().flatten() {
+                writeln!(
+                    output,
+                    "  \"{}\" -> \"{}\";",
+                    name(rule.syntax()),
+                    name(*node)
+                )?;
+            }
+        }
+        writeln!(output, "}}")
+    }
+
+    /// Removes the quotes and escape sequences of a token symbol.
+    fn unescape(symbol: &str) -> String {
+        let mut res = String::new();
+        let mut it = symbol[1..symbol.len() - 1].chars();
+        while let Some(c) = it.next() {
+            match c {
+                '\\' => match it.next() {
+                    Some('n') => res.push('\n'),
+                    Some('r') => res.push('\r'),
+                    Some('t') => res.push('\t'),
+                    Some(c) => res.push(c),
+                    None => {}
+                },
+                c => res.push(c),
+            }
+        }
+        res
+    }
+
+    fn json_string(value: &str) -> String {
+        let mut res = "\"".to_string();
+        for c in value.chars() {
+            match c {
+                '"' => res.push_str("\\\""),
+                '\\' => res.push_str("\\\\"),
+                '\n' => res.push_str("\\n"),
+                '\r' => res.push_str("\\r"),
+                '\t' => res.push_str("\\t"),
+                c if c.is_control() => res.push_str(&format!("\\u{:04x}", c as u32)),
+                c => res.push(c),
+            }
+        }
+        res.push('"');
+        res
+    }
+
+    fn guarding_predicate(cst: &Cst, regex: Regex) -> Option<Predicate> {
+        match regex {
+            Regex::Concat(concat) => match concat.operands(cst).next() {
+                Some(Regex::Predicate(pred)) => Some(pred),
+                _ => None,
+            },
+            Regex::Paren(paren) => paren
+                .inner(cst)
+                .and_then(|inner| Self::guarding_predicate(cst, inner)),
+            _ => None,
+        }
+    }
+
+    fn skip_first(cst: &Cst, op: Regex) -> Option<Regex> {
+        if let Regex::Concat(concat) = op {
+            concat
+                .operands(cst)
+                .filter(|op| !matches!(op, Regex::Predicate(_)))
+                .nth(1)
+        } else {
+            None
+        }
+    }
+
+    fn collect_decisions<'a>(
+        cst: &Cst,
+        sema: &SemanticData<'a>,
+        regex: Regex,
+        rule: RuleDecl,
+        left_recursive: &[Regex],
+        decisions: &mut Vec<GuardedDecision<'a>>,
+    ) {
+        let predict = |op: Regex| {
+            sema.predict_sets
+                .get(&op.syntax())
+                .cloned()
+                .unwrap_or_default()
+        };
+        match regex {
+            Regex::Alternation(alt) => {
+                for (i, branch) in left_recursive.iter().enumerate() {
+                    let (Some(predicate), Some(op)) = (
+                        Self::guarding_predicate(cst, *branch),
+                        Self::skip_first(cst, *branch),
+                    ) else {
+                        continue;
+                    };
+                    let mut competing = sema
+                        .left_rec_local_follow_sets
+                        .get(&alt.syntax())
+                        .cloned()
+                        .unwrap_or_default();
+                    for (j, other) in left_recursive.iter().enumerate() {
+                        if let Some(other) = Self::skip_first(cst, *other).filter(|_| i != j) {
+                            competing.extend(predict(other));
+                        }
+                    }
+                    decisions.push(GuardedDecision {
+                        rule,
+                        predicate,
+                        guarded: op,
+                        prediction: predict(op),
+                        competing,
+                    });
+                }
+                let branches = alt
+                    .operands(cst)
+                    .filter(|op| !left_recursive.contains(op))
+                    .collect::<Vec<_>>();
+                for (i, op) in branches.iter().enumerate() {
+                    if let Some(predicate) = Self::guarding_predicate(cst, *op) {
+                        let mut competing = BTreeSet::new();
+                        for (j, other) in branches.iter().enumerate() {
+                            if i != j {
+                                competing.extend(predict(*other));
+                            }
+                        }
+                        decisions.push(GuardedDecision {
+                            rule,
+                            predicate,
+                            guarded: *op,
+                            prediction: predict(*op),
+                            competing,
+                        });
+                    }
+                }
+                for op in alt.operands(cst) {
+                    Self::collect_decisions(cst, sema, op, rule, &[], decisions);
+                }
+            }
+            Regex::Star(_) | Regex::Plus(_) | Regex::Optional(_) | Regex::Bounded(_) => {
+                let op = match regex {
+                    Regex::Star(star) => star.operand(cst),
+                    Regex::Plus(plus) => plus.operand(cst),
+                    Regex::Optional(opt) => opt.operand(cst),
+                    Regex::Bounded(bounded) => bounded.operand(cst),
+                    _ => unreachable!(),
+                };
+                if let Some(op) = op {
+                    if let Some(predicate) = Self::guarding_predicate(cst, op) {
+                        decisions.push(GuardedDecision {
+                            rule,
+                            predicate,
+                            guarded: op,
+                            prediction: predict(op),
+                            competing: sema
+                                .follow_sets
+                                .get(&regex.syntax())
+                                .cloned()
+                                .unwrap_or_default(),
+                        });
+                    }
+                    Self::collect_decisions(cst, sema, op, rule, &[], decisions);
+                }
+            }
+            Regex::Separated(sep) => {
+                if let (Some(op), Some(separator)) = (sep.operand(cst), sep.separator(cst)) {
+                    if let Some(predicate) = Self::guarding_predicate(cst, separator) {
+                        decisions.push(GuardedDecision {
+                            rule,
+                            predicate,
+                            guarded: separator,
+                            prediction: predict(separator),
+                            competing: sema
+                                .follow_sets
+                                .get(&regex.syntax())
+                                .cloned()
+                                .unwrap_or_default(),
+                        });
+                    }
+                    Self::collect_decisions(cst, sema, op, rule, &[], decisions);
+                    Self::collect_decisions(cst, sema, separator, rule, &[], decisions);
+                }
+            }
+            Regex::Concat(concat) => {
+                for op in concat.operands(cst) {
+                    Self::collect_decisions(cst, sema, op, rule, &[], decisions);
+                }
+            }
+            Regex::Paren(paren) => {
+                if let Some(inner) = paren.inner(cst) {
+                    Self::collect_decisions(cst, sema, inner, rule, &[], decisions);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -3,7 +3,8 @@ use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use codespan_reporting::diagnostic::Severity;
 
 use super::ast::*;
-use super::diag::LanguageErrors;
+use super::diag::{Fix, LanguageErrors, INVALID_SYNTAX};
+use super::lint::{Lint, WARNINGS};
 use super::parser::*;
 
 #[derive(PartialEq, Eq, Clone)]
@@ -37,17 +38,83 @@ pub struct SemanticPass;
 
 impl SemanticPass {
     pub fn run<'a>(cst: &'a Cst, diags: &mut Vec<Diagnostic>) -> SemanticData<'a> {
-        let mut sema = SemanticData::default();
+        Self::run_with_limits(cst, diags, ComplexityLimits::default())
+    }
+
+    pub fn run_with_limits<'a>(
+        cst: &'a Cst,
+        diags: &mut Vec<Diagnostic>,
+        limits: ComplexityLimits,
+    ) -> SemanticData<'a> {
+        Self::analyze(cst, diags, limits, None)
+    }
+
+    /// Checks that the start and entry rules do not collide with the methods
+    /// of the driver, if the parser is generated in table mode.
+    pub fn check_table_names(cst: &Cst, sema: &SemanticData, diags: &mut Vec<Diagnostic>) {
+        for rule in sema.start.iter().chain(sema.entries.iter()) {
+            if let Some((name, span)) = rule.name(cst) {
+                if TABLE_METHODS.contains(&name) {
+                    diags.push(Diagnostic::reserved_rule_name(&span, name));
+                }
+            }
+        }
+    }
+
+    /// Same as [`SemanticPass::run`], but only recalculates the first and
+    /// follow sets and the LL(1) conflicts of the rules that are affected by
+    /// the changes since the analysis that filled the `cache`.
+    pub fn run_incremental<'a>(
+        cst: &'a Cst,
+        diags: &mut Vec<Diagnostic>,
+        cache: &mut AnalysisCache,
+    ) -> SemanticData<'a> {
+        Self::analyze(cst, diags, ComplexityLimits::default(), Some(cache))
+    }
+
+    fn analyze<'a>(
+        cst: &'a Cst,
+        diags: &mut Vec<Diagnostic>,
+        limits: ComplexityLimits,
+        cache: Option<&mut AnalysisCache>,
+    ) -> SemanticData<'a> {
+        let mut sema = SemanticData {
+            lookahead: 1,
+            ..Default::default()
+        };
+        Self::suggest_semicolons(cst, diags, &mut sema);
         GeneralCheck::new().run(cst, diags, &mut sema);
         if !diags.iter().any(|d| d.severity == Severity::Error) {
-            LL1Validator::run(cst, diags, &mut sema);
+            LL1Validator::run(cst, diags, &mut sema, cache);
             UsageValidator::run(cst, diags, &mut sema);
             if !diags.iter().any(|d| d.severity == Severity::Error) {
                 RecoverySetGenerator::new().run(cst, &mut sema);
+                ComplexityCheck::new(limits).run(cst, diags, &sema);
             }
         }
         sema
     }
+
+    /// Suggests inserting a `;` for syntax errors that expect one at the start
+    /// of a line, as the declaration on the previous line is not terminated.
+    fn suggest_semicolons(cst: &Cst, diags: &[Diagnostic], sema: &mut SemanticData) {
+        let source = cst.unparse();
+        for diag in diags.iter() {
+            if diag.code.as_deref() != Some(INVALID_SYNTAX) || !diag.message.contains("';'") {
+                continue;
+            }
+            let Some(label) = diag.labels.first() else {
+                continue;
+            };
+            let Some(offset) = cst.previous_token_end(label.range.start) else {
+                continue;
+            };
+            if source[offset..label.range.start].contains('\n') || label.range.start == source.len()
+            {
+                sema.fixes.push(Fix::insert_semicolon(&label.range, offset));
+            }
+        }
+    }
 }
 
 #[derive(Eq, Ord, PartialEq, PartialOrd, Clone, Copy, Default)]
@@ -65,9 +132,19 @@ pub struct SemanticData<'a> {
     pub patterns: HashMap<RuleDecl, Pattern>,
     pub right_associative: HashSet<&'a str>,
     pub skipped: BTreeSet<TokenDecl>,
+    pub inserted: BTreeSet<TokenDecl>,
+    pub sync: BTreeSet<TokenDecl>,
     pub start: Option<RuleDecl>,
+    /// Rules with the `entry` attribute, which get their own parse function.
+    pub entries: Vec<RuleDecl>,
+    /// Rules with the `inline` attribute, which are expanded at their
+    /// references.
+    pub inlined: HashSet<RuleDecl>,
     pub predicates: BTreeMap<NodeRef, (&'a str, &'a str)>,
     pub actions: BTreeMap<NodeRef, (&'a str, &'a str)>,
+    pub inline_actions: BTreeMap<NodeRef, (&'a str, usize)>,
+    pub syntactic_predicates: BTreeMap<NodeRef, (&'a str, usize)>,
+    pub tagged_actions: BTreeMap<NodeRef, (&'a str, &'a str, &'a str)>,
     pub rule_bindings: BTreeSet<&'a str>,
     pub first_sets: HashMap<NodeRef, BTreeSet<TokenName<'a>>>,
     pub follow_sets: HashMap<NodeRef, BTreeSet<TokenName<'a>>>,
@@ -75,13 +152,107 @@ pub struct SemanticData<'a> {
     pub recovery_sets: HashMap<NodeRef, BTreeSet<TokenName<'a>>>,
     pub left_rec_local_follow_sets: HashMap<NodeRef, BTreeSet<TokenName<'a>>>,
     pub used: HashSet<NodeRef>,
+    /// Rules and tokens that are referenced by each rule.
+    pub references: HashMap<RuleDecl, BTreeSet<NodeRef>>,
+    /// Subgraphs of rules that are not reachable from the start rule.
+    pub orphans: Vec<Orphans>,
+    /// Suggested edits for the diagnostics of the semantic pass.
+    pub fixes: Vec<Fix>,
+    /// Tokens that are matched by a wildcard.
+    pub wildcard_tokens: BTreeSet<TokenName<'a>>,
     pub has_rule_binding: HashSet<RuleDecl>,
+    /// Maximum number of tokens used to predict an alternative.
+    pub lookahead: usize,
+    /// Number of syntax errors after which the generated parser stops.
+    pub max_errors: Option<usize>,
+    /// Additional derive macros of the generated `Token` enum.
+    pub token_derives: Vec<&'a str>,
+    /// Content of additional attributes of the generated `Token` enum.
+    pub token_attributes: Vec<String>,
+    /// Token sequences, one of which must be a prefix of the input to select
+    /// the alternative, for alternatives that are not predicted by a single
+    /// token.
+    pub lookahead_sets: HashMap<NodeRef, BTreeSet<Vec<TokenName<'a>>>>,
+}
+
+/// Connected subgraph of unused rules and the unused tokens they reference.
+pub struct Orphans {
+    /// Rules of the subgraph that are not referenced by other rules.
+    pub roots: Vec<RuleDecl>,
+    /// Rules and tokens that are only reachable from the roots.
+    pub members: Vec<NodeRef>,
+}
+
+/// Converts a rule or node name to the name of its syntax tree node kind.
+pub fn snake_to_pascal_case(name: &str) -> String {
+    let mut res = String::new();
+    let mut upper = true;
+    for c in name.chars() {
+        if upper {
+            res.push(c.to_ascii_uppercase());
+            upper = false;
+        } else if c == '_' {
+            upper = true;
+        } else {
+            res.push(c);
+        }
+    }
+    res
 }
 
+/// Maximum value of a lookahead declaration.
+pub const MAX_LOOKAHEAD: usize = 8;
+
+/// Maximum bound of a bounded repetition like `a{2,4}`.
+pub const MAX_REPETITION: usize = 65535;
+
+/// Languages of the tagged actions that are used by the backends.
+pub const ACTION_LANGUAGES: &[&str] = &["rust"];
+
+/// Methods of the generated parser, which must not be used as rule names.
+pub(crate) const PARSER_METHODS: &[&str] = &[
+    "error",
+    "skip_rest",
+    "advance",
+    "is_cancelled",
+    "is_skipped",
+    "init_skip",
+    "advance_with_error",
+    "peek",
+    "span",
+    "close",
+    "new",
+    "parse",
+    "parse_cancellable",
+    "entry",
+    "build",
+    "insert_token",
+];
+
+/// Methods of the driver of a table-driven parser, which collide with the
+/// functions of the start and entry rules.
+pub(crate) const TABLE_METHODS: &[&str] = &[
+    "run_rule",
+    "exec",
+    "cell",
+    "predict",
+    "decision_error",
+    "precedence",
+    "open_rule_node",
+    "predicate",
+    "action",
+    "insert",
+];
+
 #[derive(Default)]
 struct GeneralCheck<'a> {
     symbol_table: std::collections::HashMap<&'a str, NodeRef>,
     current_rule: Option<RuleDecl>,
+    inline_action_count: usize,
+    syntactic_predicate_count: usize,
+    in_syntactic_predicate: bool,
+    tagged_blocks: BTreeMap<(&'a str, &'a str), Span>,
+    node_names: Vec<(&'a str, Span)>,
 }
 
 impl<'a> GeneralCheck<'a> {
@@ -140,10 +311,30 @@ impl<'a> GeneralCheck<'a> {
                 .for_each(|decl| self.check_right_decl(cst, decl, diags, sema));
             file.skip_decls(cst)
                 .for_each(|decl| self.check_skip_decl(cst, decl, diags, sema));
+            file.insert_decls(cst)
+                .for_each(|decl| self.check_insert_decl(cst, decl, diags, sema));
+            file.sync_decls(cst)
+                .for_each(|decl| self.check_sync_decl(cst, decl, diags, sema));
+            sema.wildcard_tokens = file
+                .token_decls(cst)
+                .filter(|decl| !sema.skipped.contains(decl))
+                .filter_map(|decl| decl.name(cst).map(|(name, _)| TokenName(name)))
+                .collect();
             file.rule_decls(cst)
                 .for_each(|decl| self.check_rule_decl(cst, decl, diags, sema));
             file.start_decls(cst)
                 .for_each(|decl| self.check_start_decl(cst, decl, diags, sema));
+            sema.entries = file
+                .rule_decls(cst)
+                .filter(|decl| decl.is_entry(cst))
+                .collect();
+            file.lookahead_decls(cst)
+                .for_each(|decl| Self::check_lookahead_decl(cst, decl, diags, sema));
+            file.max_errors_decls(cst)
+                .for_each(|decl| Self::check_max_errors_decl(cst, decl, diags, sema));
+            file.token_derive_decls(cst)
+                .for_each(|decl| Self::check_token_derive_decl(cst, decl, sema));
+            self.check_generated_names(cst, file, diags, sema);
 
             file.rule_decls(cst).for_each(|decl| {
                 if let Some(start) = sema.start {
@@ -156,6 +347,7 @@ impl<'a> GeneralCheck<'a> {
                     || self.check_right_recursive(cst, sema, decl)
                     || self.check_forwarding_or_empty(cst, sema, decl);
             });
+            Self::check_inline_rules(cst, file, diags, sema);
         }
         if let Some(start) = sema.start {
             for (name, rule) in sema.decl_bindings.iter() {
@@ -169,7 +361,184 @@ impl<'a> GeneralCheck<'a> {
             diags.push(Diagnostic::missing_start_rule());
         }
     }
+    /// Checks that the attributes in the regex of a rule precede the first
+    /// element of an alternative.
+    fn check_attribute_positions(cst: &Cst, decl: RuleDecl, diags: &mut Vec<Diagnostic>) {
+        let Some((_, name_span)) = decl.name(cst) else {
+            return;
+        };
+        let mut valid = HashSet::new();
+        for node in (decl.syntax().0 + 1..cst.end(decl.syntax()).0).map(NodeRef) {
+            if let Some(concat) = Concat::cast(cst, node) {
+                if concat.operands(cst).next().is_none() {
+                    continue;
+                }
+                for child in cst.children(node) {
+                    if let Some(attr) = Attribute::cast(cst, child) {
+                        valid.insert(attr);
+                    } else if Regex::cast(cst, child).is_some() {
+                        break;
+                    }
+                }
+            } else if let Some(attr) = Attribute::cast(cst, node) {
+                // the attributes of the rule precede its name
+                let span = attr.span(cst);
+                if span.start > name_span.start && !valid.contains(&attr) {
+                    diags.push(Diagnostic::invalid_attribute_pos(&span));
+                }
+            }
+        }
+    }
+    /// Checks that the rules with the `inline` attribute can be expanded at
+    /// their references.
+    fn check_inline_rules(
+        cst: &'a Cst,
+        file: File,
+        diags: &mut Vec<Diagnostic>,
+        sema: &mut SemanticData<'a>,
+    ) {
+        let inline: HashSet<_> = file
+            .rule_decls(cst)
+            .filter(|decl| decl.is_inline(cst))
+            .collect();
+        let references = |rule: RuleDecl| {
+            (rule.syntax().0..cst.end(rule.syntax()).0)
+                .filter_map(|node| sema.decl_bindings.get(&NodeRef(node)))
+                .filter_map(|decl| RuleDecl::cast(cst, *decl))
+                .filter(|decl| inline.contains(decl))
+                .collect::<Vec<_>>()
+        };
+        let mut inlined = HashSet::new();
+        for rule in file.rule_decls(cst).filter(|decl| inline.contains(decl)) {
+            let Some((name, span)) = rule.name(cst) else {
+                continue;
+            };
+            // references through other inlined rules are expanded as well
+            let mut visited = HashSet::new();
+            let mut pending = references(rule);
+            let mut recursive = false;
+            while let Some(next) = pending.pop() {
+                if next == rule {
+                    recursive = true;
+                    break;
+                }
+                if visited.insert(next) {
+                    pending.extend(references(next));
+                }
+            }
+            if recursive {
+                diags.push(Diagnostic::invalid_inline_rule(
+                    &span,
+                    name,
+                    "the rule references itself through inlined rules",
+                ));
+            } else if sema.patterns.contains_key(&rule) {
+                diags.push(Diagnostic::invalid_inline_rule(
+                    &span,
+                    name,
+                    "the rule does not always create its node",
+                ));
+            } else {
+                inlined.insert(rule);
+            }
+        }
+        sema.inlined = inlined;
+    }
+    /// Checks that rule names and node names do not collide in the generated code.
+    fn check_generated_names(
+        &mut self,
+        cst: &'a Cst,
+        file: File,
+        diags: &mut Vec<Diagnostic>,
+        sema: &SemanticData<'a>,
+    ) {
+        let mut methods = PARSER_METHODS
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<HashSet<_>>();
+        for (rule_name, num) in sema.predicates.values() {
+            methods.insert(format!("predicate_{rule_name}_{num}"));
+        }
+        for (rule_name, num) in sema.actions.values() {
+            methods.insert(format!("action_{rule_name}_{num}"));
+        }
+        for (rule_name, index) in sema.inline_actions.values() {
+            methods.insert(format!("inline_action_{rule_name}_{index}"));
+        }
+        for (rule_name, num, _) in sema.tagged_actions.values() {
+            methods.insert(format!("action_{rule_name}_{num}"));
+        }
+        for (rule_name, index) in sema.syntactic_predicates.values() {
+            methods.insert(format!("syntactic_predicate_{rule_name}_{index}"));
+            methods.insert(format!("recognize_syntactic_predicate_{rule_name}_{index}"));
+        }
+        if !sema.syntactic_predicates.is_empty() {
+            for rule in file.rule_decls(cst) {
+                if let Some((name, _)) = rule.name(cst) {
+                    methods.insert(format!("recognize_{name}"));
+                }
+            }
+        }
+        for rule in sema.entries.iter() {
+            if let Some((name, span)) = rule.name(cst) {
+                let entry = format!("parse_{name}");
+                if methods.contains(&entry) {
+                    diags.push(Diagnostic::reserved_rule_name(&span, name));
+                }
+                methods.insert(entry);
+            }
+        }
+        let mut names = vec![];
+        for rule in file.rule_decls(cst) {
+            if let Some((name, span)) = rule.name(cst) {
+                if methods.contains(name) {
+                    diags.push(Diagnostic::reserved_rule_name(&span, name));
+                } else {
+                    names.push((name, span));
+                }
+            }
+        }
+        let mut node_kinds: HashMap<String, (&str, Span)> = HashMap::new();
+        for (name, span) in names.iter().chain(self.node_names.iter()) {
+            let kind = snake_to_pascal_case(name);
+            if kind == "Error" {
+                diags.push(Diagnostic::reserved_rule_name(span, name));
+            } else if let Some((other, other_span)) = node_kinds.get(&kind) {
+                if other != name {
+                    diags.push(Diagnostic::node_kind_collision(span, &kind, other_span));
+                }
+            } else {
+                node_kinds.insert(kind, (name, span.clone()));
+            }
+        }
+    }
+    /// Checks that the attributes of a declaration are known and only allow
+    /// known warnings.
+    fn check_attributes(cst: &Cst, decl: impl Attributed, diags: &mut Vec<Diagnostic>) {
+        let is_token = TokenDecl::cast(cst, decl.syntax()).is_some();
+        let is_rule = RuleDecl::cast(cst, decl.syntax()).is_some();
+        for attr in decl.attributes(cst) {
+            match attr.name(cst) {
+                Some(("allow", _)) => {}
+                Some(("ignore_case", _)) if is_token => continue,
+                Some(("entry" | "inline", _)) if is_rule => continue,
+                Some((name, span)) => {
+                    diags.push(Diagnostic::unknown_attribute(&span, "attribute", name));
+                    continue;
+                }
+                None => continue,
+            }
+            for (name, span) in attr.arguments(cst) {
+                if !WARNINGS.iter().any(|(warning, _)| *warning == name)
+                    && !Lint::ALL.iter().any(|lint| lint.name() == name)
+                {
+                    diags.push(Diagnostic::unknown_attribute(&span, "warning", name));
+                }
+            }
+        }
+    }
     fn check_token_decl(&mut self, cst: &'a Cst, decl: TokenDecl, diags: &mut Vec<Diagnostic>) {
+        Self::check_attributes(cst, decl, diags);
         if let Some((name, name_span)) = decl.name(cst) {
             if name == "EOF" {
                 diags.push(Diagnostic::predefined_token_name(&name_span));
@@ -183,8 +552,22 @@ impl<'a> GeneralCheck<'a> {
         if let Some((name, _)) = decl.symbol(cst) {
             self.bind_symbol(cst, name, "token", decl.syntax(), diags);
         }
+        if let Some((regex, span)) = decl.regex(cst) {
+            if regex.len() >= 2 {
+                let regex = regex[1..regex.len() - 1].replace("\\/", "/");
+                if let Err(err) = regex_syntax::parse(&regex) {
+                    let reason = match err {
+                        regex_syntax::Error::Parse(err) => err.kind().to_string(),
+                        regex_syntax::Error::Translate(err) => err.kind().to_string(),
+                        err => err.to_string(),
+                    };
+                    diags.push(Diagnostic::invalid_token_regex(&span, &reason));
+                }
+            }
+        }
     }
     fn bind_rule_decl(&mut self, cst: &'a Cst, decl: RuleDecl, diags: &mut Vec<Diagnostic>) {
+        Self::check_attributes(cst, decl, diags);
         if let Some((name, name_span)) = decl.name(cst) {
             self.bind_symbol(cst, name, "rule", decl.syntax(), diags);
             if name.starts_with(|c: char| c.is_uppercase()) {
@@ -200,8 +583,27 @@ impl<'a> GeneralCheck<'a> {
         sema: &mut SemanticData<'a>,
     ) {
         self.current_rule = Some(decl);
+        self.inline_action_count = 0;
+        self.syntactic_predicate_count = 0;
+        self.tagged_blocks.clear();
         decl.regex(cst)
             .inspect(|regex| self.check_regex(cst, *regex, diags, sema, false, false, false));
+        Self::check_attribute_positions(cst, decl, diags);
+
+        let mut numbers = BTreeMap::new();
+        for ((number, _), span) in self.tagged_blocks.iter() {
+            numbers.entry(*number).or_insert(span);
+        }
+        if decl.allows(cst, "missing_action_block") {
+            return;
+        }
+        for (number, span) in numbers {
+            for language in ACTION_LANGUAGES {
+                if !self.tagged_blocks.contains_key(&(number, language)) {
+                    diags.push(Diagnostic::missing_action_block(span, number, language));
+                }
+            }
+        }
     }
     fn check_start_decl(
         &mut self,
@@ -218,6 +620,48 @@ impl<'a> GeneralCheck<'a> {
             sema.start = Some(rule_decl);
         }
     }
+    fn check_lookahead_decl(
+        cst: &'a Cst,
+        lookahead_decl: LookaheadDecl,
+        diags: &mut Vec<Diagnostic>,
+        sema: &mut SemanticData<'a>,
+    ) {
+        if let Some((value, span)) = lookahead_decl.value(cst) {
+            match value.parse::<usize>() {
+                Ok(k @ 1..=MAX_LOOKAHEAD) => sema.lookahead = k,
+                _ => diags.push(Diagnostic::invalid_lookahead(&span)),
+            }
+        }
+    }
+    fn check_max_errors_decl(
+        cst: &'a Cst,
+        max_errors_decl: MaxErrorsDecl,
+        diags: &mut Vec<Diagnostic>,
+        sema: &mut SemanticData<'a>,
+    ) {
+        if let Some((value, span)) = max_errors_decl.value(cst) {
+            match value.parse::<usize>() {
+                Ok(n @ 1..) => sema.max_errors = Some(n),
+                _ => diags.push(Diagnostic::invalid_max_errors(&span)),
+            }
+        }
+    }
+    fn check_token_derive_decl(
+        cst: &'a Cst,
+        token_derive_decl: TokenDeriveDecl,
+        sema: &mut SemanticData<'a>,
+    ) {
+        token_derive_decl.derives(cst, |(name, _)| {
+            if !sema.token_derives.contains(&name) {
+                sema.token_derives.push(name);
+            }
+        });
+        token_derive_decl.attributes(cst, |(attribute, _)| {
+            let attribute = &attribute[1..attribute.len() - 1];
+            sema.token_attributes
+                .push(attribute.replace("\\'", "'").replace("\\\\", "\\"));
+        });
+    }
     fn check_right_decl(
         &mut self,
         cst: &'a Cst,
@@ -262,6 +706,52 @@ impl<'a> GeneralCheck<'a> {
             }
         });
     }
+    fn check_insert_decl(
+        &mut self,
+        cst: &'a Cst,
+        insert_decl: InsertDecl,
+        diags: &mut Vec<Diagnostic>,
+        sema: &mut SemanticData<'a>,
+    ) {
+        insert_decl.token_names(cst, |(name, name_span)| {
+            if let Some(node) = self.get_symbol_binding(name, false, name_span.clone(), diags) {
+                if let Some(token_decl) = TokenDecl::cast(cst, node) {
+                    if sema.skipped.contains(&token_decl) {
+                        diags.push(Diagnostic::insert_skipped(&name_span));
+                    } else if sema.inserted.contains(&token_decl) {
+                        diags.push(Diagnostic::redefine_as_inserted(&name_span));
+                    } else {
+                        sema.inserted.insert(token_decl);
+                    }
+                } else {
+                    diags.push(Diagnostic::expected_token(&name_span));
+                }
+            }
+        });
+    }
+    fn check_sync_decl(
+        &mut self,
+        cst: &'a Cst,
+        sync_decl: SyncDecl,
+        diags: &mut Vec<Diagnostic>,
+        sema: &mut SemanticData<'a>,
+    ) {
+        sync_decl.token_names(cst, |(name, name_span)| {
+            if let Some(node) = self.get_symbol_binding(name, false, name_span.clone(), diags) {
+                if let Some(token_decl) = TokenDecl::cast(cst, node) {
+                    if sema.skipped.contains(&token_decl) {
+                        diags.push(Diagnostic::sync_skipped(&name_span));
+                    } else if sema.sync.contains(&token_decl) {
+                        diags.push(Diagnostic::redefine_as_sync(&name_span));
+                    } else {
+                        sema.sync.insert(token_decl);
+                    }
+                } else {
+                    diags.push(Diagnostic::expected_token(&name_span));
+                }
+            }
+        });
+    }
     #[allow(clippy::too_many_arguments)]
     fn check_regex(
         &mut self,
@@ -273,11 +763,26 @@ impl<'a> GeneralCheck<'a> {
         in_loop: bool,
         at_concat_end: bool,
     ) {
+        if self.in_syntactic_predicate
+            && matches!(
+                regex,
+                Regex::SyntacticPredicate(_)
+                    | Regex::Predicate(_)
+                    | Regex::Action(_)
+                    | Regex::Binding(_)
+                    | Regex::OpenNode(_)
+                    | Regex::CloseNode(_)
+            )
+        {
+            diags.push(Diagnostic::invalid_syntactic_predicate(&regex.span(cst)));
+            return;
+        }
         match regex {
             Regex::Alternation(regex) => regex
                 .operands(cst)
                 .for_each(|regex| self.check_regex(cst, regex, diags, sema, true, false, false)),
             Regex::Concat(regex) => {
+                Self::check_attributes(cst, regex, diags);
                 let mut in_alt = in_alt;
                 let mut in_loop = in_loop;
                 let mut ops = regex.operands(cst).peekable();
@@ -300,6 +805,25 @@ impl<'a> GeneralCheck<'a> {
                     self.check_regex(cst, *regex, diags, sema, false, in_loop, false)
                 });
             }
+            Regex::SyntacticPredicate(regex) => {
+                if !in_alt && !in_loop {
+                    diags.push(Diagnostic::invalid_predicate_pos(&regex.span(cst)));
+                }
+                if let Some(rule_name) = self
+                    .current_rule
+                    .and_then(|rule| rule.name(cst).map(|(name, _)| name))
+                {
+                    // syntactic predicates are numbered by their position in the rule
+                    self.syntactic_predicate_count += 1;
+                    sema.syntactic_predicates
+                        .insert(regex.syntax(), (rule_name, self.syntactic_predicate_count));
+                }
+                self.in_syntactic_predicate = true;
+                regex.inner(cst).inspect(|regex| {
+                    self.check_regex(cst, *regex, diags, sema, false, false, false)
+                });
+                self.in_syntactic_predicate = false;
+            }
             Regex::Optional(regex) => {
                 regex.operand(cst).inspect(|regex| {
                     self.check_regex(cst, *regex, diags, sema, false, true, false)
@@ -315,6 +839,30 @@ impl<'a> GeneralCheck<'a> {
                     self.check_regex(cst, *regex, diags, sema, false, true, false)
                 });
             }
+            Regex::Separated(regex) => {
+                regex.operand(cst).inspect(|regex| {
+                    self.check_regex(cst, *regex, diags, sema, false, false, false)
+                });
+                regex.separator(cst).inspect(|regex| {
+                    self.check_regex(cst, *regex, diags, sema, false, true, false)
+                });
+            }
+            Regex::Bounded(regex) => {
+                // only repetitions with a variable count contain a decision
+                let mut in_loop = false;
+                if let Some((min, max)) = regex.bounds(cst) {
+                    if min > MAX_REPETITION
+                        || max.is_some_and(|max| max == 0 || max < min || max > MAX_REPETITION)
+                    {
+                        let span = regex.bounds_span(cst).unwrap_or(regex.span(cst));
+                        diags.push(Diagnostic::invalid_repetition_bounds(&span));
+                    }
+                    in_loop = max != Some(min);
+                }
+                regex.operand(cst).inspect(|regex| {
+                    self.check_regex(cst, *regex, diags, sema, false, in_loop, false)
+                });
+            }
             Regex::Name(regex) => {
                 if let Some((name, name_span)) = regex.value(cst) {
                     let rule_binding = name.starts_with(|c: char| c.is_lowercase());
@@ -359,13 +907,28 @@ impl<'a> GeneralCheck<'a> {
                 }
             }
             Regex::Action(regex) => {
-                if let Some((value, _)) = regex.value(cst) {
-                    if let Some(rule_name) = self
-                        .current_rule
-                        .and_then(|rule| rule.name(cst).map(|(name, _)| name))
-                    {
+                if let Some(rule_name) = self
+                    .current_rule
+                    .and_then(|rule| rule.name(cst).map(|(name, _)| name))
+                {
+                    if let Some((value, _)) = regex.value(cst) {
                         sema.actions
                             .insert(regex.syntax(), (rule_name, &value[1..]));
+                    } else if regex.code(cst).is_some() {
+                        // inline actions are numbered by their position in the rule
+                        self.inline_action_count += 1;
+                        sema.inline_actions
+                            .insert(regex.syntax(), (rule_name, self.inline_action_count));
+                    } else if let Some((number, language, _, span)) = regex.tagged(cst) {
+                        if let Some(old_span) = self.tagged_blocks.get(&(number, language)) {
+                            diags.push(Diagnostic::duplicate_action_block(
+                                &span, number, language, old_span,
+                            ));
+                        } else {
+                            self.tagged_blocks.insert((number, language), span);
+                        }
+                        sema.tagged_actions
+                            .insert(regex.syntax(), (rule_name, number, language));
                     }
                 }
             }
@@ -382,17 +945,19 @@ impl<'a> GeneralCheck<'a> {
                         if let Some(rule) = self.current_rule {
                             sema.has_rule_binding.insert(rule);
                         }
+                        self.node_names.push((name, name_span));
                         sema.rule_bindings.insert(name);
                     }
                 }
             }
-            Regex::OpenNode(_) => {}
+            Regex::Wildcard(_) | Regex::OpenNode(_) => {}
             Regex::CloseNode(regex) => {
                 if let Some(name) = regex.node_name(cst) {
                     if !name.is_empty() {
                         if name.starts_with(|c: char| c.is_uppercase()) {
                             diags.push(Diagnostic::uppercase_rule(&regex.span(cst), name));
                         }
+                        self.node_names.push((name, regex.span(cst)));
                         sema.rule_bindings.insert(name);
                     }
                 }
@@ -433,7 +998,7 @@ impl<'a> GeneralCheck<'a> {
                 Regex::Symbol(_) => true,
                 Regex::Paren(op) => op
                     .inner(cst)
-                    .map_or(false, |op| is_operator(sema, cst, &op, outer)),
+                    .is_some_and(|op| is_operator(sema, cst, &op, outer)),
                 Regex::Alternation(op) if outer => {
                     let mut res = true;
                     op.operands(cst).for_each(|n| {
@@ -484,10 +1049,9 @@ impl<'a> GeneralCheck<'a> {
         if let Some(Regex::Alternation(alt)) = rule.regex(cst) {
             for alt_op in alt.operands(cst) {
                 if let Regex::Concat(concat) = alt_op {
-                    if let Some(concat_op) = concat
-                        .operands(cst)
-                        .find(|op| !matches!(op, Regex::Predicate(_)))
-                    {
+                    if let Some(concat_op) = concat.operands(cst).find(|op| {
+                        !matches!(op, Regex::Predicate(_) | Regex::SyntacticPredicate(_))
+                    }) {
                         if self.name_references_rule(cst, sema, rule, concat_op) {
                             branches.push(alt_op)
                         }
@@ -520,16 +1084,15 @@ impl<'a> GeneralCheck<'a> {
                             }
                         }
                     }
-                    Regex::Name(ref name) => {
+                    Regex::Name(ref name)
                         if sema
                             .decl_bindings
                             .get(&name.syntax())
                             .and_then(|n| RuleDecl::cast(cst, *n))
-                            .is_some()
-                        {
-                            // exit branch
-                            branches.push(op)
-                        }
+                            .is_some() =>
+                    {
+                        // exit branch
+                        branches.push(op)
                     }
                     _ => {}
                 }
@@ -553,7 +1116,7 @@ impl<'a> GeneralCheck<'a> {
             match regex {
                 Regex::Paren(paren) => paren
                     .inner(cst)
-                    .map_or(false, |inner| is_rule_ref(sema, cst, inner)),
+                    .is_some_and(|inner| is_rule_ref(sema, cst, inner)),
                 Regex::Alternation(alt) => {
                     for op in alt.operands(cst) {
                         if !is_rule_ref(sema, cst, op) {
@@ -564,10 +1127,9 @@ impl<'a> GeneralCheck<'a> {
                 }
                 Regex::Concat(concat) => {
                     let mut it = concat.operands(cst);
-                    if !it
-                        .next()
-                        .map_or(false, |op| matches!(op, Regex::Predicate(_)))
-                    {
+                    if !it.next().is_some_and(|op| {
+                        matches!(op, Regex::Predicate(_) | Regex::SyntacticPredicate(_))
+                    }) {
                         return false;
                     }
                     is_rule_ref(sema, cst, it.next().unwrap())
@@ -586,7 +1148,7 @@ impl<'a> GeneralCheck<'a> {
             match regex {
                 Regex::Concat(concat) => {
                     let mut it = concat.operands(cst);
-                    if !it.next().map_or(false, |op| is_rule_ref(sema, cst, op)) {
+                    if !it.next().is_some_and(|op| is_rule_ref(sema, cst, op)) {
                         return false;
                     }
                     for op in it {
@@ -616,24 +1178,107 @@ impl<'a> GeneralCheck<'a> {
 
 struct LL1Validator;
 
+/// LL(1) conflict at a decision, for which an example input is added to the
+/// diagnostic.
+struct Conflict<'a> {
+    diag: usize,
+    regex: Regex,
+    token: TokenName<'a>,
+}
+
 impl<'a> LL1Validator {
     /// Validates that the grammar is an LL(1) grammar.
-    fn run(cst: &'a Cst, diags: &mut Vec<Diagnostic>, sema: &mut SemanticData<'a>) {
+    fn run(
+        cst: &'a Cst,
+        diags: &mut Vec<Diagnostic>,
+        sema: &mut SemanticData<'a>,
+        cache: Option<&mut AnalysisCache>,
+    ) {
         if let Some(file) = File::cast(cst, NodeRef::ROOT) {
-            Self::calc_first(cst, sema, file);
-            Self::calc_follow(cst, sema, file);
+            let rules = file.rule_decls(cst).collect::<Vec<_>>();
+            let affected = match cache.as_deref() {
+                Some(cache) if sema.lookahead == 1 => cache.restore(cst, sema, file),
+                _ => None,
+            };
+            let first_rules = affected.as_ref().map_or(rules.clone(), |affected| {
+                rules
+                    .iter()
+                    .filter(|rule| affected.first.contains(rule))
+                    .copied()
+                    .collect()
+            });
+            let follow_rules = affected.as_ref().map_or(rules.clone(), |affected| {
+                rules
+                    .iter()
+                    .filter(|rule| affected.follow_updates.contains(rule))
+                    .copied()
+                    .collect()
+            });
+            Self::calc_first(cst, sema, &first_rules);
+            Self::check_left_recursion_cycles(cst, sema, diags, file);
+            Self::calc_follow(cst, sema, &follow_rules);
             Self::calc_predict(sema);
-            Self::check(cst, sema, diags, file);
+            if sema.lookahead > 1 {
+                LookaheadCalculator::new(sema.lookahead).run(cst, sema, file);
+            }
+            let mut conflicts = vec![];
+            let mut results = vec![];
+            for rule in rules.iter() {
+                let (diag_start, conflict_start) = (diags.len(), conflicts.len());
+                match &affected {
+                    Some(affected) if !affected.contains(rule) => {
+                        cache.as_deref().unwrap().restore_conflicts(
+                            cst,
+                            *rule,
+                            affected,
+                            diags,
+                            &mut conflicts,
+                        );
+                    }
+                    _ => Self::check(cst, sema, diags, &mut conflicts, *rule),
+                }
+                results.push((*rule, diag_start, conflict_start));
+            }
+            if let Some(cache) = cache {
+                cache.store(cst, sema, file, diags, &conflicts, &results);
+            }
+            if !conflicts.is_empty() {
+                Self::add_examples(cst, sema, diags, conflicts);
+            }
+        }
+    }
+
+    /// Adds a note with the shortest input that reaches the decision of each
+    /// conflict and continues with a conflicting token.
+    fn add_examples(
+        cst: &'a Cst,
+        sema: &SemanticData<'a>,
+        diags: &mut [Diagnostic],
+        conflicts: Vec<Conflict<'a>>,
+    ) {
+        let samples = Samples::new(cst, sema);
+        for conflict in conflicts {
+            if let Some(example) = samples.example(conflict.regex, Some(conflict.token)) {
+                let example = example
+                    .iter()
+                    .map(|token| token.0)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                diags[conflict.diag].notes.push(format!(
+                    "note: the input `{example}` is ambiguous at `{}`",
+                    conflict.token.0
+                ));
+            }
         }
     }
 
-    /// Calculates the first set for each grammar rule.
-    fn calc_first(cst: &'a Cst, sema: &mut SemanticData<'a>, file: File) {
+    /// Calculates the first set for each of the grammar `rules`.
+    fn calc_first(cst: &'a Cst, sema: &mut SemanticData<'a>, rules: &[RuleDecl]) {
         // Iterates until there are no more changes in the first sets
         let mut change = true;
         while change {
             change = false;
-            for rule in file.rule_decls(cst) {
+            for rule in rules.iter() {
                 if let Some(regex) = rule.regex(cst) {
                     Self::calc_first_regex(cst, sema, regex, &mut change);
                 }
@@ -689,6 +1334,9 @@ impl<'a> LL1Validator {
                         .insert(TokenName(name));
                 }
             }
+            Regex::Wildcard(_) => {
+                entry.extend(sema.wildcard_tokens.iter());
+            }
             Regex::Concat(concat) => {
                 let mut use_next = true;
                 for op in concat.operands(cst) {
@@ -698,7 +1346,7 @@ impl<'a> LL1Validator {
                         let op_first = sema.first_sets[&op.syntax()].clone();
                         use_next = op_first.contains(&TokenName("ɛ"));
                         let first = sema.first_sets.get_mut(&regex.syntax()).unwrap();
-                        first.extend(op_first.into_iter());
+                        first.extend(op_first);
                         first.remove(&TokenName("ɛ"));
                     }
                 }
@@ -716,7 +1364,7 @@ impl<'a> LL1Validator {
                     sema.first_sets
                         .get_mut(&regex.syntax())
                         .unwrap()
-                        .extend(op_first.into_iter());
+                        .extend(op_first);
                 }
             }
             Regex::Star(star) => {
@@ -747,6 +1395,33 @@ impl<'a> LL1Validator {
                         .extend(op_first);
                 }
             }
+            Regex::Bounded(bounded) => {
+                if let Some(op) = bounded.operand(cst) {
+                    Self::calc_first_regex(cst, sema, op, change);
+                    let op_first = sema.first_sets[&op.syntax()].clone();
+                    let first = sema.first_sets.get_mut(&regex.syntax()).unwrap();
+                    first.extend(op_first);
+                    if bounded.bounds(cst).is_some_and(|(min, _)| min == 0) {
+                        first.insert(TokenName("ɛ"));
+                    }
+                }
+            }
+            Regex::Separated(sep) => {
+                // `a % b` is equivalent to `a (b a)*`
+                if let (Some(op), Some(separator)) = (sep.operand(cst), sep.separator(cst)) {
+                    Self::calc_first_regex(cst, sema, op, change);
+                    Self::calc_first_regex(cst, sema, separator, change);
+                    let mut op_first = sema.first_sets[&op.syntax()].clone();
+                    if op_first.contains(&TokenName("ɛ")) {
+                        op_first.extend(sema.first_sets[&separator.syntax()].iter());
+                        op_first.insert(TokenName("ɛ"));
+                    }
+                    sema.first_sets
+                        .get_mut(&regex.syntax())
+                        .unwrap()
+                        .extend(op_first);
+                }
+            }
             Regex::Paren(paren) => {
                 if let Some(op) = paren.inner(cst) {
                     Self::calc_first_regex(cst, sema, op, change);
@@ -757,6 +1432,17 @@ impl<'a> LL1Validator {
                         .extend(op_first);
                 }
             }
+            Regex::SyntacticPredicate(pred) => {
+                // the first sets of the predicate are used by its recognizer,
+                // but the predicate itself consumes no tokens
+                if let Some(op) = pred.inner(cst) {
+                    Self::calc_first_regex(cst, sema, op, change);
+                }
+                sema.first_sets
+                    .get_mut(&regex.syntax())
+                    .unwrap()
+                    .insert(TokenName("ɛ"));
+            }
             _ => {
                 entry.insert(TokenName("ɛ"));
             }
@@ -764,22 +1450,170 @@ impl<'a> LL1Validator {
         *change |= sema.first_sets[&regex.syntax()].len() != size;
     }
 
-    /// Calculates the follow set for each grammar rule.
-    fn calc_follow(cst: &'a Cst, sema: &mut SemanticData<'a>, file: File) {
-        if let Some(start_rule_regex) = sema.start.and_then(|start| start.regex(cst)) {
-            sema.follow_sets
-                .entry(start_rule_regex.syntax())
-                .or_default()
-                .insert(TokenName("EOF"));
+    /// Reports cycles of rules that call each other before consuming a token.
+    ///
+    /// Cycles of a rule to itself are left recursive rules that are checked
+    /// separately, so only cycles through at least two rules are reported.
+    fn check_left_recursion_cycles(
+        cst: &'a Cst,
+        sema: &SemanticData<'a>,
+        diags: &mut Vec<Diagnostic>,
+        file: File,
+    ) {
+        let mut calls = HashMap::new();
+        for rule in file.rule_decls(cst) {
+            let mut refs = vec![];
+            if let Some(regex) = rule.regex(cst) {
+                Self::leftmost_calls(cst, sema, regex, &mut refs);
+            }
+            let mut targets = HashSet::new();
+            refs.retain(|(target, _): &(RuleDecl, Name)| {
+                *target != rule && targets.insert(*target)
+            });
+            calls.insert(rule, refs);
         }
-        // Iterates until there are no more changes in the follow sets
-        let mut change = true;
-        while change {
-            change = false;
-            for rule in file.rule_decls(cst) {
-                if let Some(regex) = rule.regex(cst) {
-                    sema.follow_sets.entry(regex.syntax()).or_default();
-                    Self::calc_follow_regex(cst, sema, regex, regex, &mut change);
+        let mut reported = HashSet::new();
+        for rule in file.rule_decls(cst) {
+            if reported.contains(&rule) {
+                continue;
+            }
+            // breadth first search for the shortest path back to the rule
+            let mut parents: HashMap<RuleDecl, (RuleDecl, Name)> = HashMap::new();
+            let mut queue = std::collections::VecDeque::from([rule]);
+            let mut found = None;
+            'search: while let Some(current) = queue.pop_front() {
+                for (target, name) in calls[&current].iter() {
+                    if *target == rule {
+                        found = Some((current, *name));
+                        break 'search;
+                    }
+                    if !parents.contains_key(target) {
+                        parents.insert(*target, (current, *name));
+                        queue.push_back(*target);
+                    }
+                }
+            }
+            let Some((mut current, name)) = found else {
+                continue;
+            };
+            let mut path = vec![(current, name)];
+            while current != rule {
+                let (parent, name) = parents[&current];
+                path.push((parent, name));
+                current = parent;
+            }
+            path.reverse();
+            let rule_name = |rule: RuleDecl| rule.name(cst).map_or("", |(name, _)| name);
+            let mut cycle = rule_name(rule).to_string();
+            let references = path
+                .iter()
+                .map(|(caller, name)| {
+                    let callee = name.value(cst).map_or("", |(name, _)| name);
+                    cycle += &format!(" -> {callee}");
+                    let msg = format!("`{}` calls `{callee}`", rule_name(*caller));
+                    (name.span(cst), msg)
+                })
+                .collect();
+            reported.extend(path.iter().map(|(caller, _)| *caller));
+            diags.push(Diagnostic::left_recursion_cycle(references, cycle));
+        }
+    }
+
+    /// Collects the rule references that may be called before `regex`
+    /// consumes a token.
+    fn leftmost_calls(
+        cst: &'a Cst,
+        sema: &SemanticData<'a>,
+        regex: Regex,
+        refs: &mut Vec<(RuleDecl, Name)>,
+    ) {
+        let nullable = |regex: Regex| {
+            sema.first_sets
+                .get(&regex.syntax())
+                .is_some_and(|first| first.contains(&TokenName("ɛ")))
+        };
+        match regex {
+            Regex::Name(name) => {
+                if let Some(rule) = sema
+                    .decl_bindings
+                    .get(&name.syntax())
+                    .and_then(|decl| RuleDecl::cast(cst, *decl))
+                {
+                    refs.push((rule, name));
+                }
+            }
+            Regex::Concat(concat) => {
+                for op in concat.operands(cst) {
+                    Self::leftmost_calls(cst, sema, op, refs);
+                    if !nullable(op) {
+                        break;
+                    }
+                }
+            }
+            Regex::Alternation(alt) => {
+                for op in alt.operands(cst) {
+                    Self::leftmost_calls(cst, sema, op, refs);
+                }
+            }
+            Regex::Separated(sep) => {
+                if let Some(op) = sep.operand(cst) {
+                    Self::leftmost_calls(cst, sema, op, refs);
+                    if let Some(separator) = sep.separator(cst).filter(|_| nullable(op)) {
+                        Self::leftmost_calls(cst, sema, separator, refs);
+                    }
+                }
+            }
+            Regex::Star(star) => {
+                if let Some(op) = star.operand(cst) {
+                    Self::leftmost_calls(cst, sema, op, refs);
+                }
+            }
+            Regex::Plus(plus) => {
+                if let Some(op) = plus.operand(cst) {
+                    Self::leftmost_calls(cst, sema, op, refs);
+                }
+            }
+            Regex::Bounded(bounded) => {
+                if let Some(op) = bounded.operand(cst) {
+                    Self::leftmost_calls(cst, sema, op, refs);
+                }
+            }
+            Regex::Optional(opt) => {
+                if let Some(op) = opt.operand(cst) {
+                    Self::leftmost_calls(cst, sema, op, refs);
+                }
+            }
+            Regex::Paren(paren) => {
+                if let Some(op) = paren.inner(cst) {
+                    Self::leftmost_calls(cst, sema, op, refs);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Calculates the follow set for each grammar rule by visiting the
+    /// `rules`.
+    fn calc_follow(cst: &'a Cst, sema: &mut SemanticData<'a>, rules: &[RuleDecl]) {
+        for rule_regex in sema
+            .start
+            .iter()
+            .chain(sema.entries.iter())
+            .filter_map(|rule| rule.regex(cst))
+        {
+            sema.follow_sets
+                .entry(rule_regex.syntax())
+                .or_default()
+                .insert(TokenName("EOF"));
+        }
+        // Iterates until there are no more changes in the follow sets
+        let mut change = true;
+        while change {
+            change = false;
+            for rule in rules.iter() {
+                if let Some(regex) = rule.regex(cst) {
+                    sema.follow_sets.entry(regex.syntax()).or_default();
+                    Self::calc_follow_regex(cst, sema, regex, regex, &mut change);
                 }
             }
         }
@@ -867,6 +1701,22 @@ impl<'a> LL1Validator {
                     Self::calc_follow_regex(cst, sema, op, rule_regex, change);
                 }
             }
+            Regex::Bounded(bounded) => {
+                if let Some(op) = bounded.operand(cst) {
+                    let follow = sema.follow_sets.entry(regex.syntax()).or_default().clone();
+                    let repeated = bounded
+                        .bounds(cst)
+                        .is_some_and(|(_, max)| max.map_or(true, |max| max > 1));
+                    let op_first = &sema.first_sets[&op.syntax()];
+                    let op_follow = sema.follow_sets.entry(op.syntax()).or_default();
+                    if repeated {
+                        op_follow.extend(op_first.iter());
+                        op_follow.remove(&TokenName("ɛ"));
+                    }
+                    op_follow.extend(follow);
+                    Self::calc_follow_regex(cst, sema, op, rule_regex, change);
+                }
+            }
             Regex::Optional(opt) => {
                 if let Some(op) = opt.operand(cst) {
                     let follow = sema.follow_sets.entry(regex.syntax()).or_default().clone();
@@ -877,6 +1727,34 @@ impl<'a> LL1Validator {
                     Self::calc_follow_regex(cst, sema, op, rule_regex, change);
                 }
             }
+            Regex::Separated(sep) => {
+                if let (Some(op), Some(separator)) = (sep.operand(cst), sep.separator(cst)) {
+                    let follow = sema.follow_sets.entry(regex.syntax()).or_default().clone();
+                    // the operand is followed by another separator or the end of the list
+                    let separator_first = &sema.first_sets[&separator.syntax()];
+                    let mut op_follow = separator_first.clone();
+                    if separator_first.contains(&TokenName("ɛ")) {
+                        op_follow.extend(sema.first_sets[&op.syntax()].iter());
+                    }
+                    op_follow.remove(&TokenName("ɛ"));
+                    op_follow.extend(follow);
+                    // the separator is followed by another list
+                    let mut separator_follow = sema.first_sets[&regex.syntax()].clone();
+                    if separator_follow.remove(&TokenName("ɛ")) {
+                        separator_follow.extend(op_follow.iter());
+                    }
+                    sema.follow_sets
+                        .entry(op.syntax())
+                        .or_default()
+                        .extend(op_follow);
+                    sema.follow_sets
+                        .entry(separator.syntax())
+                        .or_default()
+                        .extend(separator_follow);
+                    Self::calc_follow_regex(cst, sema, op, rule_regex, change);
+                    Self::calc_follow_regex(cst, sema, separator, rule_regex, change);
+                }
+            }
             Regex::Paren(paren) => {
                 if let Some(inner) = paren.inner(cst) {
                     let follow = sema.follow_sets.entry(regex.syntax()).or_default().clone();
@@ -896,45 +1774,189 @@ impl<'a> LL1Validator {
             let mut set = first.clone();
             if set.contains(&TokenName("ɛ")) {
                 set.remove(&TokenName("ɛ"));
-                set.extend(sema.follow_sets[node].iter());
+                // regexes within syntactic predicates have no follow set
+                set.extend(sema.follow_sets.get(node).into_iter().flatten());
             }
             sema.predict_sets.insert(*node, set);
         }
     }
 
     /// Checks if LL(1) condition holds for the all regexes.
-    fn check(cst: &Cst, sema: &SemanticData<'a>, diags: &mut Vec<Diagnostic>, file: File) {
-        for rule in file.rule_decls(cst) {
-            let mut left_recursive = &vec![];
-            if let Some(
-                Pattern::LeftRecursive(ref branches) | Pattern::OperatorPrecedence(ref branches),
-            ) = sema.patterns.get(&rule)
-            {
-                left_recursive = branches;
-            }
-            if let Some(regex) = rule.regex(cst) {
-                Self::check_regex(cst, sema, diags, regex, rule, left_recursive);
-            }
+    fn check(
+        cst: &Cst,
+        sema: &SemanticData<'a>,
+        diags: &mut Vec<Diagnostic>,
+        conflicts: &mut Vec<Conflict<'a>>,
+        rule: RuleDecl,
+    ) {
+        let mut left_recursive = &vec![];
+        if let Some(
+            Pattern::LeftRecursive(ref branches) | Pattern::OperatorPrecedence(ref branches),
+        ) = sema.patterns.get(&rule)
+        {
+            left_recursive = branches;
+        }
+        if let Some(regex) = rule.regex(cst) {
+            Self::check_regex(cst, sema, diags, conflicts, regex, rule, left_recursive);
         }
     }
 
     fn has_predicate(cst: &Cst, regex: Regex) -> bool {
         match regex {
             Regex::Concat(concat) => {
-                matches!(concat.operands(cst).next(), Some(Regex::Predicate(_)))
+                matches!(
+                    concat.operands(cst).next(),
+                    Some(Regex::Predicate(_) | Regex::SyntacticPredicate(_))
+                )
             }
             Regex::Paren(paren) => paren
                 .inner(cst)
-                .map_or(false, |inner| Self::has_predicate(cst, inner)),
+                .is_some_and(|inner| Self::has_predicate(cst, inner)),
+            _ => false,
+        }
+    }
+
+    /// Leading predicate of an alternation branch.
+    fn guard(cst: &Cst, regex: Regex) -> Option<Regex> {
+        match regex {
+            Regex::Concat(concat) => concat
+                .operands(cst)
+                .next()
+                .filter(|op| matches!(op, Regex::Predicate(_) | Regex::SyntacticPredicate(_))),
+            Regex::Paren(paren) => paren.inner(cst).and_then(|inner| Self::guard(cst, inner)),
+            _ => None,
+        }
+    }
+
+    /// Checks if two regexes are written the same, ignoring whitespace.
+    fn same_regex(cst: &Cst, a: Regex, b: Regex) -> bool {
+        let same_operands = |a: Vec<Regex>, b: Vec<Regex>| {
+            a.len() == b.len()
+                && a.into_iter()
+                    .zip(b)
+                    .all(|(a, b)| Self::same_regex(cst, a, b))
+        };
+        match (a, b) {
+            (Regex::Name(a), Regex::Name(b)) => {
+                a.value(cst).map(|v| v.0) == b.value(cst).map(|v| v.0)
+            }
+            (Regex::Symbol(a), Regex::Symbol(b)) => {
+                a.value(cst).map(|v| v.0) == b.value(cst).map(|v| v.0)
+            }
+            (Regex::Predicate(a), Regex::Predicate(b)) => {
+                a.value(cst).map(|v| v.0) == b.value(cst).map(|v| v.0)
+            }
+            (Regex::Wildcard(_), Regex::Wildcard(_)) => true,
+            (Regex::Concat(a), Regex::Concat(b)) => {
+                same_operands(a.operands(cst).collect(), b.operands(cst).collect())
+            }
+            (Regex::Alternation(a), Regex::Alternation(b)) => {
+                same_operands(a.operands(cst).collect(), b.operands(cst).collect())
+            }
+            (Regex::Paren(a), Regex::Paren(b)) => same_operands(
+                a.inner(cst).into_iter().collect(),
+                b.inner(cst).into_iter().collect(),
+            ),
+            (Regex::SyntacticPredicate(a), Regex::SyntacticPredicate(b)) => {
+                a.is_negative(cst) == b.is_negative(cst)
+                    && same_operands(
+                        a.inner(cst).into_iter().collect(),
+                        b.inner(cst).into_iter().collect(),
+                    )
+            }
+            (Regex::Optional(a), Regex::Optional(b)) => same_operands(
+                a.operand(cst).into_iter().collect(),
+                b.operand(cst).into_iter().collect(),
+            ),
+            (Regex::Star(a), Regex::Star(b)) => same_operands(
+                a.operand(cst).into_iter().collect(),
+                b.operand(cst).into_iter().collect(),
+            ),
+            (Regex::Plus(a), Regex::Plus(b)) => same_operands(
+                a.operand(cst).into_iter().collect(),
+                b.operand(cst).into_iter().collect(),
+            ),
+            (Regex::Bounded(a), Regex::Bounded(b)) => {
+                a.bounds(cst) == b.bounds(cst)
+                    && same_operands(
+                        a.operand(cst).into_iter().collect(),
+                        b.operand(cst).into_iter().collect(),
+                    )
+            }
+            (Regex::Separated(a), Regex::Separated(b)) => same_operands(
+                [a.operand(cst), a.separator(cst)]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+                [b.operand(cst), b.separator(cst)]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+            ),
             _ => false,
         }
     }
 
+    /// Reports guarded branches of an alternation that are never selected,
+    /// because each lookahead that predicts them is already taken by earlier
+    /// branches with the same predicate.
+    ///
+    /// Overlaps with unguarded branches are reported as LL(1) conflicts.
+    fn check_shadowed(
+        cst: &Cst,
+        sema: &SemanticData<'a>,
+        diags: &mut Vec<Diagnostic>,
+        branches: &[Regex],
+    ) {
+        let sequences = |op: Regex| -> Vec<Vec<TokenName>> {
+            match sema.lookahead_sets.get(&op.syntax()) {
+                Some(sequences) => sequences.iter().cloned().collect(),
+                None => sema.predict_sets[&op.syntax()]
+                    .iter()
+                    .map(|token| vec![*token])
+                    .collect(),
+            }
+        };
+        for (i, op) in branches.iter().enumerate() {
+            let Some(guard) = Self::guard(cst, *op) else {
+                continue;
+            };
+            let mut uncovered = sequences(*op);
+            if uncovered.is_empty() {
+                continue;
+            }
+            let mut shadowing = vec![];
+            for earlier in branches[..i].iter() {
+                if !Self::guard(cst, *earlier)
+                    .is_some_and(|other| Self::same_regex(cst, guard, other))
+                {
+                    continue;
+                }
+                let earlier_sequences = sequences(*earlier);
+                let len = uncovered.len();
+                uncovered.retain(|sequence| {
+                    !earlier_sequences
+                        .iter()
+                        .any(|prefix| sequence.starts_with(prefix))
+                });
+                if uncovered.len() != len {
+                    shadowing.push(earlier.span(cst));
+                }
+            }
+            if uncovered.is_empty() {
+                diags.push(Diagnostic::unreachable_alternative(
+                    &op.span(cst),
+                    shadowing,
+                ));
+            }
+        }
+    }
+
     fn skip_first(cst: &Cst, op: Regex) -> Regex {
         if let Regex::Concat(concat) = op {
             concat
                 .operands(cst)
-                .filter(|op| !matches!(op, Regex::Predicate(_)))
+                .filter(|op| !matches!(op, Regex::Predicate(_) | Regex::SyntacticPredicate(_)))
                 .nth(1)
                 .unwrap()
         } else {
@@ -942,10 +1964,12 @@ impl<'a> LL1Validator {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn check_intersection(
         cst: &Cst,
         sema: &SemanticData<'a>,
         diags: &mut Vec<Diagnostic>,
+        conflicts: &mut Vec<Conflict<'a>>,
         op: Regex,
         branches: impl Iterator<Item = Regex>,
         i: usize,
@@ -953,6 +1977,7 @@ impl<'a> LL1Validator {
     ) {
         let prediction = &sema.predict_sets[&op.syntax()];
         let mut related = vec![];
+        let mut token = None;
         for op in branches.skip(i + 1) {
             let op = if left_rec {
                 Self::skip_first(cst, op)
@@ -965,11 +1990,17 @@ impl<'a> LL1Validator {
                 .copied()
                 .collect::<BTreeSet<_>>();
             if !intersection.is_empty() {
+                token = token.or(intersection.first().copied());
                 let set = format!("with token set: {:?}", intersection);
                 related.push((cst.get_span(op.syntax()).unwrap().clone(), set));
             }
         }
-        if !related.is_empty() {
+        if let Some(token) = token {
+            conflicts.push(Conflict {
+                diag: diags.len(),
+                regex: op,
+                token,
+            });
             if left_rec {
                 diags.push(Diagnostic::ll1_conflict_left_rec(&op.span(cst), related));
             } else {
@@ -983,6 +2014,7 @@ impl<'a> LL1Validator {
         cst: &Cst,
         sema: &SemanticData<'a>,
         diags: &mut Vec<Diagnostic>,
+        conflicts: &mut Vec<Conflict<'a>>,
         regex: Regex,
         rule: RuleDecl,
         left_recursive: &[Regex],
@@ -999,7 +2031,12 @@ impl<'a> LL1Validator {
                             .intersection(local_follow)
                             .copied()
                             .collect::<BTreeSet<_>>();
-                        if !intersection.is_empty() {
+                        if let Some(token) = intersection.first().copied() {
+                            conflicts.push(Conflict {
+                                diag: diags.len(),
+                                regex: op,
+                                token,
+                            });
                             let set = format!("with token set: {:?}", intersection);
                             let related = vec![(rule.name(cst).unwrap_or_default().1, set)];
                             diags.push(Diagnostic::ll1_conflict_left_rec(
@@ -1013,6 +2050,7 @@ impl<'a> LL1Validator {
                         cst,
                         sema,
                         diags,
+                        conflicts,
                         op,
                         left_recursive.iter().copied(),
                         i,
@@ -1022,22 +2060,31 @@ impl<'a> LL1Validator {
                 let non_recursive_branches =
                     || alt.operands(cst).filter(|op| !left_recursive.contains(op));
 
+                let resolved = non_recursive_branches()
+                    .any(|op| sema.lookahead_sets.contains_key(&op.syntax()));
                 for (i, op) in non_recursive_branches().enumerate() {
-                    if Self::has_predicate(cst, op) {
+                    if Self::has_predicate(cst, op) || resolved {
                         continue;
                     }
                     Self::check_intersection(
                         cst,
                         sema,
                         diags,
+                        conflicts,
                         op,
                         non_recursive_branches(),
                         i,
                         false,
                     );
                 }
+                Self::check_shadowed(
+                    cst,
+                    sema,
+                    diags,
+                    &non_recursive_branches().collect::<Vec<_>>(),
+                );
                 for op in alt.operands(cst) {
-                    Self::check_regex(cst, sema, diags, op, rule, &[]);
+                    Self::check_regex(cst, sema, diags, conflicts, op, rule, &[]);
                 }
             }
             Regex::Star(star) => {
@@ -1047,10 +2094,15 @@ impl<'a> LL1Validator {
                         .copied()
                         .collect::<BTreeSet<_>>();
                     if !Self::has_predicate(cst, op) && !intersection.is_empty() {
+                        conflicts.push(Conflict {
+                            diag: diags.len(),
+                            regex,
+                            token: *intersection.first().unwrap(),
+                        });
                         let set = format!("with token set: {:?}", intersection);
                         diags.push(Diagnostic::ll1_conflict_rep(&regex.span(cst), set));
                     }
-                    Self::check_regex(cst, sema, diags, op, rule, &[]);
+                    Self::check_regex(cst, sema, diags, conflicts, op, rule, &[]);
                 }
             }
             Regex::Plus(plus) => {
@@ -1060,10 +2112,56 @@ impl<'a> LL1Validator {
                         .copied()
                         .collect::<BTreeSet<_>>();
                     if !Self::has_predicate(cst, op) && !intersection.is_empty() {
+                        conflicts.push(Conflict {
+                            diag: diags.len(),
+                            regex,
+                            token: *intersection.first().unwrap(),
+                        });
+                        let set = format!("with token set: {:?}", intersection);
+                        diags.push(Diagnostic::ll1_conflict_rep(&regex.span(cst), set));
+                    }
+                    Self::check_regex(cst, sema, diags, conflicts, op, rule, &[]);
+                }
+            }
+            Regex::Bounded(bounded) => {
+                if let Some(op) = bounded.operand(cst) {
+                    // a repetition with a fixed count makes no decision
+                    let decision = bounded
+                        .bounds(cst)
+                        .is_some_and(|(min, max)| max != Some(min));
+                    let intersection = sema.follow_sets[&regex.syntax()]
+                        .intersection(&sema.predict_sets[&op.syntax()])
+                        .copied()
+                        .collect::<BTreeSet<_>>();
+                    if decision && !Self::has_predicate(cst, op) && !intersection.is_empty() {
+                        conflicts.push(Conflict {
+                            diag: diags.len(),
+                            regex,
+                            token: *intersection.first().unwrap(),
+                        });
+                        let set = format!("with token set: {:?}", intersection);
+                        diags.push(Diagnostic::ll1_conflict_rep(&regex.span(cst), set));
+                    }
+                    Self::check_regex(cst, sema, diags, conflicts, op, rule, &[]);
+                }
+            }
+            Regex::Separated(sep) => {
+                if let (Some(op), Some(separator)) = (sep.operand(cst), sep.separator(cst)) {
+                    let intersection = sema.follow_sets[&regex.syntax()]
+                        .intersection(&sema.predict_sets[&separator.syntax()])
+                        .copied()
+                        .collect::<BTreeSet<_>>();
+                    if !Self::has_predicate(cst, separator) && !intersection.is_empty() {
+                        conflicts.push(Conflict {
+                            diag: diags.len(),
+                            regex,
+                            token: *intersection.first().unwrap(),
+                        });
                         let set = format!("with token set: {:?}", intersection);
                         diags.push(Diagnostic::ll1_conflict_rep(&regex.span(cst), set));
                     }
-                    Self::check_regex(cst, sema, diags, op, rule, &[]);
+                    Self::check_regex(cst, sema, diags, conflicts, op, rule, &[]);
+                    Self::check_regex(cst, sema, diags, conflicts, separator, rule, &[]);
                 }
             }
             Regex::Optional(opt) => {
@@ -1073,29 +2171,33 @@ impl<'a> LL1Validator {
                         .copied()
                         .collect::<BTreeSet<_>>();
                     if !Self::has_predicate(cst, op) && !intersection.is_empty() {
+                        conflicts.push(Conflict {
+                            diag: diags.len(),
+                            regex,
+                            token: *intersection.first().unwrap(),
+                        });
                         let set = format!("with token set: {:?}", intersection);
                         diags.push(Diagnostic::ll1_conflict_opt(&regex.span(cst), set));
                     }
-                    Self::check_regex(cst, sema, diags, op, rule, &[]);
+                    Self::check_regex(cst, sema, diags, conflicts, op, rule, &[]);
                 }
             }
-            Regex::Name(name) => {
+            Regex::Name(name)
                 if sema
                     .first_sets
                     .get(&name.syntax())
-                    .map_or(false, |first| first.is_empty())
-                {
-                    diags.push(Diagnostic::consume_tokens(&regex.span(cst)));
-                }
+                    .is_some_and(|first| first.is_empty()) =>
+            {
+                diags.push(Diagnostic::consume_tokens(&regex.span(cst)));
             }
             Regex::Concat(concat) => {
                 for op in concat.operands(cst) {
-                    Self::check_regex(cst, sema, diags, op, rule, &[]);
+                    Self::check_regex(cst, sema, diags, conflicts, op, rule, &[]);
                 }
             }
             Regex::Paren(paren) => {
                 if let Some(inner) = paren.inner(cst) {
-                    Self::check_regex(cst, sema, diags, inner, rule, &[]);
+                    Self::check_regex(cst, sema, diags, conflicts, inner, rule, &[]);
                 }
             }
             _ => {}
@@ -1103,123 +2205,973 @@ impl<'a> LL1Validator {
     }
 }
 
-struct UsageValidator;
+/// Results of a previous analysis of a grammar, which are reused for the rules
+/// that are not affected by later changes.
+///
+/// The first sets of a rule depend on the rules it references and the follow
+/// sets on the rules that reference it. Changes outside of rules, as well as
+/// added, removed, or renamed rules, require a full analysis.
+#[derive(Default)]
+pub struct AnalysisCache {
+    /// Text of the declarations that are no rules followed by the rule names.
+    globals: String,
+    rules: HashMap<String, CachedRule>,
+}
 
-impl UsageValidator {
-    fn run(cst: &Cst, diags: &mut Vec<Diagnostic>, sema: &mut SemanticData) {
-        if let Some(file) = File::cast(cst, NodeRef::ROOT) {
-            if let Some(rule) = sema.start {
-                sema.used.insert(rule.syntax());
-            }
-            for token in sema.skipped.iter() {
-                sema.used.insert(token.syntax());
-            }
-            let mut change = true;
-            while change {
-                let count = sema.used.len();
-                for rule in file.rule_decls(cst) {
-                    if sema.used.contains(&rule.syntax()) {
-                        if let Some(regex) = rule.regex(cst) {
-                            Self::set_regex(cst, sema, regex);
-                        }
-                    }
-                }
-                change = count != sema.used.len();
-            }
-            for rule in file.rule_decls(cst) {
-                if !sema.used.contains(&rule.syntax()) {
-                    diags.push(Diagnostic::unused_rule(&rule.span(cst)));
-                }
+/// Analysis results of a rule, where the nodes are relative to the rule node
+/// and the spans are relative to the start of the rule.
+struct CachedRule {
+    text: String,
+    /// Names of the referenced rules.
+    references: Vec<String>,
+    first_sets: Vec<(usize, Vec<String>)>,
+    follow_sets: Vec<(usize, Vec<String>)>,
+    left_rec_local_follow_sets: Vec<(usize, Vec<String>)>,
+    diags: Vec<Diagnostic>,
+    /// Diagnostic index, regex node, and token of the LL(1) conflicts.
+    conflicts: Vec<(usize, usize, String)>,
+}
+
+/// Rules for which the analysis results are recalculated.
+struct Affected<'a> {
+    /// Rules whose first sets may have changed.
+    first: HashSet<RuleDecl>,
+    /// Rules whose follow sets may have changed.
+    follow: HashSet<RuleDecl>,
+    /// Rules that are visited to recalculate the follow sets.
+    follow_updates: HashSet<RuleDecl>,
+    tokens: HashMap<&'a str, TokenName<'a>>,
+}
+
+impl<'a> Affected<'a> {
+    fn contains(&self, rule: &RuleDecl) -> bool {
+        self.first.contains(rule) || self.follow.contains(rule)
+    }
+}
+
+impl AnalysisCache {
+    fn globals(cst: &Cst, file: File) -> String {
+        let mut globals = String::new();
+        for child in cst.children(NodeRef::ROOT) {
+            if RuleDecl::cast(cst, child).is_none() {
+                globals += &cst.text(child);
+                globals.push('\n');
             }
-            for token in file.token_decls(cst) {
-                if !sema.used.contains(&token.syntax()) {
-                    diags.push(Diagnostic::unused_token(&token.span(cst)));
-                }
+        }
+        for rule in file.rule_decls(cst) {
+            if let Some((name, _)) = rule.name(cst) {
+                globals += name;
+                globals.push(' ');
             }
         }
+        globals
     }
-    fn set_regex(cst: &Cst, sema: &mut SemanticData, regex: Regex) {
-        match regex {
-            Regex::Alternation(alt) => alt
-                .operands(cst)
-                .for_each(|op| Self::set_regex(cst, sema, op)),
-            Regex::Concat(concat) => concat
-                .operands(cst)
-                .for_each(|op| Self::set_regex(cst, sema, op)),
-            Regex::Paren(paren) => {
-                if let Some(inner) = paren.inner(cst) {
-                    Self::set_regex(cst, sema, inner)
-                }
-            }
-            Regex::Optional(opt) => {
-                if let Some(op) = opt.operand(cst) {
-                    Self::set_regex(cst, sema, op)
-                }
+
+    /// Rules that are referenced in the subtree of `rule`.
+    fn references(cst: &Cst, sema: &SemanticData, rule: RuleDecl) -> Vec<RuleDecl> {
+        (rule.syntax().0..cst.end(rule.syntax()).0)
+            .filter_map(|node| sema.decl_bindings.get(&NodeRef(node)))
+            .filter_map(|decl| RuleDecl::cast(cst, *decl))
+            .collect()
+    }
+
+    /// Fills the first and follow sets of the rules that are not affected by
+    /// the changes since the last analysis.
+    ///
+    /// Returns `None` if a full analysis is required.
+    fn restore<'a>(
+        &self,
+        cst: &'a Cst,
+        sema: &mut SemanticData<'a>,
+        file: File,
+    ) -> Option<Affected<'a>> {
+        if self.rules.is_empty() || self.globals != Self::globals(cst, file) {
+            return None;
+        }
+        let mut cached = HashMap::new();
+        let mut rules = HashMap::new();
+        for rule in file.rule_decls(cst) {
+            let (name, _) = rule.name(cst)?;
+            rules.insert(name, rule);
+            let entry = self
+                .rules
+                .get(name)
+                .filter(|entry| entry.text == cst.text(rule.syntax()));
+            cached.insert(rule, entry);
+        }
+
+        let mut referrers: HashMap<RuleDecl, Vec<RuleDecl>> = HashMap::new();
+        let mut references: HashMap<RuleDecl, Vec<RuleDecl>> = HashMap::new();
+        for rule in file.rule_decls(cst) {
+            for reference in Self::references(cst, sema, rule) {
+                referrers.entry(reference).or_default().push(rule);
+                references.entry(rule).or_default().push(reference);
             }
-            Regex::Star(star) => {
-                if let Some(op) = star.operand(cst) {
-                    Self::set_regex(cst, sema, op)
+        }
+        let closure = |start: HashSet<RuleDecl>, edges: &HashMap<RuleDecl, Vec<RuleDecl>>| {
+            let mut result = start.clone();
+            let mut pending: Vec<_> = start.into_iter().collect();
+            while let Some(rule) = pending.pop() {
+                for next in edges.get(&rule).into_iter().flatten() {
+                    if result.insert(*next) {
+                        pending.push(*next);
+                    }
                 }
             }
-            Regex::Plus(plus) => {
-                if let Some(op) = plus.operand(cst) {
-                    Self::set_regex(cst, sema, op)
+            result
+        };
+        let changed: Vec<_> = cached
+            .iter()
+            .filter(|(_, entry)| entry.is_none())
+            .map(|(rule, _)| *rule)
+            .collect();
+        let first = closure(changed.iter().copied().collect(), &referrers);
+        // rules that are no longer referenced by a changed rule lose follow tokens
+        let unreferenced = changed
+            .iter()
+            .filter_map(|rule| rule.name(cst))
+            .filter_map(|(name, _)| self.rules.get(name))
+            .flat_map(|entry| entry.references.iter())
+            .filter_map(|name| rules.get(name.as_str()).copied());
+        let follow = closure(
+            first.iter().copied().chain(unreferenced).collect(),
+            &references,
+        );
+        let mut follow_updates = follow.clone();
+        for rule in follow.iter() {
+            follow_updates.extend(referrers.get(rule).into_iter().flatten());
+        }
+
+        let tokens: HashMap<&'a str, TokenName<'a>> = file
+            .token_decls(cst)
+            .filter_map(|token| token.name(cst))
+            .map(|(name, _)| (name, TokenName(name)))
+            .chain([("ɛ", TokenName("ɛ")), ("EOF", TokenName("EOF"))])
+            .collect();
+        let restore_sets =
+            |rule: RuleDecl,
+             entries: &[(usize, Vec<String>)],
+             sets: &mut HashMap<NodeRef, BTreeSet<TokenName<'a>>>| {
+                for (offset, names) in entries.iter() {
+                    let set = names
+                        .iter()
+                        .filter_map(|name| tokens.get(name.as_str()).copied())
+                        .collect();
+                    sets.insert(NodeRef(rule.syntax().0 + offset), set);
                 }
+            };
+        for (rule, entry) in cached {
+            let Some(entry) = entry else {
+                continue;
+            };
+            if !first.contains(&rule) {
+                restore_sets(rule, &entry.first_sets, &mut sema.first_sets);
             }
-            Regex::Name(name) => {
-                sema.decl_bindings
-                    .get(&name.syntax())
-                    .map(|node| sema.used.insert(*node));
-            }
-            Regex::Symbol(sym) => {
-                sema.decl_bindings
-                    .get(&sym.syntax())
-                    .map(|node| sema.used.insert(*node));
+            if !follow.contains(&rule) {
+                restore_sets(rule, &entry.follow_sets, &mut sema.follow_sets);
+                restore_sets(
+                    rule,
+                    &entry.left_rec_local_follow_sets,
+                    &mut sema.left_rec_local_follow_sets,
+                );
             }
-            Regex::Predicate(_)
-            | Regex::Action(_)
-            | Regex::Binding(_)
-            | Regex::OpenNode(_)
-            | Regex::CloseNode(_) => {}
         }
+        Some(Affected {
+            first,
+            follow,
+            follow_updates,
+            tokens,
+        })
     }
-}
-
-#[derive(Default)]
-struct RecoverySetGenerator {
-    dom: HashMap<Regex, HashSet<Regex>>,
-    pred: HashMap<Regex, HashSet<Regex>>,
-}
-impl RecoverySetGenerator {
-    fn new() -> Self {
-        Self::default()
-    }
-    fn run(&mut self, cst: &Cst, sema: &mut SemanticData) {
-        let file = if let Some(file) = File::cast(cst, NodeRef::ROOT) {
-            file
-        } else {
-            return;
-        };
 
-        let start = if let Some(start_regex) = sema.start.and_then(|start| start.regex(cst)) {
-            start_regex
-        } else {
+    /// Adds the cached diagnostics and conflicts of a `rule` that is not
+    /// affected by the changes.
+    fn restore_conflicts<'a>(
+        &self,
+        cst: &'a Cst,
+        rule: RuleDecl,
+        affected: &Affected<'a>,
+        diags: &mut Vec<Diagnostic>,
+        conflicts: &mut Vec<Conflict<'a>>,
+    ) {
+        let Some(entry) = rule.name(cst).and_then(|(name, _)| self.rules.get(name)) else {
             return;
         };
-        for rule in file.rule_decls(cst) {
-            if sema.used.contains(&rule.syntax()) {
-                if let Some(regex) = rule.regex(cst) {
-                    self.set_regex_pred(cst, sema, regex);
-                }
+        let start = rule.span(cst).start;
+        let diag_start = diags.len();
+        for diag in entry.diags.iter() {
+            let mut diag = diag.clone();
+            for label in diag.labels.iter_mut() {
+                label.range = label.range.start + start..label.range.end + start;
+            }
+            diags.push(diag);
+        }
+        for (diag, offset, token) in entry.conflicts.iter() {
+            let regex = Regex::cast(cst, NodeRef(rule.syntax().0 + offset));
+            if let (Some(regex), Some(token)) = (regex, affected.tokens.get(token.as_str())) {
+                conflicts.push(Conflict {
+                    diag: diag_start + diag,
+                    regex,
+                    token: *token,
+                });
+            }
+        }
+    }
+
+    /// Stores the analysis results of all rules, where `results` contains the
+    /// indices of the first diagnostic and conflict of each rule.
+    fn store(
+        &mut self,
+        cst: &Cst,
+        sema: &SemanticData,
+        file: File,
+        diags: &[Diagnostic],
+        conflicts: &[Conflict],
+        results: &[(RuleDecl, usize, usize)],
+    ) {
+        self.globals = Self::globals(cst, file);
+        self.rules.clear();
+        for (i, (rule, diag_start, conflict_start)) in results.iter().enumerate() {
+            let Some((name, _)) = rule.name(cst) else {
+                continue;
+            };
+            let (diag_end, conflict_end) = results
+                .get(i + 1)
+                .map_or((diags.len(), conflicts.len()), |(_, diag, conflict)| {
+                    (*diag, *conflict)
+                });
+            let nodes = rule.syntax().0..cst.end(rule.syntax()).0;
+            let entries = |sets: &HashMap<NodeRef, BTreeSet<TokenName>>| {
+                nodes
+                    .clone()
+                    .filter_map(|node| {
+                        sets.get(&NodeRef(node)).map(|set| {
+                            let names = set.iter().map(|token| token.0.to_string()).collect();
+                            (node - nodes.start, names)
+                        })
+                    })
+                    .collect()
+            };
+            let start = rule.span(cst).start;
+            let diags = diags[*diag_start..diag_end]
+                .iter()
+                .map(|diag| {
+                    let mut diag = diag.clone();
+                    for label in diag.labels.iter_mut() {
+                        label.range = label.range.start - start..label.range.end - start;
+                    }
+                    diag
+                })
+                .collect();
+            let conflicts = conflicts[*conflict_start..conflict_end]
+                .iter()
+                .map(|conflict| {
+                    (
+                        conflict.diag - diag_start,
+                        conflict.regex.syntax().0 - nodes.start,
+                        conflict.token.0.to_string(),
+                    )
+                })
+                .collect();
+            self.rules.insert(
+                name.to_string(),
+                CachedRule {
+                    text: cst.text(rule.syntax()),
+                    references: Self::references(cst, sema, *rule)
+                        .into_iter()
+                        .filter_map(|rule| rule.name(cst).map(|(name, _)| name.to_string()))
+                        .collect(),
+                    first_sets: entries(&sema.first_sets),
+                    follow_sets: entries(&sema.follow_sets),
+                    left_rec_local_follow_sets: entries(&sema.left_rec_local_follow_sets),
+                    diags,
+                    conflicts,
+                },
+            );
+        }
+    }
+}
+
+/// Token sequences of at most `k` tokens.
+type Sequences<'a> = BTreeSet<Vec<TokenName<'a>>>;
+
+/// Resolves LL(1) conflicts of alternations with the first `k` tokens.
+///
+/// The sets are the FIRST_k and FOLLOW_k sets of the strong LL(k) condition.
+/// An alternation is only resolved if all its conflicts are resolved, and it
+/// is not the top level alternation of a rule with a recursive pattern.
+struct LookaheadCalculator<'a> {
+    k: usize,
+    first: HashMap<NodeRef, Sequences<'a>>,
+    follow: HashMap<NodeRef, Sequences<'a>>,
+}
+
+impl<'a> LookaheadCalculator<'a> {
+    fn new(k: usize) -> Self {
+        Self {
+            k,
+            first: HashMap::new(),
+            follow: HashMap::new(),
+        }
+    }
+
+    fn run(&mut self, cst: &'a Cst, sema: &mut SemanticData<'a>, file: File) {
+        let mut change = true;
+        while change {
+            change = false;
+            for rule in file.rule_decls(cst) {
+                if let Some(regex) = rule.regex(cst) {
+                    self.calc_first_regex(cst, sema, regex, &mut change);
+                }
+            }
+        }
+        for rule_regex in sema
+            .start
+            .iter()
+            .chain(sema.entries.iter())
+            .filter_map(|rule| rule.regex(cst))
+        {
+            self.follow
+                .entry(rule_regex.syntax())
+                .or_default()
+                .insert(vec![TokenName("EOF")]);
+        }
+        let mut change = true;
+        while change {
+            change = false;
+            for rule in file.rule_decls(cst) {
+                if let Some(regex) = rule.regex(cst) {
+                    self.calc_follow_regex(cst, sema, regex, &mut change);
+                }
+            }
+        }
+        for rule in file.rule_decls(cst) {
+            if let Some(regex) = rule.regex(cst) {
+                let is_recursive = matches!(
+                    sema.patterns.get(&rule),
+                    Some(
+                        Pattern::LeftRecursive(_)
+                            | Pattern::OperatorPrecedence(_)
+                            | Pattern::RightRecursiveForwarding(_)
+                    )
+                );
+                self.resolve(cst, sema, regex, is_recursive);
+            }
+        }
+    }
+
+    /// Concatenates the sequences and truncates them to `k` tokens.
+    fn concat(&self, first: &Sequences<'a>, second: &Sequences<'a>) -> Sequences<'a> {
+        let mut res = BTreeSet::new();
+        for seq in first.iter() {
+            if seq.len() == self.k || seq.last() == Some(&TokenName("EOF")) {
+                res.insert(seq.clone());
+                continue;
+            }
+            for next in second.iter() {
+                let mut seq = seq.clone();
+                seq.extend(next.iter().take(self.k - seq.len()));
+                res.insert(seq);
+            }
+        }
+        res
+    }
+
+    fn calc_first_regex(
+        &mut self,
+        cst: &'a Cst,
+        sema: &SemanticData<'a>,
+        regex: Regex,
+        change: &mut bool,
+    ) {
+        let empty = || BTreeSet::from([vec![]]);
+        let first = match regex {
+            Regex::Name(_) | Regex::Symbol(_) => {
+                let decl = sema.decl_bindings.get(&regex.syntax()).copied();
+                if let Some(rule) = decl.and_then(|decl| RuleDecl::cast(cst, decl)) {
+                    rule.regex(cst).map_or_else(empty, |rule_regex| {
+                        self.first
+                            .get(&rule_regex.syntax())
+                            .cloned()
+                            .unwrap_or_default()
+                    })
+                } else if let Some((name, _)) = decl
+                    .and_then(|decl| TokenDecl::cast(cst, decl))
+                    .and_then(|token| token.name(cst))
+                {
+                    BTreeSet::from([vec![TokenName(name)]])
+                } else {
+                    BTreeSet::new()
+                }
+            }
+            Regex::Concat(concat) => {
+                let mut first = empty();
+                for op in concat.operands(cst) {
+                    self.calc_first_regex(cst, sema, op, change);
+                    first = self.concat(&first, &self.first[&op.syntax()]);
+                }
+                first
+            }
+            Regex::Alternation(alt) => {
+                let mut first = BTreeSet::new();
+                for op in alt.operands(cst) {
+                    self.calc_first_regex(cst, sema, op, change);
+                    first.extend(self.first[&op.syntax()].iter().cloned());
+                }
+                first
+            }
+            Regex::Star(_)
+            | Regex::Plus(_)
+            | Regex::Bounded(_)
+            | Regex::Optional(_)
+            | Regex::Paren(_) => {
+                let op = match regex {
+                    Regex::Star(star) => star.operand(cst),
+                    Regex::Plus(plus) => plus.operand(cst),
+                    Regex::Bounded(bounded) => bounded.operand(cst),
+                    Regex::Optional(opt) => opt.operand(cst),
+                    Regex::Paren(paren) => paren.inner(cst),
+                    _ => unreachable!(),
+                };
+                let Some(op) = op else {
+                    self.first.entry(regex.syntax()).or_default();
+                    return;
+                };
+                self.calc_first_regex(cst, sema, op, change);
+                let op_first = &self.first[&op.syntax()];
+                let mut repeated = empty();
+                repeated.extend(
+                    self.first
+                        .get(&regex.syntax())
+                        .into_iter()
+                        .flatten()
+                        .cloned(),
+                );
+                match regex {
+                    Regex::Star(_) => {
+                        let mut first = self.concat(op_first, &repeated);
+                        first.insert(vec![]);
+                        first
+                    }
+                    Regex::Plus(_) => self.concat(op_first, &repeated),
+                    Regex::Bounded(bounded) => {
+                        let (min, max) = bounded.bounds(cst).unwrap_or((0, None));
+                        let mut first = if max == Some(1) {
+                            op_first.clone()
+                        } else {
+                            self.concat(op_first, &repeated)
+                        };
+                        if min == 0 {
+                            first.insert(vec![]);
+                        }
+                        first
+                    }
+                    Regex::Optional(_) => {
+                        let mut first = op_first.clone();
+                        first.insert(vec![]);
+                        first
+                    }
+                    _ => op_first.clone(),
+                }
+            }
+            Regex::Separated(sep) => {
+                let (Some(op), Some(separator)) = (sep.operand(cst), sep.separator(cst)) else {
+                    self.first.entry(regex.syntax()).or_default();
+                    return;
+                };
+                self.calc_first_regex(cst, sema, op, change);
+                self.calc_first_regex(cst, sema, separator, change);
+                let repeated = self.separated_tail(regex, separator);
+                self.concat(&self.first[&op.syntax()], &repeated)
+            }
+            Regex::Wildcard(_) => sema
+                .wildcard_tokens
+                .iter()
+                .map(|token| vec![*token])
+                .collect(),
+            _ => empty(),
+        };
+        let entry = self.first.entry(regex.syntax()).or_default();
+        let size = entry.len();
+        entry.extend(first);
+        *change |= entry.len() != size;
+    }
+
+    /// Returns the sequences of the repeated part `(b a)*` of a separated list
+    /// `a % b`.
+    fn separated_tail(&self, regex: Regex, separator: Regex) -> Sequences<'a> {
+        let mut repeated = self.concat(
+            &self.first[&separator.syntax()],
+            self.first.get(&regex.syntax()).unwrap_or(&BTreeSet::new()),
+        );
+        repeated.insert(vec![]);
+        repeated
+    }
+
+    fn add_follow(&mut self, regex: Regex, follow: Sequences<'a>, change: &mut bool) {
+        let entry = self.follow.entry(regex.syntax()).or_default();
+        let size = entry.len();
+        entry.extend(follow);
+        *change |= entry.len() != size;
+    }
+
+    fn calc_follow_regex(
+        &mut self,
+        cst: &'a Cst,
+        sema: &SemanticData<'a>,
+        regex: Regex,
+        change: &mut bool,
+    ) {
+        let follow = self.follow.entry(regex.syntax()).or_default().clone();
+        match regex {
+            Regex::Name(name) => {
+                if let Some(rule_regex) = sema
+                    .decl_bindings
+                    .get(&name.syntax())
+                    .and_then(|decl| RuleDecl::cast(cst, *decl))
+                    .and_then(|rule| rule.regex(cst))
+                {
+                    self.add_follow(rule_regex, follow, change);
+                }
+            }
+            Regex::Concat(concat) => {
+                let mut follow = follow;
+                for op in concat.operands(cst).collect::<Vec<_>>().into_iter().rev() {
+                    self.add_follow(op, follow.clone(), change);
+                    self.calc_follow_regex(cst, sema, op, change);
+                    follow = self.concat(&self.first[&op.syntax()], &follow);
+                }
+            }
+            Regex::Alternation(alt) => {
+                for op in alt.operands(cst) {
+                    self.add_follow(op, follow.clone(), change);
+                    self.calc_follow_regex(cst, sema, op, change);
+                }
+            }
+            Regex::Star(_)
+            | Regex::Plus(_)
+            | Regex::Bounded(_)
+            | Regex::Optional(_)
+            | Regex::Paren(_) => {
+                let op = match regex {
+                    Regex::Star(star) => star.operand(cst),
+                    Regex::Plus(plus) => plus.operand(cst),
+                    Regex::Bounded(bounded) => bounded.operand(cst),
+                    Regex::Optional(opt) => opt.operand(cst),
+                    Regex::Paren(paren) => paren.inner(cst),
+                    _ => unreachable!(),
+                };
+                let Some(op) = op else {
+                    return;
+                };
+                let is_repeated =
+                    |bounded: Bounded| bounded.bounds(cst).is_some_and(|(_, max)| max != Some(1));
+                let op_follow = match regex {
+                    Regex::Star(_) | Regex::Plus(_) => {
+                        let mut repeated = self.first[&regex.syntax()].clone();
+                        repeated.insert(vec![]);
+                        self.concat(&repeated, &follow)
+                    }
+                    Regex::Bounded(bounded) if is_repeated(bounded) => {
+                        let mut repeated = self.first[&regex.syntax()].clone();
+                        repeated.insert(vec![]);
+                        self.concat(&repeated, &follow)
+                    }
+                    _ => follow,
+                };
+                self.add_follow(op, op_follow, change);
+                self.calc_follow_regex(cst, sema, op, change);
+            }
+            Regex::Separated(sep) => {
+                let (Some(op), Some(separator)) = (sep.operand(cst), sep.separator(cst)) else {
+                    return;
+                };
+                let op_follow = self.concat(&self.separated_tail(regex, separator), &follow);
+                let separator_follow = self.concat(&self.first[&regex.syntax()], &follow);
+                self.add_follow(op, op_follow, change);
+                self.add_follow(separator, separator_follow, change);
+                self.calc_follow_regex(cst, sema, op, change);
+                self.calc_follow_regex(cst, sema, separator, change);
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks if the sets contain sequences, where one is a prefix of the other.
+    fn conflicts(first: &Sequences<'a>, second: &Sequences<'a>) -> bool {
+        first.iter().any(|a| {
+            second
+                .iter()
+                .any(|b| a.iter().zip(b.iter()).all(|(a, b)| a == b))
+        })
+    }
+
+    fn resolve(&self, cst: &'a Cst, sema: &mut SemanticData<'a>, regex: Regex, top_level: bool) {
+        match regex {
+            Regex::Alternation(alt) => {
+                let ops = alt.operands(cst).collect::<Vec<_>>();
+                if !top_level {
+                    self.resolve_alternation(cst, sema, regex, &ops);
+                }
+                for op in ops {
+                    self.resolve(cst, sema, op, false);
+                }
+            }
+            Regex::Concat(concat) => {
+                for op in concat.operands(cst) {
+                    self.resolve(cst, sema, op, false);
+                }
+            }
+            Regex::Star(_)
+            | Regex::Plus(_)
+            | Regex::Bounded(_)
+            | Regex::Optional(_)
+            | Regex::Paren(_) => {
+                let op = match regex {
+                    Regex::Star(star) => star.operand(cst),
+                    Regex::Plus(plus) => plus.operand(cst),
+                    Regex::Bounded(bounded) => bounded.operand(cst),
+                    Regex::Optional(opt) => opt.operand(cst),
+                    Regex::Paren(paren) => paren.inner(cst),
+                    _ => unreachable!(),
+                };
+                if let Some(op) = op {
+                    self.resolve(cst, sema, op, false);
+                }
+            }
+            Regex::Separated(sep) => {
+                for op in [sep.operand(cst), sep.separator(cst)].into_iter().flatten() {
+                    self.resolve(cst, sema, op, false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Calculates the lookahead sets of the alternatives, if the alternation
+    /// is not LL(1) but LL(k).
+    fn resolve_alternation(
+        &self,
+        cst: &'a Cst,
+        sema: &mut SemanticData<'a>,
+        regex: Regex,
+        ops: &[Regex],
+    ) {
+        let follow = &self.follow[&regex.syntax()];
+        let predict = |op: &Regex| self.concat(&self.first[&op.syntax()], follow);
+        let mut guarded = vec![BTreeSet::new(); ops.len()];
+        for (i, op) in ops.iter().enumerate() {
+            if LL1Validator::has_predicate(cst, *op) {
+                continue;
+            }
+            for other in ops.iter().skip(i + 1) {
+                let conflict = sema.predict_sets[&op.syntax()]
+                    .intersection(&sema.predict_sets[&other.syntax()])
+                    .copied()
+                    .collect::<BTreeSet<_>>();
+                if conflict.is_empty() {
+                    continue;
+                }
+                let restrict = |op: &Regex| {
+                    predict(op)
+                        .into_iter()
+                        .filter(|seq| seq.first().is_some_and(|token| conflict.contains(token)))
+                        .collect::<Sequences>()
+                };
+                if Self::conflicts(&restrict(op), &restrict(other)) {
+                    return;
+                }
+                guarded[i].extend(conflict);
+            }
+        }
+        for (op, guarded) in ops.iter().zip(guarded) {
+            if guarded.is_empty() {
+                continue;
+            }
+            let mut sequences = predict(op)
+                .into_iter()
+                .filter(|seq| seq.first().is_some_and(|token| guarded.contains(token)))
+                .collect::<Sequences>();
+            for token in sema.predict_sets[&op.syntax()].difference(&guarded) {
+                sequences.insert(vec![*token]);
+            }
+            sema.lookahead_sets.insert(op.syntax(), sequences);
+        }
+    }
+}
+
+struct UsageValidator;
+
+impl UsageValidator {
+    fn run(cst: &Cst, diags: &mut Vec<Diagnostic>, sema: &mut SemanticData) {
+        if let Some(file) = File::cast(cst, NodeRef::ROOT) {
+            for rule in file.rule_decls(cst) {
+                let mut references = BTreeSet::new();
+                if let Some(regex) = rule.regex(cst) {
+                    Self::set_regex(cst, sema, regex, &mut references);
+                }
+                sema.references.insert(rule, references);
+            }
+            for token in sema.skipped.iter() {
+                sema.used.insert(token.syntax());
+            }
+            let mut pending: Vec<_> = sema
+                .start
+                .iter()
+                .chain(sema.entries.iter())
+                .map(|rule| rule.syntax())
+                .collect();
+            while let Some(node) = pending.pop() {
+                if !sema.used.insert(node) {
+                    continue;
+                }
+                if let Some(rule) = RuleDecl::cast(cst, node) {
+                    pending.extend(sema.references[&rule].iter());
+                }
+            }
+
+            // connected subgraphs of unused rules and the tokens only they use
+            let mut neighbors: HashMap<NodeRef, Vec<NodeRef>> = HashMap::new();
+            for rule in file.rule_decls(cst) {
+                if sema.used.contains(&rule.syntax()) {
+                    continue;
+                }
+                for node in sema.references[&rule].iter() {
+                    if !sema.used.contains(node) && *node != rule.syntax() {
+                        neighbors.entry(rule.syntax()).or_default().push(*node);
+                        neighbors.entry(*node).or_default().push(rule.syntax());
+                    }
+                }
+            }
+            let mut grouped = HashSet::new();
+            for rule in file.rule_decls(cst) {
+                if sema.used.contains(&rule.syntax()) || !grouped.insert(rule.syntax()) {
+                    continue;
+                }
+                let mut nodes = vec![];
+                let mut pending = vec![rule.syntax()];
+                while let Some(node) = pending.pop() {
+                    nodes.push(node);
+                    for neighbor in neighbors.get(&node).into_iter().flatten() {
+                        if grouped.insert(*neighbor) {
+                            pending.push(*neighbor);
+                        }
+                    }
+                }
+                nodes.sort();
+                // rules that are not referenced within the subgraph are its
+                // roots, and for a cycle the first rule is taken
+                let mut roots = nodes
+                    .iter()
+                    .filter_map(|node| RuleDecl::cast(cst, *node))
+                    .filter(|rule| {
+                        !nodes
+                            .iter()
+                            .filter_map(|node| RuleDecl::cast(cst, *node))
+                            .any(|other| {
+                                other != *rule && sema.references[&other].contains(&rule.syntax())
+                            })
+                    })
+                    .collect::<Vec<_>>();
+                if roots.is_empty() {
+                    roots.push(rule);
+                }
+                let members = nodes
+                    .into_iter()
+                    .filter(|node| !roots.iter().any(|root| root.syntax() == *node))
+                    .collect();
+                sema.orphans.push(Orphans { roots, members });
+            }
+            for orphans in sema.orphans.iter() {
+                // the rules that are only reachable from allowed rules are
+                // intentionally unused as well
+                if orphans.roots.iter().all(|root| root.allows(cst, "unused")) {
+                    continue;
+                }
+                let span = orphans.roots[0].span(cst);
+                let name_span = |node: NodeRef| {
+                    RuleDecl::cast(cst, node)
+                        .and_then(|rule| rule.name(cst))
+                        .or_else(|| TokenDecl::cast(cst, node).and_then(|token| token.name(cst)))
+                        .map(|(_, span)| span)
+                };
+                let roots = orphans.roots[1..]
+                    .iter()
+                    .filter_map(|root| name_span(root.syntax()))
+                    .collect();
+                let members = orphans
+                    .members
+                    .iter()
+                    .filter_map(|node| name_span(*node))
+                    .collect();
+                diags.push(Diagnostic::unused_rule(&span, roots, members));
+                let removals = orphans
+                    .roots
+                    .iter()
+                    .map(|root| root.syntax())
+                    .chain(orphans.members.iter().copied())
+                    .filter(|node| RuleDecl::cast(cst, *node).is_some())
+                    .map(|node| cst.removal_span(node))
+                    .collect();
+                sema.fixes.push(Fix::remove_rules(&span, removals));
+            }
+            let skip = file.skip_decls(cst).next();
+            for list in cst
+                .children(NodeRef::ROOT)
+                .filter(|c| cst.get_rule(*c, Rule::TokenList).is_some())
+            {
+                let tokens = cst
+                    .children(list)
+                    .filter_map(|c| TokenDecl::cast(cst, c))
+                    .collect::<Vec<_>>();
+                for (i, token) in tokens.iter().enumerate() {
+                    if sema.used.contains(&token.syntax())
+                        || grouped.contains(&token.syntax())
+                        || token.allows(cst, "unused")
+                    {
+                        continue;
+                    }
+                    let span = token.span(cst);
+                    diags.push(Diagnostic::unused_token(&span));
+                    let removal = match (i.checked_sub(1), tokens.get(i + 1)) {
+                        (Some(prev), _) => tokens[prev].span(cst).end..span.end,
+                        (None, Some(next)) => span.start..next.span(cst).start,
+                        (None, None) => cst.removal_span(list),
+                    };
+                    sema.fixes.push(Fix::remove_token(&span, removal));
+                    let name = token.name(cst).unwrap_or_default().0;
+                    let semi = skip
+                        .and_then(|skip| {
+                            cst.children(skip.syntax())
+                                .find_map(|c| cst.get_token(c, Token::Semi))
+                        })
+                        .map(|(_, semi)| semi.start);
+                    let insertion = match semi {
+                        Some(offset) => (offset..offset, format!(" {name}")),
+                        None => {
+                            let offset = cst.get_span(list).unwrap().end;
+                            (offset..offset, format!("\nskip {name};"))
+                        }
+                    };
+                    sema.fixes.push(Fix::skip_token(&span, insertion));
+                }
+            }
+        }
+    }
+    /// Collects the rules and tokens that are referenced in the `regex`.
+    fn set_regex(cst: &Cst, sema: &SemanticData, regex: Regex, references: &mut BTreeSet<NodeRef>) {
+        match regex {
+            Regex::Alternation(alt) => alt
+                .operands(cst)
+                .for_each(|op| Self::set_regex(cst, sema, op, references)),
+            Regex::Concat(concat) => concat
+                .operands(cst)
+                .for_each(|op| Self::set_regex(cst, sema, op, references)),
+            Regex::Paren(paren) => {
+                if let Some(inner) = paren.inner(cst) {
+                    Self::set_regex(cst, sema, inner, references)
+                }
+            }
+            Regex::SyntacticPredicate(pred) => {
+                if let Some(inner) = pred.inner(cst) {
+                    Self::set_regex(cst, sema, inner, references)
+                }
+            }
+            Regex::Optional(opt) => {
+                if let Some(op) = opt.operand(cst) {
+                    Self::set_regex(cst, sema, op, references)
+                }
+            }
+            Regex::Star(star) => {
+                if let Some(op) = star.operand(cst) {
+                    Self::set_regex(cst, sema, op, references)
+                }
+            }
+            Regex::Plus(plus) => {
+                if let Some(op) = plus.operand(cst) {
+                    Self::set_regex(cst, sema, op, references)
+                }
+            }
+            Regex::Bounded(bounded) => {
+                if let Some(op) = bounded.operand(cst) {
+                    Self::set_regex(cst, sema, op, references)
+                }
+            }
+            Regex::Separated(sep) => {
+                for op in [sep.operand(cst), sep.separator(cst)].into_iter().flatten() {
+                    Self::set_regex(cst, sema, op, references)
+                }
+            }
+            Regex::Name(name) => {
+                sema.decl_bindings
+                    .get(&name.syntax())
+                    .map(|node| references.insert(*node));
+            }
+            Regex::Symbol(sym) => {
+                sema.decl_bindings
+                    .get(&sym.syntax())
+                    .map(|node| references.insert(*node));
+            }
+            Regex::Wildcard(_) => {
+                if let Some(file) = File::cast(cst, NodeRef::ROOT) {
+                    for token in file.token_decls(cst) {
+                        if !sema.skipped.contains(&token) {
+                            references.insert(token.syntax());
+                        }
+                    }
+                }
+            }
+            Regex::Predicate(_)
+            | Regex::Action(_)
+            | Regex::Binding(_)
+            | Regex::OpenNode(_)
+            | Regex::CloseNode(_) => {}
+        }
+    }
+}
+
+#[derive(Default)]
+struct RecoverySetGenerator {
+    dom: HashMap<Regex, HashSet<Regex>>,
+    pred: HashMap<Regex, HashSet<Regex>>,
+}
+impl RecoverySetGenerator {
+    fn new() -> Self {
+        Self::default()
+    }
+    fn run<'a>(&mut self, cst: &'a Cst, sema: &mut SemanticData<'a>) {
+        let file = if let Some(file) = File::cast(cst, NodeRef::ROOT) {
+            file
+        } else {
+            return;
+        };
+
+        let start = if let Some(start_regex) = sema.start.and_then(|start| start.regex(cst)) {
+            start_regex
+        } else {
+            return;
+        };
+        for rule in file.rule_decls(cst) {
+            if sema.used.contains(&rule.syntax()) {
+                if let Some(regex) = rule.regex(cst) {
+                    self.set_regex_pred(cst, sema, regex);
+                }
             }
         }
 
         let nodes_no_start: HashSet<_> = self.pred.keys().copied().collect();
+        // entry rules that are not referenced are additional roots
+        let roots: Vec<_> = std::iter::once(start)
+            .chain(sema.entries.iter().filter_map(|rule| rule.regex(cst)))
+            .filter(|regex| !nodes_no_start.contains(regex))
+            .collect();
         let mut nodes = nodes_no_start.clone();
-        nodes.insert(start);
+        nodes.extend(roots.iter().copied());
 
-        // start node dominates itself
-        self.dom.insert(start, HashSet::from_iter([start]));
+        // root nodes dominate themselves
+        for root in roots.iter() {
+            self.dom.insert(*root, HashSet::from_iter([*root]));
+        }
         // other nodes are initialized with all nodes as dominators
         for regex in nodes_no_start.iter() {
             self.dom.insert(*regex, nodes.clone());
@@ -1246,15 +3198,29 @@ impl RecoverySetGenerator {
             }
         }
 
+        // synchronization tokens end all loops except the loops of the root rules
+        let sync: Vec<_> = sema
+            .sync
+            .iter()
+            .filter_map(|token| token.name(cst).map(|(name, _)| TokenName(name)))
+            .collect();
+
         // calculate recovery set for loops
-        if let Regex::Star(_) | Regex::Plus(_) = start {
-            sema.recovery_sets.entry(start.syntax()).or_default();
+        for root in roots.iter() {
+            if let Regex::Star(_) | Regex::Plus(_) | Regex::Separated(_) | Regex::Bounded(_) = root
+            {
+                sema.recovery_sets.entry(root.syntax()).or_default();
+            }
         }
         for regex in nodes_no_start.iter() {
             let op = if let Regex::Star(star) = regex {
                 star.operand(cst).unwrap()
             } else if let Regex::Plus(plus) = regex {
                 plus.operand(cst).unwrap()
+            } else if let Regex::Separated(sep) = regex {
+                sep.operand(cst).unwrap()
+            } else if let Regex::Bounded(bounded) = regex {
+                bounded.operand(cst).unwrap()
             } else {
                 continue;
             };
@@ -1266,6 +3232,10 @@ impl RecoverySetGenerator {
                     .or_default()
                     .extend(dom_follow);
             }
+            sema.recovery_sets
+                .entry(regex.syntax())
+                .or_default()
+                .extend(sync.iter().copied());
             for sym in op_follow.iter() {
                 sema.recovery_sets
                     .entry(regex.syntax())
@@ -1319,6 +3289,18 @@ impl RecoverySetGenerator {
                     self.set_regex_pred(cst, sema, op);
                 }
             }
+            Regex::Bounded(bounded) => {
+                if let Some(op) = bounded.operand(cst) {
+                    self.add_pred(op, regex);
+                    self.set_regex_pred(cst, sema, op);
+                }
+            }
+            Regex::Separated(sep) => {
+                for op in [sep.operand(cst), sep.separator(cst)].into_iter().flatten() {
+                    self.add_pred(op, regex);
+                    self.set_regex_pred(cst, sema, op);
+                }
+            }
             Regex::Optional(opt) => {
                 if let Some(op) = opt.operand(cst) {
                     self.add_pred(op, regex);
@@ -1332,7 +3314,493 @@ impl RecoverySetGenerator {
                 }
             }
             Regex::Symbol(_)
+            | Regex::Wildcard(_)
+            | Regex::Predicate(_)
+            | Regex::SyntacticPredicate(_)
+            | Regex::Action(_)
+            | Regex::Binding(_)
+            | Regex::OpenNode(_)
+            | Regex::CloseNode(_) => {}
+        }
+    }
+}
+
+/// Thresholds for the rule complexity check.
+#[derive(Clone, Copy, Debug)]
+pub struct ComplexityLimits {
+    /// Maximum nesting depth of alternations, repetitions, and options.
+    pub max_depth: usize,
+    /// Maximum number of alternation branches in a rule.
+    pub max_alternatives: usize,
+}
+
+impl Default for ComplexityLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 6,
+            max_alternatives: 64,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Complexity {
+    depth: usize,
+    alternatives: usize,
+    lines: usize,
+}
+
+struct ComplexityCheck {
+    limits: ComplexityLimits,
+    metrics: HashMap<Regex, Complexity>,
+}
+
+impl ComplexityCheck {
+    fn new(limits: ComplexityLimits) -> Self {
+        Self {
+            limits,
+            metrics: HashMap::new(),
+        }
+    }
+
+    /// Warns about rules that result in huge parser functions.
+    fn run(&mut self, cst: &Cst, diags: &mut Vec<Diagnostic>, sema: &SemanticData) {
+        let Some(file) = File::cast(cst, NodeRef::ROOT) else {
+            return;
+        };
+        for rule in file.rule_decls(cst) {
+            let Some(regex) = rule
+                .regex(cst)
+                .filter(|_| !rule.allows(cst, "complex_rule"))
+            else {
+                continue;
+            };
+            self.metrics.clear();
+            let complexity = self.calc_regex(cst, sema, regex);
+            let name_span = rule.name(cst).unwrap_or_default().1;
+            if complexity.depth > self.limits.max_depth {
+                let splits = self.depth_splits(cst, regex);
+                diags.push(Diagnostic::complex_rule(
+                    &name_span,
+                    format!(
+                        "nesting depth {} exceeds the limit of {}",
+                        complexity.depth, self.limits.max_depth
+                    ),
+                    complexity.lines,
+                    splits,
+                ));
+            } else if complexity.alternatives > self.limits.max_alternatives {
+                let splits = self.alternative_splits(cst, regex);
+                diags.push(Diagnostic::complex_rule(
+                    &name_span,
+                    format!(
+                        "{} alternatives exceed the limit of {}",
+                        complexity.alternatives, self.limits.max_alternatives
+                    ),
+                    complexity.lines,
+                    splits,
+                ));
+            }
+        }
+    }
+
+    fn set_len(sets: &HashMap<NodeRef, BTreeSet<TokenName>>, regex: Regex) -> usize {
+        sets.get(&regex.syntax()).map_or(0, |set| set.len())
+    }
+
+    /// Calculates the complexity of a regex and estimates the number of lines
+    /// generated for it by the backend.
+    fn calc_regex(&mut self, cst: &Cst, sema: &SemanticData, regex: Regex) -> Complexity {
+        let mut res = Complexity::default();
+        let add = |res: &mut Complexity, op: Complexity, nested: usize| {
+            res.depth = res.depth.max(op.depth + nested);
+            res.alternatives += op.alternatives;
+            res.lines += op.lines;
+        };
+        match regex {
+            Regex::Concat(concat) => {
+                for op in concat.operands(cst) {
+                    let op = self.calc_regex(cst, sema, op);
+                    add(&mut res, op, 0);
+                }
+            }
+            Regex::Alternation(alt) => {
+                res.lines = 5 + Self::set_len(&sema.predict_sets, regex);
+                for op in alt.operands(cst) {
+                    let op_complexity = self.calc_regex(cst, sema, op);
+                    add(&mut res, op_complexity, 1);
+                    res.alternatives += 1;
+                    res.lines += 1 + Self::set_len(&sema.predict_sets, op);
+                }
+            }
+            Regex::Star(_) | Regex::Plus(_) | Regex::Bounded(_) | Regex::Optional(_) => {
+                let op = match regex {
+                    Regex::Star(star) => star.operand(cst),
+                    Regex::Plus(plus) => plus.operand(cst),
+                    Regex::Bounded(bounded) => bounded.operand(cst),
+                    Regex::Optional(opt) => opt.operand(cst),
+                    _ => unreachable!(),
+                };
+                if let Some(op) = op {
+                    let op_complexity = self.calc_regex(cst, sema, op);
+                    add(&mut res, op_complexity, 1);
+                    res.lines += 6
+                        + Self::set_len(&sema.first_sets, op)
+                        + Self::set_len(&sema.follow_sets, regex)
+                        + Self::set_len(&sema.predict_sets, regex)
+                        + Self::set_len(&sema.recovery_sets, regex);
+                    let twice = match regex {
+                        Regex::Plus(_) => true,
+                        Regex::Bounded(bounded) => bounded
+                            .bounds(cst)
+                            .is_some_and(|(min, max)| min > 0 && max != Some(min)),
+                        _ => false,
+                    };
+                    if twice {
+                        // the operand is generated twice
+                        res.lines += op_complexity.lines;
+                    }
+                }
+            }
+            Regex::Separated(sep) => {
+                if let (Some(op), Some(separator)) = (sep.operand(cst), sep.separator(cst)) {
+                    let op_complexity = self.calc_regex(cst, sema, op);
+                    let separator_complexity = self.calc_regex(cst, sema, separator);
+                    add(&mut res, op_complexity, 1);
+                    add(&mut res, separator_complexity, 1);
+                    // the operand is generated twice
+                    res.lines += 6
+                        + op_complexity.lines
+                        + Self::set_len(&sema.first_sets, separator)
+                        + Self::set_len(&sema.follow_sets, regex)
+                        + Self::set_len(&sema.predict_sets, separator)
+                        + Self::set_len(&sema.recovery_sets, regex);
+                }
+            }
+            Regex::Paren(paren) => {
+                if let Some(inner) = paren.inner(cst) {
+                    res = self.calc_regex(cst, sema, inner);
+                }
+            }
+            Regex::Predicate(_) | Regex::SyntacticPredicate(_) => {}
+            Regex::Wildcard(_) => res.lines = 4 + Self::set_len(&sema.first_sets, regex),
+            Regex::Name(_)
+            | Regex::Symbol(_)
+            | Regex::Action(_)
+            | Regex::Binding(_)
+            | Regex::OpenNode(_)
+            | Regex::CloseNode(_) => res.lines = 1,
+        }
+        self.metrics.insert(regex, res);
+        res
+    }
+
+    fn operands(cst: &Cst, regex: Regex) -> Vec<Regex> {
+        match regex {
+            Regex::Concat(concat) => concat.operands(cst).collect(),
+            Regex::Alternation(alt) => alt.operands(cst).collect(),
+            Regex::Star(star) => star.operand(cst).into_iter().collect(),
+            Regex::Plus(plus) => plus.operand(cst).into_iter().collect(),
+            Regex::Bounded(bounded) => bounded.operand(cst).into_iter().collect(),
+            Regex::Separated(sep) => [sep.operand(cst), sep.separator(cst)]
+                .into_iter()
+                .flatten()
+                .collect(),
+            Regex::Optional(opt) => opt.operand(cst).into_iter().collect(),
+            Regex::Paren(paren) => paren.inner(cst).into_iter().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Suggests the largest regex on the deepest nesting path that can be moved
+    /// into a separate rule without exceeding the depth limit.
+    fn depth_splits(&self, cst: &Cst, regex: Regex) -> Vec<Span> {
+        let metrics = &self.metrics[&regex];
+        if metrics.depth <= self.limits.max_depth && !matches!(regex, Regex::Paren(_)) {
+            return vec![regex.span(cst)];
+        }
+        Self::operands(cst, regex)
+            .into_iter()
+            .max_by_key(|op| self.metrics[op].depth)
+            .map_or(vec![], |op| self.depth_splits(cst, op))
+    }
+
+    /// Suggests the alternation branches and repeated regexes with the most
+    /// alternatives that can be moved into separate rules.
+    fn alternative_splits(&self, cst: &Cst, regex: Regex) -> Vec<Span> {
+        let mut candidates = vec![];
+        let mut stack = Self::operands(cst, regex);
+        while let Some(op) = stack.pop() {
+            let alternatives = self.metrics[&op].alternatives;
+            if alternatives <= self.limits.max_alternatives && !matches!(op, Regex::Concat(_)) {
+                if alternatives > 1 {
+                    candidates.push((alternatives, op));
+                }
+            } else {
+                stack.extend(Self::operands(cst, op));
+            }
+        }
+        candidates.sort_by_key(|(alternatives, op)| (std::cmp::Reverse(*alternatives), *op));
+        candidates
+            .into_iter()
+            .take(3)
+            .map(|(_, op)| op.span(cst))
+            .collect()
+    }
+}
+
+/// Shortest token sequences for the regexes of a grammar.
+///
+/// These are used to construct example inputs for reports and diagnostics.
+#[derive(Default)]
+pub struct Samples<'a> {
+    /// Shortest token sequence derived from a regex.
+    pub yields: HashMap<NodeRef, Vec<TokenName<'a>>>,
+    /// Shortest token sequence leading from the start rule to a regex.
+    pub prefixes: HashMap<NodeRef, Vec<TokenName<'a>>>,
+}
+
+impl<'a> Samples<'a> {
+    pub fn new(cst: &'a Cst, sema: &SemanticData<'a>) -> Self {
+        let mut samples = Self::default();
+        if let Some(file) = File::cast(cst, NodeRef::ROOT) {
+            samples.calc_yields(cst, sema, file);
+            samples.calc_prefixes(cst, sema, file);
+        }
+        samples
+    }
+
+    /// Returns an example input that reaches the regex and continues with the token.
+    pub fn example(
+        &self,
+        regex: Regex,
+        token: Option<TokenName<'a>>,
+    ) -> Option<Vec<TokenName<'a>>> {
+        let mut example = self.prefixes.get(&regex.syntax())?.clone();
+        example.extend(token);
+        Some(example)
+    }
+
+    fn calc_yields(&mut self, cst: &'a Cst, sema: &SemanticData<'a>, file: File) {
+        // Iterates until no shorter yields are found
+        let mut change = true;
+        while change {
+            change = false;
+            for rule in file.rule_decls(cst) {
+                if let Some(regex) = rule.regex(cst) {
+                    self.calc_yield_regex(cst, sema, regex, &mut change);
+                }
+            }
+        }
+    }
+
+    fn calc_yield_regex(
+        &mut self,
+        cst: &'a Cst,
+        sema: &SemanticData<'a>,
+        regex: Regex,
+        change: &mut bool,
+    ) -> Option<Vec<TokenName<'a>>> {
+        let new = match regex {
+            Regex::Name(name) => {
+                let decl = sema.decl_bindings.get(&name.syntax());
+                if let Some(rule) = decl.and_then(|decl| RuleDecl::cast(cst, *decl)) {
+                    rule.regex(cst).map_or(Some(vec![]), |regex| {
+                        self.yields.get(&regex.syntax()).cloned()
+                    })
+                } else {
+                    decl.and_then(|decl| TokenDecl::cast(cst, *decl))
+                        .and_then(|token| token.name(cst))
+                        .map(|(name, _)| vec![TokenName(name)])
+                }
+            }
+            Regex::Symbol(sym) => sema
+                .decl_bindings
+                .get(&sym.syntax())
+                .and_then(|decl| TokenDecl::cast(cst, *decl))
+                .and_then(|token| token.name(cst))
+                .map(|(name, _)| vec![TokenName(name)]),
+            Regex::Wildcard(_) => sema.wildcard_tokens.first().map(|token| vec![*token]),
+            Regex::Concat(concat) => {
+                let mut res = Some(vec![]);
+                for op in concat.operands(cst) {
+                    let op_yield = self.calc_yield_regex(cst, sema, op, change);
+                    res = res.zip(op_yield).map(|(mut res, op_yield)| {
+                        res.extend(op_yield);
+                        res
+                    });
+                }
+                res
+            }
+            Regex::Alternation(alt) => {
+                let mut res: Option<Vec<TokenName>> = None;
+                for op in alt.operands(cst) {
+                    if let Some(op_yield) = self.calc_yield_regex(cst, sema, op, change) {
+                        if res.as_ref().map_or(true, |res| op_yield.len() < res.len()) {
+                            res = Some(op_yield);
+                        }
+                    }
+                }
+                res
+            }
+            Regex::Star(star) => {
+                if let Some(op) = star.operand(cst) {
+                    self.calc_yield_regex(cst, sema, op, change);
+                }
+                Some(vec![])
+            }
+            Regex::Optional(opt) => {
+                if let Some(op) = opt.operand(cst) {
+                    self.calc_yield_regex(cst, sema, op, change);
+                }
+                Some(vec![])
+            }
+            Regex::Plus(plus) => plus
+                .operand(cst)
+                .and_then(|op| self.calc_yield_regex(cst, sema, op, change)),
+            Regex::Bounded(bounded) => {
+                let min = bounded.bounds(cst).map_or(0, |(min, _)| min);
+                bounded
+                    .operand(cst)
+                    .and_then(|op| self.calc_yield_regex(cst, sema, op, change))
+                    .filter(|_| min <= MAX_REPETITION)
+                    .map(|op_yield| op_yield.repeat(min))
+            }
+            Regex::Separated(sep) => {
+                if let Some(separator) = sep.separator(cst) {
+                    self.calc_yield_regex(cst, sema, separator, change);
+                }
+                sep.operand(cst)
+                    .and_then(|op| self.calc_yield_regex(cst, sema, op, change))
+            }
+            Regex::Paren(paren) => paren
+                .inner(cst)
+                .and_then(|inner| self.calc_yield_regex(cst, sema, inner, change)),
+            Regex::Predicate(_)
+            | Regex::SyntacticPredicate(_)
+            | Regex::Action(_)
+            | Regex::Binding(_)
+            | Regex::OpenNode(_)
+            | Regex::CloseNode(_) => Some(vec![]),
+        };
+        if let Some(new) = new {
+            let old = self.yields.get(&regex.syntax());
+            if old.map_or(true, |old| new.len() < old.len()) {
+                self.yields.insert(regex.syntax(), new.clone());
+                *change = true;
+            }
+        }
+        self.yields.get(&regex.syntax()).cloned()
+    }
+
+    fn calc_prefixes(&mut self, cst: &'a Cst, sema: &SemanticData<'a>, file: File) {
+        if let Some(start_regex) = sema.start.and_then(|start| start.regex(cst)) {
+            self.prefixes.insert(start_regex.syntax(), vec![]);
+        }
+        // Iterates until no shorter prefixes are found
+        let mut change = true;
+        while change {
+            change = false;
+            for rule in file.rule_decls(cst) {
+                if let Some(regex) = rule.regex(cst) {
+                    if let Some(prefix) = self.prefixes.get(&regex.syntax()).cloned() {
+                        self.calc_prefix_regex(cst, sema, regex, prefix, &mut change);
+                    }
+                }
+            }
+        }
+    }
+
+    fn calc_prefix_regex(
+        &mut self,
+        cst: &'a Cst,
+        sema: &SemanticData<'a>,
+        regex: Regex,
+        prefix: Vec<TokenName<'a>>,
+        change: &mut bool,
+    ) {
+        let is_shorter = |samples: &Self, node: NodeRef, prefix: &Vec<TokenName>| {
+            samples
+                .prefixes
+                .get(&node)
+                .map_or(true, |old| prefix.len() < old.len())
+        };
+        if is_shorter(self, regex.syntax(), &prefix) {
+            self.prefixes.insert(regex.syntax(), prefix.clone());
+        }
+        match regex {
+            Regex::Name(name) => {
+                if let Some(rule_regex) = sema
+                    .decl_bindings
+                    .get(&name.syntax())
+                    .and_then(|decl| RuleDecl::cast(cst, *decl))
+                    .and_then(|rule| rule.regex(cst))
+                {
+                    if is_shorter(self, rule_regex.syntax(), &prefix) {
+                        self.prefixes.insert(rule_regex.syntax(), prefix);
+                        *change = true;
+                    }
+                }
+            }
+            Regex::Concat(concat) => {
+                let mut prefix = prefix;
+                for op in concat.operands(cst) {
+                    self.calc_prefix_regex(cst, sema, op, prefix.clone(), change);
+                    if let Some(op_yield) = self.yields.get(&op.syntax()) {
+                        prefix.extend(op_yield);
+                    } else {
+                        // unproductive regex
+                        break;
+                    }
+                }
+            }
+            Regex::Alternation(alt) => {
+                for op in alt.operands(cst) {
+                    self.calc_prefix_regex(cst, sema, op, prefix.clone(), change);
+                }
+            }
+            Regex::Star(star) => {
+                if let Some(op) = star.operand(cst) {
+                    self.calc_prefix_regex(cst, sema, op, prefix, change);
+                }
+            }
+            Regex::Plus(plus) => {
+                if let Some(op) = plus.operand(cst) {
+                    self.calc_prefix_regex(cst, sema, op, prefix, change);
+                }
+            }
+            Regex::Bounded(bounded) => {
+                if let Some(op) = bounded.operand(cst) {
+                    self.calc_prefix_regex(cst, sema, op, prefix, change);
+                }
+            }
+            Regex::Separated(sep) => {
+                if let Some(op) = sep.operand(cst) {
+                    self.calc_prefix_regex(cst, sema, op, prefix.clone(), change);
+                    // the separator is reached after the first operand
+                    if let (Some(separator), Some(op_yield)) =
+                        (sep.separator(cst), self.yields.get(&op.syntax()))
+                    {
+                        let mut prefix = prefix;
+                        prefix.extend(op_yield);
+                        self.calc_prefix_regex(cst, sema, separator, prefix, change);
+                    }
+                }
+            }
+            Regex::Optional(opt) => {
+                if let Some(op) = opt.operand(cst) {
+                    self.calc_prefix_regex(cst, sema, op, prefix, change);
+                }
+            }
+            Regex::Paren(paren) => {
+                if let Some(inner) = paren.inner(cst) {
+                    self.calc_prefix_regex(cst, sema, inner, prefix, change);
+                }
+            }
+            Regex::Symbol(_)
+            | Regex::Wildcard(_)
             | Regex::Predicate(_)
+            | Regex::SyntacticPredicate(_)
             | Regex::Action(_)
             | Regex::Binding(_)
             | Regex::OpenNode(_)
@@ -0,0 +1,485 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use codespan_reporting::diagnostic::{LabelStyle, Severity};
+use codespan_reporting::files::{Files, Location, SimpleFiles};
+use logos::Logos;
+
+use super::ast::*;
+use super::diag::*;
+use super::parser::*;
+use super::sema::snake_to_pascal_case;
+
+/// Source of a grammar, which consists of the main file and all files that
+/// are transitively imported with `import` declarations.
+///
+/// The files are merged into a single text that is parsed as one grammar.
+/// Diagnostics for the merged text are mapped back to the individual files.
+pub struct GrammarSource {
+    text: String,
+    files: SimpleFiles<String, String>,
+    /// Start offset of each file in the merged text.
+    offsets: Vec<usize>,
+    paths: Vec<PathBuf>,
+}
+
+impl GrammarSource {
+    /// Loads the grammar at `path` and the files it imports.
+    ///
+    /// Import paths are relative to the importing file, and each file is only
+    /// included once. Imports that cannot be read are reported as diagnostics.
+    pub fn load(path: &str, diags: &mut Vec<Diagnostic>) -> std::io::Result<Self> {
+        let mut source = Self::new();
+        let mut visited = HashSet::new();
+        let main = PathBuf::from(path);
+        visited.insert(main.canonicalize()?);
+        source.add(path.to_string(), main, std::fs::read_to_string(path)?);
+
+        let mut index = 0;
+        while index < source.paths.len() {
+            let offset = source.offsets[index];
+            let file = source.files.get(index).unwrap();
+            let text = file.source().clone();
+            let dir = source.paths[index]
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+            for (import, span) in Self::imports(&text) {
+                let span = span.start + offset..span.end + offset;
+                let path = dir.join(import);
+                let result = path
+                    .canonicalize()
+                    .and_then(|canonical| Ok((canonical, std::fs::read_to_string(&path)?)));
+                match result {
+                    Ok((canonical, text)) => {
+                        if visited.insert(canonical) {
+                            source.add(path.to_string_lossy().into_owned(), path, text);
+                        }
+                    }
+                    Err(err) => diags.push(Diagnostic::unresolved_import(
+                        &span,
+                        import,
+                        &err.to_string(),
+                    )),
+                }
+            }
+            index += 1;
+        }
+        source.add_implicit_tokens(diags);
+        Ok(source)
+    }
+
+    fn new() -> Self {
+        Self {
+            text: String::new(),
+            files: SimpleFiles::new(),
+            offsets: vec![],
+            paths: vec![],
+        }
+    }
+
+    fn add(&mut self, name: String, path: PathBuf, text: String) {
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+        self.offsets.push(self.text.len());
+        self.text.push_str(&text);
+        self.files.add(name, text);
+        self.paths.push(path);
+    }
+
+    /// Adds a token list for the string literals in rules that are not the
+    /// symbol of a declared token.
+    ///
+    /// The name of each token is derived from its symbol (e.g. `LParen` for
+    /// `'('`). Literals whose name cannot be derived are left undefined, and
+    /// names that are already used by another token are reported as
+    /// collisions.
+    fn add_implicit_tokens(&mut self, diags: &mut Vec<Diagnostic>) {
+        let text = &self.text;
+        let (tokens, ranges) = tokenize(Token::lexer(text), &mut vec![]);
+        let cst = Parser::parse(text, tokens, ranges, &mut vec![]);
+        let Some(file) = File::cast(&cst, NodeRef::ROOT) else {
+            return;
+        };
+        let mut symbols = HashSet::new();
+        let mut names = HashMap::new();
+        for decl in file.token_decls(&cst) {
+            if let Some((symbol, _)) = decl.symbol(&cst) {
+                symbols.insert(symbol);
+            }
+            if let Some((name, span)) = decl.name(&cst) {
+                names.insert(name.to_string(), span);
+            }
+        }
+        let mut literals = vec![];
+        for decl in file.rule_decls(&cst) {
+            let mut stack = vec![decl.syntax()];
+            while let Some(node) = stack.pop() {
+                let children: Vec<_> = cst.children(node).collect();
+                stack.extend(children.into_iter().rev());
+                if let Some((symbol, span)) = Symbol::cast(&cst, node).and_then(|s| s.value(&cst)) {
+                    if symbols.insert(symbol) {
+                        literals.push((symbol, span));
+                    }
+                }
+            }
+        }
+        let mut list = String::new();
+        for (symbol, span) in literals {
+            let Some(name) = Self::implicit_token_name(symbol) else {
+                continue;
+            };
+            if let Some(old_span) = names.get(&name) {
+                diags.push(Diagnostic::implicit_token_collision(&span, &name, old_span));
+                continue;
+            }
+            list += &format!(" {name}={symbol}");
+            names.insert(name, span);
+        }
+        if !list.is_empty() {
+            let text = format!("token{list};\n");
+            self.text.push('\n');
+            self.offsets.push(self.text.len());
+            self.text.push_str(&text);
+            self.files.add("<implicit tokens>".to_string(), text);
+        }
+    }
+
+    /// Name of the implicit token for a string literal `symbol`.
+    ///
+    /// Identifiers are converted to Pascal case and punctuation characters
+    /// are named individually, so `'if'` is `If` and `'->'` is `MinusGt`.
+    pub(crate) fn implicit_token_name(symbol: &str) -> Option<String> {
+        let value = symbol[1..symbol.len() - 1]
+            .replace("\\'", "'")
+            .replace("\\\\", "\\");
+        if value.starts_with('<') && value.ends_with('>') {
+            return None;
+        }
+        let mut name = String::new();
+        let mut word = String::new();
+        for c in value.chars().chain(std::iter::once(' ')) {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                word.push(c);
+                continue;
+            }
+            if !word.is_empty() {
+                name += match word.trim_matches('_') {
+                    "" => "Underscore".to_string(),
+                    word => snake_to_pascal_case(word),
+                }
+                .as_str();
+                word.clear();
+            }
+            name += match c {
+                ' ' => "",
+                '(' => "LParen",
+                ')' => "RParen",
+                '[' => "LBrak",
+                ']' => "RBrak",
+                '{' => "LBrace",
+                '}' => "RBrace",
+                '<' => "Lt",
+                '>' => "Gt",
+                '=' => "Equal",
+                '+' => "Plus",
+                '-' => "Minus",
+                '*' => "Star",
+                '/' => "Slash",
+                '%' => "Percent",
+                '&' => "Amp",
+                '|' => "Pipe",
+                '^' => "Hat",
+                '~' => "Tilde",
+                '!' => "Excl",
+                '?' => "Quest",
+                '.' => "Dot",
+                ',' => "Comma",
+                ':' => "Colon",
+                ';' => "Semi",
+                '#' => "Hash",
+                '@' => "At",
+                '$' => "Dollar",
+                '\'' => "Quote",
+                '"' => "DoubleQuote",
+                '`' => "Backtick",
+                '\\' => "Backslash",
+                _ => return None,
+            };
+        }
+        name.starts_with(|c: char| c.is_ascii_uppercase())
+            .then_some(name)
+    }
+
+    /// Paths and spans of the import declarations in `text`.
+    fn imports(text: &str) -> Vec<(&str, Span)> {
+        let (tokens, ranges) = tokenize(Token::lexer(text), &mut vec![]);
+        let cst = Parser::parse(text, tokens, ranges, &mut vec![]);
+        let Some(file) = File::cast(&cst, NodeRef::ROOT) else {
+            return vec![];
+        };
+        file.import_decls(&cst)
+            .filter_map(|decl| decl.path(&cst))
+            .map(|(_, span)| (&text[span.start + 1..span.end - 1], span))
+            .collect()
+    }
+
+    /// Merged text of all files.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Paths of all files, starting with the main file.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    pub fn files(&self) -> &SimpleFiles<String, String> {
+        &self.files
+    }
+
+    /// File and file local offset of an `offset` in the merged text.
+    fn locate(&self, offset: usize) -> (usize, usize) {
+        let id = self.offsets.partition_point(|start| *start <= offset) - 1;
+        let len = self.files.get(id).unwrap().source().len();
+        (id, (offset - self.offsets[id]).min(len))
+    }
+
+    /// File and file local span of a `span` in the merged text.
+    fn locate_span(&self, span: &Span) -> (usize, Span) {
+        let (id, start) = self.locate(span.start);
+        let len = self.files.get(id).unwrap().source().len();
+        let end = (span.end - self.offsets[id]).clamp(start, len);
+        (id, start..end)
+    }
+
+    /// Name of the file that contains the `offset` in the merged text.
+    pub fn file_name(&self, offset: usize) -> &str {
+        let (id, _) = self.locate(offset);
+        self.files.get(id).unwrap().name()
+    }
+
+    /// Location of an `offset` in the merged text.
+    pub fn location(&self, offset: usize) -> Location {
+        let (id, offset) = self.locate(offset);
+        self.files.location(id, offset).unwrap()
+    }
+
+    /// Maps the labels of a diagnostic for the merged text to the files.
+    pub fn diagnostic(
+        &self,
+        diag: &Diagnostic,
+    ) -> codespan_reporting::diagnostic::Diagnostic<usize> {
+        let labels = diag
+            .labels
+            .iter()
+            .map(|label| {
+                let (id, range) = self.locate_span(&label.range);
+                codespan_reporting::diagnostic::Label {
+                    style: label.style,
+                    file_id: id,
+                    range,
+                    message: label.message.clone(),
+                }
+            })
+            .collect();
+        codespan_reporting::diagnostic::Diagnostic {
+            severity: diag.severity,
+            code: diag.code.clone(),
+            message: diag.message.clone(),
+            labels,
+            notes: diag.notes.clone(),
+        }
+    }
+
+    /// Formats a diagnostic as a JSON object on a single line.
+    ///
+    /// The location is the primary label of the diagnostic, where the byte
+    /// range is relative to the file. Diagnostics without a label refer to
+    /// the main file. The `fixes` of the diagnostic are listed with their
+    /// edits.
+    pub fn json(&self, diag: &Diagnostic, fixes: &[&Fix]) -> String {
+        let diag = self.diagnostic(diag);
+        let label = diag
+            .labels
+            .iter()
+            .find(|label| label.style == LabelStyle::Primary);
+        let file = label.map_or(0, |label| label.file_id);
+        let mut json = format!(
+            "{{\"file\":{}",
+            json_string(self.files.get(file).unwrap().name())
+        );
+        if let Some(label) = label {
+            let location = self.files.location(file, label.range.start).unwrap();
+            json += &format!(
+                ",\"start\":{},\"end\":{},\"line\":{},\"column\":{}",
+                label.range.start, label.range.end, location.line_number, location.column_number
+            );
+        }
+        let severity = match diag.severity {
+            Severity::Bug => "bug",
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+            Severity::Help => "help",
+        };
+        json += &format!(",\"severity\":\"{severity}\"");
+        let code = diag.code.as_deref().map_or("null".to_string(), json_string);
+        json += &format!(",\"code\":{code}");
+        json += &format!(",\"message\":{}", json_string(&diag.message));
+        let notes: Vec<_> = diag.notes.iter().map(|note| json_string(note)).collect();
+        json += &format!(",\"notes\":[{}]", notes.join(","));
+        let fixes: Vec<_> = fixes
+            .iter()
+            .map(|fix| {
+                let edits: Vec<_> = fix
+                    .edits
+                    .iter()
+                    .map(|(span, text)| {
+                        let (id, span) = self.locate_span(span);
+                        format!(
+                            "{{\"file\":{},\"start\":{},\"end\":{},\"text\":{}}}",
+                            json_string(self.files.get(id).unwrap().name()),
+                            span.start,
+                            span.end,
+                            json_string(text)
+                        )
+                    })
+                    .collect();
+                format!(
+                    "{{\"message\":{},\"applicability\":\"{}\",\"edits\":[{}]}}",
+                    json_string(&fix.message),
+                    fix.applicability.name(),
+                    edits.join(",")
+                )
+            })
+            .collect();
+        json += &format!(",\"fixes\":[{}]}}", fixes.join(","));
+        json
+    }
+
+    /// Formats a diagnostic as a SARIF result object.
+    ///
+    /// The primary label is the location of the result and the secondary
+    /// labels are its related locations. Notes are appended to the message,
+    /// and the `fixes` are replacements of byte regions.
+    pub fn sarif(&self, diag: &Diagnostic, fixes: &[&Fix]) -> String {
+        let diag = self.diagnostic(diag);
+        let location = |label: &codespan_reporting::diagnostic::Label<usize>| {
+            let start = self
+                .files
+                .location(label.file_id, label.range.start)
+                .unwrap();
+            let end = self.files.location(label.file_id, label.range.end).unwrap();
+            let mut json = format!(
+                "{{\"physicalLocation\":{{\"artifactLocation\":{{\"uri\":{}}},\"region\":{{\
+                 \"startLine\":{},\"startColumn\":{},\"endLine\":{},\"endColumn\":{}}}}}",
+                json_string(self.files.get(label.file_id).unwrap().name()),
+                start.line_number,
+                start.column_number,
+                end.line_number,
+                end.column_number
+            );
+            if label.style == LabelStyle::Secondary && !label.message.is_empty() {
+                json += &format!(",\"message\":{{\"text\":{}}}", json_string(&label.message));
+            }
+            json.push('}');
+            json
+        };
+        let mut json = "{".to_string();
+        if let Some(code) = &diag.code {
+            json += &format!("\"ruleId\":{},", json_string(code));
+        }
+        let level = match diag.severity {
+            Severity::Bug | Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note | Severity::Help => "note",
+        };
+        let mut message = diag.message.clone();
+        for note in diag.notes.iter() {
+            message.push('\n');
+            message.push_str(note);
+        }
+        json += &format!(
+            "\"level\":\"{level}\",\"message\":{{\"text\":{}}}",
+            json_string(&message)
+        );
+        let locations: Vec<_> = diag
+            .labels
+            .iter()
+            .filter(|label| label.style == LabelStyle::Primary)
+            .take(1)
+            .map(location)
+            .collect();
+        json += &format!(",\"locations\":[{}]", locations.join(","));
+        let related: Vec<_> = diag
+            .labels
+            .iter()
+            .filter(|label| label.style == LabelStyle::Secondary)
+            .map(location)
+            .collect();
+        if !related.is_empty() {
+            json += &format!(",\"relatedLocations\":[{}]", related.join(","));
+        }
+        let fixes: Vec<_> = fixes
+            .iter()
+            .map(|fix| {
+                let changes: Vec<_> = fix
+                    .edits
+                    .iter()
+                    .map(|(span, text)| {
+                        let (id, span) = self.locate_span(span);
+                        format!(
+                            "{{\"artifactLocation\":{{\"uri\":{}}},\"replacements\":[{{\
+                             \"deletedRegion\":{{\"byteOffset\":{},\"byteLength\":{}}},\
+                             \"insertedContent\":{{\"text\":{}}}}}]}}",
+                            json_string(self.files.get(id).unwrap().name()),
+                            span.start,
+                            span.len(),
+                            json_string(text)
+                        )
+                    })
+                    .collect();
+                format!(
+                    "{{\"description\":{{\"text\":{}}},\"artifactChanges\":[{}]}}",
+                    json_string(&fix.message),
+                    changes.join(",")
+                )
+            })
+            .collect();
+        if !fixes.is_empty() {
+            json += &format!(",\"fixes\":[{}]", fixes.join(","));
+        }
+        json.push('}');
+        json
+    }
+}
+
+/// Formats SARIF result objects as a SARIF log with a single run of lelwel
+/// in the `version`.
+pub fn sarif_log(results: &[String], version: &str) -> String {
+    format!(
+        "{{\"version\":\"2.1.0\",\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",\
+         \"runs\":[{{\"tool\":{{\"driver\":{{\"name\":\"lelwel\",\"version\":\"{version}\",\
+         \"informationUri\":\"https://github.com/0x2a-42/lelwel\"}}}},\"results\":[{}]}}]}}",
+        results.join(",")
+    )
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = "\"".to_string();
+    for c in value.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            '\r' => json += "\\r",
+            '\t' => json += "\\t",
+            c if c.is_control() => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
@@ -1,8 +1,10 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use tower_lsp::lsp_types::*;
 
 use super::lookup::*;
 use crate::frontend::ast::{AstNode, File, Named};
-use crate::{Cst, Node, NodeRef, Rule, Token};
+use crate::{Cst, Node, NodeRef, Rule, SemanticData, Token};
 
 fn add_top_level_items(cst: &Cst, file: File, items: &mut Vec<CompletionItem>) {
     if file.start_decls(cst).count() == 0 {
@@ -29,6 +31,39 @@ fn add_top_level_items(cst: &Cst, file: File, items: &mut Vec<CompletionItem>) {
         insert_text_format: Some(InsertTextFormat::SNIPPET),
         ..Default::default()
     });
+    for (keyword, placeholder) in [
+        ("skip", "TokenName"),
+        ("insert", "TokenName"),
+        ("sync", "TokenName"),
+        ("token_derive", "Hash"),
+        ("import", "'file.llw'"),
+    ] {
+        items.push(CompletionItem {
+            label: format!("{keyword} {placeholder};"),
+            kind: Some(CompletionItemKind::KEYWORD),
+            insert_text: Some(format!("{keyword} ${{1:{placeholder}}};")),
+            insert_text_format: Some(InsertTextFormat::SNIPPET),
+            ..Default::default()
+        });
+    }
+    if file.lookahead_decls(cst).count() == 0 {
+        items.push(CompletionItem {
+            label: "lookahead 2;".to_string(),
+            kind: Some(CompletionItemKind::KEYWORD),
+            insert_text: Some("lookahead ${1:2};".to_string()),
+            insert_text_format: Some(InsertTextFormat::SNIPPET),
+            ..Default::default()
+        });
+    }
+    if file.max_errors_decls(cst).count() == 0 {
+        items.push(CompletionItem {
+            label: "max_errors 10;".to_string(),
+            kind: Some(CompletionItemKind::KEYWORD),
+            insert_text: Some("max_errors ${1:10};".to_string()),
+            insert_text_format: Some(InsertTextFormat::SNIPPET),
+            ..Default::default()
+        });
+    }
     items.push(CompletionItem {
         label: "rule_name: regex;".to_string(),
         insert_text: Some("${1:rule_name}: ${2:regex};".to_string()),
@@ -37,9 +72,53 @@ fn add_top_level_items(cst: &Cst, file: File, items: &mut Vec<CompletionItem>) {
     });
 }
 
+/// Sort texts of the rule and token declarations, which are ranked by the
+/// number of top level declarations between them and the position, and then
+/// by how often they are used.
+fn rank_declarations(
+    cst: &Cst,
+    sema: &SemanticData,
+    file: File,
+    pos: usize,
+) -> HashMap<NodeRef, String> {
+    let top_level: Vec<_> = cst
+        .children(NodeRef::ROOT)
+        .filter_map(|node| {
+            cst.get_span(node)
+                .filter(|_| matches!(cst.get(node), Node::Rule(..)))
+        })
+        .collect();
+    let index = |pos: usize| top_level.partition_point(|span| span.end <= pos);
+    let current = index(pos);
+    let mut uses: HashMap<NodeRef, usize> = HashMap::new();
+    for decl in sema.decl_bindings.values() {
+        *uses.entry(*decl).or_default() += 1;
+    }
+    let mut decls: Vec<_> = file
+        .rule_decls(cst)
+        .map(|rule| rule.syntax())
+        .chain(file.token_decls(cst).map(|token| token.syntax()))
+        .filter_map(|decl| {
+            let distance = index(cst.get_span(decl)?.start).abs_diff(current);
+            Some((
+                distance,
+                Reverse(uses.get(&decl).copied().unwrap_or_default()),
+                decl,
+            ))
+        })
+        .collect();
+    decls.sort();
+    decls
+        .into_iter()
+        .enumerate()
+        .map(|(rank, (_, _, decl))| (decl, format!("{rank:04}")))
+        .collect()
+}
+
 fn add_reference_items(
     cst: &Cst,
     file: File,
+    ranks: &HashMap<NodeRef, String>,
     items: &mut Vec<CompletionItem>,
     with_rules: bool,
     with_tokens: bool,
@@ -53,6 +132,7 @@ fn add_reference_items(
                     ..Default::default()
                 }),
                 kind: Some(CompletionItemKind::REFERENCE),
+                sort_text: ranks.get(&rule.syntax()).cloned(),
                 ..Default::default()
             });
         }
@@ -66,6 +146,7 @@ fn add_reference_items(
                     ..Default::default()
                 }),
                 kind: Some(CompletionItemKind::REFERENCE),
+                sort_text: ranks.get(&token.syntax()).cloned(),
                 ..Default::default()
             });
             if let Some(symbol) = token.symbol(cst) {
@@ -76,6 +157,7 @@ fn add_reference_items(
                         ..Default::default()
                     }),
                     kind: Some(CompletionItemKind::REFERENCE),
+                    sort_text: ranks.get(&token.syntax()).cloned(),
                     ..Default::default()
                 });
             }
@@ -90,6 +172,7 @@ fn add_reference_items(
             }),
             kind: Some(CompletionItemKind::OPERATOR),
             insert_text: Some("?${1:1}".to_string()),
+            sort_text: Some("~".to_string()),
             insert_text_format: Some(InsertTextFormat::SNIPPET),
             ..Default::default()
         });
@@ -101,16 +184,42 @@ fn add_reference_items(
             }),
             kind: Some(CompletionItemKind::OPERATOR),
             insert_text: Some("#${1:1}".to_string()),
+            sort_text: Some("~".to_string()),
+            insert_text_format: Some(InsertTextFormat::SNIPPET),
+            ..Default::default()
+        });
+        items.push(CompletionItem {
+            label: "=> { code }".to_string(),
+            label_details: Some(CompletionItemLabelDetails {
+                description: Some("Inline Action".to_string()),
+                ..Default::default()
+            }),
+            kind: Some(CompletionItemKind::OPERATOR),
+            insert_text: Some("=> { ${1:code} }".to_string()),
+            sort_text: Some("~".to_string()),
+            insert_text_format: Some(InsertTextFormat::SNIPPET),
+            ..Default::default()
+        });
+        items.push(CompletionItem {
+            label: "#1:rust { code }".to_string(),
+            label_details: Some(CompletionItemLabelDetails {
+                description: Some("Tagged Action".to_string()),
+                ..Default::default()
+            }),
+            kind: Some(CompletionItemKind::OPERATOR),
+            insert_text: Some("#${1:1}:${2:rust} { ${3:code} }".to_string()),
+            sort_text: Some("~".to_string()),
             insert_text_format: Some(InsertTextFormat::SNIPPET),
             ..Default::default()
         });
     }
 }
 
-pub fn completion(cst: &Cst, pos: usize) -> Option<CompletionResponse> {
+pub fn completion(cst: &Cst, sema: &SemanticData, pos: usize) -> Option<CompletionResponse> {
     let mut items = vec![];
 
     let file = File::cast(cst, NodeRef::ROOT)?;
+    let ranks = rank_declarations(cst, sema, file, pos);
     if let Some(node) = lookup_node(cst, NodeRef::ROOT, pos) {
         match cst.get(node) {
             Node::Rule(
@@ -122,7 +231,7 @@ pub fn completion(cst: &Cst, pos: usize) -> Option<CompletionResponse> {
                 | Rule::Atomic,
                 _,
             ) => {
-                add_reference_items(cst, file, &mut items, true, true);
+                add_reference_items(cst, file, &ranks, &mut items, true, true);
             }
             Node::Rule(Rule::RuleDecl, _) => {
                 if let Some((_, range)) = cst
@@ -130,19 +239,19 @@ pub fn completion(cst: &Cst, pos: usize) -> Option<CompletionResponse> {
                     .find_map(|n| cst.get_token(n, Token::Colon))
                 {
                     if pos > range.start {
-                        add_reference_items(cst, file, &mut items, true, true);
+                        add_reference_items(cst, file, &ranks, &mut items, true, true);
                     }
                 } else {
                     add_top_level_items(cst, file, &mut items);
                 }
             }
-            Node::Rule(Rule::SkipDecl | Rule::RightDecl, _) => {
-                add_reference_items(cst, file, &mut items, false, true);
+            Node::Rule(Rule::SkipDecl | Rule::RightDecl | Rule::InsertDecl | Rule::SyncDecl, _) => {
+                add_reference_items(cst, file, &ranks, &mut items, false, true);
             }
             Node::Rule(Rule::StartDecl, _) => {
-                add_reference_items(cst, file, &mut items, true, false);
+                add_reference_items(cst, file, &ranks, &mut items, true, false);
             }
-            Node::Rule(Rule::TokenList | Rule::TokenDecl, _) => {}
+            Node::Rule(Rule::TokenList | Rule::TokenDecl | Rule::TokenDeriveDecl, _) => {}
             _ => {
                 add_top_level_items(cst, file, &mut items);
             }
@@ -1,10 +1,62 @@
-use crate::frontend::ast::{AstNode, Regex, RuleDecl};
-use crate::{Cst, NodeRef, Rule, SemanticData, Token};
+use crate::frontend::ast::{AstNode, Named, Regex, RuleDecl};
+use crate::frontend::diag::{
+    LL1_CONFLICT_ALT, LL1_CONFLICT_LEFT_REC, LL1_CONFLICT_OPT, LL1_CONFLICT_REP,
+};
+use crate::frontend::parser::Diagnostic;
+use crate::{Cst, NodeRef, Rule, SemanticData};
 use logos::Span;
 
 use super::lookup::*;
 
-pub fn hover(cst: &Cst, sema: &SemanticData, pos: usize) -> Option<(String, Span)> {
+/// Describes whether the decision at `regex` is conflict-free, where conflicts
+/// are found in the LL(1) conflict diagnostics.
+fn decision(cst: &Cst, sema: &SemanticData, diags: &[Diagnostic], regex: Regex) -> String {
+    let spans: Vec<Span> = match regex {
+        Regex::Alternation(alternation) => {
+            alternation.operands(cst).map(|op| op.span(cst)).collect()
+        }
+        Regex::Optional(_)
+        | Regex::Star(_)
+        | Regex::Plus(_)
+        | Regex::Separated(_)
+        | Regex::Bounded(_) => {
+            vec![regex.span(cst)]
+        }
+        _ => return String::new(),
+    };
+    // the conflicts are only checked if there are no other errors
+    if !sema.predict_sets.contains_key(&regex.syntax()) {
+        return String::new();
+    }
+    let conflict = diags.iter().find(|diag| {
+        matches!(
+            diag.code.as_deref(),
+            Some(LL1_CONFLICT_ALT | LL1_CONFLICT_LEFT_REC | LL1_CONFLICT_REP | LL1_CONFLICT_OPT)
+        ) && diag
+            .labels
+            .first()
+            .is_some_and(|label| spans.contains(&label.range))
+    });
+    match conflict {
+        Some(diag) => {
+            let sets = diag
+                .labels
+                .iter()
+                .filter(|label| !label.message.is_empty())
+                .map(|label| label.message.as_str())
+                .collect::<Vec<_>>();
+            format!("**Decision:** {} {}\n", diag.message, sets.join(", "))
+        }
+        None => "**Decision:** conflict-free\n".to_string(),
+    }
+}
+
+pub fn hover(
+    cst: &Cst,
+    sema: &SemanticData,
+    diags: &[Diagnostic],
+    pos: usize,
+) -> Option<(String, Span)> {
     let node = lookup_node(cst, NodeRef::ROOT, pos)?;
     let span = cst.get_span(node)?;
 
@@ -21,15 +73,16 @@ pub fn hover(cst: &Cst, sema: &SemanticData, pos: usize) -> Option<(String, Span
             .predict_sets
             .get(&regex.syntax())
             .map_or("{}".to_string(), |s| format!("{s:?}"));
+        let decision = decision(cst, sema, diags, regex);
 
         match regex {
-            Regex::Star(_) | Regex::Plus(_) => {
+            Regex::Star(_) | Regex::Plus(_) | Regex::Separated(_) | Regex::Bounded(_) => {
                 let recovery = &sema
                     .recovery_sets
                     .get(&regex.syntax())
                     .map_or("{}".to_string(), |s| format!("{s:?}"));
                 Some((format!(
-                    "**First:** {first}\n**Follow:** {follow}\n**Predict:** {predict}\n**Recovery:** {recovery}\n"
+                    "**First:** {first}\n**Follow:** {follow}\n**Predict:** {predict}\n**Recovery:** {recovery}\n{decision}"
                 ), span))
             }
             Regex::Name(_) | Regex::Symbol(_) => {
@@ -42,33 +95,37 @@ pub fn hover(cst: &Cst, sema: &SemanticData, pos: usize) -> Option<(String, Span
                             r == Rule::TokenList || r == Rule::RuleDecl
                         })
                     });
-                let mut comment_nodes = vec![];
-                if let Some(comment_attached_node) = comment_attached_node {
-                    for i in 1.. {
-                        if let Some(node) = cst.get_token(
-                            NodeRef(comment_attached_node.0.saturating_sub(i)),
-                            Token::DocComment,
-                        ) {
-                            comment_nodes.push(node);
-                        } else {
-                            break;
-                        }
-                    }
-                }
+                let comment_nodes =
+                    comment_attached_node.map_or(vec![], |node| cst.doc_comments(node));
                 let mut comment = String::new();
-                for (val, _) in comment_nodes.iter().rev() {
+                for val in comment_nodes.iter() {
                     comment.push_str(val.strip_prefix("///").unwrap().trim_start());
                 }
                 if !comment.is_empty() {
                     comment.push_str("---\n")
                 }
+                // follow set of the referenced rule in all its uses
+                let rule_follow = sema
+                    .decl_bindings
+                    .get(&node)
+                    .and_then(|decl| RuleDecl::cast(cst, *decl))
+                    .and_then(|rule| Some((rule.name(cst)?.0, rule.regex(cst)?)))
+                    .map_or(String::new(), |(name, regex)| {
+                        let follow = sema
+                            .follow_sets
+                            .get(&regex.syntax())
+                            .map_or("{}".to_string(), |s| format!("{s:?}"));
+                        format!("**Follow of `{name}`:** {follow}\n")
+                    });
                 Some((
-                    format!("{comment}**First:** {first}\n**Follow:** {follow}\n**Predict:** {predict}\n"),
+                    format!("{comment}**First:** {first}\n**Follow:** {follow}\n{rule_follow}**Predict:** {predict}\n"),
                     span,
                 ))
             }
             _ => Some((
-                format!("**First:** {first}\n**Follow:** {follow}\n**Predict:** {predict}\n"),
+                format!(
+                    "**First:** {first}\n**Follow:** {follow}\n**Predict:** {predict}\n{decision}"
+                ),
                 span,
             )),
         }
@@ -1,6 +1,9 @@
 use codespan_reporting::files::{Files, SimpleFile};
 use tower_lsp::lsp_types::{Location, Url};
 
+use std::collections::HashMap;
+
+use crate::frontend::ast::{AstNode, File, Named, RuleDecl, TokenDecl};
 use crate::frontend::parser::Span;
 use crate::{Cst, Node, NodeRef, Rule, SemanticData};
 
@@ -11,10 +14,7 @@ fn contains(span: &Span, pos: usize) -> bool {
 pub fn lookup_node(cst: &Cst, node: NodeRef, pos: usize) -> Option<NodeRef> {
     cst.children(node)
         .filter(|node| matches!(cst.get(*node), Node::Rule(..)))
-        .find(|node| {
-            cst.get_span(*node)
-                .map_or(false, |span| contains(&span, pos))
-        })
+        .find(|node| cst.get_span(*node).is_some_and(|span| contains(&span, pos)))
         .and_then(|node| lookup_node(cst, node, pos).or(Some(node)))
 }
 pub fn find_node<P: Fn(Rule) -> bool>(
@@ -31,13 +31,102 @@ pub fn find_node<P: Fn(Rule) -> bool>(
                 false
             }
         })
-        .find(|node| {
-            cst.get_span(*node)
-                .map_or(false, |span| contains(&span, pos))
-        })
+        .find(|node| cst.get_span(*node).is_some_and(|span| contains(&span, pos)))
         .and_then(|node| find_node(cst, node, pos, pred).or(Some(node)))
 }
 
+/// Declarations referenced by names in directives, which are not bound by the
+/// semantic pass.
+fn directive_references<'a>(cst: &'a Cst) -> Vec<(Span, NodeRef)> {
+    let Some(file) = File::cast(cst, NodeRef::ROOT) else {
+        return vec![];
+    };
+    let mut decls = HashMap::new();
+    for rule in file.rule_decls(cst) {
+        if let Some((name, _)) = rule.name(cst) {
+            decls.insert(name, rule.syntax());
+        }
+    }
+    for token in file.token_decls(cst) {
+        for (name, _) in [token.name(cst), token.symbol(cst)].into_iter().flatten() {
+            decls.insert(name, token.syntax());
+        }
+    }
+    let mut names = vec![];
+    let mut add = |(name, span): (&'a str, Span)| names.push((name, span));
+    for decl in file.start_decls(cst) {
+        if let Some(name) = decl.rule_name(cst) {
+            add(name);
+        }
+    }
+    for decl in file.right_decls(cst) {
+        decl.token_names(cst, &mut add);
+    }
+    for decl in file.skip_decls(cst) {
+        decl.token_names(cst, &mut add);
+    }
+    for decl in file.insert_decls(cst) {
+        decl.token_names(cst, &mut add);
+    }
+    for decl in file.sync_decls(cst) {
+        decl.token_names(cst, &mut add);
+    }
+    names
+        .into_iter()
+        .filter_map(|(name, span)| decls.get(name).map(|decl| (span, *decl)))
+        .collect()
+}
+
+/// Declaration that is referenced or declared at the position.
+fn lookup_declaration(
+    cst: &Cst,
+    sema: &SemanticData,
+    node: NodeRef,
+    pos: usize,
+) -> Option<NodeRef> {
+    if let Some(decl) = sema.decl_bindings.get(&node) {
+        return Some(*decl);
+    }
+    if RuleDecl::cast(cst, node).is_some() || TokenDecl::cast(cst, node).is_some() {
+        return Some(node);
+    }
+    directive_references(cst)
+        .into_iter()
+        .find(|(span, _)| contains(span, pos))
+        .map(|(_, decl)| decl)
+}
+
+/// Actions or predicates with the same number in the same rule, which all
+/// refer to the same function of the parser.
+fn lookup_numbered(sema: &SemanticData, node: NodeRef) -> Vec<NodeRef> {
+    if let Some(predicate) = sema.predicates.get(&node) {
+        return sema
+            .predicates
+            .iter()
+            .filter_map(|(node, other)| (other == predicate).then_some(*node))
+            .collect();
+    }
+    let action = sema.actions.get(&node).copied().or_else(|| {
+        sema.tagged_actions
+            .get(&node)
+            .map(|(rule, number, _)| (*rule, *number))
+    });
+    let Some(action) = action else {
+        return vec![];
+    };
+    sema.actions
+        .iter()
+        .filter_map(|(node, other)| (*other == action).then_some(*node))
+        .chain(
+            sema.tagged_actions
+                .iter()
+                .filter_map(|(node, (rule, number, _))| {
+                    ((*rule, *number) == action).then_some(*node)
+                }),
+        )
+        .collect()
+}
+
 pub fn lookup_definition(
     cst: &Cst,
     sema: &SemanticData,
@@ -52,9 +141,8 @@ pub fn lookup_definition(
         } else if let Some((rule_name, number)) = sema.actions.get(&node) {
             lookup_parser_impl_definition("action", rule_name, number, parser_path)
         } else {
-            sema.decl_bindings
-                .get(&node)
-                .and_then(|node| cst.get_span(*node))
+            lookup_declaration(cst, sema, node, pos)
+                .and_then(|node| cst.get_span(node))
                 .map(|span| Location {
                     uri: uri.clone(),
                     range: super::compat::span_to_range(file, &span),
@@ -63,25 +151,41 @@ pub fn lookup_definition(
     })
 }
 
-pub fn lookup_references(
-    cst: &Cst,
-    sema: &SemanticData,
-    pos: usize,
-    with_def: bool,
-) -> Vec<NodeRef> {
-    if let Some(def) = lookup_node(cst, NodeRef::ROOT, pos) {
+pub fn lookup_references(cst: &Cst, sema: &SemanticData, pos: usize, with_def: bool) -> Vec<Span> {
+    let Some(node) = lookup_node(cst, NodeRef::ROOT, pos) else {
+        return vec![];
+    };
+    let numbered = lookup_numbered(sema, node);
+    let mut refs = if !numbered.is_empty() {
+        numbered
+            .into_iter()
+            .filter_map(|node| cst.get_span(node))
+            .collect()
+    } else if let Some(def) = lookup_declaration(cst, sema, node, pos) {
         let mut refs = sema
             .decl_bindings
             .iter()
             .filter_map(|(k, v)| (*v == def).then_some(*k))
+            .filter_map(|node| cst.get_span(node))
+            .chain(
+                directive_references(cst)
+                    .into_iter()
+                    .filter_map(|(span, decl)| (decl == def).then_some(span)),
+            )
             .collect::<Vec<_>>();
         if with_def {
-            refs.push(def)
+            // the name of the declaration instead of the whole declaration
+            let name = RuleDecl::cast(cst, def)
+                .and_then(|rule| rule.name(cst))
+                .or_else(|| TokenDecl::cast(cst, def).and_then(|token| token.name(cst)));
+            refs.extend(name.map(|(_, span)| span));
         }
         refs
     } else {
         vec![]
-    }
+    };
+    refs.sort_by_key(|span| span.start);
+    refs
 }
 
 /// Heuristic search for predicate or action implementation in the parser.rs file.
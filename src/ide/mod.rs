@@ -1,10 +1,15 @@
 #![cfg(feature = "lsp")]
 
+use crate::frontend::diag::{deduplicate, Applicability};
+use crate::frontend::lint::{LintLevels, LintPass};
+use crate::frontend::sema::AnalysisCache;
 use crate::{tokenize, Parser, SemanticPass, Token};
 use codespan_reporting::diagnostic::{LabelStyle, Severity};
 use codespan_reporting::files::SimpleFile;
 use logos::{Logos, Span};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 use tower_lsp::lsp_types::*;
@@ -12,13 +17,22 @@ use tower_lsp::lsp_types::*;
 use self::completion::*;
 use self::hover::*;
 use self::lookup::*;
+use self::refactor::*;
+use self::semantic::*;
+pub use self::server::serve;
 
 mod completion;
 mod hover;
 mod lookup;
+mod refactor;
+mod semantic;
+mod server;
 
 struct Analyzer {
     handle: JoinHandle<()>,
+    /// Flag that stops the parse of an outdated source, which cannot be
+    /// aborted by the task handle, as it does not yield.
+    cancel: Arc<AtomicBool>,
     req_tx: mpsc::Sender<Request>,
     noti_rx: mpsc::Receiver<Notification>,
 }
@@ -26,26 +40,40 @@ struct Analyzer {
 #[derive(Default)]
 pub struct Cache {
     analyzers: HashMap<Url, Analyzer>,
+    /// Results of the last analysis of each document, which are reused for
+    /// the rules that are not affected by an edit.
+    results: HashMap<Url, Arc<Mutex<AnalysisCache>>>,
 }
 
 impl Cache {
     pub fn analyze(&mut self, uri: &Url, text: String) {
         let (req_tx, req_rx) = mpsc::channel::<Request>(32);
         let (noti_tx, noti_rx) = mpsc::channel::<Notification>(32);
-        let handle = tokio::spawn(analyze(uri.clone(), text, req_rx, noti_tx));
+        let results = self.results.entry(uri.clone()).or_default().clone();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let handle = tokio::spawn(analyze(
+            uri.clone(),
+            text,
+            results,
+            cancel.clone(),
+            req_rx,
+            noti_tx,
+        ));
 
         self.analyzers.insert(
             uri.clone(),
             Analyzer {
                 handle,
+                cancel,
                 req_tx,
                 noti_rx,
             },
         );
     }
     pub fn invalidate(&self, uri: &Url) {
-        if self.analyzers.contains_key(uri) {
-            self.analyzers[uri].handle.abort();
+        if let Some(analyzer) = self.analyzers.get(uri) {
+            analyzer.cancel.store(true, Ordering::Relaxed);
+            analyzer.handle.abort();
         }
     }
     pub async fn get_diagnostics(&mut self, uri: &Url) -> Vec<Diagnostic> {
@@ -96,6 +124,30 @@ impl Cache {
             vec![]
         }
     }
+    pub async fn code_actions(&mut self, uri: &Url, range: Range) -> Vec<CodeActionOrCommand> {
+        let analyzer = self.analyzers.get_mut(uri).unwrap();
+        assert!(!analyzer.handle.is_finished());
+        analyzer
+            .req_tx
+            .send(Request::CodeAction(range))
+            .await
+            .unwrap();
+        if let Some(Notification::CodeActions(actions)) = analyzer.noti_rx.recv().await {
+            actions
+        } else {
+            vec![]
+        }
+    }
+    pub async fn semantic_tokens(&mut self, uri: &Url) -> Vec<SemanticToken> {
+        let analyzer = self.analyzers.get_mut(uri).unwrap();
+        assert!(!analyzer.handle.is_finished());
+        analyzer.req_tx.send(Request::SemanticTokens).await.unwrap();
+        if let Some(Notification::SemanticTokens(tokens)) = analyzer.noti_rx.recv().await {
+            tokens
+        } else {
+            vec![]
+        }
+    }
     pub async fn completion(&mut self, params: CompletionParams) -> Option<CompletionResponse> {
         let analyzer = self
             .analyzers
@@ -120,7 +172,9 @@ enum Request {
     Hover(Position),
     GotoDefinition(Position),
     References(Position, bool),
+    CodeAction(Range),
     Completion(CompletionParams),
+    SemanticTokens,
 }
 
 enum Notification {
@@ -128,12 +182,16 @@ enum Notification {
     Hover(Option<(String, Range)>),
     GotoDefinition(Option<Location>),
     References(Vec<Location>),
+    CodeActions(Vec<CodeActionOrCommand>),
     Completion(Option<CompletionResponse>),
+    SemanticTokens(Vec<SemanticToken>),
 }
 
 async fn analyze(
     uri: Url,
     source: String,
+    results: Arc<Mutex<AnalysisCache>>,
+    cancel: Arc<AtomicBool>,
     mut req: mpsc::Receiver<Request>,
     noti: mpsc::Sender<Notification>,
 ) {
@@ -142,8 +200,20 @@ async fn analyze(
     let mut diags = vec![];
 
     let (tokens, ranges) = tokenize(Token::lexer(&source), &mut diags);
-    let cst = Parser::parse(&source, tokens, ranges, &mut diags);
-    let sema = SemanticPass::run(&cst, &mut diags);
+    let Ok(cst) = Parser::parse_cancellable(&source, tokens, ranges, &mut diags, cancel) else {
+        return;
+    };
+    let sema = {
+        // results of an analysis that panicked are discarded
+        let mut results = results.lock().unwrap_or_else(|err| {
+            let mut results = err.into_inner();
+            *results = AnalysisCache::default();
+            results
+        });
+        SemanticPass::run_incremental(&cst, &mut diags, &mut results)
+    };
+    LintPass::run(&cst, &sema, &LintLevels::default(), &mut diags);
+    deduplicate(&mut diags);
     let file = SimpleFile::new(path.to_str().unwrap(), source.as_str());
 
     while let Some(req) = req.recv().await {
@@ -161,7 +231,7 @@ async fn analyze(
             }
             Request::Hover(pos) => {
                 let pos = compat::position_to_offset(&file, &pos);
-                let res = hover(&cst, &sema, pos)
+                let res = hover(&cst, &sema, &diags, pos)
                     .map(|(msg, span)| (msg, compat::span_to_range(&file, &span)));
                 noti.send(Notification::Hover(res)).await.unwrap();
             }
@@ -176,19 +246,70 @@ async fn analyze(
                 let pos = compat::position_to_offset(&file, &pos);
                 let ranges = lookup_references(&cst, &sema, pos, with_def)
                     .into_iter()
-                    .map(|node| {
-                        Location::new(
-                            uri.clone(),
-                            compat::span_to_range(&file, &cst.get_span(node).unwrap()),
-                        )
-                    })
+                    .map(|span| Location::new(uri.clone(), compat::span_to_range(&file, &span)))
                     .collect();
                 noti.send(Notification::References(ranges)).await.unwrap();
             }
+            Request::CodeAction(range) => {
+                let start = compat::position_to_offset(&file, &range.start);
+                let end = compat::position_to_offset(&file, &range.end);
+                let mut actions: Vec<_> = sema
+                    .fixes
+                    .iter()
+                    .filter(|fix| fix.span.start <= end && start <= fix.span.end)
+                    .map(|fix| {
+                        let edits = fix
+                            .edits
+                            .iter()
+                            .map(|(span, text)| {
+                                TextEdit::new(compat::span_to_range(&file, span), text.clone())
+                            })
+                            .collect();
+                        CodeActionOrCommand::CodeAction(CodeAction {
+                            title: fix.message.clone(),
+                            kind: Some(CodeActionKind::QUICKFIX),
+                            is_preferred: Some(
+                                fix.applicability == Applicability::MachineApplicable,
+                            ),
+                            edit: Some(WorkspaceEdit::new(HashMap::from([(uri.clone(), edits)]))),
+                            ..Default::default()
+                        })
+                    })
+                    .collect();
+                let refactorings = [
+                    (start < end)
+                        .then(|| extract_rule(&cst, &sema, &source, start..end))
+                        .flatten()
+                        .map(|refactoring| (refactoring, CodeActionKind::REFACTOR_EXTRACT)),
+                    inline_rule(&cst, &sema, &source, start)
+                        .map(|refactoring| (refactoring, CodeActionKind::REFACTOR_INLINE)),
+                ];
+                for (refactoring, kind) in refactorings.into_iter().flatten() {
+                    let edits = refactoring
+                        .edits
+                        .iter()
+                        .map(|(span, text)| {
+                            TextEdit::new(compat::span_to_range(&file, span), text.clone())
+                        })
+                        .collect();
+                    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                        title: refactoring.title,
+                        kind: Some(kind),
+                        edit: Some(WorkspaceEdit::new(HashMap::from([(uri.clone(), edits)]))),
+                        ..Default::default()
+                    }));
+                }
+                noti.send(Notification::CodeActions(actions)).await.unwrap();
+            }
             Request::Completion(params) => {
                 let pos =
                     compat::position_to_offset(&file, &params.text_document_position.position);
-                noti.send(Notification::Completion(completion(&cst, pos)))
+                noti.send(Notification::Completion(completion(&cst, &sema, pos)))
+                    .await
+                    .unwrap();
+            }
+            Request::SemanticTokens => {
+                noti.send(Notification::SemanticTokens(semantic_tokens(&cst, &file)))
                     .await
                     .unwrap();
             }
@@ -0,0 +1,196 @@
+use crate::frontend::ast::{AstNode, File, Named, Regex, RuleDecl};
+use crate::frontend::parser::Span;
+use crate::frontend::sema::Pattern;
+use crate::{Cst, NodeRef, SemanticData};
+
+use super::lookup::*;
+
+/// Refactoring of the grammar text with a title and the edits to apply.
+pub struct Refactoring {
+    pub title: String,
+    pub edits: Vec<(Span, String)>,
+}
+
+/// Calls `f` for all regexes in the subtree of `syntax` with their parent
+/// regex.
+fn visit<F: FnMut(Regex, Option<Regex>)>(
+    cst: &Cst,
+    syntax: NodeRef,
+    parent: Option<Regex>,
+    f: &mut F,
+) {
+    let parent = match Regex::cast(cst, syntax) {
+        Some(regex) => {
+            f(regex, parent);
+            Some(regex)
+        }
+        None => parent,
+    };
+    for child in cst.children(syntax) {
+        visit(cst, child, parent, f);
+    }
+}
+
+/// Checks if the regexes in the subtree of `syntax` can be moved to another
+/// rule, which is not the case for elements that are numbered or bound per
+/// rule.
+fn is_movable(cst: &Cst, syntax: NodeRef) -> bool {
+    let mut movable = true;
+    visit(cst, syntax, None, &mut |regex, _| {
+        movable &= !matches!(
+            regex,
+            Regex::Predicate(_)
+                | Regex::Action(_)
+                | Regex::Binding(_)
+                | Regex::OpenNode(_)
+                | Regex::CloseNode(_)
+                | Regex::SyntacticPredicate(_)
+        );
+    });
+    movable
+}
+
+/// Moves the regex fragment in the `selection` to a new rule, which is
+/// declared after the rule of the fragment.
+///
+/// The selection must cover a regex, or a sequence of operands of a
+/// concatenation or alternation.
+pub fn extract_rule(
+    cst: &Cst,
+    sema: &SemanticData,
+    source: &str,
+    selection: Span,
+) -> Option<Refactoring> {
+    let text = source.get(selection.clone())?;
+    let start = selection.start + (text.len() - text.trim_start().len());
+    let end = selection.end - (text.len() - text.trim_end().len());
+    if start >= end {
+        return None;
+    }
+    let file = File::cast(cst, NodeRef::ROOT)?;
+    let rule = file.rule_decls(cst).find(|rule| {
+        let span = rule.span(cst);
+        span.start <= start && end <= span.end
+    })?;
+    let body = rule.regex(cst)?;
+
+    let mut fragment = vec![];
+    visit(cst, body.syntax(), None, &mut |regex, _| {
+        let operands: Vec<_> = match regex {
+            Regex::Concat(concat) => concat.operands(cst).collect(),
+            Regex::Alternation(alternation) => alternation.operands(cst).collect(),
+            _ => vec![regex],
+        };
+        let first = operands.iter().position(|op| op.span(cst).start == start);
+        let last = operands.iter().position(|op| op.span(cst).end == end);
+        if let (Some(first), Some(last)) = (first, last) {
+            if fragment.is_empty() && first <= last {
+                fragment = operands[first..=last].to_vec();
+            }
+        }
+    });
+    if fragment.is_empty() || !fragment.iter().all(|op| is_movable(cst, op.syntax())) {
+        return None;
+    }
+    // an extracted left recursive reference would be an indirect left recursion
+    if let Some(Pattern::LeftRecursive(_) | Pattern::OperatorPrecedence(_)) =
+        sema.patterns.get(&rule)
+    {
+        let mut recursive = false;
+        for op in fragment.iter() {
+            visit(cst, op.syntax(), None, &mut |regex, _| {
+                recursive |= matches!(regex, Regex::Name(_))
+                    && sema.decl_bindings.get(&regex.syntax()) == Some(&rule.syntax());
+            });
+        }
+        if recursive {
+            return None;
+        }
+    }
+
+    let names: Vec<_> = file
+        .rule_decls(cst)
+        .filter_map(|rule| rule.name(cst))
+        .map(|(name, _)| name)
+        .collect();
+    let name = (0..)
+        .map(|i| match i {
+            0 => "new_rule".to_string(),
+            i => format!("new_rule{i}"),
+        })
+        .find(|name| !names.contains(&name.as_str()))?;
+    let rule_end = rule.span(cst).end;
+    Some(Refactoring {
+        title: format!("Extract into rule `{name}`"),
+        edits: vec![
+            (start..end, name.clone()),
+            (
+                rule_end..rule_end,
+                format!("\n{name}: {};", &source[start..end]),
+            ),
+        ],
+    })
+}
+
+/// Replaces the references of the rule at `pos` with its regex and removes
+/// the rule.
+pub fn inline_rule(
+    cst: &Cst,
+    sema: &SemanticData,
+    source: &str,
+    pos: usize,
+) -> Option<Refactoring> {
+    let node = lookup_node(cst, NodeRef::ROOT, pos)?;
+    let rule = match sema.decl_bindings.get(&node) {
+        Some(decl) => RuleDecl::cast(cst, *decl)?,
+        // the cursor must be on the name of the declaration
+        None => RuleDecl::cast(cst, node)
+            .filter(|rule| rule.name(cst).is_some_and(|(_, span)| span.contains(&pos)))?,
+    };
+    let (name, _) = rule.name(cst)?;
+    let body = rule.regex(cst)?;
+    if sema.start == Some(rule) || !is_movable(cst, body.syntax()) {
+        return None;
+    }
+    let file = File::cast(cst, NodeRef::ROOT)?;
+    let text = &source[body.span(cst)];
+
+    let mut edits = vec![];
+    let mut recursive = false;
+    for caller in file.rule_decls(cst) {
+        visit(cst, caller.syntax(), None, &mut |regex, parent| {
+            if !matches!(regex, Regex::Name(_))
+                || sema.decl_bindings.get(&regex.syntax()) != Some(&rule.syntax())
+            {
+                return;
+            }
+            recursive |= caller == rule;
+            // keep the precedence of the inlined regex
+            let grouped = match body {
+                Regex::Alternation(_) => !matches!(
+                    parent,
+                    None | Some(Regex::Alternation(_) | Regex::Paren(_) | Regex::Optional(_))
+                ),
+                Regex::Concat(_) => matches!(
+                    parent,
+                    Some(Regex::Star(_) | Regex::Plus(_) | Regex::Separated(_) | Regex::Bounded(_))
+                ),
+                _ => false,
+            };
+            let replacement = if grouped {
+                format!("({text})")
+            } else {
+                text.to_string()
+            };
+            edits.push((regex.span(cst), replacement));
+        });
+    }
+    if edits.is_empty() || recursive {
+        return None;
+    }
+    edits.push((cst.removal_span(rule.syntax()), String::new()));
+    Some(Refactoring {
+        title: format!("Inline rule `{name}`"),
+        edits,
+    })
+}
@@ -0,0 +1,154 @@
+use codespan_reporting::files::SimpleFile;
+use std::collections::HashSet;
+use tower_lsp::lsp_types::*;
+
+use crate::frontend::ast::{AstNode, File, Named};
+use crate::{Cst, Node, NodeRef, Rule, Token};
+
+/// Types of the semantic tokens, which are referenced by their index.
+///
+/// Rules are functions and tokens are enum members, like in the generated
+/// parser. Bindings and node marks name the kind of a syntax tree node.
+pub const TOKEN_TYPES: [SemanticTokenType; 11] = [
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::ENUM_MEMBER,
+    SemanticTokenType::MACRO,
+    SemanticTokenType::DECORATOR,
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::STRING,
+    SemanticTokenType::REGEXP,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::COMMENT,
+    SemanticTokenType::OPERATOR,
+];
+
+/// Modifiers of the semantic tokens, which are referenced by their bit.
+pub const TOKEN_MODIFIERS: [SemanticTokenModifier; 2] = [
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::DOCUMENTATION,
+];
+
+const DECLARATION: u32 = 1 << 0;
+const DOCUMENTATION: u32 = 1 << 1;
+
+struct Classifier<'a> {
+    rules: HashSet<&'a str>,
+    tokens: HashSet<&'a str>,
+}
+
+impl Classifier<'_> {
+    /// Type index and modifiers of a token with the `kind` and `text` in a
+    /// node of the `parent` rule.
+    fn classify(&self, kind: Token, text: &str, parent: Rule) -> Option<(u32, u32)> {
+        let declaration = match parent {
+            Rule::RuleDecl | Rule::TokenDecl => DECLARATION,
+            _ => 0,
+        };
+        match kind {
+            Token::Id | Token::AttrStart if parent == Rule::Attribute => Some((3, 0)),
+            Token::Id if parent != Rule::TokenDecl && self.rules.contains(text) => {
+                Some((0, declaration))
+            }
+            Token::Id if self.tokens.contains(text) => Some((1, declaration)),
+            Token::Action | Token::InlineAction | Token::TaggedAction => Some((2, 0)),
+            Token::Predicate => Some((3, 0)),
+            Token::Token
+            | Token::Start
+            | Token::Right
+            | Token::Skip
+            | Token::Insert
+            | Token::Lookahead
+            | Token::Import
+            | Token::Sync
+            | Token::MaxErrors
+            | Token::TokenDerive => Some((4, 0)),
+            Token::Binding | Token::OpenNode | Token::CloseNode => Some((5, 0)),
+            Token::Str => Some((6, 0)),
+            Token::Regex => Some((7, 0)),
+            Token::Number => Some((8, 0)),
+            Token::Comment => Some((9, 0)),
+            Token::DocComment => Some((9, DOCUMENTATION)),
+            // arrow of a syntactic predicate
+            Token::Arrow => Some((3, 0)),
+            Token::Or | Token::Star | Token::Plus | Token::Percent | Token::Not => Some((10, 0)),
+            _ => None,
+        }
+    }
+
+    fn collect(
+        &self,
+        cst: &Cst,
+        file: &SimpleFile<&str, &str>,
+        node: NodeRef,
+        parent: Rule,
+        tokens: &mut Vec<(Range, u32, u32)>,
+    ) {
+        for child in cst.children(node) {
+            if let Node::Rule(rule, _) = cst.get(child) {
+                self.collect(cst, file, child, rule, tokens);
+                continue;
+            }
+            let (Some(kind), Some(span)) = (cst.token_kind(child), cst.get_span(child)) else {
+                continue;
+            };
+            let text = &file.source()[span.clone()];
+            let Some((token_type, modifiers)) = self.classify(kind, text, parent) else {
+                continue;
+            };
+            // tokens that span multiple lines are split into one token per line
+            let mut start = span.start;
+            for line in text.split_inclusive('\n') {
+                let end = start + line.trim_end_matches(['\r', '\n']).len();
+                if end > start {
+                    let range = super::compat::span_to_range(file, &(start..end));
+                    tokens.push((range, token_type, modifiers));
+                }
+                start += line.len();
+            }
+        }
+    }
+}
+
+/// Semantic tokens of the grammar, where the positions are encoded relative
+/// to the previous token.
+pub fn semantic_tokens(cst: &Cst, file: &SimpleFile<&str, &str>) -> Vec<SemanticToken> {
+    let Some(grammar) = File::cast(cst, NodeRef::ROOT) else {
+        return vec![];
+    };
+    let classifier = Classifier {
+        rules: grammar
+            .rule_decls(cst)
+            .filter_map(|rule| rule.name(cst))
+            .map(|(name, _)| name)
+            .collect(),
+        tokens: grammar
+            .token_decls(cst)
+            .filter_map(|token| token.name(cst))
+            .map(|(name, _)| name)
+            .collect(),
+    };
+    let mut tokens = vec![];
+    classifier.collect(cst, file, NodeRef::ROOT, Rule::File, &mut tokens);
+
+    let (mut line, mut character) = (0, 0);
+    tokens
+        .into_iter()
+        .map(|(range, token_type, token_modifiers_bitset)| {
+            let delta_line = range.start.line - line;
+            let delta_start = if delta_line == 0 {
+                range.start.character - character
+            } else {
+                range.start.character
+            };
+            (line, character) = (range.start.line, range.start.character);
+            SemanticToken {
+                delta_line,
+                delta_start,
+                length: range.end.character - range.start.character,
+                token_type,
+                token_modifiers_bitset,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,160 @@
+use tokio::sync::RwLock;
+use tower_lsp::jsonrpc::Result;
+use tower_lsp::lsp_types::*;
+use tower_lsp::{Client, LanguageServer, LspService, Server};
+
+use super::semantic::{TOKEN_MODIFIERS, TOKEN_TYPES};
+use super::Cache;
+
+/// Runs the language server on stdin and stdout until the client exits.
+pub async fn serve() {
+    let stdin = tokio::io::stdin();
+    let stdout = tokio::io::stdout();
+
+    let (service, socket) = LspService::new(|client| Backend {
+        client,
+        cache: RwLock::new(Cache::default()),
+    });
+    Server::new(stdin, stdout, socket).serve(service).await;
+}
+
+struct Backend {
+    client: Client,
+    cache: RwLock<Cache>,
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+        Ok(InitializeResult {
+            server_info: None,
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                completion_provider: Some(CompletionOptions::default()),
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: SemanticTokensLegend {
+                                token_types: TOKEN_TYPES.to_vec(),
+                                token_modifiers: TOKEN_MODIFIERS.to_vec(),
+                            },
+                            full: Some(SemanticTokensFullOptions::Bool(true)),
+                            ..Default::default()
+                        },
+                    ),
+                ),
+                ..Default::default()
+            },
+        })
+    }
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let text = params.text_document.text;
+        let diagnostics = {
+            let mut cache = self.cache.write().await;
+            cache.analyze(&uri, text);
+            cache.get_diagnostics(&uri).await
+        };
+        self.client
+            .publish_diagnostics(uri, diagnostics, None)
+            .await;
+    }
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let text = params.content_changes.into_iter().next().unwrap().text;
+        let diagnostics = {
+            let mut cache = self.cache.write().await;
+            cache.invalidate(&uri);
+            cache.analyze(&uri, text);
+            cache.get_diagnostics(&uri).await
+        };
+        self.client
+            .publish_diagnostics(uri, diagnostics, None)
+            .await;
+    }
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.cache
+            .write()
+            .await
+            .invalidate(&params.text_document.uri);
+    }
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let pos = params.text_document_position_params.position;
+        if let Some((msg, range)) = self.cache.write().await.hover(&uri, pos).await {
+            Ok(Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: msg,
+                }),
+                range: Some(range),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let pos = params.text_document_position_params.position;
+        if let Some(location) = self.cache.write().await.goto_definition(&uri, pos).await {
+            Ok(Some(GotoDefinitionResponse::Scalar(location)))
+        } else {
+            Ok(None)
+        }
+    }
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let uri = params.text_document_position.text_document.uri;
+        let pos = params.text_document_position.position;
+        let with_decl = params.context.include_declaration;
+        let locs = self
+            .cache
+            .write()
+            .await
+            .references(&uri, pos, with_decl)
+            .await;
+        Ok(Some(locs))
+    }
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = params.text_document.uri;
+        let actions = self
+            .cache
+            .write()
+            .await
+            .code_actions(&uri, params.range)
+            .await;
+        Ok(Some(actions))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let resp = self.cache.write().await.completion(params).await;
+        Ok(resp)
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        let data = self
+            .cache
+            .write()
+            .await
+            .semantic_tokens(&params.text_document.uri)
+            .await;
+        Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: None,
+            data,
+        })))
+    }
+}
@@ -0,0 +1,445 @@
+pub mod pest;
+pub mod yacc;
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::Path;
+
+use codespan_reporting::diagnostic::Label;
+use logos::{Lexer, Logos};
+
+use crate::frontend::parser::{Diagnostic, Span};
+
+/// Keywords of the grammar syntax, which cannot be used as rule names.
+const KEYWORDS: &[&str] = &["token", "start", "right", "skip"];
+
+/// Maximum line length of the translated grammar, where longer rules are
+/// split into multiple lines.
+const MAX_LINE_LENGTH: usize = 80;
+
+/// Formats of grammars that can be translated to lelwel grammars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Yacc or bison grammar.
+    Yacc,
+    /// Pest grammar.
+    Pest,
+}
+
+impl Format {
+    /// Format of the grammar file at `path`, which is derived from its
+    /// extension.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "y" | "yy" | "ypp" => Some(Format::Yacc),
+            "pest" => Some(Format::Pest),
+            _ => None,
+        }
+    }
+
+    /// Translates the grammar `source` to a lelwel grammar.
+    ///
+    /// Constructs that cannot be translated are reported in `diags`.
+    pub fn translate(self, source: &str, diags: &mut Vec<Diagnostic>) -> Grammar {
+        match self {
+            Format::Yacc => yacc::translate(source, diags),
+            Format::Pest => pest::translate(source, diags),
+        }
+    }
+}
+
+/// Regex of a translated rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Regex {
+    /// Reference to a rule or token.
+    Name(String),
+    /// Token symbol including the quotation marks.
+    Symbol(String),
+    /// Concatenation, which matches the empty word if there are no operands.
+    Concat(Vec<Regex>),
+    Alternation(Vec<Regex>),
+    Optional(Box<Regex>),
+    Star(Box<Regex>),
+    Plus(Box<Regex>),
+    /// Repetition with a minimum and an optional maximum count.
+    Bounded(Box<Regex>, usize, Option<usize>),
+    /// Syntactic predicate, which holds if the input starts with the regex.
+    Predicate(Box<Regex>),
+    /// Negative syntactic predicate, which holds if the input does not start
+    /// with the regex.
+    NegativePredicate(Box<Regex>),
+}
+
+impl Regex {
+    /// Binding strength of the regex, where operands with a lower strength
+    /// must be parenthesized.
+    fn strength(&self) -> u8 {
+        match self {
+            Regex::Alternation(_) => 0,
+            Regex::Concat(ops) if ops.len() != 1 => 1,
+            Regex::Concat(ops) => ops[0].strength(),
+            Regex::Star(_) | Regex::Plus(_) | Regex::Bounded(..) => 2,
+            Regex::Name(_)
+            | Regex::Symbol(_)
+            | Regex::Optional(_)
+            | Regex::Predicate(_)
+            | Regex::NegativePredicate(_) => 3,
+        }
+    }
+
+    /// Checks if the regex references the rule or token `name`.
+    fn references(&self, name: &str) -> bool {
+        match self {
+            Regex::Name(other) => other == name,
+            Regex::Symbol(_) => false,
+            Regex::Concat(ops) | Regex::Alternation(ops) => {
+                ops.iter().any(|op| op.references(name))
+            }
+            Regex::Optional(op)
+            | Regex::Star(op)
+            | Regex::Plus(op)
+            | Regex::Bounded(op, ..)
+            | Regex::Predicate(op)
+            | Regex::NegativePredicate(op) => op.references(name),
+        }
+    }
+
+    fn write_operand(&self, f: &mut std::fmt::Formatter<'_>, strength: u8) -> std::fmt::Result {
+        if self.strength() < strength {
+            write!(f, "({self})")
+        } else {
+            write!(f, "{self}")
+        }
+    }
+}
+
+impl std::fmt::Display for Regex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Regex::Name(name) | Regex::Symbol(name) => write!(f, "{name}"),
+            Regex::Concat(ops) => {
+                for (i, op) in ops.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    op.write_operand(f, 2)?;
+                }
+                Ok(())
+            }
+            Regex::Alternation(ops) => {
+                for (i, op) in ops.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " | ")?;
+                    }
+                    op.write_operand(f, 1)?;
+                }
+                Ok(())
+            }
+            Regex::Optional(op) => write!(f, "[{op}]"),
+            Regex::Star(op) => {
+                op.write_operand(f, 3)?;
+                write!(f, "*")
+            }
+            Regex::Plus(op) => {
+                op.write_operand(f, 3)?;
+                write!(f, "+")
+            }
+            Regex::Bounded(op, min, max) => {
+                op.write_operand(f, 3)?;
+                match max {
+                    Some(max) if max == min => write!(f, "{{{min}}}"),
+                    Some(max) => write!(f, "{{{min},{max}}}"),
+                    None => write!(f, "{{{min},}}"),
+                }
+            }
+            Regex::Predicate(op) => write!(f, "({op})=>"),
+            Regex::NegativePredicate(op) => write!(f, "!({op})"),
+        }
+    }
+}
+
+/// Rule of a translated grammar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub name: String,
+    pub regex: Regex,
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = &self.name;
+        let line = format!("{name}: {};", self.regex);
+        if line.len() <= MAX_LINE_LENGTH {
+            return writeln!(f, "{line}");
+        }
+        match &self.regex {
+            Regex::Alternation(ops) => {
+                writeln!(f, "{name}:")?;
+                for (i, op) in ops.iter().enumerate() {
+                    writeln!(f, "{} {op}", if i == 0 { " " } else { "|" })?;
+                }
+            }
+            Regex::Optional(op) if matches!(**op, Regex::Alternation(_)) => {
+                let Regex::Alternation(ops) = &**op else {
+                    unreachable!()
+                };
+                writeln!(f, "{name}:")?;
+                for (i, op) in ops.iter().enumerate() {
+                    writeln!(f, "  {} {op}", if i == 0 { "[" } else { "|" })?;
+                }
+                writeln!(f, "  ]")?;
+            }
+            regex => {
+                writeln!(f, "{name}:")?;
+                writeln!(f, "  {regex}")?;
+            }
+        }
+        writeln!(f, ";")
+    }
+}
+
+/// Token declaration of a translated grammar.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenDecl {
+    pub name: String,
+    /// Symbol or description including the quotation marks.
+    pub symbol: Option<String>,
+    /// Regular expression for the lexer without the delimiting slashes.
+    pub regex: Option<String>,
+    /// Names of the attributes without arguments.
+    pub attributes: Vec<&'static str>,
+}
+
+impl TokenDecl {
+    fn new(name: String, symbol: Option<String>) -> Self {
+        Self {
+            name,
+            symbol,
+            ..Default::default()
+        }
+    }
+}
+
+impl std::fmt::Display for TokenDecl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for attribute in self.attributes.iter() {
+            write!(f, "#[{attribute}] ")?;
+        }
+        write!(f, "{}", self.name)?;
+        if let Some(symbol) = &self.symbol {
+            write!(f, "={symbol}")?;
+        }
+        if let Some(regex) = &self.regex {
+            write!(f, " /{}/", regex.replace('/', "\\/"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Grammar that is translated from another format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Grammar {
+    pub tokens: Vec<TokenDecl>,
+    /// Names of the skipped tokens.
+    pub skip: Vec<String>,
+    /// Right associative operators.
+    pub right: Vec<String>,
+    pub start: Option<String>,
+    pub rules: Vec<Rule>,
+}
+
+impl Grammar {
+    /// Sets the `start` rule, which is wrapped in a new rule if it is
+    /// referenced, as the start rule cannot be referenced.
+    fn set_start(&mut self, start: String, names: &mut Names) {
+        if self.rules.iter().any(|rule| rule.regex.references(&start)) {
+            let name = names.rule("file");
+            self.rules.insert(
+                0,
+                Rule {
+                    name: name.clone(),
+                    regex: Regex::Name(start),
+                },
+            );
+            self.start = Some(name);
+        } else {
+            self.start = Some(start);
+        }
+    }
+}
+
+impl std::fmt::Display for Grammar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut sections = vec![];
+        if !self.tokens.is_empty() {
+            let mut list = String::new();
+            let mut line = "token".to_string();
+            for (i, token) in self.tokens.iter().enumerate() {
+                let token = token.to_string();
+                if i > 0 && line.len() + token.len() + 2 > MAX_LINE_LENGTH {
+                    writeln!(list, "{line}")?;
+                    line = "     ".to_string();
+                }
+                line += " ";
+                line += &token;
+            }
+            writeln!(list, "{line};")?;
+            sections.push(list);
+        }
+        if !self.skip.is_empty() {
+            sections.push(format!("skip {};\n", self.skip.join(" ")));
+        }
+        if !self.right.is_empty() {
+            sections.push(format!("right {};\n", self.right.join(" ")));
+        }
+        if let Some(start) = &self.start {
+            sections.push(format!("start {start};\n"));
+        }
+        if !self.rules.is_empty() {
+            sections.push(self.rules.iter().map(|rule| rule.to_string()).collect());
+        }
+        write!(f, "{}", sections.join("\n"))
+    }
+}
+
+/// Allocator of unique names in the translated grammar.
+#[derive(Debug, Default)]
+struct Names {
+    used: HashSet<String>,
+}
+
+impl Names {
+    /// Reserves a `name` that is already used in the translated grammar.
+    fn reserve(&mut self, name: String) {
+        self.used.insert(name);
+    }
+
+    /// Unique rule name for a `name` of the original grammar in snake case.
+    fn rule(&mut self, name: &str) -> String {
+        let mut name = words(name).join("_");
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            name = format!("rule_{name}").trim_end_matches('_').to_string();
+        }
+        if KEYWORDS.contains(&name.as_str())
+            || crate::frontend::sema::PARSER_METHODS.contains(&name.as_str())
+        {
+            name += "_rule";
+        }
+        self.unique(name)
+    }
+
+    /// Unique token name for a `name` of the original grammar in pascal case.
+    fn token(&mut self, name: &str) -> String {
+        let mut name: String = words(name)
+            .iter()
+            .map(|word| word[..1].to_uppercase() + &word[1..])
+            .collect();
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            name = "Token".to_string() + &name;
+        }
+        self.unique(name)
+    }
+
+    fn unique(&mut self, name: String) -> String {
+        let mut unique = name.clone();
+        let mut i = 2;
+        while !self.used.insert(unique.clone()) {
+            unique = format!("{name}{i}");
+            i += 1;
+        }
+        unique
+    }
+}
+
+/// Lowercase words of a `name` in snake, pascal, camel, or upper case.
+fn words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = vec![];
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        // a word starts at an uppercase letter after a lowercase letter or
+        // digit, or at the last uppercase letter of an acronym (e.g. `HTTPServer`)
+        let prev = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1);
+        if c.is_ascii_uppercase()
+            && !word.is_empty()
+            && (prev.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+                || next.is_some_and(|n| n.is_ascii_lowercase())
+                    && prev.is_some_and(|p| p.is_ascii_uppercase()))
+        {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c.to_ascii_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+enum LexerError {
+    #[default]
+    Invalid,
+    UnterminatedLiteral,
+    UnterminatedComment,
+    UnterminatedCode,
+}
+
+impl LexerError {
+    fn into_diagnostic(self, span: Span) -> Diagnostic {
+        let message = match self {
+            LexerError::Invalid => "invalid token",
+            LexerError::UnterminatedLiteral => "unterminated literal",
+            LexerError::UnterminatedComment => "unterminated comment",
+            LexerError::UnterminatedCode => "unterminated code block",
+        };
+        Diagnostic::error()
+            .with_message(message)
+            .with_labels(vec![Label::primary((), span)])
+    }
+}
+
+/// Lexes a literal up to the quotation mark that started it.
+fn parse_literal<'a, T: Logos<'a, Source = str>>(
+    lexer: &mut Lexer<'a, T>,
+) -> Result<(), LexerError> {
+    let quote = lexer.slice().chars().last().unwrap();
+    let mut it = lexer.remainder().chars();
+    while let Some(c) = it.next() {
+        lexer.bump(c.len_utf8());
+        match c {
+            '\n' => return Err(LexerError::UnterminatedLiteral),
+            '\\' => {
+                if let Some(c) = it.next() {
+                    lexer.bump(c.len_utf8());
+                }
+            }
+            c if c == quote => return Ok(()),
+            _ => {}
+        }
+    }
+    Err(LexerError::UnterminatedLiteral)
+}
+
+fn parse_block_comment<'a, T: Logos<'a, Source = str>>(
+    lexer: &mut Lexer<'a, T>,
+) -> Result<(), LexerError> {
+    if lexer
+        .remainder()
+        .find("*/")
+        .map(|i| lexer.bump(i + 2))
+        .is_some()
+    {
+        Ok(())
+    } else {
+        lexer.bump(lexer.remainder().len());
+        Err(LexerError::UnterminatedComment)
+    }
+}
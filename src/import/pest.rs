@@ -0,0 +1,1135 @@
+use std::collections::{HashMap, HashSet};
+
+use codespan_reporting::diagnostic::Label;
+use logos::Logos;
+
+use super::{
+    parse_block_comment, parse_literal, Grammar, LexerError, Names, Regex, Rule, TokenDecl,
+};
+use crate::frontend::parser::{Diagnostic, Span};
+use crate::frontend::source::GrammarSource;
+
+/// Builtin rules of pest that match a single character of a class.
+const CLASSES: &[(&str, &str)] = &[
+    ("ASCII_DIGIT", "0-9"),
+    ("ASCII_NONZERO_DIGIT", "1-9"),
+    ("ASCII_BIN_DIGIT", "01"),
+    ("ASCII_OCT_DIGIT", "0-7"),
+    ("ASCII_HEX_DIGIT", "0-9a-fA-F"),
+    ("ASCII_ALPHA_LOWER", "a-z"),
+    ("ASCII_ALPHA_UPPER", "A-Z"),
+    ("ASCII_ALPHA", "a-zA-Z"),
+    ("ASCII_ALPHANUMERIC", "a-zA-Z0-9"),
+    ("ASCII", "\\x00-\\x7f"),
+    ("XID_START", "\\p{XID_Start}"),
+    ("XID_CONTINUE", "\\p{XID_Continue}"),
+    ("ALPHABETIC", "\\p{Alphabetic}"),
+    ("WHITE_SPACE", "\\p{White_Space}"),
+    ("LETTER", "\\p{L}"),
+    ("UPPERCASE_LETTER", "\\p{Lu}"),
+    ("LOWERCASE_LETTER", "\\p{Ll}"),
+    ("NUMBER", "\\p{N}"),
+    ("DECIMAL_NUMBER", "\\p{Nd}"),
+    ("PUNCTUATION", "\\p{P}"),
+    ("SYMBOL", "\\p{S}"),
+    ("SPACE_SEPARATOR", "\\p{Zs}"),
+];
+
+/// Builtin rules of pest that operate on its stack.
+const STACK_OPERATIONS: &[&str] = &["POP", "POP_ALL", "PEEK", "PEEK_ALL", "DROP"];
+
+/// Rules that pest implicitly matches between the elements of non-atomic
+/// rules, which are translated to skipped tokens.
+const SKIPPED_RULES: &[&str] = &["WHITESPACE", "COMMENT"];
+
+#[derive(Logos, Debug, Clone, Copy, PartialEq)]
+#[logos(error = LexerError)]
+#[logos(skip r"[ \t\r\n\f]+")]
+#[logos(skip r"//[^\n]*")]
+enum Token {
+    #[token("/*", parse_block_comment)]
+    Comment,
+    #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*")]
+    Id,
+    #[token("\"", parse_literal)]
+    #[token("^\"", parse_literal)]
+    Str,
+    #[token("'", parse_literal)]
+    Char,
+    #[regex(r"[0-9]+")]
+    Number,
+    #[token("=")]
+    Equal,
+    #[token("{")]
+    LBrace,
+    #[token("}")]
+    RBrace,
+    #[token("(")]
+    LPar,
+    #[token(")")]
+    RPar,
+    #[token("[")]
+    LBrak,
+    #[token("]")]
+    RBrak,
+    #[token("~")]
+    Tilde,
+    #[token("|")]
+    Pipe,
+    #[token("?")]
+    Quest,
+    #[token("*")]
+    Star,
+    #[token("+")]
+    Plus,
+    #[token("&")]
+    Amp,
+    #[token("!")]
+    Excl,
+    #[token("..")]
+    DotDot,
+    #[token(",")]
+    Comma,
+    #[token("@")]
+    At,
+    #[token("$")]
+    Dollar,
+    #[token("#")]
+    Hash,
+    #[token("-")]
+    Minus,
+}
+
+/// Expression of a pest rule.
+#[derive(Debug, Clone)]
+enum Expr<'a> {
+    Ident(&'a str),
+    /// String with its value and whether it is case insensitive.
+    Str(String, bool),
+    Range(char, char),
+    Seq(Vec<Node<'a>>),
+    Choice(Vec<Node<'a>>),
+    Optional(Box<Node<'a>>),
+    Star(Box<Node<'a>>),
+    Plus(Box<Node<'a>>),
+    Repeat(Box<Node<'a>>, usize, Option<usize>),
+    Predicate(Box<Node<'a>>),
+    NegativePredicate(Box<Node<'a>>),
+    Push(Box<Node<'a>>),
+    /// Builtin rule that operates on the stack.
+    Stack(&'a str),
+}
+
+#[derive(Debug, Clone)]
+struct Node<'a> {
+    expr: Expr<'a>,
+    span: Span,
+}
+
+impl<'a> Node<'a> {
+    fn new(expr: Expr<'a>, span: Span) -> Self {
+        Self { expr, span }
+    }
+
+    /// Direct subexpressions of the node.
+    fn children(&self) -> Vec<&Node<'a>> {
+        match &self.expr {
+            Expr::Seq(nodes) | Expr::Choice(nodes) => nodes.iter().collect(),
+            Expr::Optional(node)
+            | Expr::Star(node)
+            | Expr::Plus(node)
+            | Expr::Repeat(node, ..)
+            | Expr::Predicate(node)
+            | Expr::NegativePredicate(node)
+            | Expr::Push(node) => vec![node],
+            Expr::Ident(_) | Expr::Str(..) | Expr::Range(..) | Expr::Stack(_) => vec![],
+        }
+    }
+
+    /// Visits the node and all of its subexpressions.
+    fn visit(&self, f: &mut impl FnMut(&Node<'a>)) {
+        f(self);
+        for child in self.children() {
+            child.visit(f);
+        }
+    }
+
+    /// Checks if the node matches single characters rather than strings, so
+    /// the rule must be translated to a token.
+    fn matches_characters(&self) -> bool {
+        let mut characters = false;
+        self.visit(&mut |node| match node.expr {
+            Expr::Range(..) => characters = true,
+            Expr::Ident(name) => {
+                characters |= matches!(name, "ANY" | "NEWLINE")
+                    || CLASSES.iter().any(|(class, _)| *class == name)
+            }
+            _ => {}
+        });
+        characters
+    }
+}
+
+struct PestRule<'a> {
+    name: &'a str,
+    span: Span,
+    /// Modifier of the rule, which is `_`, `@`, `$`, or `!`.
+    modifier: Option<&'a str>,
+    node: Node<'a>,
+}
+
+impl PestRule<'_> {
+    fn is_atomic(&self) -> bool {
+        matches!(self.modifier, Some("@" | "$"))
+    }
+}
+
+/// Value of a string or character literal.
+fn unescape(literal: &str) -> String {
+    let literal = literal.trim_start_matches('^');
+    let mut value = String::new();
+    let mut it = literal[1..literal.len() - 1].chars().peekable();
+    while let Some(c) = it.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match it.next() {
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('t') => value.push('\t'),
+            Some('0') => value.push('\0'),
+            Some('x') => {
+                let hex: String = it.by_ref().take(2).collect();
+                value.extend(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32));
+            }
+            Some('u') if it.peek() == Some(&'{') => {
+                let hex: String = it.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                value.extend(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32));
+            }
+            Some(c) => value.push(c),
+            None => {}
+        }
+    }
+    value
+}
+
+struct PestParser<'a> {
+    source: &'a str,
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+}
+
+impl<'a> PestParser<'a> {
+    fn new(source: &'a str, diags: &mut Vec<Diagnostic>) -> Self {
+        let mut tokens = vec![];
+        let mut lexer = Token::lexer(source);
+        while let Some(token) = lexer.next() {
+            match token {
+                Ok(Token::Comment) => {}
+                Ok(token) => tokens.push((token, lexer.span())),
+                Err(err) => diags.push(err.into_diagnostic(lexer.span())),
+            }
+        }
+        Self {
+            source,
+            tokens,
+            pos: 0,
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<Token> {
+        self.tokens.get(self.pos + offset).map(|(token, _)| *token)
+    }
+
+    fn span(&self) -> Span {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.source.len()..self.source.len(), |(_, span)| {
+                span.clone()
+            })
+    }
+
+    /// End of the previous token.
+    fn end(&self) -> usize {
+        self.pos
+            .checked_sub(1)
+            .and_then(|pos| self.tokens.get(pos))
+            .map_or(0, |(_, span)| span.end)
+    }
+
+    fn text(&self) -> &'a str {
+        &self.source[self.span()]
+    }
+
+    fn unexpected(&self, diags: &mut Vec<Diagnostic>, expected: &str) {
+        diags.push(
+            Diagnostic::error()
+                .with_message(format!("invalid syntax, expected {expected}"))
+                .with_labels(vec![Label::primary((), self.span())]),
+        );
+    }
+
+    fn expect(&mut self, token: Token, expected: &str, diags: &mut Vec<Diagnostic>) -> bool {
+        if self.peek(0) == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            self.unexpected(diags, expected);
+            false
+        }
+    }
+
+    fn is_rule_start(&self) -> bool {
+        self.peek(0) == Some(Token::Id) && self.peek(1) == Some(Token::Equal)
+    }
+
+    fn rules(&mut self, diags: &mut Vec<Diagnostic>) -> Vec<PestRule<'a>> {
+        let mut rules = vec![];
+        while self.peek(0).is_some() {
+            if !self.is_rule_start() {
+                self.unexpected(diags, "a rule");
+                self.pos += 1;
+                while self.peek(0).is_some() && !self.is_rule_start() {
+                    self.pos += 1;
+                }
+                continue;
+            }
+            let (name, span) = (self.text(), self.span());
+            self.pos += 2;
+            let modifier = match self.peek(0) {
+                Some(Token::Id | Token::At | Token::Dollar | Token::Excl)
+                    if self.peek(1) == Some(Token::LBrace)
+                        && matches!(self.text(), "_" | "@" | "$" | "!") =>
+                {
+                    self.pos += 1;
+                    Some(&self.source[self.tokens[self.pos - 1].1.clone()])
+                }
+                _ => None,
+            };
+            if !self.expect(Token::LBrace, "`{`", diags) {
+                while self.peek(0).is_some() && !self.is_rule_start() {
+                    self.pos += 1;
+                }
+                continue;
+            }
+            let node = self.choice(diags);
+            if !self.expect(Token::RBrace, "`}`", diags) {
+                while self.peek(0).is_some() && !self.is_rule_start() {
+                    self.pos += 1;
+                }
+            }
+            rules.push(PestRule {
+                name,
+                span,
+                modifier,
+                node,
+            });
+        }
+        rules
+    }
+
+    fn choice(&mut self, diags: &mut Vec<Diagnostic>) -> Node<'a> {
+        let start = self.span().start;
+        if self.peek(0) == Some(Token::Pipe) {
+            self.pos += 1;
+        }
+        let mut nodes = vec![self.sequence(diags)];
+        while self.peek(0) == Some(Token::Pipe) {
+            self.pos += 1;
+            nodes.push(self.sequence(diags));
+        }
+        if nodes.len() == 1 {
+            nodes.pop().unwrap()
+        } else {
+            Node::new(Expr::Choice(nodes), start..self.end())
+        }
+    }
+
+    fn sequence(&mut self, diags: &mut Vec<Diagnostic>) -> Node<'a> {
+        let start = self.span().start;
+        let mut nodes = vec![self.term(diags)];
+        while self.peek(0) == Some(Token::Tilde) {
+            self.pos += 1;
+            nodes.push(self.term(diags));
+        }
+        if nodes.len() == 1 {
+            nodes.pop().unwrap()
+        } else {
+            Node::new(Expr::Seq(nodes), start..self.end())
+        }
+    }
+
+    fn term(&mut self, diags: &mut Vec<Diagnostic>) -> Node<'a> {
+        let mut prefixes = vec![];
+        while let Some(token @ (Token::Amp | Token::Excl)) = self.peek(0) {
+            prefixes.push((token, self.span().start));
+            self.pos += 1;
+        }
+        let mut node = self.primary(diags);
+        loop {
+            let start = node.span.start;
+            let expr = match self.peek(0) {
+                Some(Token::Quest) => Expr::Optional(Box::new(node)),
+                Some(Token::Star) => Expr::Star(Box::new(node)),
+                Some(Token::Plus) => Expr::Plus(Box::new(node)),
+                Some(Token::LBrace)
+                    if matches!(self.peek(1), Some(Token::Number | Token::Comma)) =>
+                {
+                    self.pos += 1;
+                    let (min, max) = self.bounds(diags);
+                    Expr::Repeat(Box::new(node), min, max)
+                }
+                _ => break,
+            };
+            self.pos += 1;
+            node = Node::new(expr, start..self.end());
+        }
+        for (token, start) in prefixes.into_iter().rev() {
+            let expr = match token {
+                Token::Amp => Expr::Predicate(Box::new(node)),
+                _ => Expr::NegativePredicate(Box::new(node)),
+            };
+            node = Node::new(expr, start..self.end());
+        }
+        node
+    }
+
+    /// Bounds of a repetition up to the closing brace, which is not consumed.
+    fn bounds(&mut self, diags: &mut Vec<Diagnostic>) -> (usize, Option<usize>) {
+        let min = self.number();
+        if self.peek(0) != Some(Token::Comma) {
+            if self.peek(0) != Some(Token::RBrace) {
+                self.unexpected(diags, "`}`");
+            }
+            return (min.unwrap_or(0), min);
+        }
+        self.pos += 1;
+        let max = self.number();
+        if self.peek(0) != Some(Token::RBrace) {
+            self.unexpected(diags, "`}`");
+        }
+        (min.unwrap_or(0), max)
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        if self.peek(0) != Some(Token::Number) {
+            return None;
+        }
+        self.pos += 1;
+        self.source[self.tokens[self.pos - 1].1.clone()]
+            .parse()
+            .ok()
+    }
+
+    fn primary(&mut self, diags: &mut Vec<Diagnostic>) -> Node<'a> {
+        let span = self.span();
+        let expr = match self.peek(0) {
+            Some(Token::LPar) => {
+                self.pos += 1;
+                let node = self.choice(diags);
+                self.expect(Token::RPar, "`)`", diags);
+                return Node::new(node.expr, span.start..self.end());
+            }
+            Some(Token::Hash)
+                if self.peek(1) == Some(Token::Id) && self.peek(2) == Some(Token::Equal) =>
+            {
+                // tags only name the matched expression
+                self.pos += 3;
+                return self.term(diags);
+            }
+            Some(Token::Str) => Expr::Str(unescape(self.text()), self.text().starts_with('^')),
+            Some(Token::Char)
+                if self.peek(1) == Some(Token::DotDot) && self.peek(2) == Some(Token::Char) =>
+            {
+                let first = unescape(self.text()).chars().next().unwrap_or_default();
+                self.pos += 2;
+                let last = unescape(self.text()).chars().next().unwrap_or_default();
+                Expr::Range(first, last)
+            }
+            Some(Token::Char) => Expr::Str(unescape(self.text()), false),
+            Some(Token::Id) if self.text() == "PUSH" && self.peek(1) == Some(Token::LPar) => {
+                self.pos += 1;
+                let node = self.primary(diags);
+                return Node::new(Expr::Push(Box::new(node)), span.start..self.end());
+            }
+            Some(Token::Id) if STACK_OPERATIONS.contains(&self.text()) => {
+                let name = self.text();
+                if self.peek(1) == Some(Token::LBrak) {
+                    while !matches!(self.peek(0), Some(Token::RBrak) | None) {
+                        self.pos += 1;
+                    }
+                }
+                Expr::Stack(name)
+            }
+            Some(Token::Id) => Expr::Ident(self.text()),
+            _ => {
+                self.unexpected(diags, "an expression");
+                if !matches!(
+                    self.peek(0),
+                    Some(Token::RBrace | Token::RPar | Token::Pipe | Token::Tilde) | None
+                ) {
+                    self.pos += 1;
+                }
+                return Node::new(Expr::Seq(vec![]), span);
+            }
+        };
+        self.pos += 1;
+        Node::new(expr, span.start..self.end())
+    }
+}
+
+/// Token symbol in the grammar syntax for the string `value`, which does not
+/// exist for strings with control characters.
+fn token_symbol(value: &str) -> Option<String> {
+    if value.is_empty() || value.contains(char::is_control) {
+        return None;
+    }
+    Some(format!(
+        "'{}'",
+        value.replace('\\', "\\\\").replace('\'', "\\'")
+    ))
+}
+
+/// Name and description of a token for a string `value` that has no symbol.
+fn description(value: &str) -> (&str, String) {
+    let name = match value {
+        "\n" => "newline",
+        "\t" => "tab",
+        "\r" => "carriage return",
+        "\r\n" => "line break",
+        _ => "literal",
+    };
+    let text = match name {
+        "literal" => value.escape_default().to_string().replace('\\', "\\\\"),
+        name => name.to_string(),
+    };
+    (name, format!("'<{}>'", text.replace('\'', "\\'")))
+}
+
+/// Escapes a character for a regular expression, which may be in a class.
+fn escape_char(c: char) -> String {
+    match c {
+        '\n' => "\\n".to_string(),
+        '\r' => "\\r".to_string(),
+        '\t' => "\\t".to_string(),
+        c if c.is_control() => format!("\\x{{{:x}}}", c as u32),
+        c => regex_syntax::escape(c.encode_utf8(&mut [0; 4])),
+    }
+}
+
+/// Translation of a rule to a regular expression for the lexer, which
+/// reports a construct that cannot be translated as an error.
+struct LexerRegex<'a, 'b> {
+    rules: &'b HashMap<&'a str, &'b PestRule<'a>>,
+    /// Rules whose regex is currently translated.
+    stack: Vec<&'a str>,
+}
+
+impl<'a> LexerRegex<'a, '_> {
+    /// Items of a character class that matches the same characters as the
+    /// `node`, where `negated` is set for the class of a negative predicate.
+    fn class(&self, node: &Node<'a>, negated: bool) -> Option<String> {
+        match &node.expr {
+            Expr::Str(value, false) if value.chars().count() == 1 => {
+                Some(escape_char(value.chars().next().unwrap()))
+            }
+            Expr::Range(first, last) => {
+                Some(format!("{}-{}", escape_char(*first), escape_char(*last)))
+            }
+            Expr::Ident("NEWLINE") if negated => Some("\\n\\r".to_string()),
+            Expr::Ident(name) => CLASSES
+                .iter()
+                .find(|(class, _)| class == name)
+                .map(|(_, items)| items.to_string()),
+            Expr::Choice(nodes) => nodes.iter().map(|node| self.class(node, negated)).collect(),
+            _ => None,
+        }
+    }
+
+    /// Regular expression and its binding strength, where `0` is an
+    /// alternation, `1` a concatenation, `2` a repetition, and `3` an atom.
+    fn regex(&mut self, node: &Node<'a>) -> Result<(String, u8), (Span, &'static str)> {
+        if let Some(class) = self.class(node, false) {
+            return Ok(match &node.expr {
+                Expr::Str(..) => (class, 3),
+                _ => (format!("[{class}]"), 3),
+            });
+        }
+        Ok(match &node.expr {
+            Expr::Ident("ANY") => ("[\\s\\S]".to_string(), 3),
+            Expr::Ident("NEWLINE") => ("(?:\\n|\\r\\n|\\r)".to_string(), 3),
+            Expr::Ident("SOI" | "EOI") => {
+                return Err((node.span.clone(), "the start or end of the input"))
+            }
+            Expr::Ident(name) => {
+                let Some(rule) = self.rules.get(name) else {
+                    return Err((node.span.clone(), "an undefined rule"));
+                };
+                if self.stack.contains(name) {
+                    return Err((node.span.clone(), "a recursive rule"));
+                }
+                self.stack.push(name);
+                let regex = self.regex(&rule.node);
+                self.stack.pop();
+                regex?
+            }
+            Expr::Str(value, insensitive) => {
+                let regex: String = value.chars().map(escape_char).collect();
+                match insensitive {
+                    true => (format!("(?i:{regex})"), 3),
+                    false => (regex, if value.chars().count() == 1 { 3 } else { 1 }),
+                }
+            }
+            Expr::Range(..) => unreachable!(),
+            Expr::Seq(nodes) => {
+                let mut parts = vec![];
+                let mut i = 0;
+                while i < nodes.len() {
+                    // a negative predicate of characters followed by any
+                    // character is a negated class
+                    if let (Expr::NegativePredicate(predicate), Some(Expr::Ident("ANY"))) =
+                        (&nodes[i].expr, nodes.get(i + 1).map(|node| &node.expr))
+                    {
+                        if let Some(class) = self.class(predicate, true) {
+                            parts.push((format!("[^{class}]"), 3));
+                            i += 2;
+                            continue;
+                        }
+                    }
+                    parts.push(self.regex(&nodes[i])?);
+                    i += 1;
+                }
+                if parts.len() == 1 {
+                    parts.pop().unwrap()
+                } else {
+                    let parts: Vec<_> = parts
+                        .into_iter()
+                        .map(|(regex, strength)| match strength {
+                            0 => format!("(?:{regex})"),
+                            _ => regex,
+                        })
+                        .collect();
+                    (parts.concat(), 1)
+                }
+            }
+            Expr::Choice(nodes) => {
+                let alternatives = nodes
+                    .iter()
+                    .map(|node| self.operand(node, 1))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (alternatives.join("|"), 0)
+            }
+            Expr::Optional(node) => (self.operand(node, 3)? + "?", 2),
+            Expr::Star(node) => (self.operand(node, 3)? + "*", 2),
+            Expr::Plus(node) => (self.operand(node, 3)? + "+", 2),
+            Expr::Repeat(node, min, max) => {
+                let regex = self.operand(node, 3)?;
+                match max {
+                    Some(max) if max == min => (format!("{regex}{{{min}}}"), 2),
+                    Some(max) => (format!("{regex}{{{min},{max}}}"), 2),
+                    None => (format!("{regex}{{{min},}}"), 2),
+                }
+            }
+            Expr::Predicate(_) | Expr::NegativePredicate(_) => {
+                return Err((node.span.clone(), "a predicate"))
+            }
+            Expr::Push(_) | Expr::Stack(_) => {
+                return Err((node.span.clone(), "an operation on the stack"))
+            }
+        })
+    }
+
+    /// Regular expression of an operand, which is grouped if it binds less
+    /// than the `strength` of the operator.
+    fn operand(&mut self, node: &Node<'a>, strength: u8) -> Result<String, (Span, &'static str)> {
+        let (regex, operand) = self.regex(node)?;
+        Ok(if operand < strength {
+            format!("(?:{regex})")
+        } else {
+            regex
+        })
+    }
+}
+
+/// Ordered choice of a translated rule with the regex and span of each
+/// alternative, where alternatives without a regex are empty.
+type Choice = Vec<(Option<Regex>, Span)>;
+
+struct Translator<'a> {
+    /// Token names of rules that are translated to tokens.
+    tokens: HashMap<&'a str, String>,
+    /// Rule names of rules that are translated to rules.
+    names: HashMap<&'a str, String>,
+    /// Regexes of the string literals with their case sensitivity.
+    literals: HashMap<(String, bool), Regex>,
+    choices: Vec<Choice>,
+}
+
+impl<'a> Translator<'a> {
+    fn regex(&mut self, node: &Node<'a>, diags: &mut Vec<Diagnostic>) -> Option<Regex> {
+        let regex = match &node.expr {
+            Expr::Ident("SOI" | "EOI") => return None,
+            Expr::Ident(name) => {
+                if let Some(name) = self.tokens.get(name).or(self.names.get(name)) {
+                    Regex::Name(name.clone())
+                } else {
+                    diags.push(
+                        Diagnostic::error()
+                            .with_message(format!("undefined rule `{name}`"))
+                            .with_labels(vec![Label::primary((), node.span.clone())]),
+                    );
+                    return None;
+                }
+            }
+            Expr::Str(value, insensitive) => self.literals[&(value.clone(), *insensitive)].clone(),
+            Expr::Range(..) => unreachable!(),
+            Expr::Seq(nodes) => {
+                let ops: Vec<_> = nodes
+                    .iter()
+                    .filter_map(|node| self.regex(node, diags))
+                    .collect();
+                if ops.is_empty() {
+                    return None;
+                }
+                Regex::Concat(ops)
+            }
+            Expr::Choice(nodes) => {
+                let choice: Choice = nodes
+                    .iter()
+                    .map(|node| (self.regex(node, diags), node.span.clone()))
+                    .collect();
+                let mut ops = vec![];
+                let mut empty = false;
+                for (op, _) in choice.iter() {
+                    match op {
+                        Some(op) if !ops.contains(op) => ops.push(op.clone()),
+                        Some(_) => {}
+                        None => empty = true,
+                    }
+                }
+                self.choices.push(choice);
+                let regex = match ops.len() {
+                    0 => return None,
+                    1 => ops.pop().unwrap(),
+                    _ => Regex::Alternation(ops),
+                };
+                if empty {
+                    Regex::Optional(Box::new(regex))
+                } else {
+                    regex
+                }
+            }
+            Expr::Optional(node) => Regex::Optional(Box::new(self.regex(node, diags)?)),
+            Expr::Star(node) => Regex::Star(Box::new(self.regex(node, diags)?)),
+            Expr::Plus(node) => Regex::Plus(Box::new(self.regex(node, diags)?)),
+            Expr::Repeat(node, 0, Some(1)) => Regex::Optional(Box::new(self.regex(node, diags)?)),
+            Expr::Repeat(node, min, max) => {
+                Regex::Bounded(Box::new(self.regex(node, diags)?), *min, *max)
+            }
+            Expr::Predicate(node) => Regex::Predicate(Box::new(self.regex(node, diags)?)),
+            Expr::NegativePredicate(node) => {
+                Regex::NegativePredicate(Box::new(self.regex(node, diags)?))
+            }
+            Expr::Push(inner) => {
+                diags.push(stack_operation("PUSH", &node.span));
+                return self.regex(inner, diags);
+            }
+            Expr::Stack(name) => {
+                diags.push(stack_operation(name, &node.span));
+                return None;
+            }
+        };
+        Some(regex)
+    }
+}
+
+fn stack_operation(name: &str, span: &Span) -> Diagnostic {
+    Diagnostic::warning()
+        .with_message(format!("stack operation `{name}` is not imported"))
+        .with_labels(vec![Label::primary((), span.clone())])
+        .with_notes(vec![
+            "note: the stack of pest has no equivalent in an LL(1) grammar".to_string(),
+        ])
+}
+
+/// First tokens of the translated rules and whether the rules may be empty.
+#[derive(Default)]
+struct FirstSets {
+    first: HashMap<String, HashSet<String>>,
+    nullable: HashSet<String>,
+}
+
+impl FirstSets {
+    fn new(rules: &[Rule]) -> Self {
+        let mut sets = FirstSets::default();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in rules {
+                let (first, nullable) = sets.get(&rule.regex);
+                let entry = sets.first.entry(rule.name.clone()).or_default();
+                let len = entry.len();
+                entry.extend(first);
+                changed |= entry.len() != len;
+                if nullable {
+                    changed |= sets.nullable.insert(rule.name.clone());
+                }
+            }
+        }
+        sets
+    }
+
+    /// First tokens of the `regex` and whether it may be empty.
+    fn get(&self, regex: &Regex) -> (HashSet<String>, bool) {
+        match regex {
+            Regex::Name(name) => match self.first.get(name) {
+                Some(first) => (first.clone(), self.nullable.contains(name)),
+                None if name.starts_with(|c: char| c.is_ascii_uppercase()) => {
+                    (HashSet::from([name.clone()]), false)
+                }
+                None => (HashSet::new(), false),
+            },
+            Regex::Symbol(symbol) => (HashSet::from([symbol.clone()]), false),
+            Regex::Concat(ops) => {
+                let mut first = HashSet::new();
+                for op in ops {
+                    let (op_first, nullable) = self.get(op);
+                    first.extend(op_first);
+                    if !nullable {
+                        return (first, false);
+                    }
+                }
+                (first, true)
+            }
+            Regex::Alternation(ops) => {
+                let mut first = HashSet::new();
+                let mut nullable = false;
+                for op in ops {
+                    let (op_first, op_nullable) = self.get(op);
+                    first.extend(op_first);
+                    nullable |= op_nullable;
+                }
+                (first, nullable)
+            }
+            Regex::Optional(op) | Regex::Star(op) => (self.get(op).0, true),
+            Regex::Plus(op) => self.get(op),
+            Regex::Bounded(op, min, _) => {
+                let (first, nullable) = self.get(op);
+                (first, nullable || *min == 0)
+            }
+            Regex::Predicate(_) | Regex::NegativePredicate(_) => (HashSet::new(), true),
+        }
+    }
+}
+
+/// Checks if the `regex` starts with a syntactic predicate, which resolves the
+/// choice like the ordered choice of pest.
+fn starts_with_predicate(regex: &Regex) -> bool {
+    match regex {
+        Regex::Predicate(_) | Regex::NegativePredicate(_) => true,
+        Regex::Concat(ops) => ops.first().is_some_and(starts_with_predicate),
+        _ => false,
+    }
+}
+
+/// Reports ordered choices whose meaning changes in an LL(1) grammar, as the
+/// alternatives are not tried in order.
+fn check_choices(choices: &[Choice], rules: &[Rule], diags: &mut Vec<Diagnostic>) {
+    let sets = FirstSets::new(rules);
+    for choice in choices {
+        let alternatives: Vec<_> = choice
+            .iter()
+            .map(|(regex, span)| {
+                let predicated = regex.as_ref().is_some_and(starts_with_predicate);
+                let (first, nullable) = regex
+                    .as_ref()
+                    .map_or((HashSet::new(), true), |regex| sets.get(regex));
+                (first, nullable, predicated, span)
+            })
+            .collect();
+        for (i, (first, nullable, predicated, span)) in alternatives.iter().enumerate() {
+            if *predicated {
+                continue;
+            }
+            if let Some((_, _, _, other)) = alternatives[..i]
+                .iter()
+                .find(|(other, _, predicated, _)| !predicated && !other.is_disjoint(first))
+            {
+                diags.push(
+                    Diagnostic::warning()
+                        .with_message("alternatives of ordered choice start with the same token")
+                        .with_labels(vec![
+                            Label::primary((), (*span).clone()),
+                            Label::secondary((), (*other).clone())
+                                .with_message("previous alternative"),
+                        ])
+                        .with_notes(vec![
+                            "note: pest tries the alternatives in order, while an LL(1) parser chooses one by the next token".to_string(),
+                            "help: left factor the alternatives or resolve the choice with a predicate".to_string(),
+                        ]),
+                );
+            }
+            if *nullable && i + 1 < alternatives.len() {
+                diags.push(
+                    Diagnostic::warning()
+                        .with_message("alternative of ordered choice may match the empty string")
+                        .with_labels(vec![Label::primary((), (*span).clone())])
+                        .with_notes(vec![
+                            "note: pest never tries the following alternatives, while an LL(1) parser chooses them by the next token".to_string(),
+                        ]),
+                );
+            }
+        }
+    }
+}
+
+/// Reports string alternatives of ordered choices that are preceded by a
+/// prefix, as pest never matches them.
+fn check_prefixes(node: &Node, diags: &mut Vec<Diagnostic>) {
+    node.visit(&mut |node| {
+        let Expr::Choice(nodes) = &node.expr else {
+            return;
+        };
+        for (i, node) in nodes.iter().enumerate() {
+            let Expr::Str(value, _) = &node.expr else {
+                continue;
+            };
+            let prefix = nodes[..i].iter().find(|prefix| {
+                matches!(&prefix.expr, Expr::Str(prefix, _) if value.starts_with(prefix.as_str()))
+            });
+            if let Some(prefix) = prefix {
+                diags.push(
+                    Diagnostic::warning()
+                        .with_message("alternative of ordered choice is preceded by its prefix")
+                        .with_labels(vec![
+                            Label::primary((), node.span.clone()),
+                            Label::secondary((), prefix.span.clone()).with_message("prefix"),
+                        ])
+                        .with_notes(vec![
+                            "note: pest never matches the alternative, while the lexer matches the longest token".to_string(),
+                        ]),
+                );
+            }
+        }
+    });
+}
+
+/// Translates the pest grammar `source` to a lelwel grammar.
+///
+/// Atomic rules, rules that match character classes, and the `WHITESPACE`
+/// and `COMMENT` rules are translated to tokens with a regex for the lexer,
+/// where the `WHITESPACE` and `COMMENT` tokens are skipped. Rules that are
+/// only used by other tokens are included in their regex. Ordered choices
+/// whose meaning changes in an LL(1) grammar are reported.
+pub fn translate(source: &str, diags: &mut Vec<Diagnostic>) -> Grammar {
+    let mut parser = PestParser::new(source, diags);
+    let pest_rules = parser.rules(diags);
+    if pest_rules.is_empty() {
+        diags.push(Diagnostic::error().with_message("the grammar has no rules"));
+        return Grammar::default();
+    }
+    let rules: HashMap<&str, &PestRule> = pest_rules.iter().map(|rule| (rule.name, rule)).collect();
+    let lexical: HashSet<&str> = pest_rules
+        .iter()
+        .filter(|rule| {
+            SKIPPED_RULES.contains(&rule.name) || rule.is_atomic() || rule.node.matches_characters()
+        })
+        .map(|rule| rule.name)
+        .collect();
+    // lexical rules are tokens, unless they are only referenced by tokens
+    let mut syntactic_references = HashSet::new();
+    let mut lexical_references = HashSet::new();
+    for rule in pest_rules.iter() {
+        rule.node.visit(&mut |node| {
+            if let Expr::Ident(name) = node.expr {
+                if lexical.contains(rule.name) {
+                    lexical_references.insert(name);
+                } else {
+                    syntactic_references.insert(name);
+                }
+            }
+        });
+    }
+    let is_token = |rule: &PestRule| {
+        lexical.contains(rule.name)
+            && (SKIPPED_RULES.contains(&rule.name)
+                || syntactic_references.contains(rule.name)
+                || !lexical_references.contains(rule.name))
+    };
+
+    let mut names = Names::default();
+    let mut literals = vec![];
+    for rule in pest_rules
+        .iter()
+        .filter(|rule| !lexical.contains(rule.name))
+    {
+        rule.node.visit(&mut |node| {
+            if let Expr::Str(value, insensitive) = &node.expr {
+                if !literals.contains(&(value.clone(), *insensitive)) {
+                    literals.push((value.clone(), *insensitive));
+                }
+            }
+        });
+    }
+    for (value, insensitive) in literals.iter() {
+        if let Some(name) = token_symbol(value)
+            .filter(|_| !insensitive)
+            .and_then(|symbol| GrammarSource::implicit_token_name(&symbol))
+        {
+            names.reserve(name);
+        }
+    }
+
+    let mut grammar = Grammar::default();
+    let mut translator = Translator {
+        tokens: HashMap::new(),
+        names: HashMap::new(),
+        literals: HashMap::new(),
+        choices: vec![],
+    };
+    let whitespace = rules.contains_key("WHITESPACE");
+    for rule in pest_rules.iter() {
+        if !lexical.contains(rule.name) {
+            translator.names.insert(rule.name, names.rule(rule.name));
+            continue;
+        }
+        check_prefixes(&rule.node, diags);
+        if !is_token(rule) {
+            continue;
+        }
+        let name = names.token(rule.name);
+        let mut token = TokenDecl::new(name.clone(), None);
+        match &rule.node.expr {
+            Expr::Str(value, insensitive) if token_symbol(value).is_some() => {
+                token.symbol = token_symbol(value);
+                if *insensitive {
+                    token.attributes.push("ignore_case");
+                }
+                translator
+                    .literals
+                    .insert((value.clone(), *insensitive), Regex::Name(name.clone()));
+            }
+            _ => {
+                let mut lexer = LexerRegex {
+                    rules: &rules,
+                    stack: vec![rule.name],
+                };
+                match lexer.regex(&rule.node) {
+                    Ok((regex, _)) => token.regex = Some(regex),
+                    Err((span, construct)) => diags.push(
+                        Diagnostic::warning()
+                            .with_message(format!("regex of token `{name}` cannot be derived"))
+                            .with_labels(vec![Label::primary((), span)])
+                            .with_notes(vec![format!("note: a regex cannot match {construct}")]),
+                    ),
+                }
+            }
+        }
+        if whitespace
+            && !rule.is_atomic()
+            && !SKIPPED_RULES.contains(&rule.name)
+            && matches!(
+                rule.node.expr,
+                Expr::Seq(_) | Expr::Star(_) | Expr::Plus(_) | Expr::Repeat(..)
+            )
+        {
+            diags.push(
+                Diagnostic::warning()
+                    .with_message(format!("non-atomic rule `{}` is imported as a token", rule.name))
+                    .with_labels(vec![Label::primary((), rule.span.clone())])
+                    .with_notes(vec![
+                        "note: the token does not skip whitespace between its elements, as the rule does in pest".to_string(),
+                    ]),
+            );
+        }
+        if SKIPPED_RULES.contains(&rule.name) {
+            grammar.skip.push(name.clone());
+        }
+        grammar.tokens.push(token);
+        translator.tokens.insert(rule.name, name);
+    }
+    for (value, insensitive) in literals {
+        if translator
+            .literals
+            .contains_key(&(value.clone(), insensitive))
+        {
+            continue;
+        }
+        let symbol = token_symbol(&value);
+        let regex = match symbol
+            .as_ref()
+            .filter(|_| !insensitive)
+            .and_then(|symbol| GrammarSource::implicit_token_name(symbol))
+        {
+            Some(_) => Regex::Symbol(symbol.unwrap()),
+            None => {
+                let mut token = match &symbol {
+                    Some(symbol) => TokenDecl::new(
+                        names.token(
+                            &GrammarSource::implicit_token_name(symbol)
+                                .unwrap_or("literal".to_string()),
+                        ),
+                        Some(symbol.clone()),
+                    ),
+                    None => {
+                        let (name, description) = description(&value);
+                        let mut token = TokenDecl::new(names.token(name), Some(description));
+                        token.regex = Some(value.chars().map(escape_char).collect());
+                        token
+                    }
+                };
+                if insensitive {
+                    token.attributes.push("ignore_case");
+                }
+                let name = Regex::Name(token.name.clone());
+                grammar.tokens.push(token);
+                name
+            }
+        };
+        translator.literals.insert((value, insensitive), regex);
+    }
+
+    for rule in pest_rules
+        .iter()
+        .filter(|rule| !lexical.contains(rule.name))
+    {
+        check_prefixes(&rule.node, diags);
+        let regex = translator
+            .regex(&rule.node, diags)
+            .unwrap_or(Regex::Concat(vec![]));
+        grammar.rules.push(Rule {
+            name: translator.names[rule.name].clone(),
+            regex,
+        });
+    }
+    check_choices(&translator.choices, &grammar.rules, diags);
+
+    // the start rule is the rule that matches the start of the input, or a
+    // rule that is not referenced
+    let mut soi = None;
+    for rule in pest_rules
+        .iter()
+        .filter(|rule| !lexical.contains(rule.name))
+    {
+        rule.node.visit(&mut |node| {
+            if matches!(node.expr, Expr::Ident("SOI")) {
+                soi.get_or_insert(rule.name);
+            }
+        });
+    }
+    let start = soi
+        .or_else(|| {
+            pest_rules
+                .iter()
+                .map(|rule| rule.name)
+                .find(|name| !lexical.contains(name) && !syntactic_references.contains(name))
+        })
+        .and_then(|name| translator.names.get(name))
+        .or(grammar.rules.first().map(|rule| &rule.name))
+        .cloned();
+    if let Some(start) = start {
+        grammar.set_start(start, &mut names);
+    }
+    grammar
+}
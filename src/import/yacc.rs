@@ -0,0 +1,787 @@
+use std::collections::{HashMap, HashSet};
+
+use codespan_reporting::diagnostic::Label;
+use logos::{Lexer, Logos};
+
+use super::{
+    parse_block_comment, parse_literal, Grammar, LexerError, Names, Regex, Rule, TokenDecl,
+};
+use crate::frontend::parser::{Diagnostic, Span};
+use crate::frontend::source::GrammarSource;
+
+fn parse_prologue(lexer: &mut Lexer<'_, Token>) -> Result<(), LexerError> {
+    if lexer
+        .remainder()
+        .find("%}")
+        .map(|i| lexer.bump(i + 2))
+        .is_some()
+    {
+        Ok(())
+    } else {
+        lexer.bump(lexer.remainder().len());
+        Err(LexerError::UnterminatedCode)
+    }
+}
+
+/// Skips a braced block of C code, which may contain braces in nested blocks,
+/// literals, and comments.
+fn parse_code(lexer: &mut Lexer<'_, Token>) -> Result<(), LexerError> {
+    let remainder = lexer.remainder();
+    let bytes = remainder.as_bytes();
+    let mut depth = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    lexer.bump(i + 1);
+                    return Ok(());
+                }
+            }
+            quote @ (b'\'' | b'"') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote && bytes[i] != b'\n' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => match remainder[i + 2..].find("*/") {
+                Some(j) => i += j + 3,
+                None => break,
+            },
+            b'/' if bytes.get(i + 1) == Some(&b'/') => match remainder[i..].find('\n') {
+                Some(j) => i += j,
+                None => break,
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+    lexer.bump(remainder.len());
+    Err(LexerError::UnterminatedCode)
+}
+
+#[derive(Logos, Debug, Clone, Copy, PartialEq)]
+#[logos(error = LexerError)]
+#[logos(skip r"[ \t\r\n\f]+")]
+#[logos(skip r"//[^\n]*")]
+enum Token {
+    #[token("/*", parse_block_comment)]
+    Comment,
+    #[token("%%")]
+    Separator,
+    #[token("%{", parse_prologue)]
+    Prologue,
+    #[token("{", parse_code)]
+    #[token("%?{", parse_code)]
+    Code,
+    #[regex(r"%[a-zA-Z][a-zA-Z0-9_-]*")]
+    Directive,
+    #[regex(r"[a-zA-Z_.][a-zA-Z0-9_.]*")]
+    Id,
+    #[regex(r"\[[a-zA-Z_.][a-zA-Z0-9_.-]*\]")]
+    NamedRef,
+    #[token("'", parse_literal)]
+    Char,
+    #[token("\"", parse_literal)]
+    Str,
+    #[regex(r"<[^<>\n]*>")]
+    Tag,
+    #[regex(r"[0-9]+")]
+    #[regex(r"0[xX][0-9a-fA-F]+")]
+    Number,
+    #[token(":")]
+    Colon,
+    #[token(";")]
+    Semi,
+    #[token("|")]
+    Pipe,
+    #[token("=")]
+    Equal,
+}
+
+/// Symbol in the right hand side of a yacc rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Symbol<'a> {
+    Id(&'a str),
+    Char(&'a str),
+    Str(&'a str),
+}
+
+/// All rules of a nonterminal, which may be split into multiple groups.
+struct Production<'a> {
+    name: &'a str,
+    span: Span,
+    alternatives: Vec<Vec<(Symbol<'a>, Span)>>,
+}
+
+#[derive(Default)]
+struct Declarations<'a> {
+    /// Declared tokens in the order of their first declaration.
+    tokens: Vec<&'a str>,
+    /// String literal aliases of the tokens.
+    aliases: HashMap<&'a str, &'a str>,
+    /// Tokens with the number 0, which denote the end of the input.
+    end: HashSet<&'a str>,
+    /// Symbols of the precedence declarations.
+    operators: HashSet<&'a str>,
+    right: Vec<Symbol<'a>>,
+    /// Span of the first precedence declaration.
+    precedence: Option<Span>,
+    start: Option<(&'a str, Span)>,
+}
+
+impl<'a> Declarations<'a> {
+    fn declare(&mut self, name: &'a str) {
+        if !self.tokens.contains(&name) {
+            self.tokens.push(name);
+        }
+    }
+}
+
+struct YaccParser<'a> {
+    source: &'a str,
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+}
+
+impl<'a> YaccParser<'a> {
+    /// Tokenizes the declarations and rules of the `source`, where the
+    /// epilogue after the second `%%` is not tokenized, as it only contains
+    /// C code.
+    fn new(source: &'a str, diags: &mut Vec<Diagnostic>) -> Self {
+        let mut tokens = vec![];
+        let mut separators = 0;
+        let mut lexer = Token::lexer(source);
+        while let Some(token) = lexer.next() {
+            match token {
+                Ok(Token::Comment) => {}
+                Ok(Token::Separator) if separators == 1 => break,
+                Ok(token) => {
+                    separators += usize::from(token == Token::Separator);
+                    tokens.push((token, lexer.span()));
+                }
+                Err(err) => diags.push(err.into_diagnostic(lexer.span())),
+            }
+        }
+        Self {
+            source,
+            tokens,
+            pos: 0,
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<Token> {
+        self.tokens.get(self.pos + offset).map(|(token, _)| *token)
+    }
+
+    fn span(&self) -> Span {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.source.len()..self.source.len(), |(_, span)| {
+                span.clone()
+            })
+    }
+
+    fn text(&self) -> &'a str {
+        &self.source[self.span()]
+    }
+
+    fn unexpected(&self, diags: &mut Vec<Diagnostic>, expected: &str) {
+        diags.push(
+            Diagnostic::error()
+                .with_message(format!("invalid syntax, expected {expected}"))
+                .with_labels(vec![Label::primary((), self.span())]),
+        );
+    }
+
+    fn declarations(&mut self, diags: &mut Vec<Diagnostic>) -> Declarations<'a> {
+        let mut decls = Declarations::default();
+        loop {
+            match self.peek(0) {
+                Some(Token::Separator) => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(Token::Directive) => self.directive(&mut decls, diags),
+                Some(Token::Prologue | Token::Code) => self.pos += 1,
+                Some(_) => {
+                    self.unexpected(diags, "a declaration");
+                    self.pos += 1;
+                }
+                None => {
+                    self.unexpected(diags, "`%%` before the rules");
+                    break;
+                }
+            }
+        }
+        decls
+    }
+
+    fn directive(&mut self, decls: &mut Declarations<'a>, diags: &mut Vec<Diagnostic>) {
+        let directive = self.text();
+        let span = self.span();
+        self.pos += 1;
+        match directive {
+            "%token" => {
+                let mut last = None;
+                while let Some(token) = self.peek(0) {
+                    match token {
+                        Token::Id => {
+                            decls.declare(self.text());
+                            last = Some(self.text());
+                        }
+                        Token::Number if self.text().trim_start_matches("0x") == "0" => {
+                            decls.end.extend(last);
+                        }
+                        Token::Str => {
+                            if let Some(name) = last {
+                                decls.aliases.insert(name, self.text());
+                            }
+                        }
+                        Token::Tag | Token::Number | Token::Char => {}
+                        _ => break,
+                    }
+                    self.pos += 1;
+                }
+            }
+            "%left" | "%right" | "%nonassoc" | "%precedence" => {
+                decls.precedence.get_or_insert(span);
+                while let Some(token) = self.peek(0) {
+                    let symbol = match token {
+                        Token::Id => {
+                            decls.operators.insert(self.text());
+                            Symbol::Id(self.text())
+                        }
+                        Token::Char => Symbol::Char(self.text()),
+                        Token::Str => Symbol::Str(self.text()),
+                        Token::Tag | Token::Number => {
+                            self.pos += 1;
+                            continue;
+                        }
+                        _ => break,
+                    };
+                    if directive == "%right" {
+                        decls.right.push(symbol);
+                    }
+                    self.pos += 1;
+                }
+            }
+            "%start" => {
+                if self.peek(0) == Some(Token::Id) {
+                    decls.start = Some((self.text(), self.span()));
+                    self.pos += 1;
+                } else {
+                    self.unexpected(diags, "the name of the start rule");
+                }
+            }
+            _ => {
+                // the arguments of other directives only concern the generated code
+                while !matches!(
+                    self.peek(0),
+                    Some(Token::Directive | Token::Separator) | None
+                ) {
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Checks if the token at the `offset` starts a rule, as the terminating
+    /// semicolon of a rule is optional.
+    fn is_rule_start(&self, offset: usize) -> bool {
+        self.peek(offset) == Some(Token::Id)
+            && (self.peek(offset + 1) == Some(Token::Colon)
+                || self.peek(offset + 1) == Some(Token::NamedRef)
+                    && self.peek(offset + 2) == Some(Token::Colon))
+    }
+
+    fn rules(&mut self, diags: &mut Vec<Diagnostic>) -> Vec<Production<'a>> {
+        let mut productions: Vec<Production> = vec![];
+        while self.peek(0).is_some() {
+            if !self.is_rule_start(0) {
+                self.unexpected(diags, "a rule");
+                self.pos += 1;
+                while self.peek(0).is_some() && !self.is_rule_start(0) {
+                    self.pos += 1;
+                }
+                continue;
+            }
+            let (name, span) = (self.text(), self.span());
+            self.pos += if self.peek(1) == Some(Token::NamedRef) {
+                3
+            } else {
+                2
+            };
+            let alternatives = self.alternatives(diags);
+            match productions.iter_mut().find(|p| p.name == name) {
+                Some(production) => production.alternatives.extend(alternatives),
+                None => productions.push(Production {
+                    name,
+                    span,
+                    alternatives,
+                }),
+            }
+        }
+        productions
+    }
+
+    fn alternatives(&mut self, diags: &mut Vec<Diagnostic>) -> Vec<Vec<(Symbol<'a>, Span)>> {
+        let mut alternatives = vec![vec![]];
+        while let Some(token) = self.peek(0) {
+            let symbol = match token {
+                Token::Id if self.is_rule_start(0) => break,
+                Token::Id => Symbol::Id(self.text()),
+                Token::Char => Symbol::Char(self.text()),
+                Token::Str => Symbol::Str(self.text()),
+                Token::Pipe => {
+                    alternatives.push(vec![]);
+                    self.pos += 1;
+                    continue;
+                }
+                Token::Semi => {
+                    self.pos += 1;
+                    break;
+                }
+                Token::Code | Token::NamedRef => {
+                    // actions and named references are not translated
+                    self.pos += 1;
+                    continue;
+                }
+                Token::Directive => {
+                    match self.text() {
+                        "%empty" => self.pos += 1,
+                        "%prec" | "%dprec" | "%merge" | "%expect" | "%expect-rr" => self.pos += 2,
+                        _ => {
+                            self.unexpected(diags, "a symbol");
+                            self.pos += 1;
+                        }
+                    }
+                    continue;
+                }
+                _ => {
+                    self.unexpected(diags, "a symbol");
+                    self.pos += 1;
+                    continue;
+                }
+            };
+            alternatives.last_mut().unwrap().push((symbol, self.span()));
+            self.pos += 1;
+        }
+        alternatives
+    }
+}
+
+/// Token symbol in the grammar syntax for a character or string `literal`,
+/// which does not exist if the literal contains other escape sequences than
+/// `\'`, `\"`, and `\\`.
+fn token_symbol(literal: &str) -> Option<String> {
+    let mut symbol = "'".to_string();
+    let mut it = literal[1..literal.len() - 1].chars();
+    while let Some(c) = it.next() {
+        match c {
+            '\\' => match it.next() {
+                Some('"') => symbol.push('"'),
+                Some(c @ ('\'' | '\\')) => {
+                    symbol.push('\\');
+                    symbol.push(c);
+                }
+                _ => return None,
+            },
+            '\'' => symbol.push_str("\\'"),
+            c => symbol.push(c),
+        }
+    }
+    symbol.push('\'');
+    Some(symbol)
+}
+
+/// Token description for a character or string `literal`.
+fn description(literal: &str) -> String {
+    let text = escape_name(literal).map_or_else(
+        || {
+            literal[1..literal.len() - 1]
+                .replace('\\', "\\\\")
+                .replace('\'', "\\'")
+        },
+        str::to_string,
+    );
+    format!("'<{text}>'")
+}
+
+/// Name of the character of a `literal` that only consists of an escape
+/// sequence.
+fn escape_name(literal: &str) -> Option<&'static str> {
+    match &literal[1..literal.len() - 1] {
+        "\\n" => Some("newline"),
+        "\\t" => Some("tab"),
+        "\\r" => Some("carriage return"),
+        "\\f" => Some("form feed"),
+        "\\v" => Some("vertical tab"),
+        "\\0" => Some("null character"),
+        _ => None,
+    }
+}
+
+/// Translated rule, whose empty alternatives are removed.
+struct Translation {
+    name: String,
+    span: Span,
+    alternatives: Vec<Vec<Regex>>,
+    empty: bool,
+}
+
+impl Translation {
+    fn regex(self) -> Regex {
+        let mut alternatives: Vec<_> = self.alternatives.into_iter().map(Regex::Concat).collect();
+        let regex = match alternatives.len() {
+            0 => return Regex::Concat(vec![]),
+            1 => alternatives.pop().unwrap(),
+            _ => Regex::Alternation(alternatives),
+        };
+        if self.empty {
+            Regex::Optional(Box::new(regex))
+        } else {
+            regex
+        }
+    }
+}
+
+/// Translates the yacc or bison grammar `source` to a lelwel grammar.
+///
+/// Actions, types, and precedence declarations are not translated, and
+/// alternatives with the `error` token are removed. Left recursive rules that
+/// are not supported by lelwel are reported, as they must be rewritten.
+pub fn translate(source: &str, diags: &mut Vec<Diagnostic>) -> Grammar {
+    let mut parser = YaccParser::new(source, diags);
+    let decls = parser.declarations(diags);
+    let productions = parser.rules(diags);
+    if productions.is_empty() {
+        diags.push(Diagnostic::error().with_message("the grammar has no rules"));
+        return Grammar::default();
+    }
+
+    let mut grammar = Grammar::default();
+    let mut names = Names::default();
+    let aliased: HashMap<&str, &str> = decls
+        .aliases
+        .iter()
+        .map(|(name, alias)| (*alias, *name))
+        .collect();
+    // literals without an implicit token must be declared
+    let mut undeclared = vec![];
+    for (symbol, _) in productions
+        .iter()
+        .flat_map(|p| p.alternatives.iter().flatten())
+    {
+        let (Symbol::Char(literal) | Symbol::Str(literal)) = symbol else {
+            continue;
+        };
+        if aliased.contains_key(literal) || undeclared.iter().any(|(other, _)| other == literal) {
+            continue;
+        }
+        match token_symbol(literal) {
+            Some(symbol) => match GrammarSource::implicit_token_name(&symbol) {
+                Some(name) => names.reserve(name),
+                None => undeclared.push((*literal, Some(symbol))),
+            },
+            None => undeclared.push((*literal, None)),
+        }
+    }
+    let rules: HashMap<&str, String> = productions
+        .iter()
+        .map(|p| (p.name, names.rule(p.name)))
+        .collect();
+    let mut tokens: HashMap<&str, String> = HashMap::new();
+    for name in decls.tokens.iter() {
+        if *name == "error" || decls.end.contains(name) || rules.contains_key(name) {
+            continue;
+        }
+        let token = names.token(name);
+        // aliases with whitespace are descriptions rather than symbols
+        let symbol = decls
+            .aliases
+            .get(name)
+            .map(|alias| match token_symbol(alias) {
+                Some(symbol) if !alias.contains(char::is_whitespace) => symbol,
+                _ => description(alias),
+            });
+        grammar.tokens.push(TokenDecl::new(token.clone(), symbol));
+        tokens.insert(name, token);
+    }
+    for (symbol, span) in productions
+        .iter()
+        .flat_map(|p| p.alternatives.iter().flatten())
+    {
+        let Symbol::Id(name) = *symbol else {
+            continue;
+        };
+        if name == "error"
+            || decls.end.contains(name)
+            || rules.contains_key(name)
+            || tokens.contains_key(name)
+        {
+            continue;
+        }
+        // symbols of precedence declarations are tokens in yacc
+        if !decls.operators.contains(name) {
+            diags.push(
+                Diagnostic::warning()
+                    .with_message(format!("undeclared symbol `{name}` is imported as a token"))
+                    .with_labels(vec![Label::primary((), span.clone())]),
+            );
+        }
+        let token = names.token(name);
+        grammar.tokens.push(TokenDecl::new(token.clone(), None));
+        tokens.insert(name, token);
+    }
+    let mut literals: HashMap<&str, String> = HashMap::new();
+    for (literal, symbol) in undeclared {
+        let (token, symbol) = match symbol {
+            Some(symbol) => (names.token("literal"), symbol),
+            None => (
+                names.token(escape_name(literal).unwrap_or("literal")),
+                description(literal),
+            ),
+        };
+        grammar
+            .tokens
+            .push(TokenDecl::new(token.clone(), Some(symbol)));
+        literals.insert(literal, token);
+    }
+
+    let translate_symbol = |symbol: &Symbol| match symbol {
+        Symbol::Id(name) if decls.end.contains(name) => None,
+        Symbol::Id(name) => Some(Regex::Name(rules.get(name).or(tokens.get(name))?.clone())),
+        Symbol::Str(literal) if aliased.contains_key(literal) => {
+            Some(Regex::Name(tokens.get(aliased[literal])?.clone()))
+        }
+        Symbol::Char(literal) | Symbol::Str(literal) => match literals.get(literal) {
+            Some(token) => Some(Regex::Name(token.clone())),
+            None => Some(Regex::Symbol(token_symbol(literal)?)),
+        },
+    };
+    let mut translations = vec![];
+    for production in productions.iter() {
+        let mut translation = Translation {
+            name: rules[production.name].clone(),
+            span: production.span.clone(),
+            alternatives: vec![],
+            empty: false,
+        };
+        for alternative in production.alternatives.iter() {
+            if let Some((_, span)) = alternative
+                .iter()
+                .find(|(symbol, _)| *symbol == Symbol::Id("error"))
+            {
+                diags.push(
+                    Diagnostic::warning()
+                        .with_message("alternative with `error` token is removed")
+                        .with_labels(vec![Label::primary((), span.clone())])
+                        .with_notes(vec![
+                            "note: the generated parser recovers from syntax errors without error rules"
+                                .to_string(),
+                        ]),
+                );
+                continue;
+            }
+            let ops: Vec<_> = alternative
+                .iter()
+                .filter_map(|(symbol, _)| translate_symbol(symbol))
+                .collect();
+            if ops.is_empty() {
+                translation.empty = true;
+            } else if !translation.alternatives.contains(&ops) {
+                translation.alternatives.push(ops);
+            }
+        }
+        translations.push(translation);
+    }
+    check_left_recursion(&translations, diags);
+
+    let start = match decls.start {
+        Some((name, span)) => match rules.get(name) {
+            Some(start) => start.clone(),
+            None => {
+                diags.push(
+                    Diagnostic::error()
+                        .with_message(format!("undefined start rule `{name}`"))
+                        .with_labels(vec![Label::primary((), span)]),
+                );
+                translations[0].name.clone()
+            }
+        },
+        None => translations[0].name.clone(),
+    };
+    grammar.rules = translations
+        .into_iter()
+        .map(|translation| Rule {
+            name: translation.name.clone(),
+            regex: translation.regex(),
+        })
+        .collect();
+    grammar.set_start(start, &mut names);
+    grammar.right = decls
+        .right
+        .iter()
+        .filter_map(|symbol| match translate_symbol(symbol)? {
+            Regex::Name(name) | Regex::Symbol(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+    if let Some(span) = decls.precedence {
+        diags.push(
+            Diagnostic::warning()
+                .with_message("precedence declarations are not imported")
+                .with_labels(vec![Label::primary((), span)])
+                .with_notes(vec![
+                    "note: the precedence of the operators of a rule is given by the order of its alternatives".to_string(),
+                    "note: right associative operators are imported as a `right` declaration".to_string(),
+                ]),
+        );
+    }
+    grammar
+}
+
+/// Reports left recursion that lelwel does not support, which is indirect
+/// left recursion and direct left recursion that is not in the form of a left
+/// recursive rule.
+fn check_left_recursion(translations: &[Translation], diags: &mut Vec<Diagnostic>) {
+    let index: HashMap<&str, usize> = translations
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.name.as_str(), i))
+        .collect();
+    let rule = |op: &Regex| match op {
+        Regex::Name(name) => index.get(name.as_str()).copied(),
+        _ => None,
+    };
+    let mut nullable = vec![false; translations.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (i, t) in translations.iter().enumerate() {
+            if !nullable[i]
+                && (t.empty
+                    || t.alternatives.iter().any(|alt| {
+                        alt.iter()
+                            .all(|op| rule(op).is_some_and(|rule| nullable[rule]))
+                    }))
+            {
+                nullable[i] = true;
+                changed = true;
+            }
+        }
+    }
+    // rules that can be derived at the left of each rule
+    let left: Vec<Vec<usize>> = translations
+        .iter()
+        .map(|t| {
+            let mut left = vec![];
+            for alt in t.alternatives.iter() {
+                for op in alt.iter() {
+                    let Some(rule) = rule(op) else {
+                        break;
+                    };
+                    left.push(rule);
+                    if !nullable[rule] {
+                        break;
+                    }
+                }
+            }
+            left
+        })
+        .collect();
+    let reachable: Vec<HashSet<usize>> = (0..translations.len())
+        .map(|i| {
+            let mut reachable = HashSet::new();
+            let mut stack = left[i].clone();
+            while let Some(rule) = stack.pop() {
+                if reachable.insert(rule) {
+                    stack.extend(left[rule].iter().copied());
+                }
+            }
+            reachable
+        })
+        .collect();
+
+    let mut reported = vec![false; translations.len()];
+    for (i, t) in translations.iter().enumerate() {
+        if reported[i] || !reachable[i].contains(&i) {
+            continue;
+        }
+        let cycle: Vec<usize> = (0..translations.len())
+            .filter(|&j| j == i || reachable[i].contains(&j) && reachable[j].contains(&i))
+            .collect();
+        if cycle.len() > 1 {
+            let names: Vec<_> = cycle
+                .iter()
+                .map(|&j| format!("`{}`", translations[j].name))
+                .collect();
+            diags.push(
+                Diagnostic::warning()
+                    .with_message(format!(
+                        "indirect left recursion of rules {}",
+                        names.join(", ")
+                    ))
+                    .with_labels(
+                        cycle
+                            .iter()
+                            .map(|&j| {
+                                let span = translations[j].span.clone();
+                                if j == i {
+                                    Label::primary((), span)
+                                } else {
+                                    Label::secondary((), span)
+                                }
+                            })
+                            .collect(),
+                    )
+                    .with_notes(vec![
+                        "note: only left recursion of a rule to itself is supported".to_string(),
+                    ]),
+            );
+            for j in cycle {
+                reported[j] = true;
+            }
+            continue;
+        }
+        // the recursive alternatives must start with the rule, and there must
+        // be a non-recursive alternative that is not empty
+        let recursive = |alt: &Vec<Regex>| {
+            let prefix = alt
+                .iter()
+                .position(|op| !rule(op).is_some_and(|rule| nullable[rule]))
+                .map_or(alt.len(), |pos| pos + 1);
+            alt[..prefix].iter().any(|op| rule(op) == Some(i))
+        };
+        let supported = !t.empty
+            && t.alternatives.iter().any(|alt| !recursive(alt))
+            && t.alternatives
+                .iter()
+                .filter(|alt| recursive(alt))
+                .all(|alt| rule(&alt[0]) == Some(i));
+        if !supported {
+            let mut notes = vec![
+                "note: a left recursive rule needs a non-recursive alternative that is not empty"
+                    .to_string(),
+            ];
+            if t.empty {
+                notes.push(format!(
+                    "help: a rule like `{0}: %empty | {0} x;` can be written as `{0}: x*;`",
+                    t.name
+                ));
+            }
+            diags.push(
+                Diagnostic::warning()
+                    .with_message(format!("unsupported left recursion of rule `{}`", t.name))
+                    .with_labels(vec![Label::primary((), t.span.clone())])
+                    .with_notes(notes),
+            );
+        }
+    }
+}
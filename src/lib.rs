@@ -1,35 +1,116 @@
 #![forbid(unsafe_code)]
+// `Option::is_none_or` is not available before Rust 1.82
+#![allow(clippy::unnecessary_map_or)]
 
-use std::path::Path;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 
-use codespan_reporting::diagnostic::Severity;
-use codespan_reporting::files::SimpleFile;
-use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
+use codespan_reporting::diagnostic::{LabelStyle, Severity};
+use codespan_reporting::term::termcolor::{
+    BufferWriter, ColorChoice, ColorSpec, StandardStream, WriteColor,
+};
 use codespan_reporting::term::{self, DisplayStyle};
 use logos::Logos;
 
-use backend::rust::RustOutput;
+use backend::rust::{RustOutput, Visibility};
+use frontend::compat::Compat;
+use frontend::diag::{deduplicate, sort_by_position};
+use frontend::interpreter::{Interpreter, Trace};
+use frontend::lexer::GrammarLexer;
+use frontend::lint::{LintLevels, LintPass};
 use frontend::parser::*;
 use frontend::printer::DebugPrinter;
+use frontend::report::{Report, ReportKind};
 use frontend::sema::*;
+use frontend::source::{sarif_log, GrammarSource};
 
 use self::backend::graphviz::GraphvizOutput;
 
 pub mod backend;
+pub mod config;
 pub mod frontend;
 pub mod ide;
+pub mod import;
 
 const VERSION: &str = "0.6.2";
 
+/// Options for the [`compile_with_options`] function.
+#[derive(Default, Clone)]
+pub struct Options {
+    /// Only check the grammar for errors.
+    pub check: bool,
+    /// Level of verbosity for debug output.
+    pub verbose: u8,
+    /// Output a graphviz file for the grammar.
+    pub graph: bool,
+    /// Use short diagnostics.
+    pub short: bool,
+    /// Print diagnostics as JSON objects, one per line.
+    pub json: bool,
+    /// Print diagnostics as a SARIF log on stdout for code scanning tools.
+    pub sarif: bool,
+    /// Maximum number of diagnostics that are printed in the human readable
+    /// formats, where the rest is summarized in a note.
+    pub max_diagnostics: Option<usize>,
+    /// When to color the diagnostics, where `Auto` only colors them if stderr
+    /// is a terminal.
+    pub color: ColorChoice,
+    /// Print an analysis report of the grammar.
+    pub report: Option<ReportKind>,
+    /// Thresholds for the rule complexity warning.
+    pub limits: ComplexityLimits,
+    /// Levels of the grammar lints.
+    pub lints: LintLevels,
+    /// Generate a parser that records a trace for [`Options::replay`].
+    pub trace: bool,
+    /// Generate a table-driven parser instead of recursive descent functions.
+    pub table: bool,
+    /// Path of a parser trace to interpret with the grammar.
+    pub replay: Option<String>,
+    /// Print a diff of the generated files instead of writing them, and fail
+    /// if they are not up to date.
+    pub dry_run: bool,
+    /// Path of a dependency file to write, which lists the grammar and all
+    /// imported files for build systems.
+    pub depfile: Option<String>,
+    /// Path of an input file to interpret with the grammar.
+    pub run: Option<String>,
+    /// Path of an old version of the grammar to check compatibility with.
+    pub compat: Option<String>,
+    /// File name of the generated parser in the output directory, which is
+    /// `generated.rs` by default.
+    pub generated_file: Option<String>,
+    /// Path of the manually edited parser file relative to the grammar file,
+    /// which is `parser.rs` by default.
+    pub parser_file: Option<String>,
+    /// Append the generated code to the parser file instead of writing it to
+    /// a separate file in the output directory, which the parser file
+    /// includes.
+    pub single_file: bool,
+    /// Visibility of the items that the generated parser and the initial
+    /// parser file export, which are public by default.
+    pub visibility: Visibility,
+    /// Derive the serde traits for the `Token` enum and the syntax tree if the
+    /// `serde` feature of the crate is enabled.
+    pub serde: bool,
+}
+
+impl Options {
+    fn generated_file(&self) -> &str {
+        self.generated_file.as_deref().unwrap_or("generated.rs")
+    }
+    fn parser_file(&self) -> &str {
+        self.parser_file.as_deref().unwrap_or("parser.rs")
+    }
+}
+
 pub fn build(path: &str) {
-    let res = compile(
-        path,
-        &std::env::var("OUT_DIR").unwrap(),
-        false,
-        0,
-        false,
-        false,
-    );
+    build_with_options(path, &Options::default());
+}
+
+/// Same as [`build`], but with custom [`Options`] for the generated files.
+pub fn build_with_options(path: &str, options: &Options) {
+    let res = compile_with_options(path, &std::env::var("OUT_DIR").unwrap(), options);
     match res {
         Err(err) => {
             eprintln!("{err}");
@@ -39,9 +120,23 @@ pub fn build(path: &str) {
         Ok(true) => {}
     }
     println!("cargo:rerun-if-changed=build.rs");
-    println!("cargo:rerun-if-changed={path}");
+    if let Ok(source) = GrammarSource::load(path, &mut vec![]) {
+        for path in source.paths() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
 }
 
+/// Resolves the `Auto` color choice, as termcolor only checks the environment
+/// and not whether stderr is a terminal.
+fn color_choice(color: ColorChoice) -> ColorChoice {
+    match color {
+        ColorChoice::Auto if !std::io::stderr().is_terminal() => ColorChoice::Never,
+        color => color,
+    }
+}
+
+#[deprecated(note = "use `compile_with_options` instead")]
 pub fn compile(
     input: &str,
     output: &str,
@@ -49,50 +144,392 @@ pub fn compile(
     verbose: u8,
     graph: bool,
     short: bool,
+) -> std::io::Result<bool> {
+    let options = Options {
+        check,
+        verbose,
+        graph,
+        short,
+        ..Default::default()
+    };
+    compile_with_options(input, output, &options)
+}
+
+/// Same as [`compile`], but with custom [`Options`].
+pub fn compile_with_options(input: &str, output: &str, options: &Options) -> std::io::Result<bool> {
+    let writer = StandardStream::stderr(color_choice(options.color));
+    let mut results = vec![];
+    let success = compile_to(
+        input,
+        output,
+        options,
+        &mut writer.lock(),
+        &mut std::io::stdout().lock(),
+        &mut results,
+    )?;
+    if options.sarif {
+        println!("{}", sarif_log(&results, VERSION));
+    }
+    Ok(success)
+}
+
+/// Compiles multiple grammars in parallel, where each grammar is given by its
+/// input path, output directory, and options.
+///
+/// The output of each grammar, like its diagnostics or its report, is
+/// buffered and printed together in the order of the `grammars`. SARIF
+/// results of all grammars are printed as a single log.
+pub fn compile_all(grammars: &[(String, String, Options)]) -> bool {
+    let color = grammars
+        .first()
+        .map_or(ColorChoice::Auto, |(_, _, options)| options.color);
+    let writer = BufferWriter::stderr(color_choice(color));
+    let results: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = grammars
+            .iter()
+            .map(|(input, output, options)| {
+                let writer = &writer;
+                scope.spawn(move || {
+                    let mut buffer = writer.buffer();
+                    let mut out = vec![];
+                    let mut results = vec![];
+                    let success = match compile_to(
+                        input,
+                        output,
+                        options,
+                        &mut buffer,
+                        &mut out,
+                        &mut results,
+                    ) {
+                        Ok(success) => success,
+                        Err(err) => {
+                            writeln!(buffer, "error: {input}: {err}").unwrap();
+                            false
+                        }
+                    };
+                    (out, buffer, success, results)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    let mut success = true;
+    let mut sarif_results = vec![];
+    for (out, buffer, grammar_success, mut results) in results {
+        std::io::stdout().write_all(&out).unwrap();
+        writer.print(&buffer).unwrap();
+        success &= grammar_success;
+        sarif_results.append(&mut results);
+    }
+    if grammars.iter().any(|(_, _, options)| options.sarif) {
+        println!("{}", sarif_log(&sarif_results, VERSION));
+    }
+    success
+}
+
+fn compile_to(
+    input: &str,
+    output: &str,
+    options: &Options,
+    writer: &mut dyn WriteColor,
+    out: &mut dyn Write,
+    sarif_results: &mut Vec<String>,
 ) -> std::io::Result<bool> {
     let input_path = Path::new(input);
     input_path.try_exists()?;
 
-    let source = std::fs::read_to_string(input)?;
     let mut diags = vec![];
-    let (tokens, ranges) = tokenize(Token::lexer(&source), &mut diags);
-    let cst = Parser::parse(&source, tokens, ranges, &mut diags);
-    let sema = SemanticPass::run(&cst, &mut diags);
+    let grammar = GrammarSource::load(input, &mut diags)?;
+    let source = grammar.text();
+    let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+    let cst = Parser::parse(source, tokens, ranges, &mut diags);
+    let sema = SemanticPass::run_with_limits(&cst, &mut diags, options.limits);
+    if options.table {
+        SemanticPass::check_table_names(&cst, &sema, &mut diags);
+    }
+    LintPass::run(&cst, &sema, &options.lints, &mut diags);
+    deduplicate(&mut diags);
 
-    if verbose > 1 {
+    if options.verbose > 1 {
         println!("{cst}");
     }
-    if verbose > 0 {
+    if options.verbose > 0 {
         DebugPrinter::new().run(&cst, &sema);
     }
+    let mut success = true;
+    let mut config = codespan_reporting::term::Config::default();
+    if options.short {
+        config.display_style = DisplayStyle::Short;
+    }
     if !diags.iter().any(|d| d.severity == Severity::Error) {
-        if graph {
+        if let Some(kind) = options.report {
+            Report::run(&cst, &sema, &grammar, kind, out)?;
+        }
+        if let Some(path) = &options.replay {
+            let trace_source = std::fs::read_to_string(path)?;
+            let trace = Trace::parse(&trace_source)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+            match Interpreter::run(&cst, &sema, &trace) {
+                Ok(tree) => write!(out, "{tree}")?,
+                Err(err) => {
+                    for tree in err.partial.iter() {
+                        write!(out, "{tree}")?;
+                    }
+                    writeln!(writer, "error: replay of {path} failed at {err}")?;
+                    success = false;
+                }
+            }
+        }
+        if let Some(path) = &options.run {
+            success &= run_input(&cst, &sema, path, &config, writer, out)?;
+        }
+        if let Some(path) = &options.compat {
+            let mut old_diags = vec![];
+            let old_grammar = GrammarSource::load(path, &mut old_diags)?;
+            let old_source = old_grammar.text();
+            let (tokens, ranges) = tokenize(Token::lexer(old_source), &mut old_diags);
+            let old_cst = Parser::parse(old_source, tokens, ranges, &mut old_diags);
+            let old_sema = SemanticPass::run(&old_cst, &mut old_diags);
+            let mut old_success = true;
+            for diag in old_diags.iter().filter(|d| d.severity == Severity::Error) {
+                if options.sarif {
+                    sarif_results.push(old_grammar.sarif(diag, &[]));
+                } else if options.json {
+                    writeln!(writer, "{}", old_grammar.json(diag, &[]))?;
+                } else {
+                    let diag = old_grammar.diagnostic(diag);
+                    term::emit(writer, &config, old_grammar.files(), &diag).unwrap();
+                }
+                old_success = false;
+            }
+            if old_success {
+                Compat::run(old_source, &old_cst, &old_sema, source, &cst, &sema, out)?;
+            }
+            success &= old_success;
+        }
+        if options.graph {
             GraphvizOutput::run(&cst, &sema)?;
         }
-        if !check {
-            RustOutput::run(&cst, &sema, input_path, Path::new(output))?;
+        if options.dry_run {
+            success &=
+                RustOutput::diff(&cst, &sema, input_path, Path::new(output), options, writer)?;
+        } else if !options.check {
+            RustOutput::run(&cst, &sema, input_path, Path::new(output), options)?;
+            if let Some(path) = &options.depfile {
+                let target = if options.single_file {
+                    input_path.parent().unwrap().join(options.parser_file())
+                } else {
+                    Path::new(output).join(options.generated_file())
+                };
+                write_depfile(path, &target, grammar.paths())?;
+            }
         }
     }
 
-    let mut success = true;
-    let writer = StandardStream::stderr(ColorChoice::Auto);
+    sort_by_position(&mut diags);
+    // diagnostics of grammars with imports are grouped under a file header
+    let grouped = grammar.paths().len() > 1 && !options.short;
+    let mut current_file = None;
+    let mut omitted = 0;
+    for (i, mut diag) in diags.into_iter().enumerate() {
+        success &= diag.severity != Severity::Error;
+        let fixes: Vec<_> = sema
+            .fixes
+            .iter()
+            .filter(|fix| fix.applies_to(&diag))
+            .collect();
+        if options.sarif {
+            sarif_results.push(grammar.sarif(&diag, &fixes));
+        } else if options.json {
+            writeln!(writer, "{}", grammar.json(&diag, &fixes))?;
+        } else if options.max_diagnostics.is_some_and(|max| i >= max) {
+            omitted += 1;
+        } else {
+            if grouped {
+                let offset = diag
+                    .labels
+                    .iter()
+                    .find(|label| label.style == LabelStyle::Primary)
+                    .map_or(0, |label| label.range.start);
+                let file = grammar.file_name(offset);
+                if current_file != Some(file) {
+                    writer.set_color(ColorSpec::new().set_bold(true))?;
+                    write!(writer, "==> {file}")?;
+                    writer.reset()?;
+                    writeln!(writer, "\n")?;
+                    current_file = Some(file);
+                }
+            }
+            for fix in fixes {
+                diag.notes.push(format!("help: {}", fix.message));
+                // insertions are shown where the text is inserted
+                for (span, text) in fix.edits.iter().filter(|(span, _)| span.is_empty()) {
+                    diag.labels.push(
+                        codespan_reporting::diagnostic::Label::secondary((), span.clone())
+                            .with_message(format!("insert `{}`", text.trim())),
+                    );
+                }
+            }
+            term::emit(writer, &config, grammar.files(), &grammar.diagnostic(&diag)).unwrap();
+        }
+    }
+    if omitted > 0 {
+        let s = if omitted == 1 { "" } else { "s" };
+        let diag = Diagnostic::note().with_message(format!(
+            "{omitted} more diagnostic{s} omitted, use `--max-diagnostics` to show them"
+        ));
+        term::emit(writer, &config, grammar.files(), &grammar.diagnostic(&diag)).unwrap();
+    }
+    Ok(success)
+}
+
+/// Interprets the grammar on the input file at `path` and prints the syntax
+/// tree.
+fn run_input(
+    cst: &Cst,
+    sema: &SemanticData,
+    path: &str,
+    config: &term::Config,
+    writer: &mut dyn WriteColor,
+    out: &mut dyn Write,
+) -> std::io::Result<bool> {
+    use codespan_reporting::diagnostic::Label;
+    use codespan_reporting::files::SimpleFile;
+
+    let input = std::fs::read_to_string(path)?;
+    let file = SimpleFile::new(path, input.as_str());
+    let lexer = GrammarLexer::new(cst)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    let diag = match lexer.tokenize(&input) {
+        Ok((tokens, spans)) => match Interpreter::run_tokens(cst, sema, &tokens) {
+            Ok(tree) => {
+                write!(out, "{tree}")?;
+                return Ok(true);
+            }
+            Err(err) => {
+                for tree in err.partial.iter() {
+                    write!(out, "{tree}")?;
+                }
+                let span = spans
+                    .get(err.position)
+                    .cloned()
+                    .unwrap_or(input.len()..input.len());
+                Diagnostic::error()
+                    .with_message(err.message)
+                    .with_labels(vec![Label::primary((), span)])
+            }
+        },
+        Err(err) => {
+            let len = input[err.offset..].chars().next().map_or(0, char::len_utf8);
+            Diagnostic::error()
+                .with_message("invalid token")
+                .with_labels(vec![Label::primary((), err.offset..err.offset + len)])
+        }
+    };
+    term::emit(writer, config, &file, &diag).unwrap();
+    Ok(false)
+}
+
+/// Translates the grammar of another parser generator at `input` to a lelwel
+/// grammar and prints it on stdout.
+///
+/// The format of the grammar is derived from the file extension. Constructs
+/// that cannot be translated are reported as diagnostics, and the grammar is
+/// only printed if there are no errors.
+pub fn import_grammar(input: &str, options: &Options) -> std::io::Result<bool> {
+    use codespan_reporting::files::SimpleFile;
+
+    let format = import::Format::from_path(Path::new(input)).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("unknown grammar format of `{input}`"),
+        )
+    })?;
+    let source = std::fs::read_to_string(input)?;
+    let mut diags = vec![];
+    let grammar = format.translate(&source, &mut diags);
+    sort_by_position(&mut diags);
+
+    let file = SimpleFile::new(input, source.as_str());
+    let writer = StandardStream::stderr(color_choice(options.color));
     let mut config = codespan_reporting::term::Config::default();
-    let file = SimpleFile::new(input, &source);
-    if short {
+    if options.short {
         config.display_style = DisplayStyle::Short;
     }
-    for diag in diags {
-        term::emit(&mut writer.lock(), &config, &file, &diag).unwrap();
-        success &= diag.severity != Severity::Error;
+    for diag in diags.iter() {
+        term::emit(&mut writer.lock(), &config, &file, diag).unwrap();
+    }
+    let success = !diags.iter().any(|d| d.severity == Severity::Error);
+    if success {
+        print!("{grammar}");
     }
     Ok(success)
 }
 
+/// Writes a Makefile style dependency file at `path`, which lists the grammar
+/// files the `target` is generated from.
+fn write_depfile(path: &str, target: &Path, deps: &[PathBuf]) -> std::io::Result<()> {
+    let escape = |path: &Path| path.display().to_string().replace(' ', "\\ ");
+    let mut depfile = escape(target) + ":";
+    for dep in deps {
+        depfile += " ";
+        depfile += &escape(dep);
+    }
+    depfile.push('\n');
+    backend::write_if_changed(path, depfile.as_bytes())
+}
+
+/// Generates the parser for the grammar at the `input` path in memory.
+///
+/// This does not create the `parser.rs` file. If the grammar contains errors,
+/// the diagnostics are returned as the error.
+pub fn generate(input: &str, options: &Options) -> Result<String, String> {
+    use codespan_reporting::term::termcolor::NoColor;
+
+    let mut diags = vec![];
+    let grammar =
+        GrammarSource::load(input, &mut diags).map_err(|err| format!("{input}: {err}"))?;
+    let source = grammar.text();
+    let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+    let cst = Parser::parse(source, tokens, ranges, &mut diags);
+    let sema = SemanticPass::run_with_limits(&cst, &mut diags, options.limits);
+    if options.table {
+        SemanticPass::check_table_names(&cst, &sema, &mut diags);
+    }
+    LintPass::run(&cst, &sema, &options.lints, &mut diags);
+    deduplicate(&mut diags);
+
+    if diags.iter().any(|d| d.severity == Severity::Error) {
+        let mut writer = NoColor::new(vec![]);
+        let config = codespan_reporting::term::Config {
+            display_style: DisplayStyle::Short,
+            ..Default::default()
+        };
+        for diag in diags.iter().filter(|d| d.severity == Severity::Error) {
+            term::emit(
+                &mut writer,
+                &config,
+                grammar.files(),
+                &grammar.diagnostic(diag),
+            )
+            .unwrap();
+        }
+        return Err(String::from_utf8_lossy(&writer.into_inner()).into_owned());
+    }
+    let mut generated = vec![];
+    RustOutput::generate(&cst, &sema, options, &mut generated).map_err(|err| err.to_string())?;
+    Ok(String::from_utf8(generated).unwrap())
+}
+
 #[cfg(feature = "wasm")]
 #[wasm_bindgen::prelude::wasm_bindgen]
 pub fn generate_syntax_tree(source: &str) -> Vec<String> {
     use std::io::BufWriter;
 
+    use codespan_reporting::files::SimpleFile;
     use codespan_reporting::term::termcolor::NoColor;
     use codespan_reporting::term::Config;
 
@@ -114,7 +551,7 @@ pub fn generate_syntax_tree(source: &str) -> Vec<String> {
 #[cfg(feature = "wasm")]
 #[wasm_bindgen::prelude::wasm_bindgen]
 pub fn get_location(source: &str, start: usize, end: usize) -> Vec<usize> {
-    use codespan_reporting::files::Files;
+    use codespan_reporting::files::{Files, SimpleFile};
 
     let file = SimpleFile::new("<input>", source);
     let start_loc = file.location((), start).unwrap();
@@ -28,14 +28,21 @@ macro_rules! syntax_error_message {{
     }}
 }}
 
+/// Number of syntax errors after which the rest of the input is skipped.
+const MAX_ERRORS: usize = {3};
+
+/// Error of [`Parser::parse_cancellable`] if the parse was cancelled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+{4}struct Cancelled;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[allow(dead_code)]
-pub enum Rule {{
+{5}{4}enum Rule {{
     Error,{0}
 }}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
-pub struct NodeRef(pub CstIndex);
+{5}{4}struct NodeRef(pub CstIndex);
 
 impl NodeRef {{
     #[allow(dead_code)]
@@ -43,7 +50,7 @@ impl NodeRef {{
 }}
 
 #[derive(Debug, Copy, Clone)]
-pub enum Node {{
+{5}{4}enum Node {{
     Rule(Rule, CstIndex),
     Token(CstIndex),
 }}
@@ -53,7 +60,7 @@ struct MarkOpened(CstIndex);
 #[derive(Clone, Copy)]
 struct MarkClosed(CstIndex);
 
-pub struct CstChildren<'a> {{
+{4}struct CstChildren<'a> {{
     iter: std::slice::Iter<'a, Node>,
     offset: CstIndex,
 }}
@@ -79,7 +86,7 @@ impl<'a> Iterator for CstChildren<'a> {{
     }}
 }}
 
-pub struct Cst<'a> {{
+{6}{4}struct Cst<'a> {{
     source: &'a str,
     tokens: Vec<Token>,
     ranges: Vec<std::ops::Range<CstIndex>>,
@@ -118,7 +125,7 @@ impl<'a> Cst<'a> {{
     fn mark(&self) -> MarkClosed {{
         MarkClosed(self.nodes.len() as CstIndex)
     }}
-    pub fn children(&self, node: NodeRef) -> CstChildren {{
+    pub fn children(&self, node: NodeRef) -> CstChildren<'_> {{
         let iter = if let Some(Node::Rule(_, end_offset)) = self.nodes.get(node.0 as usize) {{
             self.nodes[node.0 as usize + 1..node.0 as usize + *end_offset as usize + 1].iter()
         }} else {{
@@ -135,23 +142,20 @@ impl<'a> Cst<'a> {{
     pub fn get_span(&self, node: NodeRef) -> Option<Span> {{
         match self.nodes.get(node.0 as usize) {{
             Some(Node::Token(idx)) => {{
-               let range = &self.ranges[*idx as usize];
-               Some(range.start as usize .. range.end as usize)
+                let range = &self.ranges[*idx as usize];
+                Some(range.start as usize..range.end as usize)
             }}
             Some(Node::Rule(_, _)) => self
                 .children(node)
                 .filter(|node_ref| {{
                     if let Node::Token(idx) = self.get(*node_ref) {{
-                        !matches!(
-                            self.tokens[idx as usize],
-                            Token::Error{1}
-                        )
+                        !Parser::is_skipped(self.tokens[idx as usize])
                     }} else {{
                         true
                     }}
                 }})
                 .filter_map(|n| self.get_span(n))
-                .reduce(|acc, e| acc.start.min(e.start) .. acc.end.max(e.end)),
+                .reduce(|acc, e| acc.start.min(e.start)..acc.end.max(e.end)),
             None => None,
         }}
     }}
@@ -180,6 +184,32 @@ impl<'a> Cst<'a> {{
             None
         }}
     }}
+    /// Re-emits the source text of all tokens in the syntax tree.
+    pub fn unparse(&self) -> String {{
+        let mut text = String::new();
+        for node in self.nodes.iter() {{
+            if let Node::Token(idx) = node {{
+                if let Some(range) = self.ranges.get(*idx as usize) {{
+                    text.push_str(&self.source[range.start as usize..range.end as usize]);
+                }}
+            }}
+        }}
+        text
+    }}
+    /// Checks that unparsing the syntax tree reproduces the source byte for
+    /// byte, and returns the offset of the first difference otherwise.
+    pub fn verify_round_trip(&self) -> Result<(), usize> {{
+        let text = self.unparse();
+        match text
+            .bytes()
+            .zip(self.source.bytes())
+            .position(|(a, b)| a != b)
+        {{
+            Some(offset) => Err(offset),
+            None if text.len() != self.source.len() => Err(text.len().min(self.source.len())),
+            None => Ok(()),
+        }}
+    }}
 }}
 
 #[allow(clippy::unnecessary_cast)]
@@ -221,6 +251,7 @@ impl std::fmt::Display for Cst<'_> {{
     }}
 }}
 
+#[allow(unused_macros)]
 macro_rules! expect {{
     ($tok:ident, $sym:literal, $self:expr, $diags:expr) => {{
         if let Token::$tok = $self.current {{
@@ -231,19 +262,38 @@ macro_rules! expect {{
     }};
 }}
 
-pub struct Parser<'a> {{
+#[allow(unused_macros)]
+macro_rules! expect_insertable {{
+    ($tok:ident, $sym:literal, $error:literal, $self:expr, $diags:expr) => {{
+        if let Token::$tok = $self.current {{
+            $self.advance(false);
+        }} else if $self.insert_token(Token::$tok) {{
+            if !$self.error_cooldown {{
+                let span = $self.span();
+                $diags.push(ins![span.start..span.start, $sym]);
+            }}
+        }} else {{
+            $self.error($diags, err![$self.span(), $error]);
+        }}
+    }};
+}}
+
+{4}struct Parser<'a> {{
     cst: Cst<'a>,
     pos: usize,
     current: Token,
     error_cooldown: bool,
+    error_count: usize,
     max_offset: usize,
+    cancel: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    cancelled: bool,
     #[allow(dead_code)]
     context: Context<'a>,
 }}
 #[allow(clippy::while_let_loop, dead_code)]
 impl<'a> Parser<'a> {{
-    fn error(&self, diags: &mut Vec<Diagnostic>, diag: Diagnostic) {{
-        if self.error_cooldown {{
+    fn error(&mut self, diags: &mut Vec<Diagnostic>, diag: Diagnostic) {{
+        if self.error_cooldown || self.error_count == MAX_ERRORS || self.cancelled {{
             return;
         }}
         if let Some(last) = diags.last() {{
@@ -252,8 +302,44 @@ impl<'a> Parser<'a> {{
             }}
         }}
         diags.push(diag);
+        self.error_count += 1;
+        if self.error_count == MAX_ERRORS && self.pos < self.cst.tokens.len() {{
+            self.skip_rest(diags);
+        }}
+    }}
+    /// Moves the remaining tokens into an error node, so parsing ends as if
+    /// the end of file was reached.
+    fn skip_rest(&mut self, diags: &mut Vec<Diagnostic>) {{
+        let m = self.cst.open();
+        while self.pos < self.cst.tokens.len() {{
+            self.cst.advance();
+            self.pos += 1;
+        }}
+        self.current = Token::EOF;
+        self.close(m, Rule::Error, diags);
     }}
+    /// Checks if the parse was cancelled, in which case the parser skips to
+    /// the end of file, so all rules return without consuming more tokens.
+    fn is_cancelled(&mut self) -> bool {{
+        if !self.cancelled
+            && self
+                .cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.load(std::sync::atomic::Ordering::Relaxed))
+        {{
+            self.cancelled = true;
+            self.pos = self.cst.tokens.len();
+            self.current = Token::EOF;
+        }}
+        self.cancelled
+    }}
+    /// Moves to the next token that is seen by the rules. Tokens of `skip`
+    /// declarations and lexer errors are added to the syntax tree, but are
+    /// never the current token.
     fn advance(&mut self, error: bool) {{
+        if self.is_cancelled() {{
+            return;
+        }}
         if !error {{
             self.error_cooldown = false;
         }}
@@ -303,7 +389,9 @@ impl<'a> Parser<'a> {{
         let m = self.cst.open();
         self.error(diags, diag);
         self.error_cooldown = true;
-        self.advance(true);
+        if self.pos < self.cst.tokens.len() {{
+            self.advance(true);
+        }}
         self.close(m, Rule::Error, diags);
     }}
     #[allow(dead_code)]
@@ -317,7 +405,8 @@ impl<'a> Parser<'a> {{
             .map_or(Token::EOF, |it| *it)
     }}
     fn span(&self) -> std::ops::Range<CstIndex> {{
-        self.cst.ranges
+        self.cst
+            .ranges
             .get(self.pos)
             .map_or(self.max_offset..self.max_offset, |span| span.clone())
     }}
@@ -326,21 +415,106 @@ impl<'a> Parser<'a> {{
         self.build(rule, NodeRef(m.0), diags);
         m
     }}
-    pub fn parse(
+    fn new(
         source: &'a str,
         tokens: Vec<Token>,
         ranges: Vec<std::ops::Range<CstIndex>>,
-        diags: &mut Vec<Diagnostic>,
-    ) -> Cst<'a> {{
-        let max_offset = source.len();
-        let mut parser = Self {{
+        cancel: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    ) -> Self {{
+        Self {{
             current: Token::EOF,
             cst: Cst::new(source, tokens, ranges),
             pos: 0,
             error_cooldown: false,
-            max_offset,
+            error_count: 0,
+            max_offset: source.len(),
+            cancel,
+            cancelled: false,
             context: Context::default(),
-        }};
+        }}
+    }}
+    pub fn parse(
+        source: &'a str,
+        tokens: Vec<Token>,
+        ranges: Vec<std::ops::Range<CstIndex>>,
+        diags: &mut Vec<Diagnostic>,
+    ) -> Cst<'a> {{
+        let mut parser = Self::new(source, tokens, ranges, None);
         parser.{2}(diags);
         parser.cst
     }}
+    /// Same as [`Parser::parse`], but the parse stops as soon as another
+    /// thread sets the `cancel` flag, e.g. because a language server received
+    /// a newer version of the source. The flag is checked whenever a token is
+    /// consumed, and the diagnostics of a cancelled parse are incomplete.
+    pub fn parse_cancellable(
+        source: &'a str,
+        tokens: Vec<Token>,
+        ranges: Vec<std::ops::Range<CstIndex>>,
+        diags: &mut Vec<Diagnostic>,
+        cancel: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    ) -> Result<Cst<'a>, Cancelled> {{
+        let mut parser = Self::new(source, tokens, ranges, Some(cancel));
+        parser.{2}(diags);
+        if parser.cancelled {{
+            Err(Cancelled)
+        }} else {{
+            Ok(parser.cst)
+        }}
+    }}
+    /// Parses the input with the function of an entry rule, which must be
+    /// followed by the end of file. If the rule creates a single node, it is
+    /// the root of the syntax tree, otherwise the nodes of the rule are
+    /// wrapped in a node of the `kind` of the rule.
+    #[allow(dead_code, clippy::unnecessary_cast)]
+    fn entry(
+        &mut self,
+        kind: Rule,
+        rule: fn(&mut Self, &mut Vec<Diagnostic>),
+        diags: &mut Vec<Diagnostic>,
+    ) {{
+        let first = self.cst.mark();
+        self.init_skip();
+        rule(self, diags);
+        let start = first.0 as usize;
+        let skipped = self.cst.nodes[start..]
+            .iter()
+            .take_while(|node| matches!(node, Node::Token(_)))
+            .count();
+        let root = match self.cst.nodes.get(start + skipped) {{
+            Some(Node::Rule(kind, len))
+                if *kind != Rule::Error
+                    && start + skipped + *len as usize + 1 == self.cst.nodes.len() =>
+            {{
+                Some(*kind)
+            }}
+            _ => None,
+        }};
+        let m = if root.is_some() {{
+            // the skipped tokens before the node of the rule become its children
+            self.cst.nodes[start..=start + skipped].rotate_right(1);
+            MarkOpened(first.0)
+        }} else {{
+            self.cst.open_before(first)
+        }};
+        if self.current != Token::EOF {{
+            self.error(diags, err![self.span(), "<end of file>"]);
+            let error_tree = self.cst.open();
+            loop {{
+                match self.cst.tokens.get(self.pos) {{
+                    None => break,
+                    _ => self.cst.advance(),
+                }}
+                self.pos += 1;
+            }}
+            self.close(error_tree, Rule::Error, diags);
+        }}
+        match root {{
+            Some(kind) => {{
+                self.cst.close(m, kind);
+            }}
+            None => {{
+                self.close(m, kind, diags);
+            }}
+        }}
+    }}
@@ -13,6 +13,16 @@ macro_rules! err {
     }
 }
 
+// TODO: change ins macro if codespan_reporting is not used
+#[allow(unused_macros)]
+macro_rules! ins {
+    [$span:expr, $tk:literal] => {
+        Diagnostic::note()
+            .with_message(format!("inserted missing '{}'", $tk))
+            .with_labels(vec![Label::primary((), $span.start as usize..$span.end as usize)])
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
 pub enum LexerError {
     #[default]
@@ -32,7 +42,7 @@ impl LexerError {
 
 // TODO: implement lexer
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Logos, Debug, PartialEq, Copy, Clone)]
+#[derive(Logos, Debug, PartialEq, Eq, Hash, Copy, Clone)]
 #[logos(error = LexerError)]
 pub enum Token {
     EOF,
@@ -71,5 +81,107 @@ pub fn tokenize(
     (tokens, ranges)
 }
 
+/// Tokens that replace the damaged part of a previous tokenization.
+///
+/// The old tokens `start..start + removed` are replaced by `tokens`, and the
+/// ranges of all following old tokens are moved by `shift` bytes.
+#[allow(dead_code)]
+pub struct TokenDelta {
+    pub start: usize,
+    pub removed: usize,
+    pub tokens: Vec<Token>,
+    pub ranges: Vec<std::ops::Range<CstIndex>>,
+    pub shift: isize,
+}
+
+#[allow(dead_code)]
+impl TokenDelta {
+    pub fn apply(self, tokens: &mut Vec<Token>, ranges: &mut Vec<std::ops::Range<CstIndex>>) {
+        let end = self.start + self.removed;
+        for range in ranges[end..].iter_mut() {
+            range.start = (range.start as isize + self.shift) as CstIndex;
+            range.end = (range.end as isize + self.shift) as CstIndex;
+        }
+        tokens.splice(self.start..end, self.tokens);
+        ranges.splice(self.start..end, self.ranges);
+    }
+}
+
+/// Number of tokens in front of an edit that [`relex`] lexes again.
+///
+/// A token in front of an edit may depend on text behind it, which the lexer
+/// read and backed off from (e.g. `1e+` is lexed as three tokens, but `1e+3`
+/// as one). Increase the number if a token of the language can depend on text
+/// behind more than two following tokens.
+#[allow(dead_code)]
+const RELEX_BACKUP: usize = 2;
+
+/// Re-lexes the part of the new `source` that is damaged by replacing the byte
+/// range `edit` of the old source with `new_len` bytes.
+///
+/// Lexing restarts [`RELEX_BACKUP`] tokens in front of the first token that
+/// touches the edit, and stops as soon as a new token behind the edit matches
+/// an old token in kind and (shifted) range.
+#[allow(dead_code, clippy::unnecessary_cast)]
+pub fn relex(
+    source: &str,
+    tokens: &[Token],
+    ranges: &[std::ops::Range<CstIndex>],
+    edit: std::ops::Range<usize>,
+    new_len: usize,
+    diags: &mut Vec<Diagnostic>,
+) -> TokenDelta {
+    let shift = new_len as isize - edit.len() as isize;
+    let damage_end = edit.start + new_len;
+    let start = ranges
+        .iter()
+        .position(|range| range.end as usize >= edit.start)
+        .unwrap_or(ranges.len())
+        .saturating_sub(RELEX_BACKUP);
+    let offset = ranges
+        .get(start)
+        .map_or(0, |range| range.start as usize)
+        .min(edit.start);
+    let mut new_tokens = vec![];
+    let mut new_ranges = vec![];
+    let mut old = start;
+
+    for (token, span) in Token::lexer(&source[offset..]).spanned() {
+        let span = span.start + offset..span.end + offset;
+        let kind = token.clone().unwrap_or(Token::Error);
+        if span.start >= damage_end {
+            while old < ranges.len() && ranges[old].start as isize + shift < span.start as isize {
+                old += 1;
+            }
+            if old < ranges.len()
+                && ranges[old].start as usize >= edit.end
+                && ranges[old].start as isize + shift == span.start as isize
+                && ranges[old].end as isize + shift == span.end as isize
+                && tokens[old] == kind
+            {
+                return TokenDelta {
+                    start,
+                    removed: old - start,
+                    tokens: new_tokens,
+                    ranges: new_ranges,
+                    shift,
+                };
+            }
+        }
+        if let Err(err) = token {
+            diags.push(err.into_diagnostic(span.clone()));
+        }
+        new_tokens.push(kind);
+        new_ranges.push(span.start as CstIndex..span.end as CstIndex);
+    }
+    TokenDelta {
+        start,
+        removed: tokens.len() - start,
+        tokens: new_tokens,
+        ranges: new_ranges,
+        shift,
+    }
+}
+
 include!(concat!(env!("OUT_DIR"), "/generated.rs"));
 
@@ -0,0 +1,277 @@
+
+/// Flag of a prediction table cell for a token that ends a repetition or an
+/// optional, or for a right associative operator.
+const EXIT: u16 = 1;
+/// Flag of a cell whose other bits are an offset into `CANDIDATES`.
+const CONFLICT: u16 = 0x8000;
+
+/// Instruction of a production, with rows of `PREDICT` and diagnostic indices.
+#[derive(Clone, Copy)]
+#[allow(dead_code)]
+enum Op {
+    Expect(Token, u16),
+    /// Expects an insertable token, with the diagnostic of the insertion.
+    Insertable(Token, u16, u16),
+    /// Expects one of the tokens of the row of a decision.
+    Set(u16),
+    Call(u16),
+    Alt(u16),
+    Star(u16),
+    Plus(u16),
+    Optional(u16),
+    /// Repetition with a minimum and a maximum (`u16::MAX` if unbounded).
+    Bounded(u16, u16, u16),
+    Action(u16),
+    Binding(Rule),
+    OpenNode(u16),
+    CloseNode(u16, Rule),
+    Return,
+}
+
+/// First branch, error, and whether entering a branch opens the rule node.
+type Decision = (u16, u16, bool);
+
+/// Production, predicate plus one, range in `LOOKAHEAD`, and whether it is an
+/// exit or forwarding branch of a recursive rule.
+type Branch = (u16, u16, u16, u16, bool);
+
+/// Structure of a rule, which determines how its node is created.
+#[allow(dead_code)]
+enum Shape {
+    Normal(u16, bool),
+    /// Rule that only creates a node if a decision opens it.
+    Forwarding(u16),
+    RightRecursiveForwarding(u16),
+    /// Decisions of the non-recursive and of the recursive branches.
+    LeftRecursive(u16, u16),
+    /// Exit rule and the row of the operator precedences plus one.
+    OperatorPrecedence(u16, u16),
+}
+
+struct Frame {
+    node_kind: Rule,
+    lhs: MarkClosed,
+    m: Option<MarkOpened>,
+    marks: Vec<(u16, MarkClosed)>,
+}
+
+#[allow(dead_code)]
+impl<'a> Parser<'a> {
+    fn run_rule(&mut self, index: u16, diags: &mut Vec<Diagnostic>) {
+        let (kind, ref shape) = RULES[index as usize];
+        let mut frame = Frame {
+            node_kind: kind,
+            lhs: self.cst.mark(),
+            m: None,
+            marks: vec![],
+        };
+        match *shape {
+            Shape::Normal(production, is_start) => {
+                let m = self.cst.open();
+                if is_start {
+                    self.init_skip();
+                }
+                self.exec(production, &mut frame, diags);
+                if is_start && self.current != Token::EOF {
+                    self.error(diags, err![self.span(), "<end of file>"]);
+                    self.skip_rest(diags);
+                }
+                self.close(m, frame.node_kind, diags);
+            }
+            Shape::Forwarding(production) => {
+                self.exec(production, &mut frame, diags);
+                if let Some(m) = frame.m {
+                    self.close(m, frame.node_kind, diags);
+                }
+            }
+            Shape::RightRecursiveForwarding(decision) => match self.predict(decision) {
+                Some((production, .., is_exit)) => {
+                    let m = (!is_exit).then(|| self.cst.open_before(frame.lhs));
+                    self.exec(*production, &mut frame, diags);
+                    if let Some(m) = m {
+                        self.close(m, frame.node_kind, diags);
+                    }
+                }
+                None => self.decision_error(decision, diags),
+            },
+            Shape::LeftRecursive(decision, recursive) => {
+                let mut lhs = frame.lhs;
+                match self.predict(decision) {
+                    Some((production, .., true)) => {
+                        self.exec(*production, &mut frame, diags)
+                    }
+                    Some((production, ..)) => {
+                        let m = self.cst.open();
+                        self.exec(*production, &mut frame, diags);
+                        self.close(m, frame.node_kind, diags);
+                    }
+                    None => self.decision_error(decision, diags),
+                }
+                loop {
+                    frame.node_kind = kind;
+                    let Some((production, ..)) = self.predict(recursive) else {
+                        break;
+                    };
+                    let m = self.cst.open_before(lhs);
+                    self.exec(*production, &mut frame, diags);
+                    lhs = self.close(m, frame.node_kind, diags);
+                }
+            }
+            Shape::OperatorPrecedence(exit, operators) => {
+                let lhs = self.cst.mark();
+                self.run_rule(exit, diags);
+                self.precedence(diags, kind, exit, operators, 0, lhs);
+            }
+        }
+    }
+    fn precedence(
+        &mut self,
+        diags: &mut Vec<Diagnostic>,
+        kind: Rule,
+        exit: u16,
+        operators: u16,
+        min_prec: u16,
+        mut lhs: MarkClosed,
+    ) {
+        loop {
+            let prec = self.cell(operators) >> 1;
+            if prec == 0 || prec < min_prec {
+                return;
+            }
+            self.advance(false);
+            let m = self.cst.open_before(lhs);
+            let rhs = self.cst.mark();
+            self.run_rule(exit, diags);
+            loop {
+                let next = self.cell(operators);
+                let next_prec = next >> 1;
+                if next_prec == 0 || !(prec < next_prec || (next & EXIT != 0 && prec == next_prec))
+                {
+                    break;
+                }
+                let min_prec = prec + if next_prec > prec { 1 } else { 0 };
+                self.precedence(diags, kind, exit, operators, min_prec, rhs);
+            }
+            lhs = self.close(m, kind, diags);
+        }
+    }
+    /// Cell of the prediction table for the current token.
+    fn cell(&self, row: u16) -> u16 {
+        PREDICT[row as usize][column(self.current)]
+    }
+    /// Branch of the decision selected by the lookahead and the predicates.
+    fn predict(&self, decision: u16) -> Option<&'static Branch> {
+        let cell = self.cell(decision);
+        let number = cell >> 1;
+        let branches = &BRANCHES[DECISIONS[decision as usize].0 as usize..];
+        let candidates: &[u16] = match number {
+            0 => &[],
+            _ if cell & CONFLICT != 0 => &CANDIDATES[((cell & !CONFLICT) >> 1) as usize..],
+            _ => std::slice::from_ref(&number),
+        };
+        candidates
+            .iter()
+            .take_while(|branch| **branch != 0)
+            .map(|branch| &branches[*branch as usize - 1])
+            .find(|(_, predicate, start, end, _)| {
+                let lookahead = &LOOKAHEAD[*start as usize..*end as usize];
+                (lookahead.is_empty()
+                    || lookahead.iter().any(|seq| {
+                        seq.iter().enumerate().all(|(i, token)| {
+                            *token == if i == 0 { self.current } else { self.peek(i) }
+                        })
+                    }))
+                    && (*predicate == 0 || self.predicate(*predicate as usize - 1))
+            })
+    }
+    fn decision_error(&mut self, decision: u16, diags: &mut Vec<Diagnostic>) {
+        let error = DECISIONS[decision as usize].1;
+        self.error(diags, diagnostic(error, self.span()));
+    }
+    fn exec(&mut self, production: u16, frame: &mut Frame, diags: &mut Vec<Diagnostic>) {
+        for op in PRODUCTIONS[production as usize..].iter() {
+            match *op {
+                Op::Return => return,
+                Op::Expect(token, error) => {
+                    if self.current == token {
+                        self.advance(false);
+                    } else {
+                        self.error(diags, diagnostic(error, self.span()));
+                    }
+                }
+                Op::Insertable(token, error, insertion) => {
+                    if self.current == token {
+                        self.advance(false);
+                    } else if self.insert(token) {
+                        if !self.error_cooldown {
+                            let span = self.span();
+                            diags.push(diagnostic(insertion, span.start..span.start));
+                        }
+                    } else {
+                        self.error(diags, diagnostic(error, self.span()));
+                    }
+                }
+                Op::Set(decision) => {
+                    if self.cell(decision) >> 1 != 0 {
+                        self.advance(false);
+                    } else {
+                        self.decision_error(decision, diags);
+                    }
+                }
+                Op::Call(rule) => self.run_rule(rule, diags),
+                Op::Alt(decision) => match self.predict(decision) {
+                    Some(branch) => self.exec(branch.0, frame, diags),
+                    None => self.decision_error(decision, diags),
+                },
+                Op::Optional(decision) => match self.predict(decision) {
+                    Some(branch) => {
+                        self.open_rule_node(decision, frame);
+                        self.exec(branch.0, frame, diags);
+                    }
+                    None if self.cell(decision) & EXIT == 0 => {
+                        self.decision_error(decision, diags)
+                    }
+                    None => {}
+                },
+                Op::Star(decision) | Op::Plus(decision) | Op::Bounded(decision, ..) => {
+                    let (min, max) = match *op {
+                        Op::Plus(_) => (1, u16::MAX),
+                        Op::Bounded(_, min, max) => (min, max),
+                        _ => (0, u16::MAX),
+                    };
+                    let body = BRANCHES[DECISIONS[decision as usize].0 as usize].0;
+                    for _ in 0..min {
+                        self.exec(body, frame, diags);
+                    }
+                    let mut count = min;
+                    while max == u16::MAX || count < max {
+                        if let Some(branch) = self.predict(decision) {
+                            self.open_rule_node(decision, frame);
+                            self.exec(branch.0, frame, diags);
+                            count = count.saturating_add(1);
+                        } else if self.cell(decision) & EXIT != 0 {
+                            break;
+                        } else {
+                            let error = DECISIONS[decision as usize].1;
+                            self.advance_with_error(diags, diagnostic(error, self.span()));
+                        }
+                    }
+                }
+                Op::Action(index) => self.action(index as usize, diags),
+                Op::Binding(kind) => frame.node_kind = kind,
+                Op::OpenNode(number) => frame.marks.push((number, self.cst.mark())),
+                Op::CloseNode(number, kind) => {
+                    if let Some((_, mark)) = frame.marks.iter().rev().find(|(n, _)| *n == number) {
+                        let open_node = self.cst.open_before(*mark);
+                        self.close(open_node, kind, diags);
+                    }
+                }
+            }
+        }
+    }
+    fn open_rule_node(&mut self, decision: u16, frame: &mut Frame) {
+        if DECISIONS[decision as usize].2 && frame.m.is_none() {
+            frame.m = Some(self.cst.open_before(frame.lhs));
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use lelwel::frontend::compat::{Compat, Compatibility};
+use lelwel::frontend::parser::{tokenize, Parser, Token};
+use lelwel::frontend::sema::SemanticPass;
+use lelwel::frontend::source::GrammarSource;
+use logos::Logos;
+
+fn compat(old: &str, new: &str) -> (Compatibility, String) {
+    let mut diags = vec![];
+    let old = GrammarSource::load(old, &mut diags).unwrap();
+    let (tokens, ranges) = tokenize(Token::lexer(old.text()), &mut diags);
+    let old_cst = Parser::parse(old.text(), tokens, ranges, &mut diags);
+    let old_sema = SemanticPass::run(&old_cst, &mut diags);
+    let new = GrammarSource::load(new, &mut diags).unwrap();
+    let (tokens, ranges) = tokenize(Token::lexer(new.text()), &mut diags);
+    let new_cst = Parser::parse(new.text(), tokens, ranges, &mut diags);
+    let new_sema = SemanticPass::run(&new_cst, &mut diags);
+    assert!(diags.is_empty());
+    let mut output = vec![];
+    let kind = Compat::run(
+        old.text(),
+        &old_cst,
+        &old_sema,
+        new.text(),
+        &new_cst,
+        &new_sema,
+        &mut output,
+    )
+    .unwrap();
+    (kind, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn unchanged() {
+    let (kind, output) = compat("tests/compat/old.llw", "tests/compat/old.llw");
+    assert_eq!(kind, Compatibility::Unchanged);
+    assert_eq!(output, "unchanged\n");
+}
+
+#[test]
+fn action_only() {
+    let (kind, output) = compat("tests/compat/old.llw", "tests/compat/action_only.llw");
+    assert_eq!(kind, Compatibility::ActionOnly);
+    assert_eq!(
+        output,
+        "action-only\n  action-only: rule `stmt` changed without changing its language\n"
+    );
+}
+
+#[test]
+fn compatible() {
+    let (kind, output) = compat("tests/compat/old.llw", "tests/compat/compatible.llw");
+    assert_eq!(kind, Compatibility::Compatible);
+    assert_eq!(
+        output,
+        concat!(
+            "compatible\n",
+            "  compatible: token `Minus` was added\n",
+            "  compatible: rule `expr` accepts additional inputs\n",
+        )
+    );
+}
+
+#[test]
+fn breaking() {
+    let (kind, output) = compat("tests/compat/old.llw", "tests/compat/breaking.llw");
+    assert_eq!(kind, Compatibility::Breaking);
+    assert_eq!(
+        output,
+        concat!(
+            "breaking\n",
+            "  breaking: token `Plus` was removed\n",
+            "  breaking: rule `expr` may reject inputs of the old grammar\n",
+        )
+    );
+}
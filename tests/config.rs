@@ -0,0 +1,47 @@
+#![cfg(feature = "config")]
+
+use std::path::Path;
+
+use lelwel::backend::rust::Visibility;
+use lelwel::config::{CommandLine, Config};
+use lelwel::frontend::lint::{Lint, LintLevel, Warning};
+use lelwel::Options;
+
+#[test]
+fn command_line_precedence() {
+    let config = Config::load("tests/config/lelwel.toml").unwrap();
+    let dir = Path::new("tests/config");
+
+    let grammars = config.grammars(dir, &Options::default(), &CommandLine::default());
+    assert_eq!(grammars[0].path, "tests/config/a.llw");
+    assert_eq!(grammars[0].output, "tests/config/a");
+    assert_eq!(grammars[1].output, "tests/config/generated");
+    assert!(grammars[0].options.table);
+    assert_eq!(grammars[0].options.limits.max_depth, 4);
+    assert_eq!(grammars[1].options.limits.max_depth, 3);
+    assert_eq!(grammars[0].options.visibility, Visibility::Crate);
+    assert_eq!(
+        grammars[1].options.lints.get(Lint::NamingConvention),
+        LintLevel::Deny
+    );
+
+    let command_line = CommandLine {
+        output: Some("out".to_string()),
+        max_depth: Some(5),
+        visibility: Some(Visibility::Public),
+        table: Some(false),
+        lints: vec![(Warning::Lint(Lint::NamingConvention), LintLevel::Allow)],
+        ..Default::default()
+    };
+    let grammars = config.grammars(dir, &Options::default(), &command_line);
+    for grammar in grammars.iter() {
+        assert_eq!(grammar.output, "out");
+        assert!(!grammar.options.table);
+        assert_eq!(grammar.options.limits.max_depth, 5);
+        assert_eq!(grammar.options.visibility, Visibility::Public);
+        assert_eq!(
+            grammar.options.lints.get(Lint::NamingConvention),
+            LintLevel::Allow
+        );
+    }
+}
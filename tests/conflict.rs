@@ -0,0 +1,44 @@
+use lelwel::frontend::diag::sort_by_position;
+use lelwel::frontend::parser::{tokenize, Parser, Token};
+use lelwel::frontend::sema::SemanticPass;
+use lelwel::frontend::source::GrammarSource;
+use logos::Logos;
+
+/// Returns the codes of the diagnostics of the grammar at `input` with the
+/// notes that show an ambiguous input.
+fn examples(input: &str) -> Vec<(String, Vec<String>)> {
+    let mut diags = vec![];
+    let grammar = GrammarSource::load(input, &mut diags).unwrap();
+    let (tokens, ranges) = tokenize(Token::lexer(grammar.text()), &mut diags);
+    let cst = Parser::parse(grammar.text(), tokens, ranges, &mut diags);
+    SemanticPass::run(&cst, &mut diags);
+    sort_by_position(&mut diags);
+    diags
+        .into_iter()
+        .map(|diag| {
+            let notes = diag
+                .notes
+                .into_iter()
+                .filter(|note| note.contains("ambiguous"))
+                .collect();
+            (diag.code.unwrap(), notes)
+        })
+        .collect()
+}
+
+#[test]
+fn example_input() {
+    assert_eq!(
+        examples("tests/conflict/example.llw"),
+        [
+            (
+                "E013".to_string(),
+                vec!["note: the input `A C` is ambiguous at `C`".to_string()]
+            ),
+            (
+                "E011".to_string(),
+                vec!["note: the input `A B C` is ambiguous at `C`".to_string()]
+            ),
+        ]
+    );
+}
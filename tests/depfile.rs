@@ -0,0 +1,37 @@
+use lelwel::Options;
+
+#[test]
+fn depfile() {
+    // the space in the directory name must be escaped
+    let dir = std::env::temp_dir().join(format!("lelwel depfile {}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("tokens.llw"), "token A B;\n").unwrap();
+    std::fs::write(
+        dir.join("grammar.llw"),
+        "import 'tokens.llw';\n\nstart s;\n\ns: A B;\n",
+    )
+    .unwrap();
+    let depfile = dir.join("grammar.d");
+    let options = Options {
+        depfile: Some(depfile.to_str().unwrap().to_string()),
+        ..Default::default()
+    };
+    let input = dir.join("grammar.llw");
+    assert!(
+        lelwel::compile_with_options(input.to_str().unwrap(), dir.to_str().unwrap(), &options)
+            .unwrap()
+    );
+
+    let escape = |name: &str| dir.join(name).display().to_string().replace(' ', "\\ ");
+    assert_eq!(
+        std::fs::read_to_string(&depfile).unwrap(),
+        format!(
+            "{}: {} {}\n",
+            escape("generated.rs"),
+            escape("grammar.llw"),
+            escape("tokens.llw")
+        )
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
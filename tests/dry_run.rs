@@ -0,0 +1,51 @@
+use lelwel::backend::diff;
+use lelwel::backend::rust::RustOutput;
+use lelwel::frontend::parser::{tokenize, Parser, Token};
+use lelwel::frontend::sema::SemanticPass;
+use lelwel::frontend::source::GrammarSource;
+use lelwel::Options;
+use logos::Logos;
+
+#[test]
+fn unified_diff() {
+    let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\n";
+    let new = "a\nb\nc\nd\nE\nf\ng\nh\ni\nj\n";
+    assert_eq!(diff::unified("old", "new", old, old), None);
+    assert_eq!(
+        diff::unified("old", "new", old, new).unwrap(),
+        "--- old\n+++ new\n@@ -2,8 +2,9 @@\n b\n c\n d\n-e\n+E\n f\n g\n h\n i\n+j\n"
+    );
+}
+
+#[test]
+fn dry_run() {
+    let dir = std::env::temp_dir().join(format!("lelwel-dry-run-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("calc.llw");
+    std::fs::copy("examples/calc/src/calc.llw", &input).unwrap();
+
+    let mut diags = vec![];
+    let grammar = GrammarSource::load(input.to_str().unwrap(), &mut diags).unwrap();
+    let source = grammar.text();
+    let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+    let cst = Parser::parse(source, tokens, ranges, &mut diags);
+    let sema = SemanticPass::run(&cst, &mut diags);
+    let options = Options::default();
+    let diff = |out: &mut Vec<u8>| RustOutput::diff(&cst, &sema, &input, &dir, &options, out);
+
+    // the missing generated file is a difference, and nothing is written
+    let mut out = vec![];
+    assert!(!diff(&mut out).unwrap());
+    let out = String::from_utf8(out).unwrap();
+    let generated = dir.join("generated.rs").display().to_string();
+    assert!(out.starts_with(&format!("--- /dev/null\n+++ {generated}\n@@ -0,0 +1,")));
+    assert!(!dir.join("generated.rs").exists());
+    assert!(!dir.join("parser.rs").exists());
+
+    RustOutput::run(&cst, &sema, &input, &dir, &options).unwrap();
+    let mut out = vec![];
+    assert!(diff(&mut out).unwrap());
+    assert!(out.is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
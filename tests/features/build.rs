@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use lelwel::Options;
+
+fn main() {
+    // the parser files are created from the skeleton on every build, so the
+    // tests use the same code as the parser file of a new grammar, and the
+    // features grammar is generated both as a recursive and a table-driven
+    // parser, where all parsers derive the serde traits with the default
+    // serde feature
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    for (grammar, table, name) in [
+        ("features", false, "parser"),
+        ("features", true, "table"),
+        ("sync", false, "sync"),
+        ("max_errors", false, "max_errors"),
+    ] {
+        let parser_file = Path::new(&out_dir).join(format!("{name}.rs"));
+        if parser_file.exists() {
+            std::fs::remove_file(&parser_file).unwrap();
+        }
+        lelwel::build_with_options(
+            &format!("src/{grammar}.llw"),
+            &Options {
+                table,
+                generated_file: Some(format!("{name}_generated.rs")),
+                parser_file: Some(parser_file.to_str().unwrap().to_string()),
+                trace: true,
+                serde: true,
+                ..Default::default()
+            },
+        );
+    }
+    lelwel::build_with_options(
+        "src/insertion.llw",
+        &Options {
+            generated_file: Some("insertion_generated.rs".to_string()),
+            parser_file: Some("insertion.rs".to_string()),
+            trace: true,
+            ..Default::default()
+        },
+    );
+}
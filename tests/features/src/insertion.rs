@@ -0,0 +1,99 @@
+use codespan_reporting::diagnostic::Label;
+use logos::Logos;
+
+pub type Span = core::ops::Range<usize>;
+pub type Diagnostic = codespan_reporting::diagnostic::Diagnostic<()>;
+
+macro_rules! err {
+    [$span:expr, $($tk:literal),*] => {
+        Diagnostic::error()
+            .with_message(syntax_error_message!($span, $($tk),*))
+            .with_labels(vec![Label::primary((), $span.start as usize..$span.end as usize)])
+    }
+}
+
+macro_rules! ins {
+    [$span:expr, $tk:literal] => {
+        Diagnostic::note()
+            .with_message(format!("inserted missing '{}'", $tk))
+            .with_labels(vec![Label::primary((), $span.start as usize..$span.end as usize)])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum LexerError {
+    #[default]
+    Invalid,
+}
+
+impl LexerError {
+    pub fn into_diagnostic(self, span: Span) -> Diagnostic {
+        match self {
+            Self::Invalid => Diagnostic::error()
+                .with_message("invalid token")
+                .with_labels(vec![Label::primary((), span)]),
+        }
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Logos, Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[logos(error = LexerError)]
+pub enum Token {
+    EOF,
+    #[token("let")]
+    Let,
+    #[regex("[a-z_][a-z0-9_]*")]
+    Id,
+    #[regex("[0-9]+")]
+    Num,
+    #[token("=")]
+    Eq,
+    #[token(";")]
+    Semi,
+    #[token("+")]
+    Plus,
+    #[regex("[ \\t\\r\\n]+")]
+    Whitespace,
+    Error,
+}
+
+type CstIndex = usize;
+
+#[derive(Default)]
+struct Context<'a> {
+    marker: std::marker::PhantomData<&'a ()>,
+}
+
+pub fn tokenize(
+    lexer: logos::Lexer<Token>,
+    diags: &mut Vec<Diagnostic>,
+) -> (Vec<Token>, Vec<std::ops::Range<CstIndex>>) {
+    let mut tokens = vec![];
+    let mut ranges = vec![];
+
+    for (token, span) in lexer.spanned() {
+        match token {
+            Ok(token) => {
+                tokens.push(token);
+            }
+            Err(err) => {
+                diags.push(err.into_diagnostic(span.clone()));
+                tokens.push(Token::Error);
+            }
+        }
+        ranges.push(span.start as CstIndex..span.end as CstIndex);
+    }
+    (tokens, ranges)
+}
+
+include!(concat!(env!("OUT_DIR"), "/insertion_generated.rs"));
+
+impl PredicatesAndActions for Parser<'_> {
+    /// A missing semicolon is inserted at the end of a line or the file.
+    fn insert_token(&self, token: Token) -> bool {
+        let before = &self.cst.source[..self.span().start];
+        token == Token::Semi
+            && (self.current == Token::EOF || before.trim_end_matches([' ', '\t']).ends_with('\n'))
+    }
+}
@@ -0,0 +1,91 @@
+//! Parser for a grammar that uses the extensions of lelwel, like LL(k)
+//! lookahead, syntactic predicates, and bounded repetitions, whose syntax
+//! trees are checked by the tests, and parsers for grammars with token
+//! insertion, synchronization tokens, and a maximum number of errors. The
+//! first grammar is also generated as a table-driven parser, which must
+//! produce the same syntax trees.
+
+pub mod insertion;
+mod parser {
+    include!(concat!(env!("OUT_DIR"), "/parser.rs"));
+}
+pub mod table {
+    include!(concat!(env!("OUT_DIR"), "/table.rs"));
+}
+pub mod max_errors {
+    include!(concat!(env!("OUT_DIR"), "/max_errors.rs"));
+}
+pub mod sync {
+    include!(concat!(env!("OUT_DIR"), "/sync.rs"));
+}
+
+use std::io::BufWriter;
+
+use codespan_reporting::files::SimpleFile;
+use codespan_reporting::term::termcolor::NoColor;
+use codespan_reporting::term::{self, Config};
+use logos::Logos;
+pub use parser::*;
+
+fn output(source: &str, cst: String, diags: &[Diagnostic]) -> Vec<String> {
+    let mut writer = NoColor::new(BufWriter::new(Vec::new()));
+    let config = Config::default();
+    let file = SimpleFile::new("<input>", source);
+    for diag in diags.iter() {
+        term::emit(&mut writer, &config, &file, diag).unwrap();
+    }
+    vec![
+        cst,
+        String::from_utf8(writer.into_inner().into_inner().unwrap()).unwrap(),
+    ]
+}
+
+/// Parses the `source` with the `parse` function of the parser `module`.
+macro_rules! generate {
+    ($module:ident, $parse:ident, $source:expr) => {{
+        let source = $source;
+        let mut diags = vec![];
+        let (tokens, ranges) = $module::tokenize($module::Token::lexer(source), &mut diags);
+        let cst = $module::Parser::$parse(source, tokens, ranges, &mut diags);
+        output(source, format!("{cst}"), &diags)
+    }};
+}
+
+/// Returns the syntax tree and the diagnostics of the `source`.
+pub fn generate_syntax_tree(source: &str) -> Vec<String> {
+    generate!(parser, parse, source)
+}
+
+/// Returns the syntax tree and the diagnostics of the `source`, which is
+/// parsed with the `expr` entry rule.
+pub fn generate_expr_syntax_tree(source: &str) -> Vec<String> {
+    generate!(parser, parse_expr, source)
+}
+
+/// Same as [`generate_syntax_tree`], but with the table-driven parser.
+pub fn generate_table_syntax_tree(source: &str) -> Vec<String> {
+    generate!(table, parse, source)
+}
+
+/// Same as [`generate_expr_syntax_tree`], but with the table-driven parser.
+pub fn generate_table_expr_syntax_tree(source: &str) -> Vec<String> {
+    generate!(table, parse_expr, source)
+}
+
+/// Returns the syntax tree and the diagnostics of the `source`, which is
+/// parsed with the parser of the grammar with token insertion.
+pub fn generate_insertion_syntax_tree(source: &str) -> Vec<String> {
+    generate!(insertion, parse, source)
+}
+
+/// Returns the syntax tree and the diagnostics of the `source`, which is
+/// parsed with the parser of the grammar with synchronization tokens.
+pub fn generate_sync_syntax_tree(source: &str) -> Vec<String> {
+    generate!(sync, parse, source)
+}
+
+/// Returns the syntax tree and the diagnostics of the `source`, which is
+/// parsed with the parser of the grammar with a maximum number of errors.
+pub fn generate_max_errors_syntax_tree(source: &str) -> Vec<String> {
+    generate!(max_errors, parse, source)
+}
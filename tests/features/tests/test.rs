@@ -0,0 +1,279 @@
+use codespan_reporting::diagnostic::Severity;
+use lelwel::frontend::interpreter::{Interpreter, Trace};
+use lelwel::frontend::sema::SemanticPass;
+use lelwel::frontend::source::GrammarSource;
+use lelwel_features::*;
+use logos::Logos;
+use std::ops::Range;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+macro_rules! check {
+    ($generate:ident, $file:literal) => {
+        let res = $generate(include_str!(concat!("data/", $file, ".txt")));
+        assert_eq!(
+            format!("{}", res[0]),
+            include_str!(concat!("data/", $file, ".tree"))
+        );
+        assert_eq!(
+            format!("{}", res[1]),
+            include_str!(concat!("data/", $file, ".diag"))
+        );
+    };
+    ($file:literal) => {
+        check!(generate_syntax_tree, $file);
+        check!(generate_table_syntax_tree, $file);
+    };
+}
+
+#[test]
+fn lookahead() {
+    check!("lookahead");
+    check!("lookahead_error");
+}
+
+#[test]
+fn syntactic_predicate() {
+    check!("syntactic_predicate");
+    check!("syntactic_predicate_error");
+}
+
+#[test]
+fn separated() {
+    check!("separated");
+    check!("separated_error");
+}
+
+#[test]
+fn negative_predicate() {
+    check!("negative_predicate");
+    check!("negative_predicate_error");
+}
+
+#[test]
+fn bounded() {
+    check!("bounded");
+    check!("bounded_error");
+}
+
+#[test]
+fn cancellation() {
+    let source = include_str!("data/separated.txt");
+    let parse = |cancel: bool| {
+        let mut diags = vec![];
+        let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+        let cancel = Arc::new(AtomicBool::new(cancel));
+        Parser::parse_cancellable(source, tokens, ranges, &mut diags, cancel)
+            .map(|cst| format!("{cst}"))
+    };
+    assert_eq!(parse(true), Err(Cancelled));
+    assert_eq!(
+        parse(false),
+        Ok(include_str!("data/separated.tree").to_string())
+    );
+}
+
+#[test]
+fn entry() {
+    check!(generate_expr_syntax_tree, "entry");
+    check!(generate_expr_syntax_tree, "entry_error");
+    check!(generate_table_expr_syntax_tree, "entry");
+    check!(generate_table_expr_syntax_tree, "entry_error");
+}
+
+#[test]
+fn relex() {
+    let lex = |source: &str| tokenize(Token::lexer(source), &mut vec![]);
+    // each edit replaces a byte range of the previous source with some text
+    let edits = [
+        (0..0, "let a = 1; c = 2; /* d"),
+        // terminates the comment, which changes the tokens in front of the edit
+        (22..22, " */"),
+        (8..9, "(1 + 2)"),
+        (11..11, "/*"),
+        (3..4, ""),
+        (0..3, "fn f() {} "),
+        (17..19, ""),
+        (35..37, ""),
+        (3..3, "1e+ "),
+        // joins the number two tokens in front of the edit
+        (6..6, "3"),
+    ];
+    let mut source = String::new();
+    let (mut tokens, mut ranges) = lex(&source);
+    for (edit, text) in edits {
+        let mut new_source = source.clone();
+        new_source.replace_range(edit.clone(), text);
+        let delta =
+            lelwel_features::relex(&new_source, &tokens, &ranges, edit, text.len(), &mut vec![]);
+        delta.apply(&mut tokens, &mut ranges);
+        assert_eq!(
+            (tokens.clone(), ranges.clone()),
+            lex(&new_source),
+            "{new_source:?}"
+        );
+        source = new_source;
+    }
+
+    // an edit only re-lexes the tokens around it
+    let source = "let a = 1;\n".repeat(100);
+    let (tokens, ranges) = lex(&source);
+    let mut new_source = source.clone();
+    new_source.replace_range(514..515, "22");
+    let delta = lelwel_features::relex(&new_source, &tokens, &ranges, 514..515, 2, &mut vec![]);
+    assert_eq!(delta.start, 417);
+    assert_eq!(delta.removed, 4);
+    assert_eq!(
+        delta.tokens,
+        [Token::Whitespace, Token::Eq, Token::Whitespace, Token::Num]
+    );
+}
+
+#[test]
+fn sync() {
+    check!(generate_sync_syntax_tree, "sync");
+    check!(generate_sync_syntax_tree, "sync_error");
+}
+
+#[test]
+fn max_errors() {
+    check!(generate_max_errors_syntax_tree, "max_errors");
+}
+
+#[test]
+fn insertion() {
+    check!(generate_insertion_syntax_tree, "insertion");
+    check!(generate_insertion_syntax_tree, "insertion_error");
+}
+
+/// Interprets the grammar at `path` on the trace of a generated parser, which
+/// must result in the same syntax tree, or stop at the first syntax error.
+fn replay(
+    path: &str,
+    source: &str,
+    cst: String,
+    trace: Vec<u8>,
+    ranges: &[Range<usize>],
+    diags: &[Diagnostic],
+) {
+    use lelwel::frontend::parser::{tokenize, Parser, Token};
+    let mut grammar_diags = vec![];
+    let grammar = GrammarSource::load(path, &mut grammar_diags).unwrap();
+    let (tokens, grammar_ranges) = tokenize(Token::lexer(grammar.text()), &mut grammar_diags);
+    let grammar_cst = Parser::parse(grammar.text(), tokens, grammar_ranges, &mut grammar_diags);
+    let sema = SemanticPass::run(&grammar_cst, &mut grammar_diags);
+    assert!(grammar_diags.is_empty());
+
+    let trace = String::from_utf8(trace).unwrap();
+    let trace = Trace::parse(&trace).unwrap();
+    match (
+        Interpreter::run(&grammar_cst, &sema, &trace),
+        diags.iter().find(|diag| diag.severity == Severity::Error),
+    ) {
+        (Ok(tree), None) => {
+            // the interpreter shows the token kinds without text and range
+            let cst = cst
+                .lines()
+                .map(|line| {
+                    let indent = line.len() - line.trim_start().len();
+                    let kind = line.trim_start().split(' ').next().unwrap();
+                    format!("{}{kind}\n", &line[..indent])
+                })
+                .collect::<String>();
+            assert_eq!(format!("{tree}"), cst);
+        }
+        (Err(err), Some(diag)) => {
+            let start = ranges
+                .get(err.position)
+                .map_or(source.len(), |range| range.start);
+            assert_eq!(start, diag.labels[0].range.start, "{err}");
+        }
+        (Ok(_), Some(diag)) => panic!("replay succeeded despite: {}", diag.message),
+        (Err(err), None) => panic!("replay failed at {err}"),
+    }
+}
+
+/// Parses the `source` with the parser `module` and replays its trace.
+macro_rules! trace {
+    ($module:ident, $path:literal, $source:expr) => {{
+        use $module::{tokenize, Parser, Token};
+        let source = $source;
+        let mut diags = vec![];
+        let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+        let cst = Parser::parse(source, tokens, ranges.clone(), &mut diags);
+        let mut trace = vec![];
+        cst.write_trace(&mut trace).unwrap();
+        replay($path, source, format!("{cst}"), trace, &ranges, &diags);
+    }};
+}
+
+#[test]
+fn trace() {
+    for source in [
+        include_str!("data/lookahead.txt"),
+        include_str!("data/lookahead_error.txt"),
+        include_str!("data/syntactic_predicate.txt"),
+        include_str!("data/syntactic_predicate_error.txt"),
+        include_str!("data/negative_predicate.txt"),
+        include_str!("data/negative_predicate_error.txt"),
+        include_str!("data/separated.txt"),
+        include_str!("data/separated_error.txt"),
+        include_str!("data/bounded.txt"),
+        include_str!("data/bounded_error.txt"),
+    ] {
+        trace!(lelwel_features, "src/features.llw", source);
+        trace!(table, "src/features.llw", source);
+    }
+    for source in [
+        include_str!("data/insertion.txt"),
+        include_str!("data/insertion_error.txt"),
+    ] {
+        trace!(insertion, "src/insertion.llw", source);
+    }
+    for source in [
+        include_str!("data/sync.txt"),
+        include_str!("data/sync_error.txt"),
+    ] {
+        trace!(sync, "src/sync.llw", source);
+    }
+    trace!(
+        max_errors,
+        "src/max_errors.llw",
+        include_str!("data/max_errors.txt")
+    );
+}
+
+#[test]
+fn keyword() {
+    assert_eq!(Token::keyword("let"), Some(Token::Let));
+    assert_eq!(Token::keyword("ip"), Some(Token::Ip));
+    assert_eq!(Token::keyword("Let"), None);
+    assert_eq!(Token::keyword("a"), None);
+    assert_eq!(table::Token::keyword("fn"), Some(table::Token::Fn));
+    // the ip keyword ignores the case
+    assert_eq!(Token::keyword("IP"), Some(Token::Ip));
+    assert_eq!(Token::keyword("iP"), Some(Token::Ip));
+    assert_eq!(Token::keyword("LET"), None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+    let source = "a = 1;";
+    let mut diags = vec![];
+    let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+    let cst = Parser::parse(source, tokens, ranges, &mut diags);
+    let json = serde_json::to_value(&cst).unwrap();
+    assert_eq!(json["source"], source);
+    assert_eq!(
+        json["tokens"],
+        serde_json::json!(["Id", "Whitespace", "Eq", "Whitespace", "Num", "Semi"])
+    );
+    assert_eq!(json["nodes"][1], serde_json::json!({"Rule": ["Assign", 7]}));
+
+    // the tokens and nodes can be read back
+    let tokens: Vec<Token> = serde_json::from_value(json["tokens"].clone()).unwrap();
+    assert_eq!(tokens[2], Token::Eq);
+    let nodes: Vec<Node> = serde_json::from_value(json["nodes"].clone()).unwrap();
+    assert!(matches!(nodes[1], Node::Rule(Rule::Assign, 7)));
+}
@@ -0,0 +1,62 @@
+use lelwel::frontend::diag::{deduplicate, sort_by_position, Fix};
+use lelwel::frontend::lint::{LintLevels, LintPass};
+use lelwel::frontend::parser::{tokenize, Diagnostic, Parser, Token};
+use lelwel::frontend::sema::SemanticPass;
+use lelwel::frontend::source::{sarif_log, GrammarSource};
+use logos::Logos;
+
+/// Formats each diagnostic of the grammar at `input` with its fixes.
+fn format(input: &str, f: impl Fn(&GrammarSource, &Diagnostic, &[&Fix]) -> String) -> Vec<String> {
+    let mut diags = vec![];
+    let grammar = GrammarSource::load(input, &mut diags).unwrap();
+    let source = grammar.text();
+    let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+    let cst = Parser::parse(source, tokens, ranges, &mut diags);
+    let sema = SemanticPass::run(&cst, &mut diags);
+    LintPass::run(&cst, &sema, &LintLevels::default(), &mut diags);
+    deduplicate(&mut diags);
+    sort_by_position(&mut diags);
+    diags
+        .iter()
+        .map(|diag| {
+            let fixes: Vec<_> = sema
+                .fixes
+                .iter()
+                .filter(|fix| fix.applies_to(diag))
+                .collect();
+            f(&grammar, diag, &fixes)
+        })
+        .collect()
+}
+
+/// Returns the diagnostics of the grammar at `input` in the JSON format, one
+/// diagnostic per line.
+fn json(input: &str) -> String {
+    format(input, |grammar, diag, fixes| {
+        format!("{}\n", grammar.json(diag, fixes))
+    })
+    .concat()
+}
+
+/// Returns the diagnostics of the grammar at `input` as a SARIF log, with a
+/// fixed version of lelwel.
+fn sarif(input: &str) -> String {
+    let results = format(input, |grammar, diag, fixes| grammar.sarif(diag, fixes));
+    format!("{}\n", sarif_log(&results, "0.0.0"))
+}
+
+#[test]
+fn json_diagnostics() {
+    assert_eq!(
+        json("tests/format/diagnostics.llw"),
+        include_str!("format/diagnostics.json")
+    );
+}
+
+#[test]
+fn sarif_diagnostics() {
+    assert_eq!(
+        sarif("tests/format/diagnostics.llw"),
+        include_str!("format/diagnostics.sarif")
+    );
+}
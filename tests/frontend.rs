@@ -1,35 +1,83 @@
 // This file was generated by the generate.sh script.
 // DO NOT EDIT THIS FILE MANUALLY!
 
-use codespan_reporting::files::SimpleFile;
 use codespan_reporting::term::termcolor::NoColor;
 use codespan_reporting::term::{self, DisplayStyle};
+use lelwel::frontend::diag::{deduplicate, sort_by_position};
+use lelwel::frontend::lint::{LintLevels, LintPass};
 use lelwel::frontend::parser::{tokenize, Parser, Token};
 use lelwel::frontend::sema::SemanticPass;
+use lelwel::frontend::source::GrammarSource;
 use logos::Logos;
 use std::io::BufWriter;
 
 fn gen_diags(input: &str) -> String {
-    let source = std::fs::read_to_string(input).unwrap();
     let mut diags = vec![];
-    let (tokens, ranges) = tokenize(Token::lexer(&source), &mut diags);
-    let cst = Parser::parse(&source, tokens, ranges, &mut diags);
-    let _ = SemanticPass::run(&cst, &mut diags);
+    let grammar = GrammarSource::load(input, &mut diags).unwrap();
+    let source = grammar.text();
+    let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+    let cst = Parser::parse(source, tokens, ranges, &mut diags);
+    assert_eq!(cst.verify_round_trip(), Ok(()));
+    let sema = SemanticPass::run(&cst, &mut diags);
+    LintPass::run(&cst, &sema, &LintLevels::default(), &mut diags);
+    deduplicate(&mut diags);
+    sort_by_position(&mut diags);
 
     let mut writer = NoColor::new(BufWriter::new(Vec::new()));
     let config = codespan_reporting::term::Config {
         display_style: DisplayStyle::Short,
         ..Default::default()
     };
-    let file = SimpleFile::new(input, &source);
     for diag in diags {
-        term::emit(&mut writer, &config, &file, &diag).unwrap();
+        term::emit(
+            &mut writer,
+            &config,
+            grammar.files(),
+            &grammar.diagnostic(&diag),
+        )
+        .unwrap();
     }
     std::str::from_utf8(writer.get_ref().buffer())
         .unwrap()
         .to_string()
 }
 
+#[test]
+#[rustfmt::skip]
+fn allow_attribute() {
+    let diags = gen_diags("tests/frontend/allow_attribute.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/allow_attribute.llw:1:74: warning[W002]: unused token");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/allow_attribute.llw:1:74: warning[W005]: token name is not in pascal case");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/allow_attribute.llw:15:1: warning[W005]: rule name is not in snake case");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/allow_attribute.llw:19:1: warning[W001]: unused rule");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn alternative_attribute() {
+    let diags = gen_diags("tests/frontend/alternative_attribute.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/alternative_attribute.llw:7:5: error[E036]: unknown attribute `inline`");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/alternative_attribute.llw:8:5: error[E041]: invalid attribute position");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/alternative_attribute.llw:10:11: error[E036]: unknown warning `unknown_lint`");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/alternative_attribute.llw:11:3: error[E041]: invalid attribute position");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn bounded_repetition() {
+    let diags = gen_diags("tests/frontend/bounded_repetition.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/bounded_repetition.llw:10:11: error[E013]: LL(1) conflict in repetition");
+    assert_eq!(lines.next(), None);
+}
+
 #[test]
 #[rustfmt::skip]
 fn calc() {
@@ -48,6 +96,25 @@ fn c() {
     assert_eq!(lines.next(), None);
 }
 
+#[test]
+#[rustfmt::skip]
+fn complex_rule() {
+    let diags = gen_diags("tests/frontend/complex_rule.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/complex_rule.llw:5:1: warning[W003]: rule is too complex");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn contextual_keyword() {
+    let diags = gen_diags("tests/frontend/contextual_keyword.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next(), None);
+}
+
 #[test]
 #[rustfmt::skip]
 fn empty() {
@@ -58,17 +125,192 @@ fn empty() {
     assert_eq!(lines.next(), None);
 }
 
+#[test]
+#[rustfmt::skip]
+fn entry_rule() {
+    let diags = gen_diags("tests/frontend/entry_rule.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/entry_rule.llw:16:1: warning[W001]: unused rule");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn generated_names() {
+    let diags = gen_diags("tests/frontend/generated_names.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/generated_names.llw:5:47: error[E022]: name `error` collides with an identifier of the generated parser");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/generated_names.llw:6:1: error[E022]: name `peek` collides with an identifier of the generated parser");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/generated_names.llw:7:17: error[E023]: node kind `FooBar` is generated for different names");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/generated_names.llw:8:13: error[E023]: node kind `FooBar2` is generated for different names");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/generated_names.llw:9:1: error[E022]: name `error` collides with an identifier of the generated parser");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/generated_names.llw:11:1: error[E022]: name `cancellable` collides with an identifier of the generated parser");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/generated_names.llw:14:1: error[E022]: name `parse_expr` collides with an identifier of the generated parser");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/generated_names.llw:15:1: error[E022]: name `skip_rest` collides with an identifier of the generated parser");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn ignore_case() {
+    let diags = gen_diags("tests/frontend/ignore_case.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/ignore_case.llw:5:3: error[E036]: unknown attribute `ignore_case`");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn implicit_token() {
+    let diags = gen_diags("tests/frontend/implicit_token.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/implicit_token.llw:8:13: error[E038]: implicit token name `LParen` is already used");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/implicit_token.llw:8:13: error[E004]: use of undefined token `'('`");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/implicit_token.llw:8:63: error[E004]: use of undefined token `'<ident>'`");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/implicit_token.llw:8:81: error[E038]: implicit token name `Dot` is already used");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/implicit_token.llw:8:81: error[E004]: use of undefined token `'dot'`");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn import() {
+    let diags = gen_diags("tests/frontend/import.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/import.llw:3:8: error[E027]: cannot import `import/missing.llw`");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/import/expr.llw:2:1: error[E005]: redefinition of rule");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn inline_action() {
+    let diags = gen_diags("tests/frontend/inline_action.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/inline_action.llw:7:6: error[E034]: unterminated inline action");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/inline_action.llw:8:6: error[E034]: unterminated inline action");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/inline_action.llw:9:1: error[E032]: invalid syntax, expected: ';'");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn inline_rule() {
+    let diags = gen_diags("tests/frontend/inline_rule.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/inline_rule.llw:11:1: error[E040]: rule `list` cannot be inlined");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/inline_rule.llw:17:1: error[E040]: rule `cycle` cannot be inlined");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/inline_rule.llw:20:1: error[E040]: rule `other` cannot be inlined");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/inline_rule.llw:23:1: error[E040]: rule `forward` cannot be inlined");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn insert_decl() {
+    let diags = gen_diags("tests/frontend/insert_decl.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/insert_decl.llw:6:12: error[E021]: skipped token cannot be inserted");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/insert_decl.llw:7:8: error[E020]: token is already insertable");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/insert_decl.llw:7:13: error[E018]: expected token");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn insert_name() {
+    let diags = gen_diags("tests/frontend/insert_name.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn invalid_character() {
+    let diags = gen_diags("tests/frontend/invalid_character.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_character.llw:6:11: error[E033]: invalid token");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn invalid_lookahead() {
+    let diags = gen_diags("tests/frontend/invalid_lookahead.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_lookahead.llw:3:11: error[E025]: invalid number of lookahead tokens");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_lookahead.llw:4:11: error[E025]: invalid number of lookahead tokens");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn invalid_max_errors() {
+    let diags = gen_diags("tests/frontend/invalid_max_errors.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_max_errors.llw:3:12: error[E030]: invalid maximum number of errors");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn invalid_repetition_bounds() {
+    let diags = gen_diags("tests/frontend/invalid_repetition_bounds.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_repetition_bounds.llw:5:5: error[E039]: invalid repetition bounds");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_repetition_bounds.llw:5:12: error[E039]: invalid repetition bounds");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_repetition_bounds.llw:5:17: error[E039]: invalid repetition bounds");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_repetition_bounds.llw:5:24: error[E039]: invalid repetition bounds");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn invalid_syntactic_predicate() {
+    let diags = gen_diags("tests/frontend/invalid_syntactic_predicate.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_syntactic_predicate.llw:6:5: error[E002]: invalid predicate position");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_syntactic_predicate.llw:7:4: error[E026]: invalid element in syntactic predicate");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_syntactic_predicate.llw:7:9: error[E026]: invalid element in syntactic predicate");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_syntactic_predicate.llw:7:12: error[E026]: invalid element in syntactic predicate");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_syntactic_predicate.llw:8:4: error[E026]: invalid element in syntactic predicate");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_syntactic_predicate.llw:9:4: error[E026]: invalid element in syntactic predicate");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_syntactic_predicate.llw:9:9: error[E026]: invalid element in syntactic predicate");
+    assert_eq!(lines.next(), None);
+}
+
 #[test]
 #[rustfmt::skip]
 fn invalid_token() {
     let diags = gen_diags("tests/frontend/invalid_token.llw");
     let mut lines = diags.lines();
 
-    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_token.llw:6:5: error: invalid token");
-    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_token.llw:6:7: error: invalid token");
-    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_token.llw:6:8: error: invalid token");
-    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_token.llw:6:9: error: invalid token");
-    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_token.llw:6:11: error: invalid token");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_token.llw:6:11: error[E032]: invalid syntax, expected one of: <semantic action>, '#[', <binding>, <close node mark>, '.', <identifier>, <inline action>, '[', '(', '!', <open node mark>, '|', <semantic predicate>, ']', ')', ';', <string literal>, <tagged action>");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn invalid_token_regex() {
+    let diags = gen_diags("tests/frontend/invalid_token_regex.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_token_regex.llw:2:13: error[E037]: invalid regular expression");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/invalid_token_regex.llw:2:31: error[E037]: invalid regular expression");
     assert_eq!(lines.next(), None);
 }
 
@@ -81,6 +323,19 @@ fn json() {
     assert_eq!(lines.next(), None);
 }
 
+#[test]
+#[rustfmt::skip]
+fn left_recursion_cycle() {
+    let diags = gen_diags("tests/frontend/left_recursion_cycle.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/left_recursion_cycle.llw:10:3: error[E031]: indirect left recursion");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/left_recursion_cycle.llw:10:3: error[E011]: LL(1) conflict in alternation");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/left_recursion_cycle.llw:15:3: error[E014]: LL(1) conflict in option");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/left_recursion_cycle.llw:19:4: error[E011]: LL(1) conflict in alternation");
+    assert_eq!(lines.next(), None);
+}
+
 #[test]
 #[rustfmt::skip]
 fn left_recursive() {
@@ -126,6 +381,17 @@ fn l() {
     assert_eq!(lines.next(), None);
 }
 
+#[test]
+#[rustfmt::skip]
+fn lookahead() {
+    let diags = gen_diags("tests/frontend/lookahead.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/lookahead.llw:9:4: error[E011]: LL(1) conflict in alternation");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/lookahead.llw:20:3: error[E011]: LL(1) conflict in alternation");
+    assert_eq!(lines.next(), None);
+}
+
 #[test]
 #[rustfmt::skip]
 fn lowercase_token() {
@@ -146,6 +412,28 @@ fn lua() {
     assert_eq!(lines.next(), None);
 }
 
+#[test]
+#[rustfmt::skip]
+fn naming_convention() {
+    let diags = gen_diags("tests/frontend/naming_convention.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/naming_convention.llw:1:11: warning[W005]: token name is not in pascal case");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/naming_convention.llw:9:1: warning[W005]: rule name is not in snake case");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn negative_predicate() {
+    let diags = gen_diags("tests/frontend/negative_predicate.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/negative_predicate.llw:7:5: error[E002]: invalid predicate position");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/negative_predicate.llw:8:7: error[E026]: invalid element in syntactic predicate");
+    assert_eq!(lines.next(), None);
+}
+
 #[test]
 #[rustfmt::skip]
 fn oberon0() {
@@ -155,6 +443,17 @@ fn oberon0() {
     assert_eq!(lines.next(), None);
 }
 
+#[test]
+#[rustfmt::skip]
+fn orphaned_rules() {
+    let diags = gen_diags("tests/frontend/orphaned_rules.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/orphaned_rules.llw:7:1: warning[W001]: unused rule");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/orphaned_rules.llw:11:1: warning[W001]: unused rule");
+    assert_eq!(lines.next(), None);
+}
+
 #[test]
 #[rustfmt::skip]
 fn predef_token() {
@@ -182,12 +481,43 @@ fn redefinition() {
     let diags = gen_diags("tests/frontend/redefinition.llw");
     let mut lines = diags.lines();
 
-    assert_eq!(lines.next().unwrap(), "tests/frontend/redefinition.llw:3:6: error: invalid syntax, expected: <identifier>");
-    assert_eq!(lines.next().unwrap(), "tests/frontend/redefinition.llw:9:1: error[E005]: redefinition of rule");
-    assert_eq!(lines.next().unwrap(), "tests/frontend/redefinition.llw:14:1: error[E005]: redefinition of rule");
+    assert_eq!(lines.next().unwrap(), "error[E008]: missing start rule");
     assert_eq!(lines.next().unwrap(), "tests/frontend/redefinition.llw:1:9: error[E005]: redefinition of token");
     assert_eq!(lines.next().unwrap(), "tests/frontend/redefinition.llw:1:13: error[E005]: redefinition of token");
-    assert_eq!(lines.next().unwrap(), "error[E008]: missing start rule");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/redefinition.llw:3:6: error[E032]: invalid syntax, expected: <identifier>");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/redefinition.llw:9:1: error[E005]: redefinition of rule");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/redefinition.llw:14:1: error[E005]: redefinition of rule");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn separated() {
+    let diags = gen_diags("tests/frontend/separated.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/separated.llw:7:11: error[E013]: LL(1) conflict in repetition");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn sync_decl() {
+    let diags = gen_diags("tests/frontend/sync_decl.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/sync_decl.llw:6:10: error[E029]: skipped token cannot be a synchronization token");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/sync_decl.llw:7:6: error[E028]: token is already a synchronization token");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/sync_decl.llw:7:11: error[E018]: expected token");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn syntactic_predicate() {
+    let diags = gen_diags("tests/frontend/syntactic_predicate.llw");
+    let mut lines = diags.lines();
+
     assert_eq!(lines.next(), None);
 }
 
@@ -197,10 +527,51 @@ fn syntax_error() {
     let diags = gen_diags("tests/frontend/syntax_error.llw");
     let mut lines = diags.lines();
 
-    assert_eq!(lines.next().unwrap(), "tests/frontend/syntax_error.llw:2:1: error: invalid syntax, expected one of: '=', <identifier>, ';'");
-    assert_eq!(lines.next().unwrap(), "tests/frontend/syntax_error.llw:8:1: error: invalid syntax, expected: ')'");
-    assert_eq!(lines.next().unwrap(), "tests/frontend/syntax_error.llw:13:2: error: invalid syntax, expected one of: <semantic action>, <binding>, <close node mark>, <identifier>, '[', '(', <open node mark>, '|', <semantic predicate>, ']', ')', ';', <string literal>");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/syntax_error.llw:2:1: error[E032]: invalid syntax, expected one of: '#[', '=', <identifier>, <regular expression>, ';'");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/syntax_error.llw:8:1: error[E032]: invalid syntax, expected: ')'");
     assert_eq!(lines.next().unwrap(), "tests/frontend/syntax_error.llw:13:1: error[E003]: use of undefined rule `b`");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/syntax_error.llw:13:2: error[E032]: invalid syntax, expected one of: <semantic action>, '#[', <binding>, <close node mark>, '.', <identifier>, <inline action>, '[', '(', '!', <open node mark>, '|', <semantic predicate>, ']', ')', ';', <string literal>, <tagged action>");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn tagged_action() {
+    let diags = gen_diags("tests/frontend/tagged_action.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/tagged_action.llw:6:6: warning[W004]: action #1 has no `rust` block");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/tagged_action.llw:6:45: error[E024]: action #2 already has a `rust` block in this rule");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn token_derive() {
+    let diags = gen_diags("tests/frontend/token_derive.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn token_description() {
+    let diags = gen_diags("tests/frontend/token_description.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/token_description.llw:2:45: warning[W002]: unused token");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn token_regex() {
+    let diags = gen_diags("tests/frontend/token_regex.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/token_regex.llw:3:20: error[E034]: unterminated regular expression");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/token_regex.llw:5:1: error[E032]: invalid syntax, expected one of: '#[', '=', <identifier>, <regular expression>, ';'");
     assert_eq!(lines.next(), None);
 }
 
@@ -217,17 +588,39 @@ fn undefined() {
     assert_eq!(lines.next(), None);
 }
 
+#[test]
+#[rustfmt::skip]
+fn unknown_attribute() {
+    let diags = gen_diags("tests/frontend/unknown_attribute.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/unknown_attribute.llw:1:11: error[E036]: unknown attribute `inline`");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/unknown_attribute.llw:1:23: error[E036]: unknown attribute `entry`");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/unknown_attribute.llw:9:9: error[E036]: unknown warning `unused_rule`");
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+#[rustfmt::skip]
+fn unreachable_alternative() {
+    let diags = gen_diags("tests/frontend/unreachable_alternative.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/unreachable_alternative.llw:7:3: warning[W009]: unreachable alternative");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/unreachable_alternative.llw:10:3: warning[W009]: unreachable alternative");
+    assert_eq!(lines.next(), None);
+}
+
 #[test]
 #[rustfmt::skip]
 fn unused_element() {
     let diags = gen_diags("tests/frontend/unused_element.llw");
     let mut lines = diags.lines();
 
-    assert_eq!(lines.next().unwrap(), "tests/frontend/unused_element.llw:9:1: warning[W001]: unused rule");
-    assert_eq!(lines.next().unwrap(), "tests/frontend/unused_element.llw:13:1: warning[W001]: unused rule");
-    assert_eq!(lines.next().unwrap(), "tests/frontend/unused_element.llw:23:1: warning[W001]: unused rule");
     assert_eq!(lines.next().unwrap(), "tests/frontend/unused_element.llw:1:7: warning[W002]: unused token");
     assert_eq!(lines.next().unwrap(), "tests/frontend/unused_element.llw:1:11: warning[W002]: unused token");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/unused_element.llw:9:1: warning[W001]: unused rule");
+    assert_eq!(lines.next().unwrap(), "tests/frontend/unused_element.llw:23:1: warning[W001]: unused rule");
     assert_eq!(lines.next(), None);
 }
 
@@ -241,3 +634,13 @@ fn uppercase_rule() {
     assert_eq!(lines.next().unwrap(), "tests/frontend/uppercase_rule.llw:9:1: error[E006]: rule name starts with upper case letter");
     assert_eq!(lines.next(), None);
 }
+
+#[test]
+#[rustfmt::skip]
+fn wildcard() {
+    let diags = gen_diags("tests/frontend/wildcard.llw");
+    let mut lines = diags.lines();
+
+    assert_eq!(lines.next().unwrap(), "tests/frontend/wildcard.llw:7:7: error[E011]: LL(1) conflict in alternation");
+    assert_eq!(lines.next(), None);
+}
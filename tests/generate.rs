@@ -0,0 +1,91 @@
+use lelwel::backend::rust::Visibility;
+use lelwel::Options;
+
+#[test]
+fn table_method_names() {
+    let options = Options {
+        table: true,
+        ..Default::default()
+    };
+    let err = lelwel::generate("tests/generate/table_names.llw", &options).unwrap_err();
+    assert_eq!(
+        err.lines().collect::<Vec<_>>(),
+        [
+            "tests/generate/table_names.llw:5:1: error[E022]: name `exec` collides with an identifier of the generated parser",
+            "tests/generate/table_names.llw:8:1: error[E022]: name `insert` collides with an identifier of the generated parser",
+        ]
+    );
+    assert!(lelwel::generate("tests/generate/table_names.llw", &Options::default()).is_ok());
+}
+
+#[test]
+fn action_code_is_kept() {
+    let generated =
+        lelwel::generate("tests/generate/action_code.llw", &Options::default()).unwrap();
+    let grammar = std::fs::read_to_string("tests/generate/action_code.llw").unwrap();
+    for code in [
+        r#"Token::A => self.log("Token::A | Token::B if self.predicate_s_1() => { self.advance(false); }"),"#,
+        r#"self.error(diags, err![self.span(), "a, b", "c"]); self.log("err![self.span(), \"a\", \"b\"] | Token::A");"#,
+    ] {
+        assert!(grammar.contains(code));
+        assert!(
+            generated.contains(code),
+            "`{code}` is not in the generated code"
+        );
+    }
+}
+
+#[test]
+fn table_is_smaller() {
+    let recursive = lelwel::generate("examples/c/src/c.llw", &Options::default()).unwrap();
+    let options = Options {
+        table: true,
+        ..Default::default()
+    };
+    let table = lelwel::generate("examples/c/src/c.llw", &options).unwrap();
+    assert!(
+        table.len() < recursive.len(),
+        "{} bytes in table mode, {} bytes in recursive mode",
+        table.len(),
+        recursive.len()
+    );
+}
+
+#[test]
+fn visibility() {
+    let dir = std::env::temp_dir().join(format!("lelwel_visibility_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("grammar.llw");
+    let (input, output) = (input.to_str().unwrap(), dir.to_str().unwrap());
+    std::fs::write(input, "token A B;\n\nstart s;\n\ns: A B;\n").unwrap();
+    for (visibility, keyword) in [
+        (Visibility::Public, "pub "),
+        (Visibility::Crate, "pub(crate) "),
+        (Visibility::Private, ""),
+    ] {
+        let options = Options {
+            visibility,
+            single_file: true,
+            ..Default::default()
+        };
+        std::fs::remove_file(dir.join("parser.rs")).ok();
+        assert!(lelwel::compile_with_options(input, output, &options).unwrap());
+        let parser = std::fs::read_to_string(dir.join("parser.rs")).unwrap();
+        for item in [
+            "enum Token",
+            "fn tokenize(",
+            "struct Parser<'a>",
+            "struct Cst<'a>",
+            "enum Rule",
+        ] {
+            assert!(
+                parser.contains(&format!("\n{keyword}{item}")),
+                "`{item}` is not declared as `{keyword}`"
+            );
+        }
+        if visibility != Visibility::Public {
+            assert!(!parser.contains("\npub "));
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
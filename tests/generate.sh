@@ -9,29 +9,35 @@ cat << EOF > $output
 // This file was generated by the generate.sh script.
 // DO NOT EDIT THIS FILE MANUALLY!
 
-use codespan_reporting::files::SimpleFile;
 use codespan_reporting::term::termcolor::NoColor;
 use codespan_reporting::term::{self, DisplayStyle};
+use lelwel::frontend::diag::{deduplicate, sort_by_position};
+use lelwel::frontend::lint::{LintLevels, LintPass};
 use lelwel::frontend::parser::{tokenize, Parser, Token};
 use lelwel::frontend::sema::SemanticPass;
+use lelwel::frontend::source::GrammarSource;
 use logos::Logos;
 use std::io::BufWriter;
 
 fn gen_diags(input: &str) -> String {
-    let source = std::fs::read_to_string(input).unwrap();
     let mut diags = vec![];
-    let (tokens, ranges) = tokenize(Token::lexer(&source), &mut diags);
-    let cst = Parser::parse(&source, tokens, ranges, &mut diags);
-    let _ = SemanticPass::run(&cst, &mut diags);
+    let grammar = GrammarSource::load(input, &mut diags).unwrap();
+    let source = grammar.text();
+    let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+    let cst = Parser::parse(source, tokens, ranges, &mut diags);
+    assert_eq!(cst.verify_round_trip(), Ok(()));
+    let sema = SemanticPass::run(&cst, &mut diags);
+    LintPass::run(&cst, &sema, &LintLevels::default(), &mut diags);
+    deduplicate(&mut diags);
+    sort_by_position(&mut diags);
 
     let mut writer = NoColor::new(BufWriter::new(Vec::new()));
     let config = codespan_reporting::term::Config {
         display_style: DisplayStyle::Short,
         ..Default::default()
     };
-    let file = SimpleFile::new(input, &source);
     for diag in diags {
-        term::emit(&mut writer, &config, &file, &diag).unwrap();
+        term::emit(&mut writer, &config, grammar.files(), &grammar.diagnostic(&diag)).unwrap();
     }
     std::str::from_utf8(writer.get_ref().buffer())
         .unwrap()
@@ -0,0 +1,248 @@
+#![cfg(feature = "lsp")]
+
+use std::cmp::Reverse;
+
+use lelwel::ide::Cache;
+use tower_lsp::lsp_types::*;
+
+fn open(cache: &mut Cache, text: &str) -> Url {
+    let uri = Url::parse("file:///grammar.llw").unwrap();
+    cache.analyze(&uri, text.to_string());
+    uri
+}
+
+#[tokio::test]
+async fn semantic_tokens() {
+    let mut cache = Cache::default();
+    let uri = open(&mut cache, "token A='a';\nstart s;\ns: A* #1;\n");
+    let tokens = cache
+        .semantic_tokens(&uri)
+        .await
+        .into_iter()
+        .map(|token| {
+            (
+                token.delta_line,
+                token.delta_start,
+                token.length,
+                token.token_type,
+                token.token_modifiers_bitset,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        [
+            // keyword, declared token, and its symbol
+            (0, 0, 5, 4, 0),
+            (0, 6, 1, 1, 1),
+            (0, 2, 3, 6, 0),
+            // keyword and rule
+            (1, 0, 5, 4, 0),
+            (0, 6, 1, 0, 0),
+            // declared rule, token, operator, and action
+            (1, 0, 1, 0, 1),
+            (0, 3, 1, 1, 0),
+            (0, 1, 1, 10, 0),
+            (0, 2, 2, 2, 0),
+        ]
+    );
+}
+
+fn ranges(locations: &[Location]) -> Vec<(u32, u32, u32)> {
+    locations
+        .iter()
+        .map(|location| {
+            let Range { start, end } = location.range;
+            assert_eq!(start.line, end.line);
+            (start.line, start.character, end.character)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn definitions_and_references() {
+    let mut cache = Cache::default();
+    let uri = open(
+        &mut cache,
+        "token A='a' B;\nstart s;\ns: t 'a' #1 t #1;\nt: B;\n",
+    );
+    let definition = |location: Option<Location>| ranges(&[location.unwrap()]);
+    // rule and token symbol in a rule body, and rule in a directive
+    let rule = cache.goto_definition(&uri, Position::new(2, 3)).await;
+    assert_eq!(definition(rule), [(3, 0, 5)]);
+    let token = cache.goto_definition(&uri, Position::new(2, 6)).await;
+    assert_eq!(definition(token), [(0, 6, 11)]);
+    let start = cache.goto_definition(&uri, Position::new(1, 6)).await;
+    assert_eq!(definition(start), [(2, 0, 17)]);
+
+    let refs = cache.references(&uri, Position::new(3, 0), true).await;
+    assert_eq!(ranges(&refs), [(2, 3, 4), (2, 12, 13), (3, 0, 1)]);
+    let refs = cache.references(&uri, Position::new(3, 0), false).await;
+    assert_eq!(ranges(&refs), [(2, 3, 4), (2, 12, 13)]);
+    let refs = cache.references(&uri, Position::new(2, 0), true).await;
+    assert_eq!(ranges(&refs), [(1, 6, 7), (2, 0, 1)]);
+    let refs = cache.references(&uri, Position::new(0, 7), true).await;
+    assert_eq!(ranges(&refs), [(0, 6, 7), (2, 5, 8)]);
+    // actions with the same number
+    let refs = cache.references(&uri, Position::new(2, 10), false).await;
+    assert_eq!(ranges(&refs), [(2, 9, 11), (2, 14, 16)]);
+}
+
+#[tokio::test]
+async fn hover() {
+    let mut cache = Cache::default();
+    let uri = open(
+        &mut cache,
+        "token A B C;\nstart s;\ns: t (A | B) t [C] B;\nt: A | C;\n",
+    );
+    let (text, range) = cache.hover(&uri, Position::new(2, 3)).await.unwrap();
+    assert_eq!(
+        text,
+        "**First:** {A, C}\n**Follow:** {A, B}\n**Follow of `t`:** {A, B, C}\n**Predict:** {A, C}\n"
+    );
+    assert_eq!(range, Range::new(Position::new(2, 3), Position::new(2, 4)));
+    let (text, _) = cache.hover(&uri, Position::new(2, 8)).await.unwrap();
+    assert!(text.ends_with("**Predict:** {A, B}\n**Decision:** conflict-free\n"));
+    let (text, _) = cache.hover(&uri, Position::new(2, 15)).await.unwrap();
+    assert!(text.ends_with("**Predict:** {B, C}\n**Decision:** conflict-free\n"));
+
+    // the optional conflicts with the token that follows it
+    cache.invalidate(&uri);
+    let uri = open(
+        &mut cache,
+        "token A B C;\nstart s;\ns: t (A | B) t [C] C;\nt: A | C;\n",
+    );
+    let (text, _) = cache.hover(&uri, Position::new(2, 15)).await.unwrap();
+    assert!(text.ends_with("**Decision:** LL(1) conflict in option with token set: {C}\n"));
+}
+
+async fn complete(cache: &mut Cache, uri: &Url, pos: Position) -> Vec<CompletionItem> {
+    let params = CompletionParams {
+        text_document_position: TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(uri.clone()),
+            pos,
+        ),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+        context: None,
+    };
+    let Some(CompletionResponse::Array(items)) = cache.completion(params).await else {
+        panic!("expected completion items");
+    };
+    items
+}
+
+#[tokio::test]
+async fn completion() {
+    let mut cache = Cache::default();
+    let uri = open(
+        &mut cache,
+        "token A B;\nstart s;\ns: u t A;\nt: B u;\nu: A;\nv: A;\n\n",
+    );
+    // names in a rule body, ranked by distance and then by usage
+    let mut items = complete(&mut cache, &uri, Position::new(2, 5)).await;
+    items.retain(|item| item.kind == Some(CompletionItemKind::REFERENCE));
+    items.sort_by(|a, b| a.sort_text.cmp(&b.sort_text));
+    let labels = items
+        .iter()
+        .map(|item| item.label.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(labels, ["s", "t", "A", "u", "B", "v"]);
+
+    // only rules in a start directive
+    let items = complete(&mut cache, &uri, Position::new(1, 6)).await;
+    let labels = items
+        .iter()
+        .map(|item| item.label.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(labels, ["s", "t", "u", "v"]);
+
+    // directives at the top level, without the start directive that already exists
+    let items = complete(&mut cache, &uri, Position::new(6, 0)).await;
+    let labels = items
+        .iter()
+        .map(|item| item.label.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        labels,
+        [
+            "token TokenName='token symbol';",
+            "right TokenName;",
+            "skip TokenName;",
+            "insert TokenName;",
+            "sync TokenName;",
+            "token_derive Hash;",
+            "import 'file.llw';",
+            "lookahead 2;",
+            "max_errors 10;",
+            "rule_name: regex;",
+        ]
+    );
+}
+
+/// Applies the edits of the code action with the `kind` to the `text`.
+fn apply(actions: &[CodeActionOrCommand], kind: CodeActionKind, text: &str) -> String {
+    let action = actions
+        .iter()
+        .find_map(|action| match action {
+            CodeActionOrCommand::CodeAction(action) if action.kind == Some(kind.clone()) => {
+                Some(action)
+            }
+            _ => None,
+        })
+        .unwrap();
+    let mut edits = action
+        .edit
+        .as_ref()
+        .unwrap()
+        .changes
+        .as_ref()
+        .unwrap()
+        .values()
+        .next()
+        .unwrap()
+        .clone();
+    let offset = |pos: Position| {
+        let line: usize = text
+            .split_inclusive('\n')
+            .take(pos.line as usize)
+            .map(str::len)
+            .sum();
+        line + pos.character as usize
+    };
+    edits.sort_by_key(|edit| Reverse(offset(edit.range.start)));
+    let mut text = text.to_string();
+    for edit in edits {
+        text.replace_range(
+            offset(edit.range.start)..offset(edit.range.end),
+            &edit.new_text,
+        );
+    }
+    text
+}
+
+#[tokio::test]
+async fn refactorings() {
+    let mut cache = Cache::default();
+    let text = "token A B C;\nstart s;\ns: A (B | C)* t;\nt: B C;\n";
+    let uri = open(&mut cache, text);
+    let range = Range::new(Position::new(2, 6), Position::new(2, 11));
+    let actions = cache.code_actions(&uri, range).await;
+    assert_eq!(
+        apply(&actions, CodeActionKind::REFACTOR_EXTRACT, text),
+        "token A B C;\nstart s;\ns: A (new_rule)* t;\nnew_rule: B | C;\nt: B C;\n"
+    );
+    let pos = Position::new(2, 14);
+    let actions = cache.code_actions(&uri, Range::new(pos, pos)).await;
+    assert_eq!(
+        apply(&actions, CodeActionKind::REFACTOR_INLINE, text),
+        "token A B C;\nstart s;\ns: A (B | C)* B C;\n"
+    );
+
+    // the start rule cannot be inlined
+    let pos = Position::new(2, 0);
+    assert!(cache
+        .code_actions(&uri, Range::new(pos, pos))
+        .await
+        .is_empty());
+}
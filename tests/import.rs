@@ -0,0 +1,50 @@
+use std::path::Path;
+
+use lelwel::import::Format;
+
+fn import(path: &str) -> (String, Vec<String>) {
+    let source = std::fs::read_to_string(path).unwrap();
+    let mut diags = vec![];
+    let grammar = Format::from_path(Path::new(path))
+        .unwrap()
+        .translate(&source, &mut diags);
+    let messages = diags.into_iter().map(|diag| diag.message).collect();
+    (grammar.to_string(), messages)
+}
+
+#[test]
+fn yacc() {
+    let (grammar, messages) = import("tests/import/calc.y");
+    assert_eq!(
+        grammar,
+        std::fs::read_to_string("tests/import/calc.llw").unwrap()
+    );
+    assert_eq!(
+        messages,
+        [
+            "undeclared symbol `VARIABLE` is imported as a token",
+            "alternative with `error` token is removed",
+            "unsupported left recursion of rule `input`",
+            "indirect left recursion of rules `args`, `call`",
+            "precedence declarations are not imported",
+        ]
+    );
+}
+
+#[test]
+fn pest() {
+    let (grammar, messages) = import("tests/import/json.pest");
+    assert_eq!(
+        grammar,
+        std::fs::read_to_string("tests/import/json.llw").unwrap()
+    );
+    assert_eq!(
+        messages,
+        [
+            "alternative of ordered choice is preceded by its prefix",
+            "alternatives of ordered choice start with the same token",
+            "alternatives of ordered choice start with the same token",
+            "alternative of ordered choice may match the empty string",
+        ]
+    );
+}
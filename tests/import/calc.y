@@ -0,0 +1,46 @@
+/* Infix notation calculator. */
+%{
+  #include <math.h>
+  #include <stdio.h>
+  int yylex (void);
+  void yyerror (char const *);
+%}
+
+%define api.value.type {double}
+%token NUM
+%token END 0 "end of file"
+%token LET "let" ASSIGN_OP ":=" ID "identifier"
+%left '-' '+'
+%left '*' '/'
+%precedence NEG /* negation--unary minus */
+%right '^'      /* exponentiation */
+
+%%
+input:
+  %empty
+| input line
+;
+
+line:
+  '\n'
+| exp '\n'            { printf ("\t%.10g\n", $1); }
+| "let" ID ":=" exp '\n'
+| error '\n'          { yyerrok; }
+;
+
+exp[result]:
+  NUM
+| exp[left] '+' exp[right] { $result = $left + $right; }
+| exp '-' exp              { $$ = $1 - $3; }
+| exp '*' exp              { $$ = $1 * $3; }
+| exp '/' exp              { $$ = $1 / $3; }
+| '-' exp %prec NEG        { $$ = -$2; }
+| exp '^' exp              { $$ = pow ($1, $3); }
+| '(' exp ')'              { $$ = $2; }
+| VARIABLE                 { $$ = lookup ("}"); }
+;
+
+args: exp | call ',' exp
+call: args
+%%
+int main (void) { return yyparse (); } /* ' " { */
@@ -0,0 +1,48 @@
+use std::collections::BTreeMap;
+
+use lelwel::frontend::diag::sort_by_position;
+use lelwel::frontend::parser::{tokenize, Parser, Token};
+use lelwel::frontend::sema::{AnalysisCache, SemanticPass};
+use logos::Logos;
+
+/// Returns the first and follow sets and the diagnostics of the analysis of
+/// the `source`, either with the `cache` or from scratch.
+fn analyze(source: &str, cache: Option<&mut AnalysisCache>) -> String {
+    let mut diags = vec![];
+    let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+    let cst = Parser::parse(source, tokens, ranges, &mut diags);
+    let sema = match cache {
+        Some(cache) => SemanticPass::run_incremental(&cst, &mut diags, cache),
+        None => SemanticPass::run(&cst, &mut diags),
+    };
+    sort_by_position(&mut diags);
+    let sets = [&sema.first_sets, &sema.follow_sets].map(|sets| {
+        sets.iter()
+            .map(|(node, set)| (node.0, set.iter().map(|t| t.0).collect::<Vec<_>>()))
+            .collect::<BTreeMap<_, _>>()
+    });
+    format!("{sets:?}\n{diags:?}")
+}
+
+#[test]
+fn same_as_full_analysis() {
+    let source = include_str!("../examples/json/src/json.llw");
+    let versions = [
+        source.to_string(),
+        // changes the follow sets of the referenced rules
+        source.replace(
+            "array: '[' [value (',' value)*] ']';",
+            "array: '[' [value (',' value)*] [','] ']';",
+        ),
+        // adds an LL(1) conflict to a rule that references the changed rule
+        source.replace("| 'null'\n", "| 'null'\n| '{'\n"),
+        // changes a declaration outside of the rules
+        source.replace("token Whitespace;", "token Whitespace Comment;"),
+        source.to_string(),
+    ];
+    assert!(versions[1..4].iter().all(|version| *version != source));
+    let mut cache = AnalysisCache::default();
+    for version in versions.iter() {
+        assert_eq!(analyze(version, Some(&mut cache)), analyze(version, None));
+    }
+}
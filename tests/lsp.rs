@@ -0,0 +1,75 @@
+#![cfg(all(feature = "cli", feature = "lsp"))]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{ChildStdin, ChildStdout, Command, Stdio};
+
+fn send(stdin: &mut ChildStdin, message: &str) {
+    write!(stdin, "Content-Length: {}\r\n\r\n{message}", message.len()).unwrap();
+    stdin.flush().unwrap();
+}
+
+fn receive(stdout: &mut BufReader<ChildStdout>) -> String {
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length: ") {
+            length = value.parse().unwrap();
+        }
+    }
+    let mut content = vec![0; length];
+    stdout.read_exact(&mut content).unwrap();
+    String::from_utf8(content).unwrap()
+}
+
+#[test]
+fn lsp() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_llw"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    send(
+        &mut stdin,
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#,
+    );
+    let response = receive(&mut stdout);
+    assert!(response.contains(r#""id":1"#));
+    assert!(response.contains(r#""hoverProvider":true"#));
+    assert!(response.contains(r#""definitionProvider":true"#));
+    send(
+        &mut stdin,
+        r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#,
+    );
+
+    // opening a grammar publishes its diagnostics
+    send(
+        &mut stdin,
+        r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///test.llw","languageId":"lelwel","version":1,"text":"token A;\nstart s;\ns: A b;\n"}}}"#,
+    );
+    let notification = loop {
+        let message = receive(&mut stdout);
+        if message.contains("textDocument/publishDiagnostics") {
+            break message;
+        }
+    };
+    assert!(notification.contains("file:///test.llw"));
+    assert!(notification.contains("undefined"));
+
+    send(
+        &mut stdin,
+        r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+    );
+    assert!(receive(&mut stdout).contains(r#""id":2"#));
+    send(&mut stdin, r#"{"jsonrpc":"2.0","method":"exit"}"#);
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+}
@@ -0,0 +1,49 @@
+use lelwel::frontend::parser::{tokenize, Parser, Token};
+use lelwel::frontend::report::{Report, ReportKind};
+use lelwel::frontend::sema::SemanticPass;
+use lelwel::frontend::source::GrammarSource;
+use logos::Logos;
+
+fn report(input: &str, kind: ReportKind) -> String {
+    let mut diags = vec![];
+    let grammar = GrammarSource::load(input, &mut diags).unwrap();
+    let source = grammar.text();
+    let (tokens, ranges) = tokenize(Token::lexer(source), &mut diags);
+    let cst = Parser::parse(source, tokens, ranges, &mut diags);
+    let sema = SemanticPass::run(&cst, &mut diags);
+    let mut output = vec![];
+    Report::run(&cst, &sema, &grammar, kind, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn predicates() {
+    assert_eq!(
+        report("tests/report/predicates.llw", ReportKind::Predicates),
+        include_str!("report/predicates.txt")
+    );
+}
+
+#[test]
+fn tokens() {
+    assert_eq!(
+        report("tests/report/tokens.llw", ReportKind::Tokens),
+        include_str!("report/tokens.json")
+    );
+}
+
+#[test]
+fn sets() {
+    assert_eq!(
+        report("tests/report/sets.llw", ReportKind::Sets),
+        include_str!("report/sets.txt")
+    );
+}
+
+#[test]
+fn reachability() {
+    assert_eq!(
+        report("tests/report/reachability.llw", ReportKind::Reachability),
+        include_str!("report/reachability.dot")
+    );
+}
@@ -0,0 +1,60 @@
+use lelwel::frontend::interpreter::Interpreter;
+use lelwel::frontend::lexer::GrammarLexer;
+use lelwel::frontend::parser::{tokenize, Parser, Token};
+use lelwel::frontend::sema::SemanticPass;
+use lelwel::frontend::source::GrammarSource;
+use logos::Logos;
+
+/// Interprets the grammar at `path` on the `input` and returns the syntax
+/// tree, or the offset of the error in the input and its message.
+fn run(path: &str, input: &str) -> Result<String, (usize, String)> {
+    let mut diags = vec![];
+    let grammar = GrammarSource::load(path, &mut diags).unwrap();
+    let (tokens, ranges) = tokenize(Token::lexer(grammar.text()), &mut diags);
+    let cst = Parser::parse(grammar.text(), tokens, ranges, &mut diags);
+    let sema = SemanticPass::run(&cst, &mut diags);
+    assert!(diags.is_empty());
+
+    let lexer = GrammarLexer::new(&cst).unwrap();
+    let (tokens, spans) = lexer
+        .tokenize(input)
+        .map_err(|err| (err.offset, "invalid token".to_string()))?;
+    match Interpreter::run_tokens(&cst, &sema, &tokens) {
+        Ok(tree) => Ok(format!("{tree}")),
+        Err(err) => Err((
+            spans
+                .get(err.position)
+                .map_or(input.len(), |span| span.start),
+            err.message,
+        )),
+    }
+}
+
+#[test]
+fn tree() {
+    // `let` is matched by its symbol and `letter` by the longer regex match
+    assert_eq!(
+        run("tests/run/stmt.llw", include_str!("run/stmt.txt")).unwrap(),
+        include_str!("run/stmt.tree")
+    );
+}
+
+#[test]
+fn syntax_error() {
+    assert_eq!(
+        run("tests/run/stmt.llw", "let a = 1;\nlet b = ;\n"),
+        Err((19, "found Semi, expected: Num".to_string()))
+    );
+    assert_eq!(
+        run("tests/run/stmt.llw", "let a = 1"),
+        Err((9, "found EOF, expected: Semi".to_string()))
+    );
+}
+
+#[test]
+fn invalid_token() {
+    assert_eq!(
+        run("tests/run/stmt.llw", "let a = $;"),
+        Err((8, "invalid token".to_string()))
+    );
+}
@@ -0,0 +1,35 @@
+use lelwel::Options;
+
+#[test]
+fn single_file() {
+    let dir = std::env::temp_dir().join(format!("lelwel_single_file_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("grammar.llw");
+    let (input, output) = (input.to_str().unwrap(), dir.to_str().unwrap());
+    let parser_file = dir.join("parser.rs");
+    std::fs::write(input, "token A B;\n\nstart s;\n\ns: A B;\n").unwrap();
+
+    // a parser file of the default mode loses the include of the generated file
+    assert!(lelwel::compile_with_options(input, output, &Options::default()).unwrap());
+    let parser = std::fs::read_to_string(&parser_file).unwrap();
+    assert!(parser.contains("include!"));
+    let options = Options {
+        single_file: true,
+        ..Default::default()
+    };
+    assert!(lelwel::compile_with_options(input, output, &options).unwrap());
+    let parser = std::fs::read_to_string(&parser_file).unwrap();
+    assert!(!parser.contains("include!"));
+    assert!(parser.ends_with(&lelwel::generate(input, &options).unwrap()));
+
+    // regeneration only replaces the code below the marker
+    std::fs::write(&parser_file, format!("// lexer\n{parser}")).unwrap();
+    std::fs::write(input, "token A B;\n\nstart s;\n\ns: A B*;\n").unwrap();
+    assert!(lelwel::compile_with_options(input, output, &options).unwrap());
+    let parser = std::fs::read_to_string(&parser_file).unwrap();
+    assert!(parser.starts_with("// lexer\n"));
+    assert_eq!(parser.matches("// generated by lelwel").count(), 1);
+    assert!(parser.ends_with(&lelwel::generate(input, &options).unwrap()));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}